@@ -0,0 +1,125 @@
+use sea_orm::{entity::prelude::*, ActiveValue};
+use serde::{Deserialize, Serialize};
+
+use crate::seconds_since_epoch;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "channel"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Deserialize, Serialize)]
+pub struct Model {
+    pub id: String,
+    pub node_id: String,
+    pub channel_id: String,
+    pub counterparty_node_id: String,
+    pub funding_txid: Option<String>,
+    pub funding_tx_index: Option<i32>,
+    pub short_channel_id: Option<i64>,
+    pub channel_value_satoshis: i64,
+    pub balance_msat: i64,
+    pub is_outbound: bool,
+    pub is_public: bool,
+    pub state: String,
+    pub closing_reason: Option<String>,
+    pub closing_txid: Option<String>,
+    pub ready_at: Option<i64>,
+    pub closing_at: Option<i64>,
+    pub closed_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    Id,
+    NodeId,
+    ChannelId,
+    CounterpartyNodeId,
+    FundingTxid,
+    FundingTxIndex,
+    ShortChannelId,
+    ChannelValueSatoshis,
+    BalanceMsat,
+    IsOutbound,
+    IsPublic,
+    State,
+    ClosingReason,
+    ClosingTxid,
+    ReadyAt,
+    ClosingAt,
+    ClosedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    Id,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = String;
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+    fn def(&self) -> ColumnDef {
+        match self {
+            Self::Id => ColumnType::String(None).def(),
+            Self::NodeId => ColumnType::String(None).def(),
+            Self::ChannelId => ColumnType::String(None).def(),
+            Self::CounterpartyNodeId => ColumnType::String(None).def(),
+            Self::FundingTxid => ColumnType::String(None).def().null(),
+            Self::FundingTxIndex => ColumnType::Integer.def().null(),
+            Self::ShortChannelId => ColumnType::BigInteger.def().null(),
+            Self::ChannelValueSatoshis => ColumnType::BigInteger.def(),
+            Self::BalanceMsat => ColumnType::BigInteger.def(),
+            Self::IsOutbound => ColumnType::Boolean.def(),
+            Self::IsPublic => ColumnType::Boolean.def(),
+            Self::State => ColumnType::String(None).def(),
+            Self::ClosingReason => ColumnType::String(None).def().null(),
+            Self::ClosingTxid => ColumnType::String(None).def().null(),
+            Self::ReadyAt => ColumnType::BigInteger.def().null(),
+            Self::ClosingAt => ColumnType::BigInteger.def().null(),
+            Self::ClosedAt => ColumnType::BigInteger.def().null(),
+            Self::CreatedAt => ColumnType::BigInteger.def(),
+            Self::UpdatedAt => ColumnType::BigInteger.def(),
+        }
+    }
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..<Self as ActiveModelTrait>::default()
+        }
+    }
+
+    fn before_save(mut self, insert: bool) -> Result<Self, DbErr> {
+        let now: i64 = seconds_since_epoch();
+        self.updated_at = ActiveValue::Set(now);
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        }
+        Ok(self)
+    }
+}
@@ -5,13 +5,20 @@ pub use sea_orm;
 pub mod prelude;
 
 pub mod access_token;
+pub mod balance_snapshot;
+pub mod channel;
+pub mod channel_event;
+pub mod forwarded_payment;
 pub mod keychain;
 pub mod kv_store;
 pub mod macaroon;
+pub mod macaroon_spend;
 pub mod node;
 pub mod payment;
+pub mod payment_attempt;
 pub mod peer;
 pub mod peer_address;
+pub mod scheduled_payment;
 pub mod script_pubkey;
 pub mod transaction;
 pub mod user;
@@ -29,6 +29,9 @@ pub struct Model {
     pub secret: Option<String>,
     pub label: Option<String>,
     pub invoice: Option<String>,
+    pub expires_at: Option<i64>,
+    pub min_amt_msat: Option<i64>,
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -48,6 +51,9 @@ pub enum Column {
     UpdatedAt,
     Label,
     Invoice,
+    ExpiresAt,
+    MinAmtMsat,
+    IdempotencyKey,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -84,6 +90,9 @@ impl ColumnTrait for Column {
             Self::Secret => ColumnType::String(None).def().null(),
             Self::Label => ColumnType::String(None).def().null(),
             Self::Invoice => ColumnType::String(None).def().null(),
+            Self::ExpiresAt => ColumnType::BigInteger.def().null(),
+            Self::MinAmtMsat => ColumnType::BigInteger.def().null(),
+            Self::IdempotencyKey => ColumnType::String(None).def().null(),
         }
     }
 }
@@ -0,0 +1,113 @@
+use sea_orm::{entity::prelude::*, ActiveValue};
+use serde::{Deserialize, Serialize};
+
+use crate::seconds_since_epoch;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "payment_attempt"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Deserialize, Serialize)]
+pub struct Model {
+    pub id: String,
+    pub node_id: String,
+    pub payment_hash: String,
+    pub payment_id: Option<String>,
+    pub status: String,
+    pub failure_code: Option<String>,
+    pub failed_short_channel_id: Option<i64>,
+    pub network_update: Option<String>,
+    pub rejected_by_dest: bool,
+    pub all_paths_failed: bool,
+    pub amt_msat: i64,
+    pub fee_msat: i64,
+    pub hops: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    Id,
+    NodeId,
+    PaymentHash,
+    PaymentId,
+    Status,
+    FailureCode,
+    FailedShortChannelId,
+    NetworkUpdate,
+    RejectedByDest,
+    AllPathsFailed,
+    AmtMsat,
+    FeeMsat,
+    Hops,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    Id,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = String;
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+    fn def(&self) -> ColumnDef {
+        match self {
+            Self::Id => ColumnType::String(None).def(),
+            Self::NodeId => ColumnType::String(None).def(),
+            Self::PaymentHash => ColumnType::String(None).def(),
+            Self::PaymentId => ColumnType::String(None).def().null(),
+            Self::Status => ColumnType::String(None).def(),
+            Self::FailureCode => ColumnType::String(None).def().null(),
+            Self::FailedShortChannelId => ColumnType::BigInteger.def().null(),
+            Self::NetworkUpdate => ColumnType::String(None).def().null(),
+            Self::RejectedByDest => ColumnType::Boolean.def(),
+            Self::AllPathsFailed => ColumnType::Boolean.def(),
+            Self::AmtMsat => ColumnType::BigInteger.def(),
+            Self::FeeMsat => ColumnType::BigInteger.def(),
+            Self::Hops => ColumnType::String(None).def(),
+            Self::CreatedAt => ColumnType::BigInteger.def(),
+            Self::UpdatedAt => ColumnType::BigInteger.def(),
+        }
+    }
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..<Self as ActiveModelTrait>::default()
+        }
+    }
+
+    fn before_save(mut self, insert: bool) -> Result<Self, DbErr> {
+        let now: i64 = seconds_since_epoch();
+        self.updated_at = ActiveValue::Set(now);
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        }
+        Ok(self)
+    }
+}
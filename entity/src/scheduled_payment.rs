@@ -0,0 +1,110 @@
+use sea_orm::{entity::prelude::*, ActiveValue};
+use serde::{Deserialize, Serialize};
+
+use crate::seconds_since_epoch;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "scheduled_payment"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Deserialize, Serialize)]
+pub struct Model {
+    pub id: String,
+    pub node_id: String,
+    pub kind: String,
+    pub dest_pubkey: Option<String>,
+    pub invoice_url: Option<String>,
+    pub amt_msat: i64,
+    pub interval_secs: i64,
+    pub next_run_at: i64,
+    pub last_run_at: Option<i64>,
+    pub last_payment_hash: Option<String>,
+    pub last_error: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    Id,
+    NodeId,
+    Kind,
+    DestPubkey,
+    InvoiceUrl,
+    AmtMsat,
+    IntervalSecs,
+    NextRunAt,
+    LastRunAt,
+    LastPaymentHash,
+    LastError,
+    Enabled,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    Id,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = String;
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+    fn def(&self) -> ColumnDef {
+        match self {
+            Self::Id => ColumnType::String(None).def(),
+            Self::NodeId => ColumnType::String(None).def(),
+            Self::Kind => ColumnType::String(None).def(),
+            Self::DestPubkey => ColumnType::String(None).def().null(),
+            Self::InvoiceUrl => ColumnType::String(None).def().null(),
+            Self::AmtMsat => ColumnType::BigInteger.def(),
+            Self::IntervalSecs => ColumnType::BigInteger.def(),
+            Self::NextRunAt => ColumnType::BigInteger.def(),
+            Self::LastRunAt => ColumnType::BigInteger.def().null(),
+            Self::LastPaymentHash => ColumnType::String(None).def().null(),
+            Self::LastError => ColumnType::String(None).def().null(),
+            Self::Enabled => ColumnType::Boolean.def(),
+            Self::CreatedAt => ColumnType::BigInteger.def(),
+            Self::UpdatedAt => ColumnType::BigInteger.def(),
+        }
+    }
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..<Self as ActiveModelTrait>::default()
+        }
+    }
+
+    fn before_save(mut self, insert: bool) -> Result<Self, DbErr> {
+        let now: i64 = seconds_since_epoch();
+        self.updated_at = ActiveValue::Set(now);
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        }
+        Ok(self)
+    }
+}
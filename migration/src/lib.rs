@@ -12,6 +12,16 @@ mod m20220428_000004_create_keychains_table;
 mod m20220616_000001_create_peers_table;
 mod m20220701_000001_create_peer_addresses_table;
 mod m20220808_000001_create_users_table;
+mod m20220820_000001_create_forwarded_payments_table;
+mod m20220822_000001_create_payment_attempts_table;
+mod m20220824_000001_add_expires_at_to_payments;
+mod m20220826_000001_add_min_amt_msat_to_payments;
+mod m20220828_000001_add_idempotency_key_to_payments;
+mod m20220830_000001_create_macaroon_spends_table;
+mod m20220901_000001_create_scheduled_payments_table;
+mod m20220903_000001_create_channel_events_table;
+mod m20220905_000001_create_balance_snapshots_table;
+mod m20220909_000001_create_channels_table;
 
 pub struct Migrator;
 
@@ -32,6 +42,16 @@ impl MigratorTrait for Migrator {
             Box::new(m20220616_000001_create_peers_table::Migration),
             Box::new(m20220701_000001_create_peer_addresses_table::Migration),
             Box::new(m20220808_000001_create_users_table::Migration),
+            Box::new(m20220820_000001_create_forwarded_payments_table::Migration),
+            Box::new(m20220822_000001_create_payment_attempts_table::Migration),
+            Box::new(m20220824_000001_add_expires_at_to_payments::Migration),
+            Box::new(m20220826_000001_add_min_amt_msat_to_payments::Migration),
+            Box::new(m20220828_000001_add_idempotency_key_to_payments::Migration),
+            Box::new(m20220830_000001_create_macaroon_spends_table::Migration),
+            Box::new(m20220901_000001_create_scheduled_payments_table::Migration),
+            Box::new(m20220903_000001_create_channel_events_table::Migration),
+            Box::new(m20220905_000001_create_balance_snapshots_table::Migration),
+            Box::new(m20220909_000001_create_channels_table::Migration),
         ]
     }
 }
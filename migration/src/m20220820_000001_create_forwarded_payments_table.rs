@@ -0,0 +1,83 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220820_000001_create_forwarded_payments_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(ForwardedPayment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ForwardedPayment::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ForwardedPayment::NodeId).string().not_null())
+                    .col(ColumnDef::new(ForwardedPayment::FromChannelId).string())
+                    .col(ColumnDef::new(ForwardedPayment::ToChannelId).string())
+                    .col(ColumnDef::new(ForwardedPayment::FeesEarnedMsat).big_integer())
+                    .col(
+                        ColumnDef::new(ForwardedPayment::ClaimFromOnchainTx)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ForwardedPayment::HoursSinceEpoch)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ForwardedPayment::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ForwardedPayment::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ForwardedPayment::Table)
+                    .name("idx-nodeid-hourssinceepoch")
+                    .col(ForwardedPayment::NodeId)
+                    .col(ForwardedPayment::HoursSinceEpoch)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(ForwardedPayment::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum ForwardedPayment {
+    Table,
+    Id,
+    NodeId,
+    FromChannelId,
+    ToChannelId,
+    FeesEarnedMsat,
+    ClaimFromOnchainTx,
+    HoursSinceEpoch,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,107 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000001_create_payment_attempts_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(PaymentAttempt::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaymentAttempt::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PaymentAttempt::NodeId).string().not_null())
+                    .col(
+                        ColumnDef::new(PaymentAttempt::PaymentHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PaymentAttempt::PaymentId).string())
+                    .col(ColumnDef::new(PaymentAttempt::Status).string().not_null())
+                    .col(ColumnDef::new(PaymentAttempt::FailureCode).string())
+                    .col(ColumnDef::new(PaymentAttempt::FailedShortChannelId).big_integer())
+                    .col(ColumnDef::new(PaymentAttempt::NetworkUpdate).text())
+                    .col(
+                        ColumnDef::new(PaymentAttempt::RejectedByDest)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaymentAttempt::AllPathsFailed)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaymentAttempt::AmtMsat)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaymentAttempt::FeeMsat)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PaymentAttempt::Hops).text().not_null())
+                    .col(
+                        ColumnDef::new(PaymentAttempt::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaymentAttempt::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(PaymentAttempt::Table)
+                    .name("idx-paymentattempt-nodeid-paymenthash")
+                    .col(PaymentAttempt::NodeId)
+                    .col(PaymentAttempt::PaymentHash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(PaymentAttempt::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum PaymentAttempt {
+    Table,
+    Id,
+    NodeId,
+    PaymentHash,
+    PaymentId,
+    Status,
+    FailureCode,
+    FailedShortChannelId,
+    NetworkUpdate,
+    RejectedByDest,
+    AllPathsFailed,
+    AmtMsat,
+    FeeMsat,
+    Hops,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,77 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220830_000001_create_macaroon_spends_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(MacaroonSpend::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MacaroonSpend::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MacaroonSpend::NodeId).string().not_null())
+                    .col(ColumnDef::new(MacaroonSpend::BudgetId).string().not_null())
+                    .col(
+                        ColumnDef::new(MacaroonSpend::AmtMsat)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MacaroonSpend::Destination).string())
+                    .col(ColumnDef::new(MacaroonSpend::PaymentHash).string())
+                    .col(
+                        ColumnDef::new(MacaroonSpend::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MacaroonSpend::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MacaroonSpend::Table)
+                    .name("idx-macaroonspend-nodeid-budgetid")
+                    .col(MacaroonSpend::NodeId)
+                    .col(MacaroonSpend::BudgetId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(MacaroonSpend::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum MacaroonSpend {
+    Table,
+    Id,
+    NodeId,
+    BudgetId,
+    AmtMsat,
+    Destination,
+    PaymentHash,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,101 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220901_000001_create_scheduled_payments_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledPayment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduledPayment::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduledPayment::NodeId).string().not_null())
+                    .col(ColumnDef::new(ScheduledPayment::Kind).string().not_null())
+                    .col(ColumnDef::new(ScheduledPayment::DestPubkey).string())
+                    .col(ColumnDef::new(ScheduledPayment::InvoiceUrl).string())
+                    .col(
+                        ColumnDef::new(ScheduledPayment::AmtMsat)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledPayment::IntervalSecs)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledPayment::NextRunAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledPayment::LastRunAt).big_integer())
+                    .col(ColumnDef::new(ScheduledPayment::LastPaymentHash).string())
+                    .col(ColumnDef::new(ScheduledPayment::LastError).text())
+                    .col(
+                        ColumnDef::new(ScheduledPayment::Enabled)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledPayment::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledPayment::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ScheduledPayment::Table)
+                    .name("idx-scheduledpayment-nodeid-nextrunat")
+                    .col(ScheduledPayment::NodeId)
+                    .col(ScheduledPayment::NextRunAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(ScheduledPayment::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum ScheduledPayment {
+    Table,
+    Id,
+    NodeId,
+    Kind,
+    DestPubkey,
+    InvoiceUrl,
+    AmtMsat,
+    IntervalSecs,
+    NextRunAt,
+    LastRunAt,
+    LastPaymentHash,
+    LastError,
+    Enabled,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220903_000001_create_channel_events_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(ChannelEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChannelEvent::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ChannelEvent::NodeId).string().not_null())
+                    .col(ColumnDef::new(ChannelEvent::ChannelId).string().not_null())
+                    .col(ColumnDef::new(ChannelEvent::CounterpartyNodeId).string())
+                    .col(ColumnDef::new(ChannelEvent::Kind).string().not_null())
+                    .col(ColumnDef::new(ChannelEvent::FundingTxid).string())
+                    .col(ColumnDef::new(ChannelEvent::ChannelValueSatoshis).big_integer())
+                    .col(ColumnDef::new(ChannelEvent::Reason).string())
+                    .col(ColumnDef::new(ChannelEvent::DestinationAddress).string())
+                    .col(ColumnDef::new(ChannelEvent::TargetFeerateSatsPerVbyte).integer())
+                    .col(ColumnDef::new(ChannelEvent::IsOutbound).boolean())
+                    .col(
+                        ColumnDef::new(ChannelEvent::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ChannelEvent::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ChannelEvent::Table)
+                    .name("idx-channelevent-nodeid-channelid")
+                    .col(ChannelEvent::NodeId)
+                    .col(ChannelEvent::ChannelId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(ChannelEvent::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum ChannelEvent {
+    Table,
+    Id,
+    NodeId,
+    ChannelId,
+    CounterpartyNodeId,
+    Kind,
+    FundingTxid,
+    ChannelValueSatoshis,
+    Reason,
+    DestinationAddress,
+    TargetFeerateSatsPerVbyte,
+    IsOutbound,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220905_000001_create_balance_snapshots_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(BalanceSnapshot::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BalanceSnapshot::NodeId).string().not_null())
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::OnchainBalanceSats)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::ChannelOutboundCapacityMsats)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::ChannelInboundCapacityMsats)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BalanceSnapshot::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(BalanceSnapshot::Table)
+                    .name("idx-balancesnapshot-nodeid-createdat")
+                    .col(BalanceSnapshot::NodeId)
+                    .col(BalanceSnapshot::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(BalanceSnapshot::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[derive(Iden)]
+enum BalanceSnapshot {
+    Table,
+    Id,
+    NodeId,
+    OnchainBalanceSats,
+    ChannelOutboundCapacityMsats,
+    ChannelInboundCapacityMsats,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,101 @@
+use sea_schema::migration::prelude::*;
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220909_000001_create_channels_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let _res = manager
+            .create_table(
+                Table::create()
+                    .table(Channel::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Channel::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Channel::NodeId).string().not_null())
+                    .col(ColumnDef::new(Channel::ChannelId).string().not_null())
+                    .col(
+                        ColumnDef::new(Channel::CounterpartyNodeId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Channel::FundingTxid).string())
+                    .col(ColumnDef::new(Channel::FundingTxIndex).integer())
+                    .col(ColumnDef::new(Channel::ShortChannelId).big_integer())
+                    .col(
+                        ColumnDef::new(Channel::ChannelValueSatoshis)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Channel::BalanceMsat)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Channel::IsOutbound).boolean().not_null())
+                    .col(ColumnDef::new(Channel::IsPublic).boolean().not_null())
+                    .col(ColumnDef::new(Channel::State).string().not_null())
+                    .col(ColumnDef::new(Channel::ClosingReason).string())
+                    .col(ColumnDef::new(Channel::ClosingTxid).string())
+                    .col(ColumnDef::new(Channel::ReadyAt).big_integer())
+                    .col(ColumnDef::new(Channel::ClosingAt).big_integer())
+                    .col(ColumnDef::new(Channel::ClosedAt).big_integer())
+                    .col(ColumnDef::new(Channel::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Channel::UpdatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Channel::Table)
+                    .name("idx-channel-nodeid-channelid")
+                    .col(Channel::NodeId)
+                    .col(Channel::ChannelId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut stmt = Table::drop();
+        stmt.table(Channel::Table);
+        manager.drop_table(stmt).await
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Iden)]
+enum Channel {
+    Table,
+    Id,
+    NodeId,
+    ChannelId,
+    CounterpartyNodeId,
+    FundingTxid,
+    FundingTxIndex,
+    ShortChannelId,
+    ChannelValueSatoshis,
+    BalanceMsat,
+    IsOutbound,
+    IsPublic,
+    State,
+    ClosingReason,
+    ClosingTxid,
+    ReadyAt,
+    ClosingAt,
+    ClosedAt,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,354 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::node::LightningNode;
+use crate::p2p::utils::net_address_to_socket_addr;
+use crate::services::node::OpenChannelRequest;
+use bitcoin::secp256k1::PublicKey;
+use entity::seconds_since_epoch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often each node checks whether its autopilot is due to run.
+const AUTOPILOT_TICK_SECS: u64 = 60;
+
+// Unreachable candidates are skipped, but only this many are tried for each channel.
+const CANDIDATES_PER_CHANNEL: usize = 3;
+
+pub const MIN_AUTOPILOT_INTERVAL_SECS: u64 = 60;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct AutopilotPolicy {
+    pub enabled: bool,
+    // plan channels and report them without connecting to anyone or spending funds
+    pub dry_run: bool,
+    pub target_channels: u32,
+    // share of the spendable on-chain balance each run may put into channels
+    pub allocation_percent: u8,
+    pub min_channel_size_sats: u64,
+    pub max_channel_size_sats: u64,
+    pub max_channels_per_run: u32,
+    // candidates need at least this many announced channels to be considered
+    pub min_peer_channels: u32,
+    pub public_channels: bool,
+    pub interval_secs: u64,
+}
+
+impl Default for AutopilotPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dry_run: false,
+            target_channels: 5,
+            allocation_percent: 60,
+            min_channel_size_sats: 100_000,
+            max_channel_size_sats: 5_000_000,
+            max_channels_per_run: 3,
+            min_peer_channels: 5,
+            public_channels: true,
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl AutopilotPolicy {
+    // Splits the allocated funds evenly over the channels still missing, opening fewer
+    // channels when an even split would leave them below the minimum size.
+    pub fn channel_sizes(&self, existing_channels: usize, spendable_sats: u64) -> Vec<u64> {
+        let missing_channels = (self.target_channels as usize).saturating_sub(existing_channels);
+        let budget_sats = spendable_sats * self.allocation_percent.min(100) as u64 / 100;
+
+        let mut num_channels = missing_channels.min(self.max_channels_per_run as usize);
+        while num_channels > 0 {
+            let size_sats = (budget_sats / num_channels as u64).min(self.max_channel_size_sats);
+            if size_sats >= self.min_channel_size_sats {
+                return vec![size_sats; num_channels];
+            }
+            num_channels -= 1;
+        }
+        vec![]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AutopilotChannel {
+    pub counterparty_pubkey: String,
+    pub counterparty_host_port: Option<String>,
+    pub amount_sats: u64,
+    pub peer_num_channels: u64,
+    pub peer_capacity_sats: u64,
+    pub channel_id: Option<String>,
+    pub error: Option<String>,
+}
+
+struct Candidate {
+    pubkey: PublicKey,
+    num_channels: u64,
+    capacity_sats: u64,
+}
+
+pub struct Autopilot {
+    node: Arc<LightningNode>,
+}
+
+impl Autopilot {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(AUTOPILOT_TICK_SECS));
+        let mut last_run_at: Option<i64> = None;
+        loop {
+            interval.tick().await;
+
+            let policy = match self
+                .node
+                .database
+                .get_autopilot_policy(self.node.id.clone())
+                .await
+            {
+                Ok(policy) => policy,
+                Err(e) => {
+                    println!("ERROR: failed to load autopilot policy: {}", e);
+                    continue;
+                }
+            };
+
+            let now = seconds_since_epoch();
+            let due = last_run_at
+                .map(|last_run_at| {
+                    now - last_run_at >= policy.interval_secs.try_into().unwrap_or(i64::MAX)
+                })
+                .unwrap_or(true);
+            if !policy.enabled || !due {
+                continue;
+            }
+
+            last_run_at = Some(now);
+            if let Err(e) = run_autopilot(&self.node, &policy).await {
+                println!("ERROR: autopilot failed: {}", e);
+            }
+        }
+    }
+}
+
+// Ranks the nodes in the local network graph we don't have a channel with yet,
+// best connected first.
+fn rank_candidates(node: &LightningNode, policy: &AutopilotPolicy) -> Vec<Candidate> {
+    let mut excluded = node
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .map(|channel| channel.counterparty.node_id)
+        .collect::<HashSet<_>>();
+    excluded.insert(node.channel_manager.get_our_node_id());
+
+    let graph = node.p2p.network_graph.read_only();
+    let mut candidates = graph
+        .nodes()
+        .iter()
+        .filter(|(_node_id, node_info)| {
+            node_info.channels.len() >= policy.min_peer_channels as usize
+                && node_info
+                    .announcement_info
+                    .as_ref()
+                    .map(|info| !info.addresses.is_empty())
+                    .unwrap_or(false)
+        })
+        .filter_map(|(node_id, node_info)| {
+            let pubkey = PublicKey::from_slice(node_id.as_slice()).ok()?;
+            if excluded.contains(&pubkey) {
+                return None;
+            }
+            let capacity_sats = node_info
+                .channels
+                .iter()
+                .filter_map(|scid| graph.channels().get(scid))
+                .filter_map(|channel| channel.capacity_sats)
+                .sum();
+            Some(Candidate {
+                pubkey,
+                num_channels: node_info.channels.len() as u64,
+                capacity_sats,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|candidate| {
+        std::cmp::Reverse((candidate.num_channels, candidate.capacity_sats))
+    });
+    candidates
+}
+
+// Connects to the first address of the candidate that works and returns it as host:port.
+async fn connect_candidate(node: &LightningNode, pubkey: &PublicKey) -> Option<String> {
+    let addresses = node
+        .p2p
+        .peer_connector
+        .get_addresses_for_pubkey(&node.id, pubkey)
+        .await
+        .ok()?;
+
+    for address in addresses {
+        let socket_addr = match net_address_to_socket_addr(address.clone()) {
+            Some(socket_addr) => socket_addr,
+            None => continue,
+        };
+        if node
+            .p2p
+            .peer_connector
+            .connect_peer_if_necessary(&node.id, *pubkey, address, node.peer_manager.clone())
+            .await
+            .is_ok()
+        {
+            return Some(socket_addr.to_string());
+        }
+    }
+    None
+}
+
+// Opens the channels the policy asks for and reports what it did, or with `dry_run`
+// what it would have done.
+pub async fn run_autopilot(
+    node: &LightningNode,
+    policy: &AutopilotPolicy,
+) -> Result<Vec<AutopilotChannel>, Error> {
+    let spendable_sats = {
+        let wallet = node.wallet.lock().unwrap();
+        wallet.get_balance().map_err(Error::Bdk)?.get_spendable()
+    };
+    let existing_channels = node.channel_manager.list_channels().len();
+    let channel_sizes = policy.channel_sizes(existing_channels, spendable_sats);
+    if channel_sizes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let candidates = rank_candidates(node, policy);
+    let mut channels = vec![];
+    if policy.dry_run {
+        for (candidate, amount_sats) in candidates.iter().zip(channel_sizes) {
+            channels.push(AutopilotChannel {
+                counterparty_pubkey: candidate.pubkey.to_string(),
+                counterparty_host_port: None,
+                amount_sats,
+                peer_num_channels: candidate.num_channels,
+                peer_capacity_sats: candidate.capacity_sats,
+                channel_id: None,
+                error: None,
+            });
+        }
+    } else {
+        let mut channel_sizes = channel_sizes.into_iter().peekable();
+        for candidate in candidates
+            .iter()
+            .take(channel_sizes.len() * CANDIDATES_PER_CHANNEL)
+        {
+            let amount_sats = match channel_sizes.peek() {
+                Some(amount_sats) => *amount_sats,
+                None => break,
+            };
+            if let Some(host_port) = connect_candidate(node, &candidate.pubkey).await {
+                channel_sizes.next();
+                channels.push(AutopilotChannel {
+                    counterparty_pubkey: candidate.pubkey.to_string(),
+                    counterparty_host_port: Some(host_port),
+                    amount_sats,
+                    peer_num_channels: candidate.num_channels,
+                    peer_capacity_sats: candidate.capacity_sats,
+                    channel_id: None,
+                    error: None,
+                });
+            }
+        }
+
+        if !channels.is_empty() {
+            let requests = channels
+                .iter()
+                .map(|channel| OpenChannelRequest {
+                    counterparty_pubkey: channel.counterparty_pubkey.clone(),
+                    amount_sats: channel.amount_sats,
+                    public: policy.public_channels,
+                    scid_alias: None,
+                    upfront_shutdown_script: None,
+                    custom_id: None,
+                    push_amount_msats: None,
+                    counterparty_host_port: channel.counterparty_host_port.clone(),
+                    forwarding_fee_proportional_millionths: None,
+                    forwarding_fee_base_msat: None,
+                    cltv_expiry_delta: None,
+                    max_dust_htlc_exposure_msat: None,
+                    force_close_avoidance_max_fee_satoshis: None,
+                })
+                .collect::<Vec<_>>();
+
+            let results = node.open_channels(requests).await?;
+            for (channel, (_request, result)) in channels.iter_mut().zip(results) {
+                match result {
+                    Ok(channel_id) => channel.channel_id = Some(hex_utils::hex_str(&channel_id)),
+                    Err(e) => channel.error = Some(e.to_string()),
+                }
+            }
+        }
+    }
+
+    for channel in channels.iter() {
+        println!(
+            "autopilot{}: {} sat channel to {} ({} channels, {} sats capacity){}",
+            if policy.dry_run { " (dry run)" } else { "" },
+            channel.amount_sats,
+            channel.counterparty_pubkey,
+            channel.peer_num_channels,
+            channel.peer_capacity_sats,
+            channel
+                .error
+                .as_ref()
+                .map(|e| format!(" failed: {}", e))
+                .unwrap_or_default()
+        );
+    }
+
+    let _res = node.event_sender.send(SenseiEvent::AutopilotRan {
+        node_id: node.id.clone(),
+        dry_run: policy.dry_run,
+        channels: channels.clone(),
+    });
+
+    Ok(channels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn autopilot_channel_sizes_test() {
+        let policy = AutopilotPolicy {
+            target_channels: 4,
+            allocation_percent: 50,
+            min_channel_size_sats: 100_000,
+            max_channel_size_sats: 1_000_000,
+            max_channels_per_run: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.channel_sizes(0, 1_200_000), vec![200_000; 3]);
+        assert_eq!(policy.channel_sizes(0, 300_000), vec![150_000]);
+        assert_eq!(policy.channel_sizes(0, 100_000_000), vec![1_000_000; 3]);
+        assert_eq!(policy.channel_sizes(3, 1_200_000), vec![600_000]);
+        assert!(policy.channel_sizes(4, 1_200_000).is_empty());
+        assert!(policy.channel_sizes(0, 150_000).is_empty());
+    }
+}
@@ -0,0 +1,504 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::chain::broadcaster::SenseiBroadcaster;
+use crate::chain::database::WalletDatabase;
+use crate::chain::fee_estimator::SenseiFeeEstimator;
+use crate::database::SenseiDatabase;
+use crate::error::Error;
+use crate::event_handler::sweep_spendable_outputs;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::keys::SenseiKeysManager;
+use crate::monitor_info::MonitorInfo;
+use crate::node::LightningNode;
+use crate::p2p::utils::{net_address_to_socket_addr, parse_peer_addr, parse_pubkey};
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::{BlockHeader, Script, WPubkeyHash};
+use entity::seconds_since_epoch;
+use lightning::chain::keysinterface::{
+    BaseSign, SpendableOutputDescriptor, StaticPaymentOutputDescriptor,
+};
+use lightning::chain::transaction::{OutPoint, TransactionData};
+use lightning::chain::Listen;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tindercrypt::cryptors::RingCryptor;
+use tokio::sync::broadcast::error::RecvError;
+
+// Inbound channels don't emit an event we can refresh on, so the channel list is
+// also checked on this interval.
+const CHANNEL_BACKUP_CHECK_SECS: u64 = 30;
+
+const CHANNEL_BACKUP_KEY: &str = "channel_backup";
+
+const RECOVERED_CHANNEL_KEY_PREFIX: &str = "recovered_channels/";
+
+// Everything needed to find our channels again after losing the node's channel state.
+// It holds no channel state, so it can only be used to ask the peers to force-close and
+// to sweep what their commitments pay us.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct StaticChannelBackup {
+    pub node_pubkey: String,
+    pub created_at: i64,
+    pub channels: Vec<ChannelBackup>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChannelBackup {
+    pub channel_id: String,
+    pub counterparty_pubkey: String,
+    pub counterparty_addresses: Vec<String>,
+    pub funding_txid: String,
+    pub funding_output_index: u16,
+    pub channel_value_satoshis: u64,
+    pub channel_keys_id: String,
+}
+
+impl ChannelBackup {
+    // Channels are always static_remotekey, so our output on the counterparty's
+    // commitment pays straight to our payment basepoint.
+    fn to_remote_output(
+        &self,
+        keys_manager: &SenseiKeysManager,
+    ) -> Option<(Script, RecoveredOutput)> {
+        let channel_keys_id: [u8; 32] =
+            hex_utils::to_vec(&self.channel_keys_id)?.try_into().ok()?;
+        let signer =
+            keys_manager.derive_channel_keys(self.channel_value_satoshis, &channel_keys_id);
+        let script = Script::new_v0_p2wpkh(&WPubkeyHash::hash(
+            &signer.pubkeys().payment_point.serialize(),
+        ));
+        Some((
+            script,
+            RecoveredOutput {
+                channel_id: self.channel_id.clone(),
+                channel_keys_id,
+                channel_value_satoshis: self.channel_value_satoshis,
+            },
+        ))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RecoveredChannel {
+    pub channel_id: String,
+    pub counterparty_pubkey: String,
+    pub connected: bool,
+}
+
+impl StaticChannelBackup {
+    // The key is derived from the node's seed so a node recreated from the same
+    // entropy can open its own backups.
+    fn encryption_key(seed: &[u8; 32]) -> [u8; 32] {
+        let mut engine = sha256::Hash::engine();
+        engine.input(b"sensei static channel backup");
+        engine.input(seed);
+        sha256::Hash::from_engine(engine).into_inner()
+    }
+
+    pub fn encrypt(&self, seed: &[u8; 32]) -> Result<Vec<u8>, tindercrypt::errors::Error> {
+        let serialized_backup = serde_json::to_vec(self).unwrap();
+        let cryptor = RingCryptor::new();
+        cryptor.seal_with_key(&Self::encryption_key(seed), &serialized_backup)
+    }
+
+    // Returns None when the backup wasn't made with this seed or isn't a backup at all.
+    pub fn decrypt(encrypted_backup: &[u8], seed: &[u8; 32]) -> Option<Self> {
+        let cryptor = RingCryptor::new();
+        let serialized_backup = cryptor
+            .open(&Self::encryption_key(seed), encrypted_backup)
+            .ok()?;
+        serde_json::from_slice(&serialized_backup).ok()
+    }
+}
+
+impl LightningNode {
+    pub async fn build_channel_backup(&self) -> Result<StaticChannelBackup, Error> {
+        let mut channels = vec![];
+        for channel in self.channel_manager.list_channels() {
+            let funding_txo = match channel.funding_txo {
+                Some(funding_txo) => funding_txo,
+                None => continue,
+            };
+            let counterparty_addresses = self
+                .p2p
+                .peer_connector
+                .get_addresses_for_pubkey(&self.id, &channel.counterparty.node_id)
+                .await?
+                .into_iter()
+                .filter_map(net_address_to_socket_addr)
+                .map(|socket_addr| socket_addr.to_string())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+
+            let monitor = self
+                .database
+                .get_value(
+                    self.id.clone(),
+                    format!(
+                        "monitors/{}_{}",
+                        funding_txo.txid.to_hex(),
+                        funding_txo.index
+                    ),
+                )
+                .await?
+                .ok_or_else(|| {
+                    Error::Generic(format!(
+                        "no channel monitor for channel {}",
+                        hex_utils::hex_str(&channel.channel_id)
+                    ))
+                })?;
+            let monitor_info = MonitorInfo::read(&monitor.v)
+                .map_err(|e| Error::Generic(format!("failed to read channel monitor: {:?}", e)))?;
+
+            channels.push(ChannelBackup {
+                channel_id: hex_utils::hex_str(&channel.channel_id),
+                counterparty_pubkey: channel.counterparty.node_id.to_string(),
+                counterparty_addresses,
+                funding_txid: funding_txo.txid.to_string(),
+                funding_output_index: funding_txo.index,
+                channel_value_satoshis: channel.channel_value_satoshis,
+                channel_keys_id: hex_utils::hex_str(&monitor_info.channel_keys_id),
+            });
+        }
+
+        Ok(StaticChannelBackup {
+            node_pubkey: self.channel_manager.get_our_node_id().to_string(),
+            created_at: seconds_since_epoch(),
+            channels,
+        })
+    }
+
+    // Rebuilds the encrypted backup, stores it and pushes it to the backup url when one
+    // is configured. Returns the encrypted backup.
+    pub async fn refresh_channel_backup(&self) -> Result<Vec<u8>, Error> {
+        let backup = self.build_channel_backup().await?;
+        let encrypted_backup = backup.encrypt(&self.seed)?;
+        self.database
+            .set_value(
+                self.id.clone(),
+                String::from(CHANNEL_BACKUP_KEY),
+                encrypted_backup.clone(),
+            )
+            .await?;
+
+        if let Some(url) = self.config.channel_backup_url.as_ref() {
+            let mut body: HashMap<String, String> = HashMap::new();
+            body.insert(String::from("node_id"), self.id.clone());
+            body.insert(String::from("node_pubkey"), backup.node_pubkey.clone());
+            body.insert(String::from("backup"), base64::encode(&encrypted_backup));
+
+            let mut request = reqwest::Client::new().post(url).json(&body);
+            if let Some(token) = self.config.channel_backup_token.as_ref() {
+                request = request.bearer_auth(token);
+            }
+            if let Err(e) = request.send().await.and_then(|res| res.error_for_status()) {
+                println!("ERROR: failed to push channel backup: {}", e);
+            }
+        }
+
+        Ok(encrypted_backup)
+    }
+
+    // Reconnects to the peers of every channel in the backup we no longer know about.
+    // When they try to reestablish a channel we have no state for we answer with an
+    // error, which makes them force-close it and put our balance back on-chain, where
+    // the recovered output watcher sweeps it into the wallet.
+    pub async fn recover_from_backup(
+        &self,
+        encrypted_backup: &[u8],
+    ) -> Result<Vec<RecoveredChannel>, Error> {
+        let backup = StaticChannelBackup::decrypt(encrypted_backup, &self.seed)
+            .ok_or_else(|| Error::Generic(String::from("failed to decrypt channel backup")))?;
+        let our_pubkey = self.channel_manager.get_our_node_id().to_string();
+        if backup.node_pubkey != our_pubkey {
+            return Err(Error::Generic(String::from(
+                "channel backup belongs to a different node",
+            )));
+        }
+
+        let known_channel_ids = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .map(|channel| hex_utils::hex_str(&channel.channel_id))
+            .collect::<HashSet<_>>();
+
+        let mut recovered_channels = vec![];
+        let mut reconnected_peers = HashSet::new();
+        for channel in backup.channels {
+            if known_channel_ids.contains(&channel.channel_id) {
+                continue;
+            }
+            self.recovered_outputs.watch(&channel).await?;
+
+            let pubkey = parse_pubkey(&channel.counterparty_pubkey)?;
+            let mut connected = reconnected_peers.contains(&pubkey);
+            if !connected {
+                // the peer only sends channel_reestablish on a fresh connection
+                self.peer_manager.disconnect_by_node_id(pubkey, false);
+                for address in channel.counterparty_addresses.iter() {
+                    let peer_addr = match parse_peer_addr(address).await {
+                        Ok(peer_addr) => peer_addr,
+                        Err(_) => continue,
+                    };
+                    if self
+                        .p2p
+                        .peer_connector
+                        .connect_peer_if_necessary(
+                            &self.id,
+                            pubkey,
+                            peer_addr,
+                            self.peer_manager.clone(),
+                        )
+                        .await
+                        .is_ok()
+                    {
+                        connected = true;
+                        reconnected_peers.insert(pubkey);
+                        break;
+                    }
+                }
+            }
+
+            println!(
+                "recovering channel {} with {}: {}",
+                channel.channel_id,
+                channel.counterparty_pubkey,
+                if connected {
+                    "reconnected"
+                } else {
+                    "peer unreachable"
+                }
+            );
+            recovered_channels.push(RecoveredChannel {
+                channel_id: channel.channel_id,
+                counterparty_pubkey: channel.counterparty_pubkey,
+                connected,
+            });
+        }
+
+        Ok(recovered_channels)
+    }
+}
+
+struct RecoveredOutput {
+    channel_id: String,
+    channel_keys_id: [u8; 32],
+    channel_value_satoshis: u64,
+}
+
+// Channels recovered from a backup have no monitor, so nothing else notices when the
+// peer's commitment pays us. Watches for their to_remote scripts and sweeps the outputs.
+pub struct RecoveredOutputWatcher {
+    node_id: String,
+    database: Arc<SenseiDatabase>,
+    wallet: Arc<Mutex<bdk::Wallet<WalletDatabase>>>,
+    keys_manager: Arc<SenseiKeysManager>,
+    fee_estimator: Arc<SenseiFeeEstimator>,
+    broadcaster: Arc<SenseiBroadcaster>,
+    watched: Mutex<HashMap<Script, RecoveredOutput>>,
+}
+
+impl RecoveredOutputWatcher {
+    pub async fn new(
+        node_id: String,
+        database: Arc<SenseiDatabase>,
+        wallet: Arc<Mutex<bdk::Wallet<WalletDatabase>>>,
+        keys_manager: Arc<SenseiKeysManager>,
+        fee_estimator: Arc<SenseiFeeEstimator>,
+        broadcaster: Arc<SenseiBroadcaster>,
+    ) -> Result<Self, Error> {
+        let watcher = Self {
+            node_id: node_id.clone(),
+            database: database.clone(),
+            wallet,
+            keys_manager,
+            fee_estimator,
+            broadcaster,
+            watched: Mutex::new(HashMap::new()),
+        };
+        for entry in database
+            .list_values(node_id, String::from(RECOVERED_CHANNEL_KEY_PREFIX))
+            .await?
+        {
+            let to_remote_output = serde_json::from_slice::<ChannelBackup>(&entry.v)
+                .ok()
+                .and_then(|channel| channel.to_remote_output(&watcher.keys_manager));
+            match to_remote_output {
+                Some((script, output)) => {
+                    watcher.watched.lock().unwrap().insert(script, output);
+                }
+                None => println!("ERROR: failed to read recovered channel {}", entry.k),
+            }
+        }
+        Ok(watcher)
+    }
+
+    pub async fn watch(&self, channel: &ChannelBackup) -> Result<(), Error> {
+        let (script, output) = channel
+            .to_remote_output(&self.keys_manager)
+            .ok_or_else(|| Error::Generic(String::from("invalid channel keys id in backup")))?;
+        self.database
+            .set_value(
+                self.node_id.clone(),
+                format!("{}{}", RECOVERED_CHANNEL_KEY_PREFIX, channel.channel_id),
+                serde_json::to_vec(channel).unwrap(),
+            )
+            .await?;
+        self.watched.lock().unwrap().insert(script, output);
+        Ok(())
+    }
+
+    pub fn watched_count(&self) -> usize {
+        self.watched.lock().unwrap().len()
+    }
+}
+
+impl Listen for RecoveredOutputWatcher {
+    fn filtered_block_connected(
+        &self,
+        _header: &BlockHeader,
+        txdata: &TransactionData,
+        _height: u32,
+    ) {
+        let mut descriptors = vec![];
+        {
+            let mut watched = self.watched.lock().unwrap();
+            for (_, tx) in txdata.iter() {
+                for (index, output) in tx.output.iter().enumerate() {
+                    let recovered = match watched.remove(&output.script_pubkey) {
+                        Some(recovered) => recovered,
+                        None => continue,
+                    };
+                    println!(
+                        "found {} sats from recovered channel {} in {}",
+                        output.value,
+                        recovered.channel_id,
+                        tx.txid()
+                    );
+                    if let Err(e) = self.database.delete_value_sync(
+                        self.node_id.clone(),
+                        format!("{}{}", RECOVERED_CHANNEL_KEY_PREFIX, recovered.channel_id),
+                    ) {
+                        println!("ERROR: failed to forget recovered channel: {}", e);
+                    }
+                    descriptors.push(SpendableOutputDescriptor::StaticPaymentOutput(
+                        StaticPaymentOutputDescriptor {
+                            outpoint: OutPoint {
+                                txid: tx.txid(),
+                                index: index as u16,
+                            },
+                            output: output.clone(),
+                            channel_keys_id: recovered.channel_keys_id,
+                            channel_value_satoshis: recovered.channel_value_satoshis,
+                        },
+                    ));
+                }
+            }
+        }
+
+        if !descriptors.is_empty() {
+            sweep_spendable_outputs(
+                &self.wallet,
+                &self.keys_manager,
+                &self.fee_estimator,
+                &self.broadcaster,
+                &descriptors,
+            );
+        }
+    }
+
+    fn block_disconnected(&self, _header: &BlockHeader, _height: u32) {}
+}
+
+// Keeps the stored backup in line with the node's channels.
+pub struct ChannelBackupManager {
+    node: Arc<LightningNode>,
+}
+
+impl ChannelBackupManager {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    fn backed_up_channels(&self) -> HashSet<[u8; 32]> {
+        self.node
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .filter(|channel| channel.funding_txo.is_some())
+            .map(|channel| channel.channel_id)
+            .collect()
+    }
+
+    pub async fn run(self) {
+        let mut event_receiver = self.node.event_sender.subscribe();
+        let mut interval = tokio::time::interval(Duration::from_secs(CHANNEL_BACKUP_CHECK_SECS));
+        let mut last_backed_up: Option<HashSet<[u8; 32]>> = None;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                event = event_receiver.recv() => match event {
+                    Ok(SenseiEvent::ChannelClosed { node_id, .. })
+                    | Ok(SenseiEvent::TransactionBroadcast { node_id, .. })
+                        if node_id == self.node.id => {}
+                    Err(RecvError::Closed) => return,
+                    _ => continue,
+                },
+            }
+
+            let channels = self.backed_up_channels();
+            if last_backed_up.as_ref() == Some(&channels) {
+                continue;
+            }
+            match self.node.refresh_channel_backup().await {
+                Ok(_) => last_backed_up = Some(channels),
+                Err(e) => println!("ERROR: failed to refresh channel backup: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn static_channel_backup_encryption_test() {
+        let backup = StaticChannelBackup {
+            node_pubkey: String::from("node"),
+            created_at: 1,
+            channels: vec![ChannelBackup {
+                channel_id: String::from("00"),
+                counterparty_pubkey: String::from("peer"),
+                counterparty_addresses: vec![String::from("127.0.0.1:9735")],
+                funding_txid: String::from("txid"),
+                funding_output_index: 0,
+                channel_value_satoshis: 100_000,
+                channel_keys_id: hex_utils::hex_str(&[3; 32]),
+            }],
+        };
+
+        let encrypted_backup = backup.encrypt(&[1; 32]).unwrap();
+        assert_eq!(
+            StaticChannelBackup::decrypt(&encrypted_backup, &[1; 32]),
+            Some(backup)
+        );
+        assert_eq!(
+            StaticChannelBackup::decrypt(&encrypted_backup, &[2; 32]),
+            None
+        );
+    }
+}
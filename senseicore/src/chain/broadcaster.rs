@@ -3,11 +3,15 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use crate::database::SenseiDatabase;
 use crate::events::SenseiEvent;
+use crate::hex_utils;
 
 use super::database::WalletDatabase;
 use bitcoin::{Transaction, Txid};
+use entity::sea_orm::ActiveValue;
 use lightning::chain::chaininterface::BroadcasterInterface;
+use lightning::chain::transaction::OutPoint;
 use tokio::sync::broadcast;
 
 pub struct SenseiBroadcaster {
@@ -16,6 +20,7 @@ pub struct SenseiBroadcaster {
     pub broadcaster: Arc<dyn BroadcasterInterface + Send + Sync>,
     pub wallet_database: Arc<Mutex<WalletDatabase>>,
     pub event_sender: broadcast::Sender<SenseiEvent>,
+    pub database: Arc<SenseiDatabase>,
 }
 
 impl SenseiBroadcaster {
@@ -24,12 +29,14 @@ impl SenseiBroadcaster {
         broadcaster: Arc<dyn BroadcasterInterface + Send + Sync>,
         wallet_database: Arc<Mutex<WalletDatabase>>,
         event_sender: broadcast::Sender<SenseiEvent>,
+        database: Arc<SenseiDatabase>,
     ) -> Self {
         Self {
             node_id,
             broadcaster,
             wallet_database,
             event_sender,
+            database,
             debounce: Mutex::new(HashMap::new()),
         }
     }
@@ -41,6 +48,7 @@ impl SenseiBroadcaster {
 
     pub fn broadcast(&self, tx: &Transaction) {
         self.broadcaster.broadcast_transaction(tx);
+        self.record_closing_tx(tx);
 
         // TODO: there's a bug here if the broadcast fails
         //       best solution is to probably setup a zmq listener
@@ -54,6 +62,39 @@ impl SenseiBroadcaster {
             })
             .unwrap_or_default();
     }
+
+    // A transaction spending a channel's funding output is what closes the channel,
+    // whether it's a cooperative close or one of our commitment transactions.
+    fn record_closing_tx(&self, tx: &Transaction) {
+        for input in &tx.input {
+            let index = match u16::try_from(input.previous_output.vout) {
+                Ok(index) => index,
+                Err(_) => continue,
+            };
+            let channel_id = OutPoint {
+                txid: input.previous_output.txid,
+                index,
+            }
+            .to_channel_id();
+
+            let channel = match self
+                .database
+                .find_channel_sync(self.node_id.clone(), hex_utils::hex_str(&channel_id))
+            {
+                Ok(Some(channel)) => channel,
+                _ => continue,
+            };
+            if channel.closing_txid.is_some() {
+                continue;
+            }
+
+            let mut channel: entity::channel::ActiveModel = channel.into();
+            channel.closing_txid = ActiveValue::Set(Some(tx.txid().to_string()));
+            if let Err(e) = self.database.update_channel_sync(channel) {
+                println!("failed to record closing transaction: {:?}", e);
+            }
+        }
+    }
 }
 
 impl BroadcasterInterface for SenseiBroadcaster {
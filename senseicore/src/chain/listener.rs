@@ -3,17 +3,25 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use crate::backup::RecoveredOutputWatcher;
 use crate::node::{ChainMonitor, ChannelManager};
+use crate::watchtower::WatchtowerServer;
 use bitcoin::BlockHeader;
 use lightning::chain::transaction::TransactionData;
 use lightning::chain::Listen;
 
 use super::database::WalletDatabase;
 
-type Listener = (Arc<ChainMonitor>, Arc<ChannelManager>, WalletDatabase);
+type Listener = (
+    Arc<ChainMonitor>,
+    Arc<ChannelManager>,
+    WalletDatabase,
+    Arc<RecoveredOutputWatcher>,
+);
 
 pub struct SenseiChainListener {
     listeners: Mutex<HashMap<String, Listener>>,
+    watchtower: Mutex<Option<Arc<WatchtowerServer>>>,
 }
 
 impl Default for SenseiChainListener {
@@ -26,6 +34,7 @@ impl SenseiChainListener {
     pub fn new() -> Self {
         Self {
             listeners: Mutex::new(HashMap::new()),
+            watchtower: Mutex::new(None),
         }
     }
 
@@ -42,6 +51,10 @@ impl SenseiChainListener {
         let mut listeners = self.listeners.lock().unwrap();
         listeners.remove(&self.get_key(&listener));
     }
+
+    pub fn set_watchtower(&self, watchtower: Arc<WatchtowerServer>) {
+        *self.watchtower.lock().unwrap() = Some(watchtower);
+    }
 }
 
 impl Listen for SenseiChainListener {
@@ -52,19 +65,31 @@ impl Listen for SenseiChainListener {
         height: u32,
     ) {
         let listeners = self.listeners.lock().unwrap();
-        for (chain_monitor, channel_manager, wallet_database) in listeners.values() {
+        for (chain_monitor, channel_manager, wallet_database, recovered_outputs) in
+            listeners.values()
+        {
             channel_manager.filtered_block_connected(header, txdata, height);
             chain_monitor.filtered_block_connected(header, txdata, height);
             wallet_database.filtered_block_connected(header, txdata, height);
+            recovered_outputs.filtered_block_connected(header, txdata, height);
+        }
+        if let Some(watchtower) = self.watchtower.lock().unwrap().as_ref() {
+            watchtower.filtered_block_connected(header, txdata, height);
         }
     }
 
     fn block_disconnected(&self, header: &BlockHeader, height: u32) {
         let listeners = self.listeners.lock().unwrap();
-        for (chain_monitor, channel_manager, wallet_database) in listeners.values() {
+        for (chain_monitor, channel_manager, wallet_database, recovered_outputs) in
+            listeners.values()
+        {
             channel_manager.block_disconnected(header, height);
             chain_monitor.block_disconnected(header, height);
             wallet_database.block_disconnected(header, height);
+            recovered_outputs.block_disconnected(header, height);
+        }
+        if let Some(watchtower) = self.watchtower.lock().unwrap().as_ref() {
+            watchtower.block_disconnected(header, height);
         }
     }
 }
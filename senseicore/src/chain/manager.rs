@@ -7,6 +7,7 @@ use std::{
 };
 
 use crate::{
+    backup::RecoveredOutputWatcher,
     config::SenseiConfig,
     node::{ChainMonitor, ChannelManager},
 };
@@ -121,6 +122,7 @@ impl SenseiChainManager {
         channel_manager: Arc<ChannelManager>,
         chain_monitor: Arc<ChainMonitor>,
         wallet_database: WalletDatabase,
+        recovered_outputs: Arc<RecoveredOutputWatcher>,
     ) -> Result<(), crate::error::Error> {
         let listeners = vec![
             (
@@ -132,13 +134,21 @@ impl SenseiChainManager {
                 chain_monitor.deref() as &(dyn Listen + Send + Sync),
             ),
             (synced_hash, &wallet_database as &(dyn Listen + Send + Sync)),
+            (
+                synced_hash,
+                recovered_outputs.deref() as &(dyn Listen + Send + Sync),
+            ),
         ];
 
         self.poller_paused.store(true, Ordering::Relaxed);
         // could skip this if synced_hash === current_tip
         let _new_tip = self.synchronize_to_tip(listeners).await.unwrap();
-        self.listener
-            .add_listener((chain_monitor, channel_manager, wallet_database));
+        self.listener.add_listener((
+            chain_monitor,
+            channel_manager,
+            wallet_database,
+            recovered_outputs,
+        ));
         self.poller_paused.store(false, Ordering::Relaxed);
         Ok(())
     }
@@ -0,0 +1,268 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelAcceptanceDecision {
+    Accept,
+    AcceptZeroConf,
+    Reject,
+}
+
+impl std::fmt::Display for ChannelAcceptanceDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            ChannelAcceptanceDecision::Accept => "accept",
+            ChannelAcceptanceDecision::AcceptZeroConf => "accept_zero_conf",
+            ChannelAcceptanceDecision::Reject => "reject",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl std::str::FromStr for ChannelAcceptanceDecision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accept" => Ok(ChannelAcceptanceDecision::Accept),
+            "accept_zero_conf" => Ok(ChannelAcceptanceDecision::AcceptZeroConf),
+            "reject" => Ok(ChannelAcceptanceDecision::Reject),
+            _ => Err(format!("unknown channel acceptance decision {}", s)),
+        }
+    }
+}
+
+// Decides which inbound channels a node accepts. `min_confirmations` is applied to the
+// channel manager when the node starts and can't be changed while it runs, the rest is
+// checked on every open channel request.
+// Requests that pass the static checks are sent to `webhook_url` when one is set, and
+// `webhook_fallback` is used when it fails to answer within `webhook_timeout_secs`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct ChannelAcceptancePolicy {
+    pub min_channel_size_sats: Option<u64>,
+    pub max_channel_size_sats: Option<u64>,
+    pub min_confirmations: Option<u32>,
+    // when not empty only these peers can open channels to us
+    pub allowed_peers: Vec<String>,
+    pub denied_peers: Vec<String>,
+    pub max_pending_channels: Option<u32>,
+    pub allow_private_channels: bool,
+    pub webhook_url: Option<String>,
+    pub webhook_timeout_secs: u64,
+    pub webhook_fallback: ChannelAcceptanceDecision,
+}
+
+impl Default for ChannelAcceptancePolicy {
+    fn default() -> Self {
+        Self {
+            min_channel_size_sats: None,
+            max_channel_size_sats: None,
+            min_confirmations: None,
+            allowed_peers: vec![],
+            denied_peers: vec![],
+            max_pending_channels: None,
+            allow_private_channels: true,
+            webhook_url: None,
+            webhook_timeout_secs: DEFAULT_WEBHOOK_TIMEOUT_SECS,
+            webhook_fallback: ChannelAcceptanceDecision::Reject,
+        }
+    }
+}
+
+pub struct InboundChannelRequest<'a> {
+    pub counterparty_node_id: &'a str,
+    pub funding_satoshis: u64,
+    pub is_public: bool,
+    pub pending_channels: usize,
+}
+
+impl ChannelAcceptancePolicy {
+    // Returns why the channel should be rejected, if it should be.
+    pub fn rejection_reason(&self, request: &InboundChannelRequest) -> Option<String> {
+        let peer = request.counterparty_node_id;
+        if self.denied_peers.iter().any(|denied| denied == peer) {
+            return Some(String::from("peer is denied"));
+        }
+        if !self.allowed_peers.is_empty()
+            && !self.allowed_peers.iter().any(|allowed| allowed == peer)
+        {
+            return Some(String::from("peer is not allowed"));
+        }
+        if let Some(min_channel_size_sats) = self.min_channel_size_sats {
+            if request.funding_satoshis < min_channel_size_sats {
+                return Some(format!(
+                    "channel size {} is below the minimum of {} sats",
+                    request.funding_satoshis, min_channel_size_sats
+                ));
+            }
+        }
+        if let Some(max_channel_size_sats) = self.max_channel_size_sats {
+            if request.funding_satoshis > max_channel_size_sats {
+                return Some(format!(
+                    "channel size {} is above the maximum of {} sats",
+                    request.funding_satoshis, max_channel_size_sats
+                ));
+            }
+        }
+        if !self.allow_private_channels && !request.is_public {
+            return Some(String::from("private channels are not allowed"));
+        }
+        if let Some(max_pending_channels) = self.max_pending_channels {
+            if request.pending_channels >= max_pending_channels as usize {
+                return Some(format!(
+                    "already {} pending inbound channels",
+                    request.pending_channels
+                ));
+            }
+        }
+        None
+    }
+}
+
+// The body posted to the acceptance webhook.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ChannelAcceptanceWebhookRequest {
+    pub node_id: String,
+    pub temporary_channel_id: String,
+    pub counterparty_node_id: String,
+    pub funding_satoshis: u64,
+    pub push_msat: u64,
+    pub channel_type: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ChannelAcceptanceWebhookResponse {
+    pub decision: ChannelAcceptanceDecision,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl ChannelAcceptancePolicy {
+    // Asks the webhook what to do with the channel, falling back to `webhook_fallback`
+    // when there is no webhook or it errors, times out or answers with something we can't read.
+    pub async fn webhook_decision(
+        &self,
+        request: &ChannelAcceptanceWebhookRequest,
+    ) -> ChannelAcceptanceWebhookResponse {
+        let url = match &self.webhook_url {
+            Some(url) => url,
+            None => {
+                return ChannelAcceptanceWebhookResponse {
+                    decision: self.webhook_fallback,
+                    reason: None,
+                }
+            }
+        };
+
+        let response = async {
+            reqwest::Client::builder()
+                .timeout(Duration::from_secs(self.webhook_timeout_secs))
+                .build()?
+                .post(url)
+                .json(request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ChannelAcceptanceWebhookResponse>()
+                .await
+        }
+        .await;
+
+        match response {
+            Ok(response) => response,
+            Err(e) => ChannelAcceptanceWebhookResponse {
+                decision: self.webhook_fallback,
+                reason: Some(format!("channel acceptance webhook failed: {}", e)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Stands in for a channel acceptance webhook, answering every request with `body`
+    // or never answering at all when there is none.
+    async fn serve_webhook(body: Option<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let _res = socket.read(&mut buf).await;
+                match body {
+                    Some(body) => {
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        let _res = socket.write_all(response.as_bytes()).await;
+                    }
+                    None => tokio::time::sleep(Duration::from_secs(60)).await,
+                }
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn channel_acceptance_webhook_test() {
+        let request = ChannelAcceptanceWebhookRequest {
+            node_id: String::from("node"),
+            temporary_channel_id: String::from("00"),
+            counterparty_node_id: String::from("peer"),
+            funding_satoshis: 100_000,
+            push_msat: 0,
+            channel_type: vec![String::from("static_remote_key")],
+        };
+
+        let cases = [
+            (
+                Some(r#"{"decision":"accept"}"#),
+                ChannelAcceptanceDecision::Accept,
+            ),
+            (
+                Some(r#"{"decision":"accept_zero_conf"}"#),
+                ChannelAcceptanceDecision::AcceptZeroConf,
+            ),
+            (
+                Some(r#"{"decision":"reject","reason":"no thanks"}"#),
+                ChannelAcceptanceDecision::Reject,
+            ),
+            (Some("not json"), ChannelAcceptanceDecision::AcceptZeroConf),
+            (None, ChannelAcceptanceDecision::AcceptZeroConf),
+        ];
+
+        for (body, expected) in cases {
+            let policy = ChannelAcceptancePolicy {
+                webhook_url: Some(serve_webhook(body).await),
+                webhook_timeout_secs: 1,
+                webhook_fallback: ChannelAcceptanceDecision::AcceptZeroConf,
+                ..Default::default()
+            };
+            let response = policy.webhook_decision(&request).await;
+            assert_eq!(response.decision, expected);
+            if expected == ChannelAcceptanceDecision::Reject {
+                assert_eq!(response.reason, Some(String::from("no thanks")));
+            }
+        }
+    }
+}
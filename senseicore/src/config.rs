@@ -45,6 +45,13 @@ pub struct SenseiConfig {
     pub region: Option<String>,
     pub poll_for_chain_updates: bool,
     pub rapid_gossip_sync_server_host: Option<String>,
+    pub balance_snapshot_interval_secs: u64,
+    pub balance_snapshot_retention_secs: u64,
+    pub channel_backup_url: Option<String>,
+    pub channel_backup_token: Option<String>,
+    pub watchtower_server: bool,
+    pub remote_watchtower_host: Option<String>,
+    pub remote_watchtower_token: Option<String>,
 }
 
 impl Default for SenseiConfig {
@@ -74,6 +81,13 @@ impl Default for SenseiConfig {
             region: None,
             poll_for_chain_updates: true,
             rapid_gossip_sync_server_host: None,
+            balance_snapshot_interval_secs: 900,
+            balance_snapshot_retention_secs: 7_776_000,
+            channel_backup_url: None,
+            channel_backup_token: None,
+            watchtower_server: false,
+            remote_watchtower_host: None,
+            remote_watchtower_token: None,
         }
     }
 }
@@ -146,4 +160,14 @@ impl SenseiConfig {
     pub fn rapid_gossip_sync_configured(&self) -> bool {
         self.rapid_gossip_sync_server_host.is_some()
     }
+
+    pub fn remote_watchtower_configured(&self) -> bool {
+        self.remote_watchtower_host.is_some() && self.remote_watchtower_token.is_some()
+    }
+
+    // Nodes push their monitor updates to a remote tower when one is configured and
+    // to this instance's own tower otherwise.
+    pub fn watchtower_client_configured(&self) -> bool {
+        self.remote_watchtower_configured() || self.watchtower_server
+    }
 }
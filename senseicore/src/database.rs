@@ -1,5 +1,13 @@
+use crate::autopilot::AutopilotPolicy;
+use crate::channel_acceptance::ChannelAcceptancePolicy;
 use crate::error::Error;
+use crate::fee_manager::FeePolicy;
+use crate::health::HealthPolicy;
 use crate::hex_utils;
+use crate::jit_channels::{JitChannel, JitChannelPolicy, JitChannelState};
+use crate::node::{ChannelEventKind, ChannelState, HTLCStatus, InvoiceStatus, PaymentOrigin};
+use crate::services::ForwardedPaymentsFilter;
+use crate::services::InvoicesFilter;
 use crate::services::PaginationRequest;
 use crate::services::PaginationResponse;
 use crate::services::PaymentsFilter;
@@ -8,22 +16,38 @@ use bdk::BlockTime;
 use bitcoin::BlockHash;
 use entity::access_token;
 use entity::access_token::Entity as AccessToken;
+use entity::balance_snapshot;
+use entity::balance_snapshot::Entity as BalanceSnapshot;
+use entity::channel;
+use entity::channel::Entity as Channel;
+use entity::channel_event;
+use entity::channel_event::Entity as ChannelEvent;
+use entity::forwarded_payment;
+use entity::forwarded_payment::Entity as ForwardedPayment;
 use entity::kv_store;
 use entity::kv_store::Entity as KVStore;
 use entity::macaroon;
 use entity::macaroon::Entity as Macaroon;
+use entity::macaroon_spend;
+use entity::macaroon_spend::Entity as MacaroonSpend;
 use entity::node;
 use entity::node::Entity as Node;
 use entity::payment;
 use entity::payment::Entity as Payment;
+use entity::payment_attempt;
+use entity::payment_attempt::Entity as PaymentAttempt;
 use entity::peer;
 use entity::peer::Entity as Peer;
 use entity::peer_address;
 use entity::peer_address::Entity as PeerAddress;
+use entity::scheduled_payment;
+use entity::scheduled_payment::Entity as ScheduledPayment;
 use entity::sea_orm;
 use entity::sea_orm::ActiveValue;
 use entity::sea_orm::QueryOrder;
 use entity::seconds_since_epoch;
+use entity::transaction;
+use entity::transaction::Entity as Transaction;
 use entity::user;
 use entity::user::Entity as User;
 use migration::Condition;
@@ -34,6 +58,7 @@ use sea_orm::entity::EntityTrait;
 use sea_orm::{prelude::*, DatabaseConnection};
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct LastSync {
@@ -304,6 +329,18 @@ impl SenseiDatabase {
             .await?)
     }
 
+    pub async fn find_payment_by_idempotency_key(
+        &self,
+        node_id: String,
+        idempotency_key: String,
+    ) -> Result<Option<payment::Model>, Error> {
+        Ok(Payment::find()
+            .filter(entity::payment::Column::NodeId.eq(node_id))
+            .filter(entity::payment::Column::IdempotencyKey.eq(idempotency_key))
+            .one(&self.connection)
+            .await?)
+    }
+
     pub async fn delete_payment(&self, node_id: String, payment_hash: String) -> Result<(), Error> {
         match self.find_payment(node_id, payment_hash).await? {
             Some(payment) => {
@@ -381,6 +418,516 @@ impl SenseiDatabase {
         ))
     }
 
+    pub async fn list_scheduled_payments(
+        &self,
+        node_id: String,
+        pagination: PaginationRequest,
+    ) -> Result<(Vec<scheduled_payment::Model>, PaginationResponse), Error> {
+        let query_string = pagination.query.unwrap_or_else(|| String::from(""));
+        let page_size: usize = pagination.take.try_into().unwrap();
+        let page: usize = pagination.page.try_into().unwrap();
+
+        let scheduled_payment_pages = ScheduledPayment::find()
+            .filter(scheduled_payment::Column::NodeId.eq(node_id))
+            .filter(
+                Condition::any()
+                    .add(scheduled_payment::Column::Id.contains(&query_string))
+                    .add(scheduled_payment::Column::DestPubkey.contains(&query_string))
+                    .add(scheduled_payment::Column::InvoiceUrl.contains(&query_string)),
+            )
+            .order_by_asc(scheduled_payment::Column::NextRunAt)
+            .paginate(&self.connection, page_size);
+
+        let scheduled_payments = scheduled_payment_pages.fetch_page(page).await?;
+        let total = scheduled_payment_pages.num_items().await?;
+        let has_more = ((page + 1) * page_size) < total;
+
+        Ok((
+            scheduled_payments,
+            PaginationResponse {
+                has_more,
+                total: total.try_into().unwrap(),
+            },
+        ))
+    }
+
+    pub async fn find_scheduled_payment(
+        &self,
+        node_id: String,
+        id: String,
+    ) -> Result<Option<scheduled_payment::Model>, Error> {
+        Ok(ScheduledPayment::find_by_id(id)
+            .filter(scheduled_payment::Column::NodeId.eq(node_id))
+            .one(&self.connection)
+            .await?)
+    }
+
+    pub async fn find_due_scheduled_payments(
+        &self,
+        node_id: String,
+        now: i64,
+    ) -> Result<Vec<scheduled_payment::Model>, Error> {
+        Ok(ScheduledPayment::find()
+            .filter(scheduled_payment::Column::NodeId.eq(node_id))
+            .filter(scheduled_payment::Column::Enabled.eq(true))
+            .filter(scheduled_payment::Column::NextRunAt.lte(now))
+            .order_by_asc(scheduled_payment::Column::NextRunAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub fn insert_forwarded_payment_sync(
+        &self,
+        forwarded_payment: forwarded_payment::ActiveModel,
+    ) -> Result<forwarded_payment::Model, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { Ok(forwarded_payment.insert(&self.connection).await?) })
+        })
+    }
+
+    fn forwarded_payments_query(
+        node_id: String,
+        filter: ForwardedPaymentsFilter,
+    ) -> Select<ForwardedPayment> {
+        let mut query =
+            ForwardedPayment::find().filter(forwarded_payment::Column::NodeId.eq(node_id));
+
+        if let Some(from_channel_id) = filter.from_channel_id {
+            query = query.filter(forwarded_payment::Column::FromChannelId.eq(from_channel_id));
+        }
+        if let Some(to_channel_id) = filter.to_channel_id {
+            query = query.filter(forwarded_payment::Column::ToChannelId.eq(to_channel_id));
+        }
+        if let Some(from_hours) = filter.from_hours_since_epoch {
+            query = query.filter(forwarded_payment::Column::HoursSinceEpoch.gte(from_hours));
+        }
+        if let Some(to_hours) = filter.to_hours_since_epoch {
+            query = query.filter(forwarded_payment::Column::HoursSinceEpoch.lte(to_hours));
+        }
+
+        query
+    }
+
+    pub async fn list_forwarded_payments(
+        &self,
+        node_id: String,
+        pagination: PaginationRequest,
+        filter: ForwardedPaymentsFilter,
+    ) -> Result<(Vec<forwarded_payment::Model>, PaginationResponse), Error> {
+        let page_size: usize = pagination.take.try_into().unwrap();
+        let page: usize = pagination.page.try_into().unwrap();
+
+        let forwarded_payment_pages = Self::forwarded_payments_query(node_id, filter)
+            .order_by_desc(forwarded_payment::Column::CreatedAt)
+            .paginate(&self.connection, page_size);
+
+        let forwarded_payments = forwarded_payment_pages.fetch_page(page).await?;
+        let total = forwarded_payment_pages.num_items().await?;
+        let has_more = ((page + 1) * page_size) < total;
+
+        Ok((
+            forwarded_payments,
+            PaginationResponse {
+                has_more,
+                total: total.try_into().unwrap(),
+            },
+        ))
+    }
+
+    pub fn find_forwarded_payments_sync(
+        &self,
+        node_id: String,
+        filter: ForwardedPaymentsFilter,
+    ) -> Result<Vec<forwarded_payment::Model>, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.find_forwarded_payments(node_id, filter).await })
+        })
+    }
+
+    pub async fn find_forwarded_payments(
+        &self,
+        node_id: String,
+        filter: ForwardedPaymentsFilter,
+    ) -> Result<Vec<forwarded_payment::Model>, Error> {
+        Ok(Self::forwarded_payments_query(node_id, filter)
+            .order_by_asc(forwarded_payment::Column::HoursSinceEpoch)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn insert_balance_snapshot(
+        &self,
+        balance_snapshot: balance_snapshot::ActiveModel,
+    ) -> Result<balance_snapshot::Model, Error> {
+        Ok(balance_snapshot.insert(&self.connection).await?)
+    }
+
+    pub async fn list_balance_snapshots(
+        &self,
+        node_id: String,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<Vec<balance_snapshot::Model>, Error> {
+        let mut query =
+            BalanceSnapshot::find().filter(balance_snapshot::Column::NodeId.eq(node_id));
+
+        if let Some(from) = from {
+            query = query.filter(balance_snapshot::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(balance_snapshot::Column::CreatedAt.lte(to));
+        }
+
+        Ok(query
+            .order_by_asc(balance_snapshot::Column::CreatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn delete_balance_snapshots_before(
+        &self,
+        node_id: String,
+        before: i64,
+    ) -> Result<u64, Error> {
+        let res = BalanceSnapshot::delete_many()
+            .filter(balance_snapshot::Column::NodeId.eq(node_id))
+            .filter(balance_snapshot::Column::CreatedAt.lt(before))
+            .exec(&self.connection)
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    pub async fn insert_channel_event(
+        &self,
+        channel_event: channel_event::ActiveModel,
+    ) -> Result<channel_event::Model, Error> {
+        Ok(channel_event.insert(&self.connection).await?)
+    }
+
+    pub fn insert_channel_event_sync(
+        &self,
+        channel_event: channel_event::ActiveModel,
+    ) -> Result<channel_event::Model, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.insert_channel_event(channel_event).await })
+        })
+    }
+
+    pub async fn find_channel_open_event(
+        &self,
+        node_id: String,
+        channel_id: String,
+    ) -> Result<Option<channel_event::Model>, Error> {
+        Ok(ChannelEvent::find()
+            .filter(channel_event::Column::NodeId.eq(node_id))
+            .filter(channel_event::Column::ChannelId.eq(channel_id))
+            .filter(channel_event::Column::Kind.eq(ChannelEventKind::Open.to_string()))
+            .one(&self.connection)
+            .await?)
+    }
+
+    pub fn find_channel_open_event_sync(
+        &self,
+        node_id: String,
+        channel_id: String,
+    ) -> Result<Option<channel_event::Model>, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.find_channel_open_event(node_id, channel_id).await })
+        })
+    }
+
+    pub async fn list_channel_events(
+        &self,
+        node_id: String,
+    ) -> Result<Vec<channel_event::Model>, Error> {
+        Ok(ChannelEvent::find()
+            .filter(channel_event::Column::NodeId.eq(node_id))
+            .order_by_asc(channel_event::Column::CreatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn find_channel(
+        &self,
+        node_id: String,
+        channel_id: String,
+    ) -> Result<Option<channel::Model>, Error> {
+        Ok(Channel::find()
+            .filter(channel::Column::NodeId.eq(node_id))
+            .filter(channel::Column::ChannelId.eq(channel_id))
+            .one(&self.connection)
+            .await?)
+    }
+
+    pub fn find_channel_sync(
+        &self,
+        node_id: String,
+        channel_id: String,
+    ) -> Result<Option<channel::Model>, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.find_channel(node_id, channel_id).await })
+        })
+    }
+
+    pub async fn insert_channel(
+        &self,
+        channel: channel::ActiveModel,
+    ) -> Result<channel::Model, Error> {
+        Ok(channel.insert(&self.connection).await?)
+    }
+
+    pub fn insert_channel_sync(
+        &self,
+        channel: channel::ActiveModel,
+    ) -> Result<channel::Model, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.insert_channel(channel).await })
+        })
+    }
+
+    pub async fn update_channel(
+        &self,
+        channel: channel::ActiveModel,
+    ) -> Result<channel::Model, Error> {
+        Ok(channel.update(&self.connection).await?)
+    }
+
+    pub fn update_channel_sync(
+        &self,
+        channel: channel::ActiveModel,
+    ) -> Result<channel::Model, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.update_channel(channel).await })
+        })
+    }
+
+    pub async fn list_channels(
+        &self,
+        node_id: String,
+        state: Option<String>,
+    ) -> Result<Vec<channel::Model>, Error> {
+        let mut query = Channel::find().filter(channel::Column::NodeId.eq(node_id));
+
+        query = match state {
+            Some(state) => {
+                query.filter(channel::Column::State.eq(state.parse::<ChannelState>()?.to_string()))
+            }
+            None => query.filter(channel::Column::State.ne(ChannelState::Closed.to_string())),
+        };
+
+        Ok(query
+            .order_by_asc(channel::Column::CreatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn list_settled_payments(
+        &self,
+        node_id: String,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<Vec<payment::Model>, Error> {
+        let mut query = Payment::find()
+            .filter(payment::Column::NodeId.eq(node_id))
+            .filter(payment::Column::Status.eq(HTLCStatus::Succeeded.to_string()));
+
+        if let Some(from) = from {
+            query = query.filter(payment::Column::UpdatedAt.gte(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(payment::Column::UpdatedAt.lte(to));
+        }
+
+        Ok(query
+            .order_by_asc(payment::Column::UpdatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn list_transactions(
+        &self,
+        node_id: String,
+    ) -> Result<Vec<transaction::Model>, Error> {
+        Ok(Transaction::find()
+            .filter(transaction::Column::NodeId.eq(node_id))
+            .order_by_asc(transaction::Column::CreatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub fn insert_payment_attempt_sync(
+        &self,
+        payment_attempt: payment_attempt::ActiveModel,
+    ) -> Result<payment_attempt::Model, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { Ok(payment_attempt.insert(&self.connection).await?) })
+        })
+    }
+
+    pub async fn list_payment_attempts(
+        &self,
+        node_id: String,
+        payment_hash: String,
+    ) -> Result<Vec<payment_attempt::Model>, Error> {
+        Ok(PaymentAttempt::find()
+            .filter(payment_attempt::Column::NodeId.eq(node_id))
+            .filter(payment_attempt::Column::PaymentHash.eq(payment_hash))
+            .order_by_asc(payment_attempt::Column::CreatedAt)
+            .all(&self.connection)
+            .await?)
+    }
+
+    pub async fn insert_macaroon_spend(
+        &self,
+        macaroon_spend: macaroon_spend::ActiveModel,
+    ) -> Result<macaroon_spend::Model, Error> {
+        Ok(macaroon_spend.insert(&self.connection).await?)
+    }
+
+    pub async fn sum_macaroon_spends_since(
+        &self,
+        node_id: String,
+        budget_id: String,
+        since: i64,
+    ) -> Result<u64, Error> {
+        let spends = MacaroonSpend::find()
+            .filter(macaroon_spend::Column::NodeId.eq(node_id.clone()))
+            .filter(macaroon_spend::Column::BudgetId.eq(budget_id))
+            .filter(macaroon_spend::Column::CreatedAt.gte(since))
+            .all(&self.connection)
+            .await?;
+
+        // payments that ended up failing never left the node, the others also spent
+        // whatever routing fees they have paid so far
+        let payment_hashes = spends
+            .iter()
+            .filter_map(|spend| spend.payment_hash.clone())
+            .collect::<Vec<String>>();
+        let payments = Payment::find()
+            .filter(entity::payment::Column::NodeId.eq(node_id))
+            .filter(entity::payment::Column::PaymentHash.is_in(payment_hashes))
+            .all(&self.connection)
+            .await?
+            .into_iter()
+            .map(|payment| (payment.payment_hash.clone(), payment))
+            .collect::<HashMap<String, entity::payment::Model>>();
+
+        Ok(spends
+            .into_iter()
+            .map(|spend| {
+                let payment = spend
+                    .payment_hash
+                    .as_ref()
+                    .and_then(|payment_hash| payments.get(payment_hash));
+                match payment {
+                    Some(payment) if payment.status == HTLCStatus::Failed.to_string() => 0,
+                    Some(payment) => {
+                        spend.amt_msat.max(0) as u64
+                            + payment.fee_paid_msat.unwrap_or(0).max(0) as u64
+                    }
+                    None => spend.amt_msat.max(0) as u64,
+                }
+            })
+            .sum())
+    }
+
+    pub async fn find_invoice(
+        &self,
+        node_id: String,
+        payment_hash: String,
+    ) -> Result<Option<payment::Model>, Error> {
+        Ok(Payment::find()
+            .filter(payment::Column::CreatedByNodeId.eq(node_id))
+            .filter(payment::Column::PaymentHash.eq(payment_hash))
+            .filter(payment::Column::Origin.eq(PaymentOrigin::InvoiceIncoming.to_string()))
+            .one(&self.connection)
+            .await?)
+    }
+
+    pub async fn cancel_invoice(&self, node_id: String, payment_hash: String) -> Result<(), Error> {
+        let payment = self
+            .find_invoice(node_id, payment_hash)
+            .await?
+            .ok_or_else(|| Error::Generic(String::from("invoice not found")))?;
+
+        if payment.status == HTLCStatus::Succeeded.to_string() {
+            return Err(Error::Generic(String::from(
+                "invoice has already been paid",
+            )));
+        }
+
+        let mut payment: payment::ActiveModel = payment.into();
+        payment.status = ActiveValue::Set(HTLCStatus::Cancelled.to_string());
+        payment.update(&self.connection).await?;
+        Ok(())
+    }
+
+    pub async fn list_invoices(
+        &self,
+        node_id: String,
+        pagination: PaginationRequest,
+        filter: InvoicesFilter,
+    ) -> Result<(Vec<payment::Model>, PaginationResponse), Error> {
+        let query_string = pagination.query.unwrap_or_else(|| String::from(""));
+        let page_size: usize = pagination.take.try_into().unwrap();
+        let page: usize = pagination.page.try_into().unwrap();
+        let now = seconds_since_epoch();
+
+        let mut query = Payment::find()
+            .filter(payment::Column::CreatedByNodeId.eq(node_id))
+            .filter(payment::Column::Origin.eq(PaymentOrigin::InvoiceIncoming.to_string()))
+            .filter(
+                Condition::any()
+                    .add(payment::Column::PaymentHash.contains(&query_string))
+                    .add(payment::Column::Label.contains(&query_string))
+                    .add(payment::Column::Invoice.contains(&query_string)),
+            );
+
+        if let Some(status) = filter.status {
+            let pending = payment::Column::Status.eq(HTLCStatus::Pending.to_string());
+            query = match status.parse::<InvoiceStatus>()? {
+                InvoiceStatus::Paid => {
+                    query.filter(payment::Column::Status.eq(HTLCStatus::Succeeded.to_string()))
+                }
+                InvoiceStatus::Cancelled => {
+                    query.filter(payment::Column::Status.eq(HTLCStatus::Cancelled.to_string()))
+                }
+                InvoiceStatus::Expired => query
+                    .filter(pending)
+                    .filter(payment::Column::ExpiresAt.lt(now)),
+                InvoiceStatus::Open => query.filter(pending).filter(
+                    Condition::any()
+                        .add(payment::Column::ExpiresAt.is_null())
+                        .add(payment::Column::ExpiresAt.gte(now)),
+                ),
+            };
+        }
+
+        let invoice_pages = query
+            .order_by_desc(payment::Column::CreatedAt)
+            .paginate(&self.connection, page_size);
+
+        let invoices = invoice_pages.fetch_page(page).await?;
+        let total = invoice_pages.num_items().await?;
+        let has_more = ((page + 1) * page_size) < total;
+
+        Ok((
+            invoices,
+            PaginationResponse {
+                has_more,
+                total: total.try_into().unwrap(),
+            },
+        ))
+    }
+
     pub async fn find_peer_address_by_id(
         &self,
         id: &str,
@@ -622,6 +1169,206 @@ impl SenseiDatabase {
         })
     }
 
+    pub async fn delete_value(&self, node_id: String, key: String) -> Result<(), Error> {
+        KVStore::delete_many()
+            .filter(kv_store::Column::NodeId.eq(node_id))
+            .filter(kv_store::Column::K.eq(key))
+            .exec(&self.connection)
+            .await?;
+        Ok(())
+    }
+
+    pub fn delete_value_sync(&self, node_id: String, key: String) -> Result<(), Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.delete_value(node_id, key).await })
+        })
+    }
+
+    pub async fn get_autopilot_policy(&self, node_id: String) -> Result<AutopilotPolicy, Error> {
+        match self
+            .get_value(node_id, String::from("autopilot_policy"))
+            .await?
+        {
+            Some(entry) => Ok(serde_json::from_slice(&entry.v).unwrap_or_default()),
+            None => Ok(AutopilotPolicy::default()),
+        }
+    }
+
+    pub async fn set_autopilot_policy(
+        &self,
+        node_id: String,
+        policy: &AutopilotPolicy,
+    ) -> Result<(), Error> {
+        let serialized_policy = serde_json::to_vec(policy).unwrap();
+        self.set_value(node_id, String::from("autopilot_policy"), serialized_policy)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_jit_channel_policy(&self, node_id: String) -> Result<JitChannelPolicy, Error> {
+        match self
+            .get_value(node_id, String::from("jit_channel_policy"))
+            .await?
+        {
+            Some(entry) => Ok(serde_json::from_slice(&entry.v).unwrap_or_default()),
+            None => Ok(JitChannelPolicy::default()),
+        }
+    }
+
+    pub async fn set_jit_channel_policy(
+        &self,
+        node_id: String,
+        policy: &JitChannelPolicy,
+    ) -> Result<(), Error> {
+        let serialized_policy = serde_json::to_vec(policy).unwrap();
+        self.set_value(
+            node_id,
+            String::from("jit_channel_policy"),
+            serialized_policy,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_jit_channel(
+        &self,
+        node_id: String,
+        payment_hash: String,
+    ) -> Result<Option<JitChannel>, Error> {
+        Ok(self
+            .get_value(node_id, format!("jit_channel/{}", payment_hash))
+            .await?
+            .and_then(|entry| serde_json::from_slice(&entry.v).ok()))
+    }
+
+    pub async fn set_jit_channel(
+        &self,
+        node_id: String,
+        jit_channel: &JitChannel,
+    ) -> Result<(), Error> {
+        let serialized_jit_channel = serde_json::to_vec(jit_channel).unwrap();
+        self.set_value(
+            node_id,
+            format!("jit_channel/{}", jit_channel.payment_hash),
+            serialized_jit_channel,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_jit_channels(&self, node_id: String) -> Result<Vec<JitChannel>, Error> {
+        Ok(self
+            .list_values(node_id, String::from("jit_channel/"))
+            .await?
+            .into_iter()
+            .filter_map(|entry| serde_json::from_slice(&entry.v).ok())
+            .collect())
+    }
+
+    pub async fn set_jit_channel_state(
+        &self,
+        node_id: String,
+        payment_hash: String,
+        state: JitChannelState,
+    ) -> Result<Option<JitChannel>, Error> {
+        let mut jit_channel = match self.get_jit_channel(node_id.clone(), payment_hash).await? {
+            Some(jit_channel) => jit_channel,
+            None => return Ok(None),
+        };
+        jit_channel.state = state;
+        self.set_jit_channel(node_id, &jit_channel).await?;
+        Ok(Some(jit_channel))
+    }
+
+    pub fn set_jit_channel_state_sync(
+        &self,
+        node_id: String,
+        payment_hash: String,
+        state: JitChannelState,
+    ) -> Result<Option<JitChannel>, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle.block_on(async move {
+                self.set_jit_channel_state(node_id, payment_hash, state)
+                    .await
+            })
+        })
+    }
+
+    pub async fn get_fee_policy(&self, node_id: String) -> Result<FeePolicy, Error> {
+        match self.get_value(node_id, String::from("fee_policy")).await? {
+            Some(entry) => serde_json::from_slice(&entry.v)
+                .map_err(|e| Error::Generic(format!("invalid fee policy: {}", e))),
+            None => Ok(FeePolicy::default()),
+        }
+    }
+
+    pub async fn set_fee_policy(&self, node_id: String, policy: &FeePolicy) -> Result<(), Error> {
+        let serialized_policy = serde_json::to_vec(policy).unwrap();
+        self.set_value(node_id, String::from("fee_policy"), serialized_policy)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_health_policy(&self, node_id: String) -> Result<HealthPolicy, Error> {
+        match self
+            .get_value(node_id, String::from("health_policy"))
+            .await?
+        {
+            Some(entry) => Ok(serde_json::from_slice(&entry.v).unwrap_or_default()),
+            None => Ok(HealthPolicy::default()),
+        }
+    }
+
+    pub async fn set_health_policy(
+        &self,
+        node_id: String,
+        policy: &HealthPolicy,
+    ) -> Result<(), Error> {
+        let serialized_policy = serde_json::to_vec(policy).unwrap();
+        self.set_value(node_id, String::from("health_policy"), serialized_policy)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_channel_acceptance_policy(
+        &self,
+        node_id: String,
+    ) -> Result<ChannelAcceptancePolicy, Error> {
+        match self
+            .get_value(node_id, String::from("channel_acceptance_policy"))
+            .await?
+        {
+            Some(entry) => Ok(serde_json::from_slice(&entry.v).unwrap_or_default()),
+            None => Ok(ChannelAcceptancePolicy::default()),
+        }
+    }
+
+    pub fn get_channel_acceptance_policy_sync(
+        &self,
+        node_id: String,
+    ) -> Result<ChannelAcceptancePolicy, Error> {
+        tokio::task::block_in_place(move || {
+            self.runtime_handle
+                .block_on(async move { self.get_channel_acceptance_policy(node_id).await })
+        })
+    }
+
+    pub async fn set_channel_acceptance_policy(
+        &self,
+        node_id: String,
+        policy: &ChannelAcceptancePolicy,
+    ) -> Result<(), Error> {
+        let serialized_policy = serde_json::to_vec(policy).unwrap();
+        self.set_value(
+            node_id,
+            String::from("channel_acceptance_policy"),
+            serialized_policy,
+        )
+        .await?;
+        Ok(())
+    }
+
     pub async fn get_entropy(&self, node_id: String) -> Result<Option<Vec<u8>>, Error> {
         self.get_value(node_id, String::from("entropy"))
             .await
@@ -823,3 +1570,286 @@ impl SenseiDatabase {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use entity::sea_orm::{ConnectOptions, Database};
+    use migration::{Migrator, MigratorTrait};
+
+    // Every connection to an in-memory sqlite database gets its own database, so keep it to one.
+    pub(crate) async fn test_database() -> SenseiDatabase {
+        let mut connect_options = ConnectOptions::new(String::from("sqlite::memory:"));
+        connect_options.max_connections(1).min_connections(1);
+        let connection = Database::connect(connect_options).await.unwrap();
+        Migrator::up(&connection, None)
+            .await
+            .expect("failed to run migrations");
+        SenseiDatabase::new(connection, tokio::runtime::Handle::current())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn balance_snapshot_retention_test() {
+        let database = test_database().await;
+        for (node_id, created_at) in [("node", 100), ("node", 200), ("other", 100)] {
+            let snapshot = balance_snapshot::ActiveModel {
+                node_id: ActiveValue::Set(node_id.to_string()),
+                onchain_balance_sats: ActiveValue::Set(created_at),
+                channel_outbound_capacity_msats: ActiveValue::Set(0),
+                channel_inbound_capacity_msats: ActiveValue::Set(0),
+                ..Default::default()
+            }
+            .insert(database.get_connection())
+            .await
+            .unwrap();
+            // created_at is stamped on insert, move it back in time
+            let mut snapshot: balance_snapshot::ActiveModel = snapshot.into();
+            snapshot.created_at = ActiveValue::Set(created_at);
+            BalanceSnapshot::update(snapshot)
+                .exec(database.get_connection())
+                .await
+                .unwrap();
+        }
+
+        let deleted = database
+            .delete_balance_snapshots_before(String::from("node"), 150)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+
+        let remaining = database
+            .list_balance_snapshots(String::from("node"), None, None)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].created_at, 200);
+
+        // other nodes keep their history
+        let other = database
+            .list_balance_snapshots(String::from("other"), None, None)
+            .await
+            .unwrap();
+        assert_eq!(other.len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fee_policy_test() {
+        let database = test_database().await;
+        let node_id = String::from("node");
+
+        assert_eq!(
+            database.get_fee_policy(node_id.clone()).await.unwrap(),
+            FeePolicy::default()
+        );
+
+        let policy = FeePolicy {
+            enabled: true,
+            min_fee_proportional_millionths: 10,
+            max_fee_proportional_millionths: 1000,
+            forwarding_fee_base_msat: Some(500),
+            interval_secs: 600,
+        };
+        database
+            .set_fee_policy(node_id.clone(), &policy)
+            .await
+            .unwrap();
+        assert_eq!(
+            database.get_fee_policy(node_id.clone()).await.unwrap(),
+            policy
+        );
+
+        // a corrupt policy must not silently turn into the default one
+        database
+            .set_value(
+                node_id.clone(),
+                String::from("fee_policy"),
+                b"{not json".to_vec(),
+            )
+            .await
+            .unwrap();
+        assert!(database.get_fee_policy(node_id).await.is_err());
+    }
+
+    async fn insert_invoice(
+        database: &SenseiDatabase,
+        payment_hash: &str,
+        status: HTLCStatus,
+        expires_at: Option<i64>,
+    ) -> payment::Model {
+        payment::ActiveModel {
+            node_id: ActiveValue::Set(String::from("node")),
+            created_by_node_id: ActiveValue::Set(String::from("node")),
+            payment_hash: ActiveValue::Set(payment_hash.to_string()),
+            status: ActiveValue::Set(status.to_string()),
+            origin: ActiveValue::Set(PaymentOrigin::InvoiceIncoming.to_string()),
+            label: ActiveValue::Set(Some(format!("{} invoice", payment_hash))),
+            expires_at: ActiveValue::Set(expires_at),
+            ..Default::default()
+        }
+        .insert(database.get_connection())
+        .await
+        .unwrap()
+    }
+
+    async fn find_invoice_status(database: &SenseiDatabase, payment_hash: &str) -> InvoiceStatus {
+        let payment = database
+            .find_invoice(String::from("node"), payment_hash.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        InvoiceStatus::from_payment(&payment, seconds_since_epoch())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn invoice_status_test() {
+        let database = test_database().await;
+        let now = seconds_since_epoch();
+
+        insert_invoice(&database, "expiring", HTLCStatus::Pending, Some(now + 60)).await;
+        assert_eq!(
+            find_invoice_status(&database, "expiring").await,
+            InvoiceStatus::Open
+        );
+        let payment = database
+            .find_invoice(String::from("node"), String::from("expiring"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            InvoiceStatus::from_payment(&payment, now + 61),
+            InvoiceStatus::Expired
+        );
+
+        insert_invoice(&database, "open", HTLCStatus::Pending, Some(now + 3600)).await;
+        database
+            .cancel_invoice(String::from("node"), String::from("open"))
+            .await
+            .unwrap();
+        assert_eq!(
+            find_invoice_status(&database, "open").await,
+            InvoiceStatus::Cancelled
+        );
+
+        // an expired invoice can still be cancelled, a paid one can't
+        insert_invoice(&database, "expired", HTLCStatus::Pending, Some(now - 1)).await;
+        assert_eq!(
+            find_invoice_status(&database, "expired").await,
+            InvoiceStatus::Expired
+        );
+        database
+            .cancel_invoice(String::from("node"), String::from("expired"))
+            .await
+            .unwrap();
+        assert_eq!(
+            find_invoice_status(&database, "expired").await,
+            InvoiceStatus::Cancelled
+        );
+
+        insert_invoice(&database, "paid", HTLCStatus::Succeeded, Some(now - 1)).await;
+        assert!(database
+            .cancel_invoice(String::from("node"), String::from("paid"))
+            .await
+            .is_err());
+        assert_eq!(
+            find_invoice_status(&database, "paid").await,
+            InvoiceStatus::Paid
+        );
+
+        assert!(database
+            .cancel_invoice(String::from("node"), String::from("missing"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn list_invoices_test() {
+        let database = test_database().await;
+        let now = seconds_since_epoch();
+
+        insert_invoice(&database, "open", HTLCStatus::Pending, Some(now + 3600)).await;
+        insert_invoice(&database, "no-expiry", HTLCStatus::Pending, None).await;
+        insert_invoice(&database, "expired", HTLCStatus::Pending, Some(now - 1)).await;
+        insert_invoice(&database, "paid", HTLCStatus::Succeeded, Some(now - 1)).await;
+        insert_invoice(
+            &database,
+            "cancelled",
+            HTLCStatus::Cancelled,
+            Some(now + 3600),
+        )
+        .await;
+        // outgoing payments and other nodes' invoices are never listed
+        payment::ActiveModel {
+            node_id: ActiveValue::Set(String::from("node")),
+            created_by_node_id: ActiveValue::Set(String::from("node")),
+            payment_hash: ActiveValue::Set(String::from("outgoing")),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            origin: ActiveValue::Set(PaymentOrigin::InvoiceOutgoing.to_string()),
+            ..Default::default()
+        }
+        .insert(database.get_connection())
+        .await
+        .unwrap();
+        payment::ActiveModel {
+            node_id: ActiveValue::Set(String::from("other")),
+            created_by_node_id: ActiveValue::Set(String::from("other")),
+            payment_hash: ActiveValue::Set(String::from("other")),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            origin: ActiveValue::Set(PaymentOrigin::InvoiceIncoming.to_string()),
+            ..Default::default()
+        }
+        .insert(database.get_connection())
+        .await
+        .unwrap();
+
+        let list = |status: Option<&str>, query: Option<&str>| {
+            let database = &database;
+            let status = status.map(String::from);
+            let query = query.map(String::from);
+            async move {
+                let pagination = PaginationRequest {
+                    page: 0,
+                    take: 10,
+                    query,
+                };
+                let (invoices, pagination) = database
+                    .list_invoices(String::from("node"), pagination, InvoicesFilter { status })
+                    .await
+                    .unwrap();
+                assert_eq!(pagination.total, invoices.len() as u64);
+                let mut payment_hashes = invoices
+                    .into_iter()
+                    .map(|invoice| invoice.payment_hash)
+                    .collect::<Vec<String>>();
+                payment_hashes.sort();
+                payment_hashes
+            }
+        };
+
+        assert_eq!(
+            list(None, None).await,
+            vec!["cancelled", "expired", "no-expiry", "open", "paid"]
+        );
+        assert_eq!(list(Some("open"), None).await, vec!["no-expiry", "open"]);
+        assert_eq!(list(Some("expired"), None).await, vec!["expired"]);
+        assert_eq!(list(Some("paid"), None).await, vec!["paid"]);
+        assert_eq!(list(Some("cancelled"), None).await, vec!["cancelled"]);
+        // the query matches labels as well as payment hashes
+        assert_eq!(
+            list(Some("open"), Some("no-expiry invoice")).await,
+            vec!["no-expiry"]
+        );
+
+        let pagination = PaginationRequest {
+            page: 0,
+            take: 10,
+            query: None,
+        };
+        let filter = InvoicesFilter {
+            status: Some(String::from("unknown")),
+        };
+        assert!(database
+            .list_invoices(String::from("node"), pagination, filter)
+            .await
+            .is_err());
+    }
+}
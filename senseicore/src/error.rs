@@ -39,6 +39,7 @@ pub enum Error {
     FundingGenerationNeverHappened,
     ChannelOpenRejected(String),
     NodeBeingStartedAlready,
+    SpendingLimitExceeded(String),
 }
 
 impl Display for Error {
@@ -73,6 +74,7 @@ impl Display for Error {
                 format!("Channel open rejected by peer: {:?}", reason)
             }
             Error::InvalidEntropyLength => String::from("invalid entropy length"),
+            Error::SpendingLimitExceeded(reason) => format!("spending limit exceeded: {}", reason),
         };
         write!(f, "{}", str)
     }
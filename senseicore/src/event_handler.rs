@@ -9,20 +9,35 @@
 
 use crate::chain::broadcaster::SenseiBroadcaster;
 use crate::chain::database::WalletDatabase;
+use crate::chain::fee_estimator::SenseiFeeEstimator;
 use crate::chain::manager::SenseiChainManager;
+use crate::channel_acceptance::{
+    ChannelAcceptanceDecision, ChannelAcceptancePolicy, ChannelAcceptanceWebhookRequest,
+    InboundChannelRequest,
+};
 use crate::config::SenseiConfig;
 use crate::database::SenseiDatabase;
 use crate::events::SenseiEvent;
 use crate::hex_utils;
-use crate::node::{ChannelManager, HTLCStatus, PaymentOrigin};
+use crate::jit_channels::JitChannelState;
+use crate::keys::SenseiKeysManager;
+use crate::node::{
+    ChainMonitor, ChannelEventKind, ChannelManager, ChannelState, HTLCStatus, InvoiceStatus,
+    PaymentFailureCode, PaymentOrigin,
+};
+use crate::services::node::{ClaimableBalance, PaymentAttemptHop};
 
 use bdk::wallet::AddressIndex;
 use bitcoin::secp256k1::{self, PublicKey};
 use bitcoin::{secp256k1::Secp256k1, Network};
 use bitcoin_bech32::WitnessProgram;
 use entity::sea_orm::ActiveValue;
+use entity::seconds_since_epoch;
 use lightning::chain::chaininterface::{BroadcasterInterface, FeeEstimator};
-use lightning::chain::keysinterface::{KeysInterface, PhantomKeysManager, Recipient};
+use lightning::chain::keysinterface::{KeysInterface, Recipient, SpendableOutputDescriptor};
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::PaymentHash;
+use lightning::routing::router::RouteHop;
 
 use lightning::{
     chain::chaininterface::ConfirmationTarget,
@@ -34,12 +49,47 @@ use std::{sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 use tokio::sync::broadcast;
 
+// Spends outputs LDK (or a channel recovered from a backup) hands us into the on-chain wallet.
+pub fn sweep_spendable_outputs(
+    wallet: &Mutex<bdk::Wallet<WalletDatabase>>,
+    keys_manager: &SenseiKeysManager,
+    fee_estimator: &SenseiFeeEstimator,
+    broadcaster: &SenseiBroadcaster,
+    outputs: &[SpendableOutputDescriptor],
+) {
+    let wallet = wallet.lock().unwrap();
+    let address_info = wallet.get_address(AddressIndex::LastUnused).unwrap();
+    let destination_address = address_info.address;
+    let output_descriptors = &outputs
+        .iter()
+        .filter(|descriptor| keys_manager.is_spendable(descriptor))
+        .collect::<Vec<_>>();
+    if output_descriptors.is_empty() {
+        return;
+    }
+
+    let tx_feerate = fee_estimator.get_est_sat_per_1000_weight(ConfirmationTarget::Normal);
+
+    let spending_tx = keys_manager
+        .spend_spendable_outputs(
+            output_descriptors,
+            Vec::new(),
+            destination_address.script_pubkey(),
+            tx_feerate,
+            &Secp256k1::new(),
+        )
+        .unwrap();
+
+    broadcaster.broadcast_transaction(&spending_tx);
+}
+
 pub struct LightningNodeEventHandler {
     pub node_id: String,
     pub config: Arc<SenseiConfig>,
     pub wallet: Arc<Mutex<bdk::Wallet<WalletDatabase>>>,
     pub channel_manager: Arc<ChannelManager>,
-    pub keys_manager: Arc<PhantomKeysManager>,
+    pub keys_manager: Arc<SenseiKeysManager>,
+    pub chain_monitor: Arc<ChainMonitor>,
     pub database: Arc<SenseiDatabase>,
     pub chain_manager: Arc<SenseiChainManager>,
     pub tokio_handle: Handle,
@@ -48,16 +98,358 @@ pub struct LightningNodeEventHandler {
     pub secp_ctx: Secp256k1<secp256k1::All>,
 }
 
+// Everything needed to answer an open channel request, possibly after the event handler
+// has moved on to other events.
+struct InboundChannelResponder {
+    node_id: String,
+    channel_manager: Arc<ChannelManager>,
+    event_sender: broadcast::Sender<SenseiEvent>,
+    temporary_channel_id: [u8; 32],
+    counterparty_node_id: PublicKey,
+    funding_satoshis: u64,
+}
+
+impl InboundChannelResponder {
+    fn respond(&self, decision: ChannelAcceptanceDecision, reason: Option<String>) {
+        let counterparty_node_id = &self.counterparty_node_id;
+        let result = match decision {
+            ChannelAcceptanceDecision::Accept => self.channel_manager.accept_inbound_channel(
+                &self.temporary_channel_id,
+                counterparty_node_id,
+                1,
+            ),
+            ChannelAcceptanceDecision::AcceptZeroConf => self
+                .channel_manager
+                .accept_inbound_channel_from_trusted_peer_0conf(
+                    &self.temporary_channel_id,
+                    counterparty_node_id,
+                    1,
+                ),
+            ChannelAcceptanceDecision::Reject => {
+                let reason = reason.unwrap_or_else(|| String::from("rejected by webhook"));
+                println!(
+                    "rejecting inbound channel from {:?}: {}",
+                    counterparty_node_id, reason
+                );
+                if let Err(e) = self.channel_manager.force_close_without_broadcasting_txn(
+                    &self.temporary_channel_id,
+                    counterparty_node_id,
+                ) {
+                    println!(
+                        "failed to reject inbound channel from {:?}: {:?}",
+                        counterparty_node_id, e
+                    );
+                }
+                let _res = self.event_sender.send(SenseiEvent::InboundChannelRejected {
+                    node_id: self.node_id.clone(),
+                    temporary_channel_id: self.temporary_channel_id,
+                    counterparty_node_id: *counterparty_node_id,
+                    funding_satoshis: self.funding_satoshis,
+                    reason,
+                });
+                return;
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                println!(
+                    "accepted inbound channel ({}) from {:?}",
+                    decision, counterparty_node_id
+                );
+            }
+            Err(e) => {
+                println!(
+                    "failed to accept inbound channel ({}) from {:?}: {:?}",
+                    decision, counterparty_node_id, e
+                );
+            }
+        }
+    }
+}
+
+impl LightningNodeEventHandler {
+    fn channel_acceptance_policy(&self) -> ChannelAcceptancePolicy {
+        match self
+            .database
+            .get_channel_acceptance_policy_sync(self.node_id.clone())
+        {
+            Ok(policy) => policy,
+            Err(e) => {
+                println!("failed to load channel acceptance policy: {:?}", e);
+                ChannelAcceptancePolicy::default()
+            }
+        }
+    }
+
+    fn inbound_channel_rejection_reason(
+        &self,
+        policy: &ChannelAcceptancePolicy,
+        temporary_channel_id: &[u8; 32],
+        counterparty_node_id: &PublicKey,
+        funding_satoshis: u64,
+    ) -> Option<String> {
+        let channels = self.channel_manager.list_channels();
+        let is_public = channels
+            .iter()
+            .find(|channel| channel.channel_id == *temporary_channel_id)
+            .map(|channel| channel.is_public)
+            .unwrap_or(true);
+        let pending_channels = channels
+            .iter()
+            .filter(|channel| {
+                channel.channel_id != *temporary_channel_id
+                    && !channel.is_outbound
+                    && !channel.is_channel_ready
+            })
+            .count();
+
+        policy.rejection_reason(&InboundChannelRequest {
+            counterparty_node_id: &counterparty_node_id.to_string(),
+            funding_satoshis,
+            is_public,
+            pending_channels,
+        })
+    }
+
+    // What we get back on chain once the channel is gone, according to its monitor.
+    fn final_balance_msat(&self, channel_id: &[u8; 32]) -> Option<i64> {
+        let funding_txo = self
+            .chain_monitor
+            .list_monitors()
+            .into_iter()
+            .find(|funding_txo| funding_txo.to_channel_id() == *channel_id)?;
+        let monitor = self.chain_monitor.get_monitor(funding_txo).ok()?;
+        let best_block_height = self.channel_manager.current_best_block().height();
+        let balance_sats = monitor
+            .get_claimable_balances()
+            .iter()
+            .map(|balance| ClaimableBalance::from_ldk(balance, best_block_height).amount_sats)
+            .sum::<u64>();
+        Some((balance_sats * 1000) as i64)
+    }
+
+    fn record_channel_closed(
+        &self,
+        channel_id: &[u8; 32],
+        channel: Option<entity::channel::Model>,
+        open_event: Option<&entity::channel_event::Model>,
+        reason: &str,
+    ) {
+        let now = seconds_since_epoch();
+        let final_balance_msat = self.final_balance_msat(channel_id);
+        let result = match channel {
+            Some(channel) => {
+                let closing_at = channel.closing_at.unwrap_or(now);
+                let mut channel: entity::channel::ActiveModel = channel.into();
+                channel.state = ActiveValue::Set(ChannelState::Closed.to_string());
+                channel.closing_reason = ActiveValue::Set(Some(reason.to_string()));
+                channel.closing_at = ActiveValue::Set(Some(closing_at));
+                channel.closed_at = ActiveValue::Set(Some(now));
+                if let Some(final_balance_msat) = final_balance_msat {
+                    channel.balance_msat = ActiveValue::Set(final_balance_msat);
+                }
+                self.database.update_channel_sync(channel)
+            }
+            None => {
+                // closed before it was ever synced, all we know is what we recorded at open
+                let open_event = match open_event {
+                    Some(open_event) => open_event,
+                    None => return,
+                };
+                let channel = entity::channel::ActiveModel {
+                    node_id: ActiveValue::Set(self.node_id.clone()),
+                    channel_id: ActiveValue::Set(hex_utils::hex_str(channel_id)),
+                    counterparty_node_id: ActiveValue::Set(
+                        open_event.counterparty_node_id.clone().unwrap_or_default(),
+                    ),
+                    funding_txid: ActiveValue::Set(open_event.funding_txid.clone()),
+                    channel_value_satoshis: ActiveValue::Set(
+                        open_event.channel_value_satoshis.unwrap_or(0),
+                    ),
+                    balance_msat: ActiveValue::Set(final_balance_msat.unwrap_or(0)),
+                    // open events without a direction predate inbound ones being recorded
+                    is_outbound: ActiveValue::Set(open_event.is_outbound.unwrap_or(true)),
+                    is_public: ActiveValue::Set(false),
+                    state: ActiveValue::Set(ChannelState::Closed.to_string()),
+                    closing_reason: ActiveValue::Set(Some(reason.to_string())),
+                    closing_at: ActiveValue::Set(Some(now)),
+                    closed_at: ActiveValue::Set(Some(now)),
+                    ..Default::default()
+                };
+                self.database.insert_channel_sync(channel)
+            }
+        };
+        if let Err(e) = result {
+            println!("failed to record channel as closed: {:?}", e);
+        }
+    }
+
+    // Invoices created for phantom payments are stored against the phantom node pubkey.
+    fn find_incoming_payment(&self, payment_hash: &str) -> Option<entity::payment::Model> {
+        self.database
+            .find_payment_sync(self.node_id.clone(), payment_hash.to_string())
+            .ok()
+            .flatten()
+            .or_else(|| {
+                let phantom_node_secret = self
+                    .keys_manager
+                    .get_node_secret(Recipient::PhantomNode)
+                    .unwrap();
+                let phantom_node_pubkey =
+                    PublicKey::from_secret_key(&self.secp_ctx, &phantom_node_secret).to_string();
+                self.database
+                    .find_payment_sync(phantom_node_pubkey, payment_hash.to_string())
+                    .unwrap_or(None)
+            })
+    }
+
+    fn set_jit_channel_state(&self, payment_hash: &str, state: JitChannelState) {
+        if let Err(e) = self.database.set_jit_channel_state_sync(
+            self.node_id.clone(),
+            payment_hash.to_string(),
+            state,
+        ) {
+            println!(
+                "ERROR: failed to update jit channel {}: {}",
+                payment_hash, e
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_payment_attempt(
+        &self,
+        payment_id: Option<&PaymentId>,
+        payment_hash: &PaymentHash,
+        path: &[RouteHop],
+        status: HTLCStatus,
+        failure_code: Option<PaymentFailureCode>,
+        failed_short_channel_id: Option<u64>,
+        network_update: Option<String>,
+        rejected_by_dest: bool,
+        all_paths_failed: bool,
+    ) {
+        let payment_attempt = new_payment_attempt(
+            self.node_id.clone(),
+            payment_id,
+            payment_hash,
+            path,
+            status,
+            failure_code,
+            failed_short_channel_id,
+            network_update,
+            rejected_by_dest,
+            all_paths_failed,
+        );
+
+        if let Err(e) = self.database.insert_payment_attempt_sync(payment_attempt) {
+            println!("failed to record payment attempt: {:?}", e);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn new_payment_attempt(
+    node_id: String,
+    payment_id: Option<&PaymentId>,
+    payment_hash: &PaymentHash,
+    path: &[RouteHop],
+    status: HTLCStatus,
+    failure_code: Option<PaymentFailureCode>,
+    failed_short_channel_id: Option<u64>,
+    network_update: Option<String>,
+    rejected_by_dest: bool,
+    all_paths_failed: bool,
+) -> entity::payment_attempt::ActiveModel {
+    // the final hop's fee_msat is the amount delivered to the recipient
+    let (amt_msat, fee_msat) = match path.split_last() {
+        Some((last_hop, hops)) => (
+            last_hop.fee_msat,
+            hops.iter().map(|hop| hop.fee_msat).sum::<u64>(),
+        ),
+        None => (0, 0),
+    };
+
+    let hops = path
+        .iter()
+        .map(PaymentAttemptHop::from)
+        .collect::<Vec<PaymentAttemptHop>>();
+
+    entity::payment_attempt::ActiveModel {
+        node_id: ActiveValue::Set(node_id),
+        payment_hash: ActiveValue::Set(hex_utils::hex_str(&payment_hash.0)),
+        payment_id: ActiveValue::Set(payment_id.map(|id| hex_utils::hex_str(&id.0))),
+        status: ActiveValue::Set(status.to_string()),
+        failure_code: ActiveValue::Set(failure_code.map(|code| code.to_string())),
+        failed_short_channel_id: ActiveValue::Set(failed_short_channel_id.map(|scid| scid as i64)),
+        network_update: ActiveValue::Set(network_update),
+        rejected_by_dest: ActiveValue::Set(rejected_by_dest),
+        all_paths_failed: ActiveValue::Set(all_paths_failed),
+        amt_msat: ActiveValue::Set(amt_msat as i64),
+        fee_msat: ActiveValue::Set(fee_msat as i64),
+        hops: ActiveValue::Set(serde_json::to_string(&hops).unwrap()),
+        ..Default::default()
+    }
+}
+
 impl EventHandler for LightningNodeEventHandler {
     fn handle_event(&self, event: &Event) {
         match event {
             Event::OpenChannelRequest {
                 temporary_channel_id,
                 counterparty_node_id,
-                funding_satoshis: _,
-                push_msat: _,
-                channel_type: _,
+                funding_satoshis,
+                push_msat,
+                channel_type,
             } => {
+                let policy = self.channel_acceptance_policy();
+                let responder = InboundChannelResponder {
+                    node_id: self.node_id.clone(),
+                    channel_manager: self.channel_manager.clone(),
+                    event_sender: self.event_sender.clone(),
+                    temporary_channel_id: *temporary_channel_id,
+                    counterparty_node_id: *counterparty_node_id,
+                    funding_satoshis: *funding_satoshis,
+                };
+
+                if let Some(reason) = self.inbound_channel_rejection_reason(
+                    &policy,
+                    temporary_channel_id,
+                    counterparty_node_id,
+                    *funding_satoshis,
+                ) {
+                    responder.respond(ChannelAcceptanceDecision::Reject, Some(reason));
+                    return;
+                }
+
+                if policy.webhook_url.is_some() {
+                    let mut required_features = vec![];
+                    if channel_type.requires_static_remote_key() {
+                        required_features.push(String::from("static_remote_key"));
+                    }
+                    if channel_type.requires_scid_privacy() {
+                        required_features.push(String::from("scid_privacy"));
+                    }
+                    if channel_type.requires_zero_conf() {
+                        required_features.push(String::from("zero_conf"));
+                    }
+                    let request = ChannelAcceptanceWebhookRequest {
+                        node_id: self.node_id.clone(),
+                        temporary_channel_id: hex_utils::hex_str(temporary_channel_id),
+                        counterparty_node_id: counterparty_node_id.to_string(),
+                        funding_satoshis: *funding_satoshis,
+                        push_msat: *push_msat,
+                        channel_type: required_features,
+                    };
+                    // don't hold up the rest of the node's events while the webhook decides
+                    self.tokio_handle.spawn(async move {
+                        let response = policy.webhook_decision(&request).await;
+                        responder.respond(response.decision, response.reason);
+                    });
+                    return;
+                }
+
                 let is_trusted_peer = match self
                     .database
                     .find_peer_sync(&self.node_id, &counterparty_node_id.to_string())
@@ -67,42 +459,9 @@ impl EventHandler for LightningNodeEventHandler {
                 };
 
                 if is_trusted_peer {
-                    match self
-                        .channel_manager
-                        .accept_inbound_channel_from_trusted_peer_0conf(
-                            temporary_channel_id,
-                            counterparty_node_id,
-                            1,
-                        ) {
-                        Ok(()) => {
-                            println!(
-                                "accepted 0-conf inbound channel from {:?}",
-                                counterparty_node_id
-                            );
-                        }
-                        Err(e) => {
-                            println!(
-                                "failed to accept 0-conf inbound channel from {:?}: {:?}",
-                                counterparty_node_id, e
-                            );
-                        }
-                    }
+                    responder.respond(ChannelAcceptanceDecision::AcceptZeroConf, None);
                 } else {
-                    match self.channel_manager.accept_inbound_channel(
-                        temporary_channel_id,
-                        counterparty_node_id,
-                        1,
-                    ) {
-                        Ok(()) => {
-                            println!("accepted inbound channel from {:?}", counterparty_node_id);
-                        }
-                        Err(e) => {
-                            println!(
-                                "failed to accept inbound channel from {:?}: {:?}",
-                                counterparty_node_id, e
-                            );
-                        }
-                    }
+                    responder.respond(ChannelAcceptanceDecision::Accept, None);
                 }
             }
             Event::FundingGenerationReady {
@@ -157,6 +516,38 @@ impl EventHandler for LightningNodeEventHandler {
                     PaymentPurpose::SpontaneousPayment(preimage) => Some(*preimage),
                 };
 
+                if let Some(payment) =
+                    self.find_incoming_payment(&hex_utils::hex_str(&payment_hash.0))
+                {
+                    let status = InvoiceStatus::from_payment(&payment, seconds_since_epoch());
+                    if status == InvoiceStatus::Cancelled || status == InvoiceStatus::Expired {
+                        println!("\nEVENT: failing payment back for {} invoice", status);
+                        self.channel_manager.fail_htlc_backwards(payment_hash);
+                        return;
+                    }
+
+                    let below_minimum = payment
+                        .min_amt_msat
+                        .map(|min_amt_msat| (*amount_msat as i64) < min_amt_msat)
+                        .unwrap_or(false);
+                    if below_minimum {
+                        println!("\nEVENT: failing payment back for amount below invoice minimum");
+                        self.channel_manager.fail_htlc_backwards(payment_hash);
+                        return;
+                    }
+
+                    // the preimage is the client's, we can only claim once we've paid them
+                    if payment.origin == PaymentOrigin::JitChannel.to_string() {
+                        self.set_jit_channel_state(&payment.payment_hash, JitChannelState::Held);
+                        let _res = self.event_sender.send(SenseiEvent::JitChannelPaymentHeld {
+                            node_id: self.node_id.clone(),
+                            payment_hash: payment.payment_hash,
+                            amount_msat: *amount_msat,
+                        });
+                        return;
+                    }
+                }
+
                 // TODO: if we want 'hodl invoices' we should have user set a flag on the invoice when they create it
                 //       then when we receive this event we can store the preimage + flag in db for this payment
                 //       user can then manually accept it
@@ -188,21 +579,7 @@ impl EventHandler for LightningNodeEventHandler {
                 let secret = payment_secret.map(|secret| hex_utils::hex_str(&secret.0));
                 let amt_msat: Option<i64> = Some((*amount_msat).try_into().unwrap());
 
-                let existing_payment = self
-                    .database
-                    .find_payment_sync(self.node_id.clone(), payment_hash.clone())
-                    .unwrap_or_else(|_| {
-                        let phantom_node_secret = self
-                            .keys_manager
-                            .get_node_secret(Recipient::PhantomNode)
-                            .unwrap();
-                        let phantom_node_pubkey =
-                            PublicKey::from_secret_key(&self.secp_ctx, &phantom_node_secret)
-                                .to_string();
-                        self.database
-                            .find_payment_sync(phantom_node_pubkey, payment_hash.clone())
-                            .unwrap_or(None)
-                    });
+                let existing_payment = self.find_incoming_payment(&payment_hash);
 
                 match existing_payment {
                     Some(payment) => {
@@ -249,11 +626,23 @@ impl EventHandler for LightningNodeEventHandler {
 
                 if let Ok(Some(payment)) = payment {
                     let amt_msat = payment.amt_msat;
+                    if payment.origin == PaymentOrigin::JitChannel.to_string() {
+                        println!(
+                            "\nEVENT: paid jit channel client, claiming held payment {}",
+                            payment.payment_hash
+                        );
+                        self.channel_manager.claim_funds(*payment_preimage);
+                        self.set_jit_channel_state(&payment.payment_hash, JitChannelState::Paid);
+                    }
 
                     let mut payment: entity::payment::ActiveModel = payment.into();
                     payment.preimage =
                         ActiveValue::Set(Some(hex_utils::hex_str(&payment_preimage.0)));
                     payment.status = ActiveValue::Set(HTLCStatus::Succeeded.to_string());
+                    if let Some(fee_paid_msat) = fee_paid_msat {
+                        payment.fee_paid_msat =
+                            ActiveValue::Set(Some((*fee_paid_msat).try_into().unwrap()));
+                    }
 
                     let _res = self.database.update_payment_sync(payment);
 
@@ -271,12 +660,58 @@ impl EventHandler for LightningNodeEventHandler {
                     );
                 }
             }
-            Event::PaymentPathSuccessful { path: _, .. } => {}
+            Event::PaymentPathSuccessful {
+                payment_id,
+                payment_hash,
+                path,
+            } => {
+                if let Some(payment_hash) = payment_hash {
+                    self.record_payment_attempt(
+                        Some(payment_id),
+                        payment_hash,
+                        path,
+                        HTLCStatus::Succeeded,
+                        None,
+                        None,
+                        None,
+                        false,
+                        false,
+                    );
+                }
+            }
             Event::PaymentPathFailed {
-                path: _,
-                short_channel_id: _,
+                payment_id,
+                payment_hash,
+                rejected_by_dest,
+                network_update,
+                all_paths_failed,
+                path,
+                short_channel_id,
                 ..
-            } => {}
+            } => {
+                let failure_code = PaymentFailureCode::from((*rejected_by_dest, network_update));
+
+                println!(
+                    "\nEVENT: payment path failed for payment hash {} at channel {:?}: {}",
+                    hex_utils::hex_str(&payment_hash.0),
+                    short_channel_id,
+                    failure_code
+                );
+
+                self.record_payment_attempt(
+                    payment_id.as_ref(),
+                    payment_hash,
+                    path,
+                    HTLCStatus::Failed,
+                    Some(failure_code),
+                    *short_channel_id,
+                    network_update
+                        .as_ref()
+                        .map(|update| format!("{:?}", update)),
+                    *rejected_by_dest,
+                    *all_paths_failed,
+                );
+            }
             Event::PaymentFailed { payment_hash, .. } => {
                 print!(
                     "\nEVENT: Failed to send payment to payment hash {:?}: exhausted payment retry attempts",
@@ -290,6 +725,10 @@ impl EventHandler for LightningNodeEventHandler {
                     .find_payment_sync(self.node_id.clone(), hex_payment_hash);
 
                 if let Ok(Some(payment)) = payment {
+                    if payment.origin == PaymentOrigin::JitChannel.to_string() {
+                        self.channel_manager.fail_htlc_backwards(payment_hash);
+                        self.set_jit_channel_state(&payment.payment_hash, JitChannelState::Failed);
+                    }
                     let mut payment: entity::payment::ActiveModel = payment.into();
                     payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
 
@@ -352,25 +791,35 @@ impl EventHandler for LightningNodeEventHandler {
                         "\nEVENT: Forwarded payment{}{}, earning {} msat {}",
                         from_prev_str, to_next_str, fee_earned, from_onchain_str
                     );
-
-                    // let forwarded_payment = ForwardedPayment {
-                    //     hours_since_epoch: utils::hours_since_epoch().unwrap(),
-                    //     from_channel_id: None,
-                    //     to_channel_id: None,
-                    //     fees_earned_msat: *fee_earned,
-                    //     total_payments: 1,
-                    // };
-
-                    // let mut database = self.database.lock().unwrap();
-                    // database
-                    //     .record_forwarded_payment(forwarded_payment)
-                    //     .unwrap();
                 } else {
                     println!(
                         "\nEVENT: Forwarded payment{}{}, claiming onchain {}",
                         from_prev_str, to_next_str, from_onchain_str
                     );
                 }
+
+                let forwarded_payment = entity::forwarded_payment::ActiveModel {
+                    node_id: ActiveValue::Set(self.node_id.clone()),
+                    from_channel_id: ActiveValue::Set(
+                        prev_channel_id.map(|id| hex_utils::hex_str(&id)),
+                    ),
+                    to_channel_id: ActiveValue::Set(
+                        next_channel_id.map(|id| hex_utils::hex_str(&id)),
+                    ),
+                    fees_earned_msat: ActiveValue::Set(
+                        fee_earned_msat.map(|fee| fee.try_into().unwrap()),
+                    ),
+                    claim_from_onchain_tx: ActiveValue::Set(*claim_from_onchain_tx),
+                    hours_since_epoch: ActiveValue::Set(seconds_since_epoch() / 3600),
+                    ..Default::default()
+                };
+
+                if let Err(e) = self
+                    .database
+                    .insert_forwarded_payment_sync(forwarded_payment)
+                {
+                    println!("failed to record forwarded payment: {:?}", e);
+                }
             }
             Event::PendingHTLCsForwardable { time_forwardable } => {
                 let forwarding_channel_manager = self.channel_manager.clone();
@@ -382,28 +831,13 @@ impl EventHandler for LightningNodeEventHandler {
                 });
             }
             Event::SpendableOutputs { outputs } => {
-                let wallet = self.wallet.lock().unwrap();
-                let address_info = wallet.get_address(AddressIndex::LastUnused).unwrap();
-                let destination_address = address_info.address;
-                let output_descriptors = &outputs.iter().collect::<Vec<_>>();
-
-                let tx_feerate = self
-                    .chain_manager
-                    .fee_estimator
-                    .get_est_sat_per_1000_weight(ConfirmationTarget::Normal);
-
-                let spending_tx = self
-                    .keys_manager
-                    .spend_spendable_outputs(
-                        output_descriptors,
-                        Vec::new(),
-                        destination_address.script_pubkey(),
-                        tx_feerate,
-                        &Secp256k1::new(),
-                    )
-                    .unwrap();
-
-                self.broadcaster.broadcast_transaction(&spending_tx);
+                sweep_spendable_outputs(
+                    &self.wallet,
+                    &self.keys_manager,
+                    &self.chain_manager.fee_estimator,
+                    &self.broadcaster,
+                    outputs,
+                );
             }
             Event::ChannelClosed {
                 channel_id,
@@ -424,6 +858,41 @@ impl EventHandler for LightningNodeEventHandler {
                     _ => format!("{:}", reason),
                 };
 
+                let channel_id_hex = hex_utils::hex_str(channel_id);
+                let open_event = self
+                    .database
+                    .find_channel_open_event_sync(self.node_id.clone(), channel_id_hex.clone())
+                    .ok()
+                    .flatten();
+                let channel = self
+                    .database
+                    .find_channel_sync(self.node_id.clone(), channel_id_hex.clone())
+                    .ok()
+                    .flatten();
+                let counterparty_node_id = open_event
+                    .as_ref()
+                    .and_then(|open_event| open_event.counterparty_node_id.clone())
+                    .or_else(|| {
+                        channel
+                            .as_ref()
+                            .map(|channel| channel.counterparty_node_id.clone())
+                    });
+
+                self.record_channel_closed(channel_id, channel, open_event.as_ref(), &reason);
+
+                let channel_event = entity::channel_event::ActiveModel {
+                    node_id: ActiveValue::Set(self.node_id.clone()),
+                    channel_id: ActiveValue::Set(channel_id_hex),
+                    counterparty_node_id: ActiveValue::Set(counterparty_node_id),
+                    kind: ActiveValue::Set(ChannelEventKind::Close.to_string()),
+                    reason: ActiveValue::Set(Some(reason.clone())),
+                    ..Default::default()
+                };
+
+                if let Err(e) = self.database.insert_channel_event_sync(channel_event) {
+                    println!("failed to record channel close: {:?}", e);
+                }
+
                 let _res = self
                     .event_sender
                     .send(SenseiEvent::ChannelClosed {
@@ -444,3 +913,112 @@ impl EventHandler for LightningNodeEventHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::database::test::test_database;
+    use crate::services::node::PaymentAttempt;
+    use lightning::ln::features::{ChannelFeatures, NodeFeatures};
+    use std::str::FromStr;
+
+    fn hop(pubkey: &str, short_channel_id: u64, fee_msat: u64) -> RouteHop {
+        RouteHop {
+            pubkey: PublicKey::from_str(pubkey).unwrap(),
+            node_features: NodeFeatures::empty(),
+            short_channel_id,
+            channel_features: ChannelFeatures::empty(),
+            fee_msat,
+            cltv_expiry_delta: 40,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn record_payment_attempt_test() {
+        let database = test_database().await;
+        let payment_hash = PaymentHash([7; 32]);
+        let path = vec![
+            hop(
+                "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619",
+                1,
+                1_000,
+            ),
+            hop(
+                "0324653eac434488002cc06bbfb7f10fe18991e35f9fe4302dbea6d2353dc0ab1c",
+                2,
+                500,
+            ),
+            hop(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+                3,
+                100_000,
+            ),
+        ];
+
+        database
+            .insert_payment_attempt_sync(new_payment_attempt(
+                String::from("node"),
+                Some(&PaymentId([8; 32])),
+                &payment_hash,
+                &path,
+                HTLCStatus::Failed,
+                Some(PaymentFailureCode::from((false, &None))),
+                Some(2),
+                None,
+                false,
+                true,
+            ))
+            .unwrap();
+        database
+            .insert_payment_attempt_sync(new_payment_attempt(
+                String::from("node"),
+                None,
+                &payment_hash,
+                &[],
+                HTLCStatus::Succeeded,
+                None,
+                None,
+                None,
+                false,
+                false,
+            ))
+            .unwrap();
+
+        let attempts = database
+            .list_payment_attempts(String::from("node"), hex_utils::hex_str(&payment_hash.0))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(PaymentAttempt::from)
+            .collect::<Vec<_>>();
+        assert_eq!(attempts.len(), 2);
+
+        let failed = attempts
+            .iter()
+            .find(|attempt| attempt.status == HTLCStatus::Failed.to_string())
+            .unwrap();
+        // the last hop's fee is what the recipient gets, the rest is routing fees
+        assert_eq!(failed.amt_msat, 100_000);
+        assert_eq!(failed.fee_msat, 1_500);
+        assert_eq!(failed.payment_id, Some(hex_utils::hex_str(&[8; 32])));
+        assert_eq!(failed.failed_short_channel_id, Some(2));
+        assert!(failed.all_paths_failed);
+        assert!(failed.failure_code.is_some());
+        assert_eq!(
+            failed
+                .hops
+                .iter()
+                .map(|hop| hop.short_channel_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        let succeeded = attempts
+            .iter()
+            .find(|attempt| attempt.status == HTLCStatus::Succeeded.to_string())
+            .unwrap();
+        assert_eq!(succeeded.amt_msat, 0);
+        assert_eq!(succeeded.fee_msat, 0);
+        assert!(succeeded.hops.is_empty());
+    }
+}
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use crate::autopilot::AutopilotChannel;
+use crate::health::{ChannelHealth, PeerHealth};
+use crate::htlc_tracker::PendingHtlc;
+
 use bitcoin::{secp256k1::PublicKey, Script, Txid};
 use serde::Serialize;
 use tokio::{sync::broadcast, task::JoinHandle};
@@ -37,6 +41,61 @@ pub enum SenseiEvent {
         user_channel_id: u64,
         reason: String,
     },
+    ChannelFeesUpdated {
+        node_id: String,
+        channel_id: String,
+        old_fee_proportional_millionths: u32,
+        new_fee_proportional_millionths: u32,
+        forwarding_fee_base_msat: Option<u32>,
+        outbound_ratio: f64,
+        recent_forwards: usize,
+    },
+    AutopilotRan {
+        node_id: String,
+        dry_run: bool,
+        channels: Vec<AutopilotChannel>,
+    },
+    HtlcNearExpiry {
+        node_id: String,
+        htlc: PendingHtlc,
+    },
+    InboundChannelRejected {
+        node_id: String,
+        temporary_channel_id: [u8; 32],
+        counterparty_node_id: PublicKey,
+        funding_satoshis: u64,
+        reason: String,
+    },
+    JitChannelPaymentHeld {
+        node_id: String,
+        payment_hash: String,
+        amount_msat: u64,
+    },
+    JitChannelOpened {
+        node_id: String,
+        payment_hash: String,
+        channel_id: Option<String>,
+        error: Option<String>,
+    },
+    WatchtowerJusticeBroadcast {
+        client_pubkey: String,
+        breach_txid: Txid,
+        justice_txid: Txid,
+    },
+    ScheduledPaymentExecuted {
+        node_id: String,
+        scheduled_payment_id: String,
+        payment_hash: Option<String>,
+        error: Option<String>,
+    },
+    ChannelUnhealthy {
+        node_id: String,
+        channel: ChannelHealth,
+    },
+    PeerOffline {
+        node_id: String,
+        peer: PeerHealth,
+    },
 }
 
 pub struct LogNotifier {}
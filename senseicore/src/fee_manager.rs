@@ -0,0 +1,213 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::node::LightningNode;
+use crate::services::ForwardedPaymentsFilter;
+use entity::seconds_since_epoch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often each node checks whether its fee policy is due to run.
+const FEE_MANAGER_TICK_SECS: u64 = 60;
+
+// Forwards within this window count as recent demand for a channel.
+const RECENT_FORWARDS_SECS: i64 = 86400;
+
+pub const MIN_FEE_POLICY_INTERVAL_SECS: u64 = 60;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct FeePolicy {
+    pub enabled: bool,
+    pub min_fee_proportional_millionths: u32,
+    pub max_fee_proportional_millionths: u32,
+    pub forwarding_fee_base_msat: Option<u32>,
+    pub interval_secs: u64,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_fee_proportional_millionths: 1,
+            max_fee_proportional_millionths: 2500,
+            forwarding_fee_base_msat: None,
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl FeePolicy {
+    // Channels with little outbound liquidity left get fees near the max so they
+    // drain slower, channels with plenty get fees near the min so they get used.
+    pub fn target_fee_proportional_millionths(&self, outbound_ratio: f64) -> u32 {
+        let range =
+            (self.max_fee_proportional_millionths - self.min_fee_proportional_millionths) as f64;
+        let outbound_ratio = outbound_ratio.clamp(0.0, 1.0);
+        self.max_fee_proportional_millionths - (range * outbound_ratio).round() as u32
+    }
+}
+
+pub struct FeeManager {
+    node: Arc<LightningNode>,
+}
+
+impl FeeManager {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(FEE_MANAGER_TICK_SECS));
+        let mut last_run_at: Option<i64> = None;
+        loop {
+            interval.tick().await;
+
+            let policy = match self
+                .node
+                .database
+                .get_fee_policy(self.node.id.clone())
+                .await
+            {
+                Ok(policy) => policy,
+                Err(e) => {
+                    println!("ERROR: failed to load fee policy: {}", e);
+                    continue;
+                }
+            };
+
+            let now = seconds_since_epoch();
+            let due = last_run_at
+                .map(|last_run_at| {
+                    now - last_run_at >= policy.interval_secs.try_into().unwrap_or(i64::MAX)
+                })
+                .unwrap_or(true);
+            if !policy.enabled || !due {
+                continue;
+            }
+
+            last_run_at = Some(now);
+            if let Err(e) = self.adjust_fees(&policy, now).await {
+                println!("ERROR: failed to adjust channel fees: {}", e);
+            }
+        }
+    }
+
+    async fn adjust_fees(&self, policy: &FeePolicy, now: i64) -> Result<(), Error> {
+        let forwarded_payments = self
+            .node
+            .database
+            .find_forwarded_payments(
+                self.node.id.clone(),
+                ForwardedPaymentsFilter {
+                    from_hours_since_epoch: Some(((now - RECENT_FORWARDS_SECS) / 3600) as u64),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let mut recent_forwards: HashMap<String, usize> = HashMap::new();
+        for forwarded_payment in forwarded_payments {
+            if let Some(to_channel_id) = forwarded_payment.to_channel_id {
+                *recent_forwards.entry(to_channel_id).or_default() += 1;
+            }
+        }
+
+        for channel in self.node.channel_manager.list_usable_channels() {
+            let channel_id = hex_utils::hex_str(&channel.channel_id);
+            let capacity_msat = channel.outbound_capacity_msat + channel.inbound_capacity_msat;
+            if capacity_msat == 0 {
+                continue;
+            }
+
+            let outbound_ratio = channel.outbound_capacity_msat as f64 / capacity_msat as f64;
+            let current_fee = channel
+                .config
+                .map(|config| config.forwarding_fee_proportional_millionths)
+                .unwrap_or_default();
+            let mut new_fee = policy.target_fee_proportional_millionths(outbound_ratio);
+
+            // a channel that is actively routing has demand, so never cut its fee
+            let forwards = recent_forwards.get(&channel_id).copied().unwrap_or(0);
+            if forwards > 0 {
+                new_fee = new_fee.max(current_fee.min(policy.max_fee_proportional_millionths));
+            }
+
+            let base_fee_changed = policy.forwarding_fee_base_msat.is_some()
+                && channel.config.map(|config| config.forwarding_fee_base_msat)
+                    != policy.forwarding_fee_base_msat;
+            if new_fee == current_fee && !base_fee_changed {
+                continue;
+            }
+
+            self.node.update_channel_config(
+                vec![channel_id.clone()],
+                false,
+                policy.forwarding_fee_base_msat,
+                Some(new_fee),
+                None,
+                None,
+            )?;
+
+            println!(
+                "fee manager: channel {} fee {} -> {} ppm (outbound ratio {:.2}, {} recent forwards)",
+                channel_id, current_fee, new_fee, outbound_ratio, forwards
+            );
+
+            let _res = self
+                .node
+                .event_sender
+                .send(SenseiEvent::ChannelFeesUpdated {
+                    node_id: self.node.id.clone(),
+                    channel_id,
+                    old_fee_proportional_millionths: current_fee,
+                    new_fee_proportional_millionths: new_fee,
+                    forwarding_fee_base_msat: policy.forwarding_fee_base_msat,
+                    outbound_ratio,
+                    recent_forwards: forwards,
+                });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn target_fee_proportional_millionths_test() {
+        let policy = FeePolicy {
+            min_fee_proportional_millionths: 100,
+            max_fee_proportional_millionths: 1100,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.target_fee_proportional_millionths(0.0), 1100);
+        assert_eq!(policy.target_fee_proportional_millionths(0.25), 850);
+        assert_eq!(policy.target_fee_proportional_millionths(0.5), 600);
+        assert_eq!(policy.target_fee_proportional_millionths(1.0), 100);
+
+        // ratios outside of 0..1 are clamped
+        assert_eq!(policy.target_fee_proportional_millionths(-0.5), 1100);
+        assert_eq!(policy.target_fee_proportional_millionths(1.5), 100);
+
+        let flat = FeePolicy {
+            min_fee_proportional_millionths: 250,
+            max_fee_proportional_millionths: 250,
+            ..Default::default()
+        };
+        assert_eq!(flat.target_fee_proportional_millionths(0.3), 250);
+    }
+}
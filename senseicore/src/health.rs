@@ -0,0 +1,395 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::node::LightningNode;
+use bitcoin::secp256k1::PublicKey;
+use entity::seconds_since_epoch;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How often each node checks its health report against its thresholds.
+pub const HEALTH_CHECK_INTERVAL_SECS: u64 = 60;
+
+// A channel is unhealthy once it has been unusable for `channel_unhealthy_after_secs`
+// and a channel peer is offline once it has been disconnected for
+// `peer_offline_after_secs`. Both are reported once per outage.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct HealthPolicy {
+    pub channel_unhealthy_after_secs: u64,
+    pub peer_offline_after_secs: u64,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            channel_unhealthy_after_secs: 3600,
+            peer_offline_after_secs: 3600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerHealthState {
+    pub offline_since: Option<i64>,
+    pub reconnect_attempts: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChannelHealthState {
+    pub last_usable_at: Option<i64>,
+    pub unusable_since: Option<i64>,
+}
+
+// Filled in by the peer connector as it checks every registered node's channels and
+// tries to reconnect their peers. Only covers the time since the node started.
+#[derive(Default)]
+pub struct HealthTracker {
+    peers: Mutex<HashMap<(String, PublicKey), PeerHealthState>>,
+    channels: Mutex<HashMap<(String, [u8; 32]), ChannelHealthState>>,
+}
+
+impl HealthTracker {
+    pub fn channel_checked(&self, node_id: &str, channel_id: [u8; 32], usable: bool, now: i64) {
+        let mut channels = self.channels.lock().unwrap();
+        let state = channels
+            .entry((node_id.to_string(), channel_id))
+            .or_default();
+        if usable {
+            state.last_usable_at = Some(now);
+            state.unusable_since = None;
+        } else if state.unusable_since.is_none() {
+            state.unusable_since = Some(now);
+        }
+    }
+
+    pub fn peer_checked(&self, node_id: &str, pubkey: PublicKey, connected: bool, now: i64) {
+        let mut peers = self.peers.lock().unwrap();
+        let state = peers.entry((node_id.to_string(), pubkey)).or_default();
+        if connected {
+            *state = PeerHealthState::default();
+        } else if state.offline_since.is_none() {
+            state.offline_since = Some(now);
+        }
+    }
+
+    pub fn reconnect_failed(&self, node_id: &str, pubkey: PublicKey, error: String) {
+        let mut peers = self.peers.lock().unwrap();
+        let state = peers.entry((node_id.to_string(), pubkey)).or_default();
+        state.reconnect_attempts += 1;
+        state.last_error = Some(error);
+    }
+
+    pub fn peer(&self, node_id: &str, pubkey: PublicKey) -> PeerHealthState {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(&(node_id.to_string(), pubkey))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn channel(&self, node_id: &str, channel_id: [u8; 32]) -> ChannelHealthState {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(&(node_id.to_string(), channel_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Forgets the channels a node no longer has and the peers it no longer has channels with.
+    pub fn retain_node(
+        &self,
+        node_id: &str,
+        peers: &HashSet<PublicKey>,
+        channel_ids: &HashSet<[u8; 32]>,
+    ) {
+        self.peers
+            .lock()
+            .unwrap()
+            .retain(|(peer_node_id, pubkey), _| peer_node_id != node_id || peers.contains(pubkey));
+        self.channels
+            .lock()
+            .unwrap()
+            .retain(|(channel_node_id, channel_id), _| {
+                channel_node_id != node_id || channel_ids.contains(channel_id)
+            });
+    }
+
+    pub fn remove_node(&self, node_id: &str) {
+        self.peers
+            .lock()
+            .unwrap()
+            .retain(|(peer_node_id, _), _| peer_node_id != node_id);
+        self.channels
+            .lock()
+            .unwrap()
+            .retain(|(channel_node_id, _), _| channel_node_id != node_id);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ChannelHealth {
+    pub channel_id: String,
+    pub counterparty_pubkey: String,
+    pub short_channel_id: Option<u64>,
+    pub is_usable: bool,
+    pub peer_connected: bool,
+    pub last_usable_at: Option<i64>,
+    pub unusable_secs: u64,
+    pub reconnect_attempts: u64,
+    pub last_error: Option<String>,
+    pub healthy: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PeerHealth {
+    pub pubkey: String,
+    pub connected: bool,
+    pub offline_secs: u64,
+    pub reconnect_attempts: u64,
+    pub last_error: Option<String>,
+    pub num_channels: u64,
+    pub offline: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct HealthReport {
+    pub channels: Vec<ChannelHealth>,
+    pub peers: Vec<PeerHealth>,
+    pub unhealthy_channels: u64,
+    pub offline_peers: u64,
+}
+
+fn secs_since(since: Option<i64>, now: i64) -> u64 {
+    since
+        .map(|since| now.saturating_sub(since).max(0) as u64)
+        .unwrap_or(0)
+}
+
+impl LightningNode {
+    pub async fn set_health_policy(&self, policy: &HealthPolicy) -> Result<(), Error> {
+        if policy.channel_unhealthy_after_secs < HEALTH_CHECK_INTERVAL_SECS
+            || policy.peer_offline_after_secs < HEALTH_CHECK_INTERVAL_SECS
+        {
+            return Err(Error::Generic(format!(
+                "thresholds must be at least {} seconds",
+                HEALTH_CHECK_INTERVAL_SECS
+            )));
+        }
+        self.database
+            .set_health_policy(self.id.clone(), policy)
+            .await
+    }
+
+    // Channels still waiting on their funding transaction are left out, they can't be
+    // usable yet.
+    pub async fn health_report(&self) -> Result<HealthReport, Error> {
+        let policy = self.database.get_health_policy(self.id.clone()).await?;
+        let tracker = &self.p2p.peer_connector.health_tracker;
+        let connected_peers = self
+            .peer_manager
+            .get_peer_node_ids()
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let now = seconds_since_epoch();
+
+        let mut report = HealthReport::default();
+        let mut peers: HashMap<PublicKey, PeerHealth> = HashMap::new();
+        for channel in self.channel_manager.list_channels() {
+            if !channel.is_channel_ready {
+                continue;
+            }
+
+            let pubkey = channel.counterparty.node_id;
+            let peer_connected = connected_peers.contains(&pubkey);
+            let peer_state = tracker.peer(&self.id, pubkey);
+            let channel_state = tracker.channel(&self.id, channel.channel_id);
+            let unusable_secs = if channel.is_usable {
+                0
+            } else {
+                secs_since(channel_state.unusable_since, now)
+            };
+            let healthy = channel.is_usable || unusable_secs < policy.channel_unhealthy_after_secs;
+            if !healthy {
+                report.unhealthy_channels += 1;
+            }
+
+            let peer = peers.entry(pubkey).or_insert_with(|| {
+                let offline_secs = if peer_connected {
+                    0
+                } else {
+                    secs_since(peer_state.offline_since, now)
+                };
+                PeerHealth {
+                    pubkey: pubkey.to_string(),
+                    connected: peer_connected,
+                    offline_secs,
+                    reconnect_attempts: peer_state.reconnect_attempts,
+                    last_error: peer_state.last_error.clone(),
+                    num_channels: 0,
+                    offline: !peer_connected && offline_secs >= policy.peer_offline_after_secs,
+                }
+            });
+            peer.num_channels += 1;
+
+            report.channels.push(ChannelHealth {
+                channel_id: hex_utils::hex_str(&channel.channel_id),
+                counterparty_pubkey: pubkey.to_string(),
+                short_channel_id: channel.short_channel_id,
+                is_usable: channel.is_usable,
+                peer_connected,
+                last_usable_at: channel_state.last_usable_at,
+                unusable_secs,
+                reconnect_attempts: peer_state.reconnect_attempts,
+                last_error: peer_state.last_error,
+                healthy,
+            });
+        }
+
+        report.peers = peers.into_values().collect();
+        report.peers.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+        report.offline_peers = report.peers.iter().filter(|peer| peer.offline).count() as u64;
+        Ok(report)
+    }
+}
+
+// Raises an event the first time a channel turns unhealthy or a channel peer goes
+// offline, and again only after it has recovered in between.
+pub struct HealthMonitor {
+    node: Arc<LightningNode>,
+}
+
+impl HealthMonitor {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS));
+        let mut unhealthy_channels: HashSet<String> = HashSet::new();
+        let mut offline_peers: HashSet<String> = HashSet::new();
+        loop {
+            interval.tick().await;
+
+            let report = match self.node.health_report().await {
+                Ok(report) => report,
+                Err(e) => {
+                    println!("ERROR: failed to build health report: {}", e);
+                    continue;
+                }
+            };
+
+            unhealthy_channels.retain(|channel_id| {
+                report
+                    .channels
+                    .iter()
+                    .any(|channel| channel.channel_id == *channel_id && !channel.healthy)
+            });
+            offline_peers.retain(|pubkey| {
+                report
+                    .peers
+                    .iter()
+                    .any(|peer| peer.pubkey == *pubkey && peer.offline)
+            });
+
+            for channel in report.channels {
+                if channel.healthy || !unhealthy_channels.insert(channel.channel_id.clone()) {
+                    continue;
+                }
+                println!(
+                    "WARNING: channel {} with {} has been unusable for {} seconds",
+                    channel.channel_id, channel.counterparty_pubkey, channel.unusable_secs
+                );
+                let _res = self.node.event_sender.send(SenseiEvent::ChannelUnhealthy {
+                    node_id: self.node.id.clone(),
+                    channel,
+                });
+            }
+
+            for peer in report.peers {
+                if !peer.offline || !offline_peers.insert(peer.pubkey.clone()) {
+                    continue;
+                }
+                println!(
+                    "WARNING: peer {} has been offline for {} seconds",
+                    peer.pubkey, peer.offline_secs
+                );
+                let _res = self.node.event_sender.send(SenseiEvent::PeerOffline {
+                    node_id: self.node.id.clone(),
+                    peer,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn health_tracker_test() {
+        let tracker = HealthTracker::default();
+        let pubkey = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let channel_id = [1; 32];
+
+        tracker.channel_checked("node", channel_id, true, 100);
+        tracker.channel_checked("node", channel_id, false, 200);
+        tracker.channel_checked("node", channel_id, false, 300);
+        let channel = tracker.channel("node", channel_id);
+        assert_eq!(channel.last_usable_at, Some(100));
+        assert_eq!(channel.unusable_since, Some(200));
+
+        tracker.peer_checked("node", pubkey, false, 200);
+        tracker.reconnect_failed("node", pubkey, String::from("timed out"));
+        tracker.peer_checked("node", pubkey, false, 300);
+        tracker.reconnect_failed("node", pubkey, String::from("refused"));
+        let peer = tracker.peer("node", pubkey);
+        assert_eq!(peer.offline_since, Some(200));
+        assert_eq!(peer.reconnect_attempts, 2);
+        assert_eq!(peer.last_error, Some(String::from("refused")));
+        assert_eq!(tracker.peer("other", pubkey), PeerHealthState::default());
+
+        tracker.peer_checked("node", pubkey, true, 400);
+        tracker.channel_checked("node", channel_id, true, 400);
+        assert_eq!(tracker.peer("node", pubkey), PeerHealthState::default());
+        assert_eq!(tracker.channel("node", channel_id).unusable_since, None);
+
+        tracker.peer_checked("node", pubkey, false, 500);
+        tracker.peer_checked("other", pubkey, false, 500);
+        tracker.retain_node("node", &HashSet::new(), &HashSet::from([channel_id]));
+        assert_eq!(tracker.peer("node", pubkey), PeerHealthState::default());
+        assert_eq!(tracker.peer("other", pubkey).offline_since, Some(500));
+        assert_eq!(
+            tracker.channel("node", channel_id).last_usable_at,
+            Some(400)
+        );
+
+        tracker.retain_node("node", &HashSet::new(), &HashSet::new());
+        assert_eq!(
+            tracker.channel("node", channel_id),
+            ChannelHealthState::default()
+        );
+
+        tracker.remove_node("other");
+        assert_eq!(tracker.peer("other", pubkey), PeerHealthState::default());
+    }
+}
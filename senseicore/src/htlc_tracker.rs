@@ -0,0 +1,598 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::database::SenseiDatabase;
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::node::{ChannelManager, LightningNode};
+use bitcoin::secp256k1::PublicKey;
+use lightning::ln::features::InitFeatures;
+use lightning::ln::msgs::{self, ChannelMessageHandler};
+use lightning::util::events::{MessageSendEvent, MessageSendEventsProvider};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How often each node checks its in-flight HTLCs for ones close to expiring.
+const HTLC_EXPIRY_CHECK_SECS: u64 = 60;
+
+// LDK goes on-chain to claim an inbound HTLC 18 blocks before it expires, so we
+// warn well before that.
+pub const HTLC_EXPIRY_WARNING_BLOCKS: i64 = 36;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcDirection {
+    Inbound,
+    Outbound,
+}
+
+impl Display for HtlcDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            HtlcDirection::Inbound => "inbound",
+            HtlcDirection::Outbound => "outbound",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PendingHtlc {
+    pub channel_id: String,
+    pub htlc_id: u64,
+    pub direction: HtlcDirection,
+    pub amount_msat: u64,
+    pub payment_hash: String,
+    pub cltv_expiry: u32,
+    pub blocks_until_expiry: i64,
+    // false when the HTLC is for one of our own payments
+    pub forward: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+struct TrackedHtlc {
+    channel_id: [u8; 32],
+    htlc_id: u64,
+    direction: HtlcDirection,
+    amount_msat: u64,
+    payment_hash: [u8; 32],
+    cltv_expiry: u32,
+}
+
+impl TrackedHtlc {
+    fn new(direction: HtlcDirection, msg: &msgs::UpdateAddHTLC) -> Self {
+        Self {
+            channel_id: msg.channel_id,
+            htlc_id: msg.htlc_id,
+            direction,
+            amount_msat: msg.amount_msat,
+            payment_hash: msg.payment_hash.0,
+            cltv_expiry: msg.cltv_expiry,
+        }
+    }
+
+    fn key(&self) -> HtlcKey {
+        (self.channel_id, self.direction, self.htlc_id)
+    }
+}
+
+type HtlcKey = ([u8; 32], HtlcDirection, u64);
+
+const HTLC_KEY_PREFIX: &str = "htlcs/";
+
+fn htlc_key((channel_id, direction, htlc_id): &HtlcKey) -> String {
+    format!(
+        "{}{}/{}/{}",
+        HTLC_KEY_PREFIX,
+        hex_utils::hex_str(channel_id),
+        direction,
+        htlc_id
+    )
+}
+
+// Committed HTLCs are stored so the ones still in flight are known again after a
+// restart. An HTLC the peer adds only counts once the peer signs a commitment with it,
+// until then a disconnect makes both sides forget it.
+struct TrackedHtlcs {
+    node_id: String,
+    database: Arc<SenseiDatabase>,
+    committed: Mutex<HashMap<HtlcKey, TrackedHtlc>>,
+    uncommitted: Mutex<HashMap<HtlcKey, (PublicKey, TrackedHtlc)>>,
+}
+
+impl TrackedHtlcs {
+    async fn load(node_id: String, database: Arc<SenseiDatabase>) -> Result<Self, Error> {
+        let committed = database
+            .list_values(node_id.clone(), String::from(HTLC_KEY_PREFIX))
+            .await?
+            .into_iter()
+            .filter_map(|entry| serde_json::from_slice::<TrackedHtlc>(&entry.v).ok())
+            .map(|htlc| (htlc.key(), htlc))
+            .collect();
+        Ok(Self {
+            node_id,
+            database,
+            committed: Mutex::new(committed),
+            uncommitted: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn commit(&self, htlc: TrackedHtlc) {
+        let key = htlc.key();
+        if let Err(e) = self.database.set_value_sync(
+            self.node_id.clone(),
+            htlc_key(&key),
+            serde_json::to_vec(&htlc).unwrap(),
+        ) {
+            println!("ERROR: failed to store htlc: {}", e);
+        }
+        self.committed.lock().unwrap().insert(key, htlc);
+    }
+
+    fn add_inbound(&self, counterparty_node_id: PublicKey, htlc: TrackedHtlc) {
+        self.uncommitted
+            .lock()
+            .unwrap()
+            .insert(htlc.key(), (counterparty_node_id, htlc));
+    }
+
+    fn commit_inbound(&self, channel_id: [u8; 32]) {
+        let htlcs = {
+            let mut uncommitted = self.uncommitted.lock().unwrap();
+            let keys = uncommitted
+                .keys()
+                .filter(|(htlc_channel_id, _, _)| *htlc_channel_id == channel_id)
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.iter()
+                .filter_map(|key| uncommitted.remove(key))
+                .map(|(_, htlc)| htlc)
+                .collect::<Vec<_>>()
+        };
+        for htlc in htlcs {
+            self.commit(htlc);
+        }
+    }
+
+    fn peer_disconnected(&self, counterparty_node_id: &PublicKey) {
+        self.uncommitted
+            .lock()
+            .unwrap()
+            .retain(|_, (node_id, _)| node_id != counterparty_node_id);
+    }
+
+    fn remove(&self, key: HtlcKey) {
+        if self.committed.lock().unwrap().remove(&key).is_some() {
+            if let Err(e) = self
+                .database
+                .delete_value_sync(self.node_id.clone(), htlc_key(&key))
+            {
+                println!("ERROR: failed to delete htlc: {}", e);
+            }
+        }
+    }
+
+    // HTLCs in channels that have closed since are resolved on-chain instead.
+    async fn pending(&self, channel_ids: &HashSet<[u8; 32]>) -> Vec<TrackedHtlc> {
+        let (pending, closed) = {
+            let mut committed = self.committed.lock().unwrap();
+            let closed = committed
+                .keys()
+                .filter(|(channel_id, _, _)| !channel_ids.contains(channel_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in closed.iter() {
+                committed.remove(key);
+            }
+            (committed.values().cloned().collect::<Vec<_>>(), closed)
+        };
+        for key in closed.iter() {
+            if let Err(e) = self
+                .database
+                .delete_value(self.node_id.clone(), htlc_key(key))
+                .await
+            {
+                println!("ERROR: failed to delete htlc: {}", e);
+            }
+        }
+        pending
+    }
+}
+
+// LDK doesn't expose the HTLCs in a channel, so this sits between the peer manager
+// and the channel manager and follows the update_add_htlc messages and their
+// fulfills and fails in both directions.
+pub struct HtlcTracker {
+    channel_manager: Arc<ChannelManager>,
+    htlcs: TrackedHtlcs,
+}
+
+impl HtlcTracker {
+    pub async fn new(
+        node_id: String,
+        database: Arc<SenseiDatabase>,
+        channel_manager: Arc<ChannelManager>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            channel_manager,
+            htlcs: TrackedHtlcs::load(node_id, database).await?,
+        })
+    }
+
+    async fn pending_htlcs(&self) -> Vec<TrackedHtlc> {
+        let channel_ids = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .map(|channel| channel.channel_id)
+            .collect::<HashSet<_>>();
+        self.htlcs.pending(&channel_ids).await
+    }
+}
+
+impl MessageSendEventsProvider for HtlcTracker {
+    fn get_and_clear_pending_msg_events(&self) -> Vec<MessageSendEvent> {
+        let events = self.channel_manager.get_and_clear_pending_msg_events();
+        for event in events.iter() {
+            if let MessageSendEvent::UpdateHTLCs { updates, .. } = event {
+                // LDK keeps the adds it has signed for and resends them after a disconnect
+                for msg in updates.update_add_htlcs.iter() {
+                    self.htlcs
+                        .commit(TrackedHtlc::new(HtlcDirection::Outbound, msg));
+                }
+                for msg in updates.update_fulfill_htlcs.iter() {
+                    self.htlcs
+                        .remove((msg.channel_id, HtlcDirection::Inbound, msg.htlc_id));
+                }
+                for msg in updates.update_fail_htlcs.iter() {
+                    self.htlcs
+                        .remove((msg.channel_id, HtlcDirection::Inbound, msg.htlc_id));
+                }
+                for msg in updates.update_fail_malformed_htlcs.iter() {
+                    self.htlcs
+                        .remove((msg.channel_id, HtlcDirection::Inbound, msg.htlc_id));
+                }
+            }
+        }
+        events
+    }
+}
+
+impl ChannelMessageHandler for HtlcTracker {
+    fn handle_open_channel(
+        &self,
+        their_node_id: &PublicKey,
+        their_features: InitFeatures,
+        msg: &msgs::OpenChannel,
+    ) {
+        self.channel_manager
+            .handle_open_channel(their_node_id, their_features, msg)
+    }
+
+    fn handle_accept_channel(
+        &self,
+        their_node_id: &PublicKey,
+        their_features: InitFeatures,
+        msg: &msgs::AcceptChannel,
+    ) {
+        self.channel_manager
+            .handle_accept_channel(their_node_id, their_features, msg)
+    }
+
+    fn handle_funding_created(&self, their_node_id: &PublicKey, msg: &msgs::FundingCreated) {
+        self.channel_manager
+            .handle_funding_created(their_node_id, msg)
+    }
+
+    fn handle_funding_signed(&self, their_node_id: &PublicKey, msg: &msgs::FundingSigned) {
+        self.channel_manager
+            .handle_funding_signed(their_node_id, msg)
+    }
+
+    fn handle_channel_ready(&self, their_node_id: &PublicKey, msg: &msgs::ChannelReady) {
+        self.channel_manager
+            .handle_channel_ready(their_node_id, msg)
+    }
+
+    fn handle_shutdown(
+        &self,
+        their_node_id: &PublicKey,
+        their_features: &InitFeatures,
+        msg: &msgs::Shutdown,
+    ) {
+        self.channel_manager
+            .handle_shutdown(their_node_id, their_features, msg)
+    }
+
+    fn handle_closing_signed(&self, their_node_id: &PublicKey, msg: &msgs::ClosingSigned) {
+        self.channel_manager
+            .handle_closing_signed(their_node_id, msg)
+    }
+
+    fn handle_update_add_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateAddHTLC) {
+        self.htlcs.add_inbound(
+            *their_node_id,
+            TrackedHtlc::new(HtlcDirection::Inbound, msg),
+        );
+        self.channel_manager
+            .handle_update_add_htlc(their_node_id, msg)
+    }
+
+    fn handle_update_fulfill_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFulfillHTLC) {
+        self.htlcs
+            .remove((msg.channel_id, HtlcDirection::Outbound, msg.htlc_id));
+        self.channel_manager
+            .handle_update_fulfill_htlc(their_node_id, msg)
+    }
+
+    fn handle_update_fail_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFailHTLC) {
+        self.htlcs
+            .remove((msg.channel_id, HtlcDirection::Outbound, msg.htlc_id));
+        self.channel_manager
+            .handle_update_fail_htlc(their_node_id, msg)
+    }
+
+    fn handle_update_fail_malformed_htlc(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &msgs::UpdateFailMalformedHTLC,
+    ) {
+        self.htlcs
+            .remove((msg.channel_id, HtlcDirection::Outbound, msg.htlc_id));
+        self.channel_manager
+            .handle_update_fail_malformed_htlc(their_node_id, msg)
+    }
+
+    fn handle_commitment_signed(&self, their_node_id: &PublicKey, msg: &msgs::CommitmentSigned) {
+        self.htlcs.commit_inbound(msg.channel_id);
+        self.channel_manager
+            .handle_commitment_signed(their_node_id, msg)
+    }
+
+    fn handle_revoke_and_ack(&self, their_node_id: &PublicKey, msg: &msgs::RevokeAndACK) {
+        self.channel_manager
+            .handle_revoke_and_ack(their_node_id, msg)
+    }
+
+    fn handle_update_fee(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFee) {
+        self.channel_manager.handle_update_fee(their_node_id, msg)
+    }
+
+    fn handle_announcement_signatures(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &msgs::AnnouncementSignatures,
+    ) {
+        self.channel_manager
+            .handle_announcement_signatures(their_node_id, msg)
+    }
+
+    fn peer_disconnected(&self, their_node_id: &PublicKey, no_connection_possible: bool) {
+        self.htlcs.peer_disconnected(their_node_id);
+        self.channel_manager
+            .peer_disconnected(their_node_id, no_connection_possible)
+    }
+
+    fn peer_connected(&self, their_node_id: &PublicKey, msg: &msgs::Init) {
+        self.channel_manager.peer_connected(their_node_id, msg)
+    }
+
+    fn handle_channel_reestablish(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &msgs::ChannelReestablish,
+    ) {
+        self.channel_manager
+            .handle_channel_reestablish(their_node_id, msg)
+    }
+
+    fn handle_channel_update(&self, their_node_id: &PublicKey, msg: &msgs::ChannelUpdate) {
+        self.channel_manager
+            .handle_channel_update(their_node_id, msg)
+    }
+
+    fn handle_error(&self, their_node_id: &PublicKey, msg: &msgs::ErrorMessage) {
+        self.channel_manager.handle_error(their_node_id, msg)
+    }
+}
+
+impl LightningNode {
+    pub async fn list_htlcs(&self, channel_id: Option<String>) -> Result<Vec<PendingHtlc>, Error> {
+        let best_block_height = self.channel_manager.current_best_block().height();
+        let phantom_node_pubkey = self.get_phantom_node_pubkey();
+
+        let mut pending_htlcs = vec![];
+        for htlc in self.htlc_tracker.pending_htlcs().await {
+            let htlc_channel_id = hex_utils::hex_str(&htlc.channel_id);
+            if channel_id
+                .as_ref()
+                .map(|channel_id| *channel_id != htlc_channel_id)
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            // our own payments have a payment record, forwards don't
+            let payment_hash = hex_utils::hex_str(&htlc.payment_hash);
+            let own_payment = self
+                .database
+                .find_payment(self.id.clone(), payment_hash.clone())
+                .await?
+                .is_some()
+                || self
+                    .database
+                    .find_payment(phantom_node_pubkey.clone(), payment_hash.clone())
+                    .await?
+                    .is_some();
+
+            pending_htlcs.push(PendingHtlc {
+                channel_id: htlc_channel_id,
+                htlc_id: htlc.htlc_id,
+                direction: htlc.direction,
+                amount_msat: htlc.amount_msat,
+                payment_hash,
+                cltv_expiry: htlc.cltv_expiry,
+                blocks_until_expiry: htlc.cltv_expiry as i64 - best_block_height as i64,
+                forward: !own_payment,
+            });
+        }
+
+        pending_htlcs.sort_by_key(|htlc| htlc.cltv_expiry);
+        Ok(pending_htlcs)
+    }
+}
+
+// Warns once about every HTLC that gets close to expiring, since a stuck HTLC
+// reaching its expiry is what makes a channel force-close.
+pub struct HtlcExpiryMonitor {
+    node: Arc<LightningNode>,
+}
+
+impl HtlcExpiryMonitor {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(HTLC_EXPIRY_CHECK_SECS));
+        let mut warned: HashSet<(String, HtlcDirection, u64)> = HashSet::new();
+        loop {
+            interval.tick().await;
+
+            let htlcs = match self.node.list_htlcs(None).await {
+                Ok(htlcs) => htlcs,
+                Err(e) => {
+                    println!("ERROR: failed to list htlcs: {}", e);
+                    continue;
+                }
+            };
+
+            let pending = htlcs
+                .iter()
+                .map(|htlc| (htlc.channel_id.clone(), htlc.direction, htlc.htlc_id))
+                .collect::<HashSet<_>>();
+            warned.retain(|key| pending.contains(key));
+
+            for htlc in htlcs {
+                if htlc.blocks_until_expiry > HTLC_EXPIRY_WARNING_BLOCKS
+                    || !warned.insert((htlc.channel_id.clone(), htlc.direction, htlc.htlc_id))
+                {
+                    continue;
+                }
+
+                println!(
+                    "WARNING: {} htlc {} of {} msats in channel {} expires in {} blocks",
+                    htlc.direction,
+                    htlc.payment_hash,
+                    htlc.amount_msat,
+                    htlc.channel_id,
+                    htlc.blocks_until_expiry
+                );
+                let _res = self.node.event_sender.send(SenseiEvent::HtlcNearExpiry {
+                    node_id: self.node.id.clone(),
+                    htlc,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use lightning::ln::PaymentHash;
+
+    fn update_add_htlc(channel_id: [u8; 32], htlc_id: u64) -> msgs::UpdateAddHTLC {
+        let mut msg: msgs::UpdateAddHTLC =
+            lightning::util::ser::Readable::read(&mut std::io::Cursor::new(
+                [
+                    &channel_id[..],
+                    &htlc_id.to_be_bytes(),
+                    &1_000u64.to_be_bytes(),
+                    &[1; 32],
+                    &500u32.to_be_bytes(),
+                    // an onion packet with version 0, a valid public key and empty hops
+                    &[0],
+                    &PublicKey::from_secret_key(
+                        &Secp256k1::new(),
+                        &SecretKey::from_slice(&[1; 32]).unwrap(),
+                    )
+                    .serialize(),
+                    &[0; 20 * 65],
+                    &[0; 32],
+                ]
+                .concat(),
+            ))
+            .unwrap();
+        msg.payment_hash = PaymentHash([htlc_id as u8; 32]);
+        msg
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tracked_htlcs_test() {
+        let database = Arc::new(crate::database::test::test_database().await);
+        let node_id = String::from("node");
+        let peer = PublicKey::from_secret_key(
+            &Secp256k1::new(),
+            &SecretKey::from_slice(&[2; 32]).unwrap(),
+        );
+        let channel_ids = [[1; 32], [2; 32]].into_iter().collect::<HashSet<_>>();
+
+        let htlcs = TrackedHtlcs::load(node_id.clone(), database.clone())
+            .await
+            .unwrap();
+        htlcs.commit(TrackedHtlc::new(
+            HtlcDirection::Outbound,
+            &update_add_htlc([1; 32], 0),
+        ));
+        htlcs.add_inbound(
+            peer,
+            TrackedHtlc::new(HtlcDirection::Inbound, &update_add_htlc([2; 32], 0)),
+        );
+        htlcs.add_inbound(
+            peer,
+            TrackedHtlc::new(HtlcDirection::Inbound, &update_add_htlc([2; 32], 1)),
+        );
+        assert_eq!(htlcs.pending(&channel_ids).await.len(), 1);
+
+        // the peer signed for the first add, the second is forgotten on disconnect
+        htlcs.commit_inbound([2; 32]);
+        htlcs.add_inbound(
+            peer,
+            TrackedHtlc::new(HtlcDirection::Inbound, &update_add_htlc([2; 32], 2)),
+        );
+        htlcs.peer_disconnected(&peer);
+        htlcs.commit_inbound([2; 32]);
+        assert_eq!(htlcs.pending(&channel_ids).await.len(), 3);
+
+        htlcs.remove(([2; 32], HtlcDirection::Inbound, 1));
+        let reloaded = TrackedHtlcs::load(node_id.clone(), database.clone())
+            .await
+            .unwrap();
+        let mut pending = reloaded.pending(&channel_ids).await;
+        pending.sort_by_key(|htlc| htlc.channel_id);
+        assert_eq!(
+            pending.iter().map(|htlc| htlc.key()).collect::<Vec<_>>(),
+            vec![
+                ([1; 32], HtlcDirection::Outbound, 0),
+                ([2; 32], HtlcDirection::Inbound, 0),
+            ]
+        );
+
+        // closing a channel drops its htlcs for good
+        reloaded.pending(&[[2; 32]].into_iter().collect()).await;
+        let reloaded = TrackedHtlcs::load(node_id, database).await.unwrap();
+        assert_eq!(reloaded.pending(&channel_ids).await.len(), 1);
+    }
+}
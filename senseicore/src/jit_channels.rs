@@ -0,0 +1,520 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::node::{HTLCStatus, LightningNode, PaymentOrigin};
+use crate::services::node::OpenChannelRequest;
+use bitcoin::hashes::Hash;
+use entity::sea_orm::{ActiveModelTrait, ActiveValue};
+use entity::seconds_since_epoch;
+use lightning::ln::PaymentHash;
+use lightning_invoice::{Invoice, InvoiceBuilder, InvoiceDescription, SignOrCreationError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+
+// Blocks added on top of the client's final cltv expiry, so the payment we hold doesn't
+// expire while we open the channel and pay the client.
+const JIT_CLTV_EXPIRY_DELTA: u64 = 72;
+
+// How long a 0-conf channel gets to become usable once it has been funded.
+const JIT_CHANNEL_READY_TIMEOUT_SECS: u64 = 60;
+
+// LDK 0.0.110 can't intercept HTLCs for a fake scid, so instead of forwarding the
+// payment we wrap the client's invoice in one of ours for the same payment hash. We hold
+// the payment when it arrives, open a 0-conf channel to the client and pay their invoice
+// over it, then claim the held payment with the preimage the client reveals.
+//
+// The client's invoice fixes what they receive, so rather than forwarding the payment
+// minus the opening fee, our invoice adds the fee on top and the payer covers it. A
+// client that wants to receive the amount minus the fee invoices for that amount.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct JitChannelPolicy {
+    pub enabled: bool,
+    pub opening_fee_base_msat: u64,
+    pub opening_fee_proportional_millionths: u32,
+    pub min_channel_size_sats: u64,
+    pub max_channel_size_sats: u64,
+}
+
+impl Default for JitChannelPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            opening_fee_base_msat: 2_000_000,
+            opening_fee_proportional_millionths: 10_000,
+            min_channel_size_sats: 100_000,
+            max_channel_size_sats: 5_000_000,
+        }
+    }
+}
+
+impl JitChannelPolicy {
+    pub fn opening_fee_msat(&self, amt_msat: u64) -> u64 {
+        self.opening_fee_base_msat
+            + amt_msat * u64::from(self.opening_fee_proportional_millionths) / 1_000_000
+    }
+
+    // Channels are twice the payment so the client can keep receiving over them once the
+    // reserve is taken out. None when that would be over the maximum.
+    pub fn channel_size_sats(&self, amt_msat: u64) -> Option<u64> {
+        let channel_size_sats = ((amt_msat + 999) / 1000 * 2).max(self.min_channel_size_sats);
+        (channel_size_sats <= self.max_channel_size_sats).then(|| channel_size_sats)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JitChannelState {
+    // waiting for the payer
+    Created,
+    // holding the payer's payment until the client is paid
+    Held,
+    Opening,
+    Opened,
+    // paying the client's invoice, what happens next is up to the payment's events
+    Paying,
+    Paid,
+    // the held payment was failed back, the payer may still retry
+    Failed,
+}
+
+// #[default] on enum variants needs a newer compiler than the one we build with
+#[allow(clippy::derivable_impls)]
+impl Default for JitChannelState {
+    fn default() -> Self {
+        JitChannelState::Created
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct JitChannel {
+    pub payment_hash: String,
+    pub client_pubkey: String,
+    pub client_invoice: String,
+    pub opening_fee_msat: u64,
+    pub channel_size_sats: u64,
+    #[serde(default)]
+    pub state: JitChannelState,
+    // set once the channel is opened so a retried payment reuses it
+    #[serde(default)]
+    pub channel_id: Option<String>,
+}
+
+impl LightningNode {
+    pub async fn create_jit_invoice(
+        &self,
+        client_invoice: &str,
+    ) -> Result<(Invoice, JitChannel), Error> {
+        let policy = self
+            .database
+            .get_jit_channel_policy(self.id.clone())
+            .await?;
+        if !policy.enabled {
+            return Err(Error::Generic(String::from(
+                "jit channels are not enabled for this node",
+            )));
+        }
+
+        let client_invoice = Invoice::from_str(client_invoice)
+            .map_err(|_| Error::Generic(String::from("invalid client invoice")))?;
+        if client_invoice.is_expired() {
+            return Err(Error::Generic(String::from("client invoice has expired")));
+        }
+        let client_pubkey = client_invoice.recover_payee_pub_key();
+        if !self
+            .peer_manager
+            .get_peer_node_ids()
+            .contains(&client_pubkey)
+        {
+            return Err(Error::Generic(String::from(
+                "the client must be connected to open a jit channel",
+            )));
+        }
+        let amt_msat = client_invoice
+            .amount_milli_satoshis()
+            .ok_or_else(|| Error::Generic(String::from("client invoice must have an amount")))?;
+        let channel_size_sats = policy.channel_size_sats(amt_msat).ok_or_else(|| {
+            Error::Generic(String::from("payment is too large for a jit channel"))
+        })?;
+        let opening_fee_msat = policy.opening_fee_msat(amt_msat);
+
+        // our invoice can't outlive the one we'll be paying
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let expiry_secs: u32 = (client_invoice.duration_since_epoch()
+            + client_invoice.expiry_time())
+        .saturating_sub(now)
+        .as_secs()
+        .try_into()
+        .unwrap_or(u32::MAX);
+
+        let payment_hash = PaymentHash((*client_invoice.payment_hash()).into_inner());
+        let payment_secret = self
+            .channel_manager
+            .create_inbound_payment_for_hash(
+                payment_hash,
+                Some(amt_msat + opening_fee_msat),
+                expiry_secs,
+            )
+            .map_err(|_| Error::Generic(String::from("invalid invoice amount")))?;
+
+        let builder = match client_invoice.description() {
+            InvoiceDescription::Direct(description) => InvoiceBuilder::new(self.invoice_currency())
+                .description(description.clone().into_inner()),
+            InvoiceDescription::Hash(hash) => {
+                InvoiceBuilder::new(self.invoice_currency()).description_hash(hash.0)
+            }
+        };
+        let raw_invoice = builder
+            .duration_since_epoch(now)
+            .payee_pub_key(self.channel_manager.get_our_node_id())
+            .payment_hash(*client_invoice.payment_hash())
+            .payment_secret(payment_secret)
+            .basic_mpp()
+            .min_final_cltv_expiry(client_invoice.min_final_cltv_expiry() + JIT_CLTV_EXPIRY_DELTA)
+            .expiry_time(Duration::from_secs(expiry_secs.into()))
+            .amount_milli_satoshis(amt_msat + opening_fee_msat)
+            .build_raw()
+            .map_err(SignOrCreationError::CreationError)?;
+        let invoice = self.sign_invoice(raw_invoice)?;
+
+        let jit_channel = JitChannel {
+            payment_hash: hex_utils::hex_str(&payment_hash.0),
+            client_pubkey: client_pubkey.to_string(),
+            client_invoice: client_invoice.to_string(),
+            opening_fee_msat,
+            channel_size_sats,
+            state: JitChannelState::Created,
+            channel_id: None,
+        };
+        self.database
+            .set_jit_channel(self.id.clone(), &jit_channel)
+            .await?;
+
+        let payment = entity::payment::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            created_by_node_id: ActiveValue::Set(self.id.clone()),
+            payment_hash: ActiveValue::Set(jit_channel.payment_hash.clone()),
+            secret: ActiveValue::Set(Some(hex_utils::hex_str(&payment_secret.0))),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            amt_msat: ActiveValue::Set(Some((amt_msat + opening_fee_msat).try_into().unwrap())),
+            origin: ActiveValue::Set(PaymentOrigin::JitChannel.to_string()),
+            invoice: ActiveValue::Set(Some(invoice.to_string())),
+            label: ActiveValue::Set(Some(format!("jit channel to {}", client_pubkey))),
+            expires_at: ActiveValue::Set(Some(seconds_since_epoch() + i64::from(expiry_secs))),
+            ..Default::default()
+        };
+        payment.insert(self.database.get_connection()).await?;
+
+        Ok((invoice, jit_channel))
+    }
+
+    async fn set_jit_channel_state(&self, payment_hash: &str, state: JitChannelState) {
+        if let Err(e) = self
+            .database
+            .set_jit_channel_state(self.id.clone(), payment_hash.to_string(), state)
+            .await
+        {
+            println!(
+                "ERROR: failed to update jit channel {}: {}",
+                payment_hash, e
+            );
+        }
+    }
+
+    // The channel opened for an earlier attempt at this payment, if it's still open.
+    fn existing_jit_channel(&self, jit_channel: &JitChannel) -> Option<[u8; 32]> {
+        let channel_id = jit_channel.channel_id.as_ref()?;
+        self.channel_manager
+            .list_channels()
+            .into_iter()
+            .map(|channel| channel.channel_id)
+            .find(|id| hex_utils::hex_str(id) == *channel_id)
+    }
+
+    // Opens the channel for a payment we're holding, or reuses the one opened for an
+    // earlier attempt, and pays the client's invoice over it. Returns the channel's id.
+    pub async fn open_jit_channel(&self, payment_hash: &str) -> Result<String, Error> {
+        let mut jit_channel = self
+            .database
+            .get_jit_channel(self.id.clone(), payment_hash.to_string())
+            .await?
+            .ok_or_else(|| Error::Generic(String::from("unknown jit channel")))?;
+        let client_invoice = Invoice::from_str(&jit_channel.client_invoice)
+            .map_err(|_| Error::Generic(String::from("invalid client invoice")))?;
+        let client_pubkey = client_invoice.recover_payee_pub_key();
+        if !self
+            .peer_manager
+            .get_peer_node_ids()
+            .contains(&client_pubkey)
+        {
+            return Err(Error::Generic(String::from(
+                "client is no longer connected",
+            )));
+        }
+
+        let channel_id = match self.existing_jit_channel(&jit_channel) {
+            Some(channel_id) => channel_id,
+            None => {
+                self.set_jit_channel_state(payment_hash, JitChannelState::Opening)
+                    .await;
+                // the client only accepts it as 0-conf when we're one of its zero_conf known peers
+                let (_request, result) = self
+                    .open_channels(vec![OpenChannelRequest {
+                        counterparty_pubkey: jit_channel.client_pubkey.clone(),
+                        amount_sats: jit_channel.channel_size_sats,
+                        public: false,
+                        scid_alias: None,
+                        upfront_shutdown_script: None,
+                        custom_id: None,
+                        push_amount_msats: None,
+                        counterparty_host_port: None,
+                        forwarding_fee_proportional_millionths: None,
+                        forwarding_fee_base_msat: None,
+                        cltv_expiry_delta: None,
+                        max_dust_htlc_exposure_msat: None,
+                        force_close_avoidance_max_fee_satoshis: None,
+                    }])
+                    .await?
+                    .pop()
+                    .unwrap();
+                let channel_id = result?;
+
+                jit_channel.state = JitChannelState::Opening;
+                jit_channel.channel_id = Some(hex_utils::hex_str(&channel_id));
+                self.database
+                    .set_jit_channel(self.id.clone(), &jit_channel)
+                    .await?;
+                channel_id
+            }
+        };
+
+        let amt_msat = client_invoice.amount_milli_satoshis().unwrap_or(0);
+        let ready_by = seconds_since_epoch() + JIT_CHANNEL_READY_TIMEOUT_SECS as i64;
+        while !self
+            .channel_manager
+            .list_usable_channels()
+            .iter()
+            .any(|channel| {
+                channel.channel_id == channel_id && channel.outbound_capacity_msat >= amt_msat
+            })
+        {
+            if seconds_since_epoch() > ready_by {
+                return Err(Error::Generic(String::from(
+                    "jit channel did not become usable in time",
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        self.set_jit_channel_state(payment_hash, JitChannelState::Opened)
+            .await;
+
+        // the held payment is claimed once this succeeds, see PaymentSent in the event handler
+        self.set_jit_channel_state(payment_hash, JitChannelState::Paying)
+            .await;
+        self.invoice_payer.pay_invoice(&client_invoice)?;
+
+        Ok(hex_utils::hex_str(&channel_id))
+    }
+
+    async fn fail_jit_payment(&self, payment_hash: &str) {
+        self.set_jit_channel_state(payment_hash, JitChannelState::Failed)
+            .await;
+        if let Some(hash) = hex_utils::to_vec(payment_hash).and_then(|hash| hash.try_into().ok()) {
+            self.channel_manager.fail_htlc_backwards(&PaymentHash(hash));
+        }
+
+        if let Ok(Some(payment)) = self
+            .database
+            .find_payment(self.id.clone(), payment_hash.to_string())
+            .await
+        {
+            let mut payment: entity::payment::ActiveModel = payment.into();
+            payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+            let _res = payment.update(self.database.get_connection()).await;
+        }
+    }
+}
+
+// Opens channels for the jit payments the event handler is holding.
+pub struct JitChannelManager {
+    node: Arc<LightningNode>,
+    in_progress: Arc<Mutex<HashSet<String>>>,
+}
+
+impl JitChannelManager {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self {
+            node,
+            in_progress: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    fn open_jit_channel(&self, payment_hash: String) {
+        if !self
+            .in_progress
+            .lock()
+            .unwrap()
+            .insert(payment_hash.clone())
+        {
+            return;
+        }
+
+        // opening the channel takes a while, other payments shouldn't wait on it
+        let node = self.node.clone();
+        let in_progress = self.in_progress.clone();
+        tokio::spawn(async move {
+            let result = node.open_jit_channel(&payment_hash).await;
+            if let Err(e) = result.as_ref() {
+                println!(
+                    "ERROR: failed to open jit channel for {}: {}",
+                    payment_hash, e
+                );
+                node.fail_jit_payment(&payment_hash).await;
+            }
+            in_progress.lock().unwrap().remove(&payment_hash);
+            let _res = node.event_sender.send(SenseiEvent::JitChannelOpened {
+                node_id: node.id.clone(),
+                payment_hash,
+                channel_id: result.as_ref().ok().cloned(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        });
+    }
+
+    async fn held_payments(&self) -> Vec<JitChannel> {
+        match self
+            .node
+            .database
+            .list_jit_channels(self.node.id.clone())
+            .await
+        {
+            Ok(jit_channels) => jit_channels
+                .into_iter()
+                .filter(|jit_channel| {
+                    matches!(
+                        jit_channel.state,
+                        JitChannelState::Held | JitChannelState::Opening | JitChannelState::Opened
+                    )
+                })
+                .collect(),
+            Err(e) => {
+                println!("ERROR: failed to list jit channels: {}", e);
+                vec![]
+            }
+        }
+    }
+
+    // We can't tell whether a payment held before a restart is still claimable, so it's
+    // failed back instead of paying the client. A retry reuses any channel we opened.
+    async fn fail_interrupted_payments(&self) {
+        for jit_channel in self.held_payments().await {
+            self.node.fail_jit_payment(&jit_channel.payment_hash).await;
+        }
+    }
+
+    // Picks up the held payments whose events we missed.
+    async fn resume_held_payments(&self) {
+        for jit_channel in self.held_payments().await {
+            self.open_jit_channel(jit_channel.payment_hash);
+        }
+    }
+
+    pub async fn run(self) {
+        let mut event_receiver = self.node.event_sender.subscribe();
+        self.fail_interrupted_payments().await;
+        loop {
+            match event_receiver.recv().await {
+                Ok(SenseiEvent::JitChannelPaymentHeld {
+                    node_id,
+                    payment_hash,
+                    ..
+                }) if node_id == self.node.id => self.open_jit_channel(payment_hash),
+                Err(RecvError::Lagged(_)) => self.resume_held_payments().await,
+                Err(RecvError::Closed) => return,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jit_channel_policy_test() {
+        let policy = JitChannelPolicy {
+            enabled: true,
+            opening_fee_base_msat: 1_000_000,
+            opening_fee_proportional_millionths: 10_000,
+            min_channel_size_sats: 100_000,
+            max_channel_size_sats: 1_000_000,
+        };
+
+        assert_eq!(policy.opening_fee_msat(50_000_000), 1_500_000);
+        assert_eq!(policy.channel_size_sats(20_000_000), Some(100_000));
+        assert_eq!(policy.channel_size_sats(300_000_500), Some(600_002));
+        assert_eq!(policy.channel_size_sats(600_000_000), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn jit_channel_state_test() {
+        let database = crate::database::test::test_database().await;
+        let node_id = String::from("node");
+
+        // channels stored before they had a state are waiting for the payer
+        database
+            .set_value(
+                node_id.clone(),
+                String::from("jit_channel/00"),
+                br#"{"payment_hash":"00","client_pubkey":"client","client_invoice":"invoice","opening_fee_msat":1000,"channel_size_sats":100000}"#.to_vec(),
+            )
+            .await
+            .unwrap();
+        let mut jit_channel = database
+            .get_jit_channel(node_id.clone(), String::from("00"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(jit_channel.state, JitChannelState::Created);
+        assert_eq!(jit_channel.channel_id, None);
+
+        jit_channel.state = JitChannelState::Opening;
+        jit_channel.channel_id = Some(String::from("11"));
+        database
+            .set_jit_channel(node_id.clone(), &jit_channel)
+            .await
+            .unwrap();
+        database
+            .set_jit_channel_state(node_id.clone(), String::from("00"), JitChannelState::Failed)
+            .await
+            .unwrap();
+        assert_eq!(
+            database
+                .set_jit_channel_state(node_id.clone(), String::from("22"), JitChannelState::Held)
+                .await
+                .unwrap()
+                .map(|jit_channel| jit_channel.payment_hash),
+            None
+        );
+
+        // the channel opened for the failed attempt is kept for a retry
+        let jit_channels = database.list_jit_channels(node_id).await.unwrap();
+        assert_eq!(jit_channels.len(), 1);
+        assert_eq!(jit_channels[0].state, JitChannelState::Failed);
+        assert_eq!(jit_channels[0].channel_id, Some(String::from("11")));
+    }
+}
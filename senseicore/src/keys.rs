@@ -0,0 +1,149 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use bitcoin::bech32::u5;
+use bitcoin::secp256k1::ecdsa::RecoverableSignature;
+use bitcoin::secp256k1::{Secp256k1, SecretKey, Signing};
+use bitcoin::{Script, Transaction, TxOut};
+use lightning::chain::keysinterface::{
+    InMemorySigner, KeyMaterial, KeysInterface, PhantomKeysManager, Recipient,
+    SpendableOutputDescriptor,
+};
+use lightning::ln::msgs::DecodeError;
+use lightning::ln::script::ShutdownScript;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+// Wraps the phantom keys manager so a cooperative close can pay out to a script
+// chosen at close time instead of one derived from the node's keys.
+pub struct SenseiKeysManager {
+    inner: PhantomKeysManager,
+    // only handed out on the thread running the close, a shutdown started by a peer
+    // message on another thread gets the node's own script
+    next_shutdown_script: Mutex<Option<(ThreadId, ShutdownScript)>>,
+    close_lock: Mutex<()>,
+}
+
+impl SenseiKeysManager {
+    pub fn new(inner: PhantomKeysManager) -> Self {
+        Self {
+            inner,
+            next_shutdown_script: Mutex::new(None),
+            close_lock: Mutex::new(()),
+        }
+    }
+
+    // Runs `close` with `shutdown_script` handed to the channel that starts shutting down.
+    // Channels that committed to a shutdown script when they were opened never ask for one,
+    // so the returned flag tells whether the script was actually used.
+    pub fn with_shutdown_script<R>(
+        &self,
+        shutdown_script: Option<ShutdownScript>,
+        close: impl FnOnce() -> R,
+    ) -> (R, bool) {
+        let _close_guard = self.close_lock.lock().unwrap();
+        let requested = shutdown_script.is_some();
+        *self.next_shutdown_script.lock().unwrap() =
+            shutdown_script.map(|shutdown_script| (thread::current().id(), shutdown_script));
+        let result = close();
+        let unused = self.next_shutdown_script.lock().unwrap().take().is_some();
+        (result, requested && !unused)
+    }
+
+    // Outputs paid to an external shutdown script still show up as spendable outputs
+    // but we hold no key for them.
+    pub fn is_spendable(&self, descriptor: &SpendableOutputDescriptor) -> bool {
+        match descriptor {
+            SpendableOutputDescriptor::StaticOutput { output, .. } => {
+                output.script_pubkey == self.inner.get_destination_script()
+                    || output.script_pubkey == self.inner.get_shutdown_scriptpubkey().into_inner()
+            }
+            _ => true,
+        }
+    }
+
+    pub fn derive_channel_keys(
+        &self,
+        channel_value_satoshis: u64,
+        channel_keys_id: &[u8; 32],
+    ) -> InMemorySigner {
+        self.inner
+            .derive_channel_keys(channel_value_satoshis, channel_keys_id)
+    }
+
+    pub fn spend_spendable_outputs<C: Signing>(
+        &self,
+        descriptors: &[&SpendableOutputDescriptor],
+        outputs: Vec<TxOut>,
+        change_destination_script: Script,
+        feerate_sat_per_1000_weight: u32,
+        secp_ctx: &Secp256k1<C>,
+    ) -> Option<Transaction> {
+        self.inner
+            .spend_spendable_outputs(
+                descriptors,
+                outputs,
+                change_destination_script,
+                feerate_sat_per_1000_weight,
+                secp_ctx,
+            )
+            .ok()
+    }
+}
+
+impl KeysInterface for SenseiKeysManager {
+    type Signer = InMemorySigner;
+
+    fn get_node_secret(&self, recipient: Recipient) -> Result<SecretKey, ()> {
+        self.inner.get_node_secret(recipient)
+    }
+
+    fn get_inbound_payment_key_material(&self) -> KeyMaterial {
+        self.inner.get_inbound_payment_key_material()
+    }
+
+    fn get_destination_script(&self) -> Script {
+        self.inner.get_destination_script()
+    }
+
+    fn get_shutdown_scriptpubkey(&self) -> ShutdownScript {
+        let mut next_shutdown_script = self.next_shutdown_script.lock().unwrap();
+        match next_shutdown_script.take() {
+            Some((thread_id, shutdown_script)) if thread_id == thread::current().id() => {
+                shutdown_script
+            }
+            other => {
+                *next_shutdown_script = other;
+                self.inner.get_shutdown_scriptpubkey()
+            }
+        }
+    }
+
+    fn get_channel_signer(&self, inbound: bool, channel_value_satoshis: u64) -> Self::Signer {
+        self.inner
+            .get_channel_signer(inbound, channel_value_satoshis)
+    }
+
+    fn get_secure_random_bytes(&self) -> [u8; 32] {
+        self.inner.get_secure_random_bytes()
+    }
+
+    fn read_chan_signer(&self, reader: &[u8]) -> Result<Self::Signer, DecodeError> {
+        self.inner.read_chan_signer(reader)
+    }
+
+    fn sign_invoice(
+        &self,
+        hrp_bytes: &[u8],
+        invoice_data: &[u5],
+        recipient: Recipient,
+    ) -> Result<RecoverableSignature, ()> {
+        self.inner.sign_invoice(hrp_bytes, invoice_data, recipient)
+    }
+}
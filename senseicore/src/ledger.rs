@@ -0,0 +1,648 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::hex_utils;
+use crate::node::{ChannelEventKind, PaymentOrigin};
+use entity::{channel_event, forwarded_payment, payment, transaction};
+use lightning::chain::transaction::OutPoint;
+use lightning_invoice::Invoice;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+// Every entry moves `amount_msat` from the credit account to the debit account.
+// Any `fee_msat` is an additional movement from the credit account to `fees`.
+pub const LIGHTNING_ACCOUNT: &str = "lightning";
+pub const ONCHAIN_ACCOUNT: &str = "onchain";
+pub const EXTERNAL_ACCOUNT: &str = "external";
+pub const ROUTING_INCOME_ACCOUNT: &str = "routing_income";
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerFormat {
+    Csv,
+    Json,
+}
+
+impl Default for LedgerFormat {
+    fn default() -> Self {
+        LedgerFormat::Csv
+    }
+}
+
+impl Display for LedgerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            LedgerFormat::Csv => "csv",
+            LedgerFormat::Json => "json",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for LedgerFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(LedgerFormat::Csv),
+            "json" => Ok(LedgerFormat::Json),
+            _ => Err(Error::Generic(format!("unknown ledger format: {}", s))),
+        }
+    }
+}
+
+impl LedgerFormat {
+    pub fn content_type(&self) -> &'static str {
+        match *self {
+            LedgerFormat::Csv => "text/csv",
+            LedgerFormat::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct LedgerEntry {
+    pub timestamp: i64,
+    pub kind: String,
+    pub reference: String,
+    pub debit_account: String,
+    pub credit_account: String,
+    pub amount_msat: u64,
+    pub fee_msat: u64,
+    pub label: Option<String>,
+    pub counterparty: Option<String>,
+}
+
+impl LedgerEntry {
+    fn payment(payment: &payment::Model) -> Self {
+        let outgoing = payment.origin == PaymentOrigin::InvoiceOutgoing.to_string()
+            || payment.origin == PaymentOrigin::SpontaneousOutgoing.to_string();
+
+        // a rebalance pays ourselves, so only its fee leaves the node
+        let rebalance = payment.origin == PaymentOrigin::Rebalance.to_string();
+
+        // a jit channel payment is passed on to the client over the channel we opened
+        let jit_channel = payment.origin == PaymentOrigin::JitChannel.to_string();
+
+        let (kind, debit_account, credit_account) = if rebalance {
+            ("rebalance", LIGHTNING_ACCOUNT, LIGHTNING_ACCOUNT)
+        } else if jit_channel {
+            ("jit_channel", LIGHTNING_ACCOUNT, LIGHTNING_ACCOUNT)
+        } else if outgoing {
+            ("payment_sent", EXTERNAL_ACCOUNT, LIGHTNING_ACCOUNT)
+        } else {
+            ("payment_received", LIGHTNING_ACCOUNT, EXTERNAL_ACCOUNT)
+        };
+
+        // keysend payees are stored as the receiving node, older keysends don't have one
+        let counterparty = if outgoing {
+            payment
+                .invoice
+                .as_ref()
+                .and_then(|invoice| invoice.parse::<Invoice>().ok())
+                .map(|invoice| invoice.recover_payee_pub_key().to_string())
+                .or_else(|| payment.received_by_node_id.clone())
+        } else {
+            None
+        };
+
+        Self {
+            timestamp: payment.updated_at,
+            kind: kind.to_string(),
+            reference: payment.payment_hash.clone(),
+            debit_account: debit_account.to_string(),
+            credit_account: credit_account.to_string(),
+            amount_msat: payment.amt_msat.unwrap_or(0).max(0) as u64,
+            fee_msat: payment.fee_paid_msat.unwrap_or(0).max(0) as u64,
+            label: payment.label.clone(),
+            counterparty,
+        }
+    }
+
+    fn forward(forwarded_payment: &forwarded_payment::Model) -> Option<Self> {
+        let fees_earned_msat = forwarded_payment.fees_earned_msat?;
+        Some(Self {
+            timestamp: forwarded_payment.created_at,
+            kind: String::from("forward"),
+            reference: format!(
+                "{}:{}",
+                forwarded_payment
+                    .from_channel_id
+                    .clone()
+                    .unwrap_or_default(),
+                forwarded_payment.to_channel_id.clone().unwrap_or_default()
+            ),
+            debit_account: LIGHTNING_ACCOUNT.to_string(),
+            credit_account: ROUTING_INCOME_ACCOUNT.to_string(),
+            amount_msat: fees_earned_msat.max(0) as u64,
+            fee_msat: 0,
+            label: None,
+            counterparty: None,
+        })
+    }
+}
+
+pub struct LedgerBuilder<'a> {
+    channel_events: &'a [channel_event::Model],
+    from: Option<i64>,
+    to: Option<i64>,
+    entries: Vec<LedgerEntry>,
+}
+
+impl<'a> LedgerBuilder<'a> {
+    pub fn new(
+        channel_events: &'a [channel_event::Model],
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Self {
+        Self {
+            channel_events,
+            from,
+            to,
+            entries: vec![],
+        }
+    }
+
+    fn in_range(&self, timestamp: i64) -> bool {
+        self.from.map(|from| timestamp >= from).unwrap_or(true)
+            && self.to.map(|to| timestamp <= to).unwrap_or(true)
+    }
+
+    fn push(&mut self, entry: LedgerEntry) {
+        if self.in_range(entry.timestamp) {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn add_payments(mut self, payments: &[payment::Model]) -> Self {
+        for payment in payments {
+            self.push(LedgerEntry::payment(payment));
+        }
+        self
+    }
+
+    pub fn add_forwarded_payments(
+        mut self,
+        forwarded_payments: &[forwarded_payment::Model],
+    ) -> Self {
+        for entry in forwarded_payments.iter().filter_map(LedgerEntry::forward) {
+            self.push(entry);
+        }
+        self
+    }
+
+    // Funding and closing transactions are transfers between our on-chain wallet
+    // and our channels rather than money leaving or entering the node.
+    pub fn add_transactions(mut self, transactions: &[transaction::Model]) -> Self {
+        let mut channels: HashMap<&str, &channel_event::Model> = HashMap::new();
+        for channel_event in self.channel_events {
+            let known = channels
+                .entry(&channel_event.channel_id)
+                .or_insert(channel_event);
+            if known.counterparty_node_id.is_none() {
+                *known = channel_event;
+            }
+        }
+
+        for transaction in transactions {
+            let details = match transaction.to_transaction_details() {
+                Ok(details) => details,
+                Err(_) => continue,
+            };
+            let timestamp = details
+                .confirmation_time
+                .as_ref()
+                .map(|block_time| block_time.timestamp as i64)
+                .unwrap_or(transaction.created_at);
+            let fee_msat = details.fee.unwrap_or(0) * 1000;
+            // stored txids are in serialized byte order, channel events use the displayed one
+            let txid = details.txid.to_string();
+
+            let funded_channels = self
+                .channel_events
+                .iter()
+                .filter(|channel_event| {
+                    channel_event.kind == ChannelEventKind::Open.to_string()
+                        && channel_event.funding_txid.as_deref() == Some(txid.as_str())
+                })
+                .collect::<Vec<_>>();
+
+            if !funded_channels.is_empty() {
+                // the on-chain fee for a batch open is booked once against the first channel
+                for (i, channel_event) in funded_channels.iter().enumerate() {
+                    self.push(LedgerEntry {
+                        timestamp,
+                        kind: String::from("channel_open"),
+                        reference: channel_event.channel_id.clone(),
+                        debit_account: LIGHTNING_ACCOUNT.to_string(),
+                        credit_account: ONCHAIN_ACCOUNT.to_string(),
+                        amount_msat: channel_event.channel_value_satoshis.unwrap_or(0).max(0)
+                            as u64
+                            * 1000,
+                        fee_msat: if i == 0 { fee_msat } else { 0 },
+                        label: None,
+                        counterparty: channel_event.counterparty_node_id.clone(),
+                    });
+                }
+                continue;
+            }
+
+            let closed_channel = details.transaction.as_ref().and_then(|tx| {
+                tx.input.iter().find_map(|input| {
+                    let channel_id = OutPoint {
+                        txid: input.previous_output.txid,
+                        index: input.previous_output.vout as u16,
+                    }
+                    .to_channel_id();
+                    channels.get(hex_utils::hex_str(&channel_id).as_str())
+                })
+            });
+
+            if let Some(channel_event) = closed_channel {
+                let reason = self
+                    .channel_events
+                    .iter()
+                    .find(|close_event| {
+                        close_event.channel_id == channel_event.channel_id
+                            && close_event.kind == ChannelEventKind::Close.to_string()
+                    })
+                    .and_then(|close_event| close_event.reason.clone());
+
+                self.push(LedgerEntry {
+                    timestamp,
+                    kind: String::from("channel_close"),
+                    reference: channel_event.channel_id.clone(),
+                    debit_account: ONCHAIN_ACCOUNT.to_string(),
+                    credit_account: LIGHTNING_ACCOUNT.to_string(),
+                    amount_msat: details.received * 1000,
+                    fee_msat: 0,
+                    label: reason,
+                    counterparty: channel_event.counterparty_node_id.clone(),
+                });
+                continue;
+            }
+
+            let entry = if details.received >= details.sent {
+                LedgerEntry {
+                    timestamp,
+                    kind: String::from("onchain_received"),
+                    reference: txid.clone(),
+                    debit_account: ONCHAIN_ACCOUNT.to_string(),
+                    credit_account: EXTERNAL_ACCOUNT.to_string(),
+                    amount_msat: (details.received - details.sent) * 1000,
+                    fee_msat: 0,
+                    label: None,
+                    counterparty: None,
+                }
+            } else {
+                let spent_msat = (details.sent - details.received) * 1000;
+                LedgerEntry {
+                    timestamp,
+                    kind: String::from("onchain_sent"),
+                    reference: txid.clone(),
+                    debit_account: EXTERNAL_ACCOUNT.to_string(),
+                    credit_account: ONCHAIN_ACCOUNT.to_string(),
+                    amount_msat: spent_msat.saturating_sub(fee_msat),
+                    fee_msat: fee_msat.min(spent_msat),
+                    label: None,
+                    counterparty: None,
+                }
+            };
+            self.push(entry);
+        }
+        self
+    }
+
+    pub fn build(mut self) -> Vec<LedgerEntry> {
+        self.entries.sort_by_key(|entry| entry.timestamp);
+        self.entries
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render(entries: &[LedgerEntry], format: &LedgerFormat) -> String {
+    match format {
+        LedgerFormat::Json => serde_json::to_string(entries).unwrap(),
+        LedgerFormat::Csv => {
+            let mut csv = String::from(
+                "timestamp,kind,reference,debit_account,credit_account,amount_msat,fee_msat,label,counterparty\n",
+            );
+            for entry in entries {
+                let row = [
+                    entry.timestamp.to_string(),
+                    entry.kind.clone(),
+                    entry.reference.clone(),
+                    entry.debit_account.clone(),
+                    entry.credit_account.clone(),
+                    entry.amount_msat.to_string(),
+                    entry.fee_msat.to_string(),
+                    entry.label.clone().unwrap_or_default(),
+                    entry.counterparty.clone().unwrap_or_default(),
+                ]
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>()
+                .join(",");
+                csv.push_str(&row);
+                csv.push('\n');
+            }
+            csv
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bitcoin::consensus::serialize;
+    use bitcoin::{OutPoint as BitcoinOutPoint, Script, Transaction, TxIn, TxOut, Witness};
+
+    fn payment(origin: PaymentOrigin, updated_at: i64) -> payment::Model {
+        payment::Model {
+            id: String::from("id"),
+            node_id: String::from("node"),
+            payment_hash: format!("hash-{}", updated_at),
+            status: String::from("succeeded"),
+            origin: origin.to_string(),
+            created_at: updated_at,
+            updated_at,
+            created_by_node_id: String::from("node"),
+            received_by_node_id: None,
+            amt_msat: Some(10_000),
+            fee_paid_msat: Some(10),
+            preimage: None,
+            secret: None,
+            label: None,
+            invoice: None,
+            expires_at: None,
+            min_amt_msat: None,
+            idempotency_key: None,
+        }
+    }
+
+    fn channel_event(
+        kind: ChannelEventKind,
+        channel_id: &str,
+        funding_txid: Option<String>,
+        is_outbound: bool,
+    ) -> channel_event::Model {
+        channel_event::Model {
+            id: String::from("id"),
+            node_id: String::from("node"),
+            channel_id: channel_id.to_string(),
+            counterparty_node_id: Some(String::from("counterparty")),
+            kind: kind.to_string(),
+            funding_txid,
+            channel_value_satoshis: Some(100_000),
+            reason: Some(String::from("cooperative close")),
+            destination_address: None,
+            target_feerate_sats_per_vbyte: None,
+            is_outbound: Some(is_outbound),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn tx(previous_output: BitcoinOutPoint, value: u64) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output,
+                script_sig: Script::new(),
+                sequence: 0xffffffff,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: Script::new(),
+            }],
+        }
+    }
+
+    fn wallet_tx(
+        tx: &Transaction,
+        received: i64,
+        sent: i64,
+        fee: i64,
+        created_at: i64,
+    ) -> transaction::Model {
+        transaction::Model {
+            id: String::from("id"),
+            created_at,
+            updated_at: created_at,
+            node_id: String::from("node"),
+            txid: hex_utils::hex_str(&serialize(&tx.txid())),
+            raw_tx: Some(serialize(tx)),
+            received: Some(received),
+            sent: Some(sent),
+            fee: Some(fee),
+            confirmation_time: None,
+        }
+    }
+
+    #[test]
+    fn ledger_payment_classification_test() {
+        let payments = vec![
+            payment(PaymentOrigin::InvoiceOutgoing, 1),
+            payment(PaymentOrigin::SpontaneousIncoming, 2),
+            payment(PaymentOrigin::Rebalance, 3),
+            payment(PaymentOrigin::JitChannel, 4),
+        ];
+        let entries = LedgerBuilder::new(&[], None, None)
+            .add_payments(&payments)
+            .build();
+
+        let accounts = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.kind.as_str(),
+                    entry.debit_account.as_str(),
+                    entry.credit_account.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            accounts,
+            vec![
+                ("payment_sent", EXTERNAL_ACCOUNT, LIGHTNING_ACCOUNT),
+                ("payment_received", LIGHTNING_ACCOUNT, EXTERNAL_ACCOUNT),
+                ("rebalance", LIGHTNING_ACCOUNT, LIGHTNING_ACCOUNT),
+                ("jit_channel", LIGHTNING_ACCOUNT, LIGHTNING_ACCOUNT),
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|entry| entry.amount_msat == 10_000 && entry.fee_msat == 10));
+    }
+
+    #[test]
+    fn ledger_forward_and_range_test() {
+        let forwards = vec![
+            forwarded_payment::Model {
+                id: String::from("id"),
+                node_id: String::from("node"),
+                from_channel_id: Some(String::from("in")),
+                to_channel_id: Some(String::from("out")),
+                fees_earned_msat: Some(1_500),
+                claim_from_onchain_tx: false,
+                hours_since_epoch: 0,
+                created_at: 5,
+                updated_at: 5,
+            },
+            forwarded_payment::Model {
+                id: String::from("id"),
+                node_id: String::from("node"),
+                from_channel_id: None,
+                to_channel_id: None,
+                fees_earned_msat: None,
+                claim_from_onchain_tx: false,
+                hours_since_epoch: 0,
+                created_at: 6,
+                updated_at: 6,
+            },
+        ];
+        let payments = vec![
+            payment(PaymentOrigin::InvoiceIncoming, 1),
+            payment(PaymentOrigin::InvoiceIncoming, 10),
+        ];
+
+        let entries = LedgerBuilder::new(&[], Some(2), Some(9))
+            .add_payments(&payments)
+            .add_forwarded_payments(&forwards)
+            .build();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, "forward");
+        assert_eq!(entries[0].reference, "in:out");
+        assert_eq!(entries[0].credit_account, ROUTING_INCOME_ACCOUNT);
+        assert_eq!(entries[0].amount_msat, 1_500);
+    }
+
+    #[test]
+    fn ledger_transaction_classification_test() {
+        let funding_tx = tx(BitcoinOutPoint::default(), 100_000);
+        let funding_outpoint = OutPoint {
+            txid: funding_tx.txid(),
+            index: 0,
+        };
+        let outbound_channel_id = hex_utils::hex_str(&funding_outpoint.to_channel_id());
+        let close_tx = tx(BitcoinOutPoint::new(funding_tx.txid(), 0), 60_000);
+
+        // an inbound channel we never funded, only its close pays us
+        let inbound_funding_tx = tx(BitcoinOutPoint::new(close_tx.txid(), 0), 200_000);
+        let inbound_channel_id = hex_utils::hex_str(
+            &OutPoint {
+                txid: inbound_funding_tx.txid(),
+                index: 0,
+            }
+            .to_channel_id(),
+        );
+        let inbound_close_tx = tx(BitcoinOutPoint::new(inbound_funding_tx.txid(), 0), 50_000);
+
+        let received_tx = tx(BitcoinOutPoint::new(inbound_close_tx.txid(), 0), 30_000);
+        let sent_tx = tx(BitcoinOutPoint::new(received_tx.txid(), 0), 20_000);
+
+        let channel_events = vec![
+            channel_event(
+                ChannelEventKind::Open,
+                &outbound_channel_id,
+                Some(funding_tx.txid().to_string()),
+                true,
+            ),
+            channel_event(ChannelEventKind::Close, &outbound_channel_id, None, true),
+            channel_event(
+                ChannelEventKind::Open,
+                &inbound_channel_id,
+                Some(inbound_funding_tx.txid().to_string()),
+                false,
+            ),
+        ];
+        let transactions = vec![
+            wallet_tx(&funding_tx, 0, 100_300, 300, 1),
+            wallet_tx(&close_tx, 60_000, 0, 200, 2),
+            wallet_tx(&inbound_close_tx, 50_000, 0, 200, 3),
+            wallet_tx(&received_tx, 30_000, 0, 150, 4),
+            wallet_tx(&sent_tx, 5_000, 25_250, 250, 5),
+        ];
+
+        let entries = LedgerBuilder::new(&channel_events, None, None)
+            .add_transactions(&transactions)
+            .build();
+        let kinds = entries
+            .iter()
+            .map(|entry| entry.kind.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "channel_open",
+                "channel_close",
+                "channel_close",
+                "onchain_received",
+                "onchain_sent"
+            ]
+        );
+
+        assert_eq!(entries[0].reference, outbound_channel_id);
+        assert_eq!(entries[0].debit_account, LIGHTNING_ACCOUNT);
+        assert_eq!(entries[0].credit_account, ONCHAIN_ACCOUNT);
+        assert_eq!(entries[0].amount_msat, 100_000_000);
+        assert_eq!(entries[0].fee_msat, 300_000);
+
+        assert_eq!(entries[1].label, Some(String::from("cooperative close")));
+        assert_eq!(entries[1].amount_msat, 60_000_000);
+        assert_eq!(entries[2].reference, inbound_channel_id);
+        assert_eq!(entries[2].counterparty, Some(String::from("counterparty")));
+
+        assert_eq!(entries[3].reference, received_tx.txid().to_string());
+        assert_eq!(entries[3].amount_msat, 30_000_000);
+
+        assert_eq!(entries[4].debit_account, EXTERNAL_ACCOUNT);
+        assert_eq!(entries[4].amount_msat, 20_000_000);
+        assert_eq!(entries[4].fee_msat, 250_000);
+    }
+
+    #[test]
+    fn ledger_render_test() {
+        let entries = vec![LedgerEntry {
+            timestamp: 7,
+            kind: String::from("payment_received"),
+            reference: String::from("hash"),
+            debit_account: LIGHTNING_ACCOUNT.to_string(),
+            credit_account: EXTERNAL_ACCOUNT.to_string(),
+            amount_msat: 1_000,
+            fee_msat: 0,
+            label: Some(String::from("coffee, \"large\"")),
+            counterparty: None,
+        }];
+
+        assert_eq!(
+            render(&entries, &LedgerFormat::Csv),
+            "timestamp,kind,reference,debit_account,credit_account,amount_msat,fee_msat,label,counterparty\n\
+             7,payment_received,hash,lightning,external,1000,0,\"coffee, \"\"large\"\"\",\n"
+        );
+
+        let json = render(&entries, &LedgerFormat::Json);
+        let parsed: Vec<LedgerEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, entries);
+        assert!(json.contains("\"label\":\"coffee, \\\"large\\\"\""));
+    }
+}
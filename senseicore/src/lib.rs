@@ -1,4 +1,7 @@
+pub mod autopilot;
+pub mod backup;
 pub mod chain;
+pub mod channel_acceptance;
 pub mod channels;
 pub mod config;
 pub mod database;
@@ -6,10 +9,19 @@ pub mod disk;
 pub mod error;
 pub mod event_handler;
 pub mod events;
+pub mod fee_manager;
+pub mod health;
 pub mod hex_utils;
+pub mod htlc_tracker;
+pub mod jit_channels;
+pub mod keys;
+pub mod ledger;
+pub mod monitor_info;
 pub mod node;
 pub mod p2p;
 pub mod persist;
+pub mod scheduler;
 pub mod services;
 pub mod utils;
 pub mod version;
+pub mod watchtower;
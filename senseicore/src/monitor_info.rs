@@ -0,0 +1,461 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::{OutPoint, Script, Txid};
+use lightning::ln::chan_utils::{CounterpartyCommitmentSecrets, HTLCOutputInCommitment};
+use lightning::ln::msgs::DecodeError;
+use lightning::util::ser::{BigSize, Readable};
+use std::io::{Cursor, Read};
+
+// The only layout of `ChannelMonitorImpl::write` this reader understands.
+const MONITOR_SERIALIZATION_VERSION: u8 = 1;
+
+// Fields of a serialized `ChannelMonitor` that LDK keeps private. Only the leading
+// part of the monitor is read, following the layout LDK 0.0.110 writes it in.
+pub struct MonitorInfo {
+    // where LDK sweeps our claims on the counterparty's commitments to
+    pub destination_script: Script,
+    // our to_remote output on the counterparty's commitments
+    pub counterparty_payment_script: Script,
+    pub channel_keys_id: [u8; 32],
+    pub holder_revocation_basepoint: PublicKey,
+    pub funding_outpoint: OutPoint,
+    pub current_counterparty_commitment_txid: Option<Txid>,
+    pub counterparty_delayed_payment_base_key: PublicKey,
+    pub on_counterparty_tx_csv: u16,
+    pub channel_value_satoshis: u64,
+    pub commitment_secrets: CounterpartyCommitmentSecrets,
+    pub current_counterparty_commitment_number: u64,
+}
+
+fn skip<R: Read>(reader: &mut R, len: u64) -> Result<(), DecodeError> {
+    let skipped = std::io::copy(&mut reader.take(len), &mut std::io::sink())?;
+    if skipped != len {
+        return Err(DecodeError::ShortRead);
+    }
+    Ok(())
+}
+
+fn read_u48<R: Read>(reader: &mut R) -> Result<u64, DecodeError> {
+    let mut bytes = [0; 6];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte)))
+}
+
+// Options are written as a zero or as the value's length plus one followed by the value.
+fn skip_option<R: Read>(reader: &mut R) -> Result<(), DecodeError> {
+    let len: BigSize = Readable::read(reader)?;
+    if len.0 > 0 {
+        skip(reader, len.0 - 1)?;
+    }
+    Ok(())
+}
+
+// TLV-based structs are prefixed with the length of their whole TLV stream.
+fn skip_tlv_stream<R: Read>(reader: &mut R) -> Result<(), DecodeError> {
+    let len: BigSize = Readable::read(reader)?;
+    skip(reader, len.0)
+}
+
+fn read_counterparty_commitment_params<R: Read>(
+    reader: &mut R,
+) -> Result<(PublicKey, u16), DecodeError> {
+    // monitors written before 0.0.100 kept per-htlc state here
+    let per_htlc_len: u64 = Readable::read(reader)?;
+    for _ in 0..per_htlc_len {
+        let _txid: Txid = Readable::read(reader)?;
+        let htlcs_count: u64 = Readable::read(reader)?;
+        for _ in 0..htlcs_count {
+            let _htlc: HTLCOutputInCommitment = Readable::read(reader)?;
+        }
+    }
+
+    let len: BigSize = Readable::read(reader)?;
+    let mut stream = vec![0; len.0 as usize];
+    reader.read_exact(&mut stream)?;
+
+    let mut stream = Cursor::new(stream);
+    let mut delayed_payment_base_key = None;
+    let mut on_counterparty_tx_csv = None;
+    while stream.position() < len.0 {
+        let record_type: BigSize = Readable::read(&mut stream)?;
+        let record_len: BigSize = Readable::read(&mut stream)?;
+        match record_type.0 {
+            0 => delayed_payment_base_key = Some(Readable::read(&mut stream)?),
+            4 => on_counterparty_tx_csv = Some(Readable::read(&mut stream)?),
+            _ => skip(&mut stream, record_len.0)?,
+        }
+    }
+
+    match (delayed_payment_base_key, on_counterparty_tx_csv) {
+        (Some(delayed_payment_base_key), Some(on_counterparty_tx_csv)) => {
+            Ok((delayed_payment_base_key, on_counterparty_tx_csv))
+        }
+        _ => Err(DecodeError::InvalidValue),
+    }
+}
+
+impl MonitorInfo {
+    pub fn read(monitor: &[u8]) -> Result<Self, DecodeError> {
+        let reader = &mut Cursor::new(monitor);
+
+        let version: u8 = Readable::read(reader)?;
+        let min_version: u8 = Readable::read(reader)?;
+        if version != MONITOR_SERIALIZATION_VERSION || min_version > MONITOR_SERIALIZATION_VERSION {
+            return Err(DecodeError::UnknownVersion);
+        }
+
+        let _latest_update_id: u64 = Readable::read(reader)?;
+        let _commitment_transaction_number_obscure_factor = read_u48(reader)?;
+        let destination_script: Script = Readable::read(reader)?;
+
+        let has_broadcasted_holder_revokable_script: u8 = Readable::read(reader)?;
+        if has_broadcasted_holder_revokable_script == 0 {
+            let _revocation_key: PublicKey = Readable::read(reader)?;
+            let _to_self_delay: u16 = Readable::read(reader)?;
+            let _redeemscript: Script = Readable::read(reader)?;
+        }
+
+        let counterparty_payment_script: Script = Readable::read(reader)?;
+        let _shutdown_script: Script = Readable::read(reader)?;
+        let channel_keys_id: [u8; 32] = Readable::read(reader)?;
+        let holder_revocation_basepoint: PublicKey = Readable::read(reader)?;
+        let funding_txid: Txid = Readable::read(reader)?;
+        let funding_output_index: u16 = Readable::read(reader)?;
+        let _funding_script: Script = Readable::read(reader)?;
+        let current_counterparty_commitment_txid: Option<Txid> = Readable::read(reader)?;
+        let _prev_counterparty_commitment_txid: Option<Txid> = Readable::read(reader)?;
+        let (counterparty_delayed_payment_base_key, on_counterparty_tx_csv) =
+            read_counterparty_commitment_params(reader)?;
+        let _funding_redeemscript: Script = Readable::read(reader)?;
+        let channel_value_satoshis: u64 = Readable::read(reader)?;
+
+        if read_u48(reader)? != 0 {
+            // the counterparty's current and previous per-commitment points
+            skip(reader, 33 + 33)?;
+        }
+        let _on_holder_tx_csv: u16 = Readable::read(reader)?;
+        let commitment_secrets: CounterpartyCommitmentSecrets = Readable::read(reader)?;
+
+        let counterparty_claimable_outpoints: u64 = Readable::read(reader)?;
+        for _ in 0..counterparty_claimable_outpoints {
+            let _txid: Txid = Readable::read(reader)?;
+            let htlcs_count: u64 = Readable::read(reader)?;
+            for _ in 0..htlcs_count {
+                // offered, amount_msat, cltv_expiry and payment_hash
+                skip(reader, 1 + 8 + 4 + 32)?;
+                // transaction_output_index and the htlc source
+                skip_option(reader)?;
+                skip_option(reader)?;
+            }
+        }
+
+        // counterparty commitments seen on-chain and payment hashes, both keyed to a
+        // 48-bit commitment number
+        for _ in 0..2 {
+            let count: u64 = Readable::read(reader)?;
+            skip(reader, count * (32 + 6))?;
+        }
+
+        let has_prev_holder_signed_commitment_tx: u8 = Readable::read(reader)?;
+        if has_prev_holder_signed_commitment_tx == 1 {
+            skip_tlv_stream(reader)?;
+        }
+        skip_tlv_stream(reader)?;
+
+        let current_counterparty_commitment_number = read_u48(reader)?;
+
+        Ok(Self {
+            destination_script,
+            counterparty_payment_script,
+            channel_keys_id,
+            holder_revocation_basepoint,
+            funding_outpoint: OutPoint {
+                txid: funding_txid,
+                vout: funding_output_index.into(),
+            },
+            current_counterparty_commitment_txid,
+            counterparty_delayed_payment_base_key,
+            on_counterparty_tx_csv,
+            channel_value_satoshis,
+            commitment_secrets,
+            current_counterparty_commitment_number,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use bitcoin::{Network, Transaction, TxIn, TxOut, WPubkeyHash, Witness};
+    use lightning::chain::chaininterface::{
+        BroadcasterInterface, ConfirmationTarget, FeeEstimator,
+    };
+    use lightning::chain::channelmonitor::{ChannelMonitor, ChannelMonitorUpdate, MonitorEvent};
+    use lightning::chain::keysinterface::{BaseSign, InMemorySigner, KeysInterface, KeysManager};
+    use lightning::chain::transaction::OutPoint as LdkOutPoint;
+    use lightning::chain::{self, BestBlock, ChannelMonitorUpdateErr};
+    use lightning::ln::channelmanager::{ChainParameters, ChannelManager};
+    use lightning::ln::features::InitFeatures;
+    use lightning::ln::msgs::{AcceptChannel, ChannelMessageHandler, Init};
+    use lightning::util::config::UserConfig;
+    use lightning::util::events::{
+        Event, EventsProvider, MessageSendEvent, MessageSendEventsProvider,
+    };
+    use lightning::util::logger::{Logger, Record};
+    use lightning::util::ser::Writeable;
+    use std::sync::{Arc, Mutex};
+
+    struct TestChain {}
+
+    impl BroadcasterInterface for TestChain {
+        fn broadcast_transaction(&self, _tx: &Transaction) {}
+    }
+
+    impl FeeEstimator for TestChain {
+        fn get_est_sat_per_1000_weight(&self, _confirmation_target: ConfirmationTarget) -> u32 {
+            253
+        }
+    }
+
+    impl Logger for TestChain {
+        fn log(&self, _record: &Record) {}
+    }
+
+    // Keeps the serialized monitors the channel manager hands over.
+    struct TestWatch {
+        monitors: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl chain::Watch<InMemorySigner> for TestWatch {
+        fn watch_channel(
+            &self,
+            _funding_txo: LdkOutPoint,
+            monitor: ChannelMonitor<InMemorySigner>,
+        ) -> Result<(), ChannelMonitorUpdateErr> {
+            self.monitors.lock().unwrap().push(monitor.encode());
+            Ok(())
+        }
+
+        fn update_channel(
+            &self,
+            _funding_txo: LdkOutPoint,
+            _update: ChannelMonitorUpdate,
+        ) -> Result<(), ChannelMonitorUpdateErr> {
+            Ok(())
+        }
+
+        fn release_pending_monitor_events(
+            &self,
+        ) -> Vec<(LdkOutPoint, Vec<MonitorEvent>, Option<PublicKey>)> {
+            vec![]
+        }
+    }
+
+    type TestChannelManager = ChannelManager<
+        InMemorySigner,
+        Arc<TestWatch>,
+        Arc<TestChain>,
+        Arc<KeysManager>,
+        Arc<TestChain>,
+        Arc<TestChain>,
+    >;
+
+    struct TestPeer {
+        keys_manager: Arc<KeysManager>,
+        watch: Arc<TestWatch>,
+        channel_manager: TestChannelManager,
+    }
+
+    impl TestPeer {
+        fn new(seed: u8, to_self_delay: u16) -> Self {
+            let chain = Arc::new(TestChain {});
+            let keys_manager = Arc::new(KeysManager::new(&[seed; 32], 42, 42));
+            let watch = Arc::new(TestWatch {
+                monitors: Mutex::new(vec![]),
+            });
+            let mut config = UserConfig::default();
+            config.channel_handshake_config.our_to_self_delay = to_self_delay;
+            let channel_manager = ChannelManager::new(
+                chain.clone(),
+                watch.clone(),
+                chain.clone(),
+                chain,
+                keys_manager.clone(),
+                config,
+                ChainParameters {
+                    network: Network::Regtest,
+                    best_block: BestBlock::from_genesis(Network::Regtest),
+                },
+            );
+            Self {
+                keys_manager,
+                watch,
+                channel_manager,
+            }
+        }
+
+        fn node_id(&self) -> PublicKey {
+            self.channel_manager.get_our_node_id()
+        }
+
+        fn message_event(&self) -> MessageSendEvent {
+            let mut events = self.channel_manager.get_and_clear_pending_msg_events();
+            assert_eq!(events.len(), 1);
+            events.remove(0)
+        }
+    }
+
+    // Funds a channel from alice to bob, returning the funding transaction, bob's
+    // accept_channel message and the monitor alice ends up with.
+    fn open_channel(
+        alice: &TestPeer,
+        bob: &TestPeer,
+        channel_value_satoshis: u64,
+    ) -> (Transaction, AcceptChannel, Vec<u8>) {
+        let init = Init {
+            features: InitFeatures::known(),
+            remote_network_address: None,
+        };
+        alice.channel_manager.peer_connected(&bob.node_id(), &init);
+        bob.channel_manager.peer_connected(&alice.node_id(), &init);
+
+        alice
+            .channel_manager
+            .create_channel(bob.node_id(), channel_value_satoshis, 0, 0, None)
+            .unwrap();
+        match alice.message_event() {
+            MessageSendEvent::SendOpenChannel { msg, .. } => bob
+                .channel_manager
+                .handle_open_channel(&alice.node_id(), InitFeatures::known(), &msg),
+            event => panic!("unexpected event {:?}", event),
+        }
+        let accept_channel = match bob.message_event() {
+            MessageSendEvent::SendAcceptChannel { msg, .. } => msg,
+            event => panic!("unexpected event {:?}", event),
+        };
+        alice.channel_manager.handle_accept_channel(
+            &bob.node_id(),
+            InitFeatures::known(),
+            &accept_channel,
+        );
+
+        let funding = Mutex::new(None);
+        alice
+            .channel_manager
+            .process_pending_events(&|event: &Event| {
+                if let Event::FundingGenerationReady {
+                    temporary_channel_id,
+                    output_script,
+                    ..
+                } = event
+                {
+                    *funding.lock().unwrap() = Some((*temporary_channel_id, output_script.clone()));
+                }
+            });
+        let (temporary_channel_id, output_script) = funding.into_inner().unwrap().unwrap();
+        let funding_tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                witness: Witness::from_vec(vec![vec![1]]),
+                ..Default::default()
+            }],
+            output: vec![TxOut {
+                value: channel_value_satoshis,
+                script_pubkey: output_script,
+            }],
+        };
+        alice
+            .channel_manager
+            .funding_transaction_generated(
+                &temporary_channel_id,
+                &bob.node_id(),
+                funding_tx.clone(),
+            )
+            .unwrap();
+
+        match alice.message_event() {
+            MessageSendEvent::SendFundingCreated { msg, .. } => bob
+                .channel_manager
+                .handle_funding_created(&alice.node_id(), &msg),
+            event => panic!("unexpected event {:?}", event),
+        }
+        match bob.message_event() {
+            MessageSendEvent::SendFundingSigned { msg, .. } => alice
+                .channel_manager
+                .handle_funding_signed(&bob.node_id(), &msg),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        let monitor = alice.watch.monitors.lock().unwrap().pop().unwrap();
+        (funding_tx, accept_channel, monitor)
+    }
+
+    #[test]
+    fn read_ldk_monitor_test() {
+        let alice = TestPeer::new(1, 150);
+        let bob = TestPeer::new(2, 200);
+        let (funding_tx, accept_channel, monitor) = open_channel(&alice, &bob, 100_000);
+
+        let monitor_info = MonitorInfo::read(&monitor).unwrap();
+        assert_eq!(
+            monitor_info.funding_outpoint,
+            OutPoint {
+                txid: funding_tx.txid(),
+                vout: 0
+            }
+        );
+        assert_eq!(monitor_info.channel_value_satoshis, 100_000);
+        assert_eq!(
+            monitor_info.destination_script,
+            alice.keys_manager.get_destination_script()
+        );
+        assert_eq!(
+            monitor_info.counterparty_delayed_payment_base_key,
+            accept_channel.delayed_payment_basepoint
+        );
+        // alice picks the delay bob's outputs are locked for
+        assert_eq!(monitor_info.on_counterparty_tx_csv, 150);
+        assert!(monitor_info.current_counterparty_commitment_txid.is_some());
+        assert_eq!(
+            monitor_info.current_counterparty_commitment_number,
+            (1 << 48) - 1
+        );
+
+        let signer = alice.keys_manager.derive_channel_keys(
+            monitor_info.channel_value_satoshis,
+            &monitor_info.channel_keys_id,
+        );
+        assert_eq!(
+            signer.pubkeys().revocation_basepoint,
+            monitor_info.holder_revocation_basepoint
+        );
+        assert_eq!(
+            monitor_info.counterparty_payment_script,
+            Script::new_v0_p2wpkh(&WPubkeyHash::hash(
+                &signer.pubkeys().payment_point.serialize()
+            ))
+        );
+
+        // layouts written by other versions are refused rather than misread
+        for (index, version) in [(0, 2), (1, 2)] {
+            let mut monitor = monitor.clone();
+            monitor[index] = version;
+            assert!(matches!(
+                MonitorInfo::read(&monitor),
+                Err(DecodeError::UnknownVersion)
+            ));
+        }
+    }
+}
@@ -7,10 +7,13 @@
 // You may not use this file except in accordance with one or both of these
 // licenses.
 
+use crate::autopilot::{self, AutopilotPolicy, MIN_AUTOPILOT_INTERVAL_SECS};
+use crate::backup::RecoveredOutputWatcher;
 use crate::chain::broadcaster::SenseiBroadcaster;
 use crate::chain::database::WalletDatabase;
 use crate::chain::fee_estimator::SenseiFeeEstimator;
 use crate::chain::manager::SenseiChainManager;
+use crate::channel_acceptance::ChannelAcceptancePolicy;
 use crate::channels::ChannelOpener;
 use crate::config::SenseiConfig;
 use crate::database::SenseiDatabase;
@@ -18,29 +21,44 @@ use crate::disk::FilesystemLogger;
 use crate::error::Error;
 use crate::event_handler::LightningNodeEventHandler;
 use crate::events::SenseiEvent;
+use crate::fee_manager::{FeePolicy, MIN_FEE_POLICY_INTERVAL_SECS};
+use crate::htlc_tracker::HtlcTracker;
+use crate::jit_channels::JitChannelPolicy;
+use crate::keys::SenseiKeysManager;
+use crate::ledger::{self, LedgerBuilder, LedgerFormat};
 use crate::p2p::bubble_gossip_route_handler::{AnyP2PGossipHandler, BubbleGossipRouteHandler};
 use crate::p2p::router::{AnyRouter, AnyScorer};
 use crate::p2p::utils::parse_peer_info;
 use crate::p2p::SenseiP2P;
 use crate::persist::{AnyKVStore, DatabaseStore, SenseiPersister};
+use crate::scheduler::{ScheduledPaymentKind, MIN_SCHEDULE_INTERVAL_SECS};
 use crate::services::node::{
-    Channel, NodeInfo, NodeRequest, NodeRequestError, NodeResponse, OpenChannelRequest,
-    OpenChannelResult, Peer, Utxo,
+    Balance, BalanceSnapshot, Channel, ChannelClaimableBalances, ChannelRoutingRevenue,
+    ClaimableBalance, ClaimableBalances, DailyRoutingRevenue, InvoiceInfo, NodeInfo, NodeRequest,
+    NodeRequestError, NodeResponse, OpenChannelRequest, OpenChannelResult, PaymentAttempt, Peer,
+    RoutingRevenue, Utxo,
 };
-use crate::services::{PaginationRequest, PaginationResponse, PaymentsFilter};
-use crate::utils::PagedVec;
+use crate::services::{
+    ChannelsFilter, ForwardedPaymentsFilter, InvoicesFilter, PaginationRequest, PaginationResponse,
+    PaymentsFilter,
+};
+use crate::utils::{macaroon_with_session_from_hex_str, PagedVec};
+use crate::watchtower::MonitorUpdateQueue;
 use crate::{hex_utils, version};
 use bdk::keys::ExtendedKey;
 use bdk::wallet::time;
 use bdk::wallet::AddressIndex;
 use bdk::TransactionDetails;
+use bitcoin::bech32::ToBase32;
 use bitcoin::hashes::Hash;
-use entity::sea_orm::{ActiveModelTrait, ActiveValue};
+use entity::sea_orm::{ActiveModelTrait, ActiveValue, ModelTrait};
+use entity::seconds_since_epoch;
 use lightning::chain::channelmonitor::ChannelMonitor;
 
-use lightning::ln::features::InvoiceFeatures;
+use lightning::ln::features::{ChannelFeatures, InvoiceFeatures, NodeFeatures};
 use lightning::ln::msgs::NetAddress;
-use lightning_invoice::payment::PaymentError;
+use lightning::ln::script::ShutdownScript;
+use lightning_invoice::payment::{PaymentError, Router as LdkRouterTrait};
 use tindercrypt::cryptors::RingCryptor;
 
 use bdk::template::DescriptorTemplateOut;
@@ -49,7 +67,7 @@ use bitcoin::hashes::sha256::Hash as Sha256;
 use bitcoin::network::constants::Network;
 use bitcoin::secp256k1::{PublicKey, Secp256k1};
 use bitcoin::util::bip32::{ChildNumber, DerivationPath, ExtendedPrivKey};
-use bitcoin::BlockHash;
+use bitcoin::{Address, BlockHash};
 use lightning::chain::chainmonitor;
 use lightning::chain::keysinterface::{
     InMemorySigner, KeysInterface, KeysManager, PhantomKeysManager, Recipient,
@@ -58,6 +76,7 @@ use lightning::chain::Watch;
 use lightning::chain::{self, Filter};
 use lightning::ln::channelmanager::{
     self, ChannelDetails, ChannelManager as LdkChannelManager, PhantomRouteHints,
+    MIN_FINAL_CLTV_EXPIRY,
 };
 use lightning::ln::channelmanager::{ChainParameters, ChannelManagerReadArgs};
 use lightning::ln::peer_handler::{
@@ -65,20 +84,27 @@ use lightning::ln::peer_handler::{
 };
 use lightning::ln::{PaymentHash, PaymentPreimage, PaymentSecret};
 use lightning::routing::gossip::{
-    NetworkGraph as LdkNetworkGraph, NodeId, P2PGossipSync, RoutingFees,
+    NetworkGraph as LdkNetworkGraph, NetworkUpdate, NodeId, P2PGossipSync, RoutingFees,
+};
+use lightning::routing::router::{
+    PaymentParameters, RouteHint, RouteHintHop, RouteHop, RouteParameters,
 };
-use lightning::routing::router::{RouteHint, RouteHintHop};
 use lightning::routing::scoring::ProbabilisticScorer;
-use lightning::util::config::UserConfig;
+use lightning::util::config::{ChannelConfig, UserConfig};
+use lightning::util::errors::APIError;
 use lightning::util::ser::{Readable, ReadableArgs, Writeable};
 use lightning_background_processor::BackgroundProcessor;
 use lightning_invoice::utils::DefaultRouter;
-use lightning_invoice::{payment, utils, Currency, Invoice, InvoiceDescription};
+use lightning_invoice::{
+    payment, utils, Currency, Invoice, InvoiceBuilder, InvoiceDescription, ParseOrSemanticError,
+    RawInvoice, SignOrCreationError,
+};
 use lightning_net_tokio::SocketDescriptor;
 use lightning_rapid_gossip_sync::RapidGossipSync;
 use macaroon::Macaroon;
 use rand::{thread_rng, RngCore};
 use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use std::fs::File;
 use std::io::Cursor;
@@ -87,12 +113,117 @@ use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{convert::From, fmt};
 use tokio::runtime::Handle;
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
+pub const DEFAULT_INVOICE_EXPIRY_SECS: u32 = 3600;
+
+// A minimum only makes sense when the payer picks the amount, or as a floor
+// below a fixed amount.
+fn check_min_amt_msat(amt_msat: Option<u64>, min_amt_msat: Option<u64>) -> Result<(), Error> {
+    match (amt_msat, min_amt_msat) {
+        (Some(amt_msat), Some(min_amt_msat)) if min_amt_msat > amt_msat => Err(Error::Generic(
+            String::from("min_amt_msat cannot be greater than amt_msat"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Channels are updated one at a time, so a failure part way through leaves the earlier
+// ones updated. Name them in the error so the caller knows what actually changed.
+fn apply_channel_configs<F>(
+    updates: Vec<(PublicKey, [u8; 32], ChannelConfig)>,
+    mut apply: F,
+) -> Result<Vec<String>, Error>
+where
+    F: FnMut(&PublicKey, &[u8; 32], &ChannelConfig) -> Result<(), APIError>,
+{
+    let mut updated_channel_ids = vec![];
+    for (counterparty_node_id, channel_id, config) in updates {
+        if let Err(e) = apply(&counterparty_node_id, &channel_id, &config) {
+            return Err(Error::Generic(format!(
+                "failed to update channel {}: {:?}, already updated: [{}]",
+                hex_utils::hex_str(&channel_id),
+                e,
+                updated_channel_ids.join(", ")
+            )));
+        }
+        updated_channel_ids.push(hex_utils::hex_str(&channel_id));
+    }
+    Ok(updated_channel_ids)
+}
+
+// Keeps the last snapshot taken in each interval, snapshots must be in time order.
+fn downsample_balance_history(
+    snapshots: impl Iterator<Item = BalanceSnapshot>,
+    interval: i64,
+) -> Vec<BalanceSnapshot> {
+    let mut history: Vec<BalanceSnapshot> = vec![];
+    for snapshot in snapshots {
+        match history.last_mut() {
+            Some(last) if last.timestamp / interval == snapshot.timestamp / interval => {
+                *last = snapshot;
+            }
+            _ => history.push(snapshot),
+        }
+    }
+    history
+}
+
+enum OutgoingPayment {
+    New(entity::payment::Model),
+    Existing(entity::payment::Model),
+}
+
+// The payment row is inserted before anything is sent, which claims its idempotency key.
+// A retry that races the original request loses on the unique index and gets the
+// original payment back instead of paying twice.
+async fn insert_outgoing_payment<F>(
+    database: &SenseiDatabase,
+    node_id: &str,
+    payment: entity::payment::ActiveModel,
+    idempotency_key: &Option<String>,
+    same_request: F,
+) -> Result<OutgoingPayment, Error>
+where
+    F: Fn(&entity::payment::Model) -> bool,
+{
+    let find_existing = || async {
+        match idempotency_key {
+            Some(idempotency_key) => {
+                database
+                    .find_payment_by_idempotency_key(node_id.to_string(), idempotency_key.clone())
+                    .await
+            }
+            None => Ok(None),
+        }
+    };
+    let existing = |payment: entity::payment::Model| {
+        if same_request(&payment) {
+            Ok(OutgoingPayment::Existing(payment))
+        } else {
+            Err(Error::Generic(String::from(
+                "idempotency key was already used for a different payment",
+            )))
+        }
+    };
+
+    if let Some(payment) = find_existing().await? {
+        return existing(payment);
+    }
+
+    match payment.insert(database.get_connection()).await {
+        Ok(payment) => Ok(OutgoingPayment::New(payment)),
+        Err(e) => match find_existing().await? {
+            Some(payment) => existing(payment),
+            None => Err(e.into()),
+        },
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct LocalInvoice {
     pub payment_hash: String,
@@ -235,6 +366,7 @@ pub enum HTLCStatus {
     Pending,
     Succeeded,
     Failed,
+    Cancelled,
     Unknown,
 }
 
@@ -244,6 +376,7 @@ impl Display for HTLCStatus {
             HTLCStatus::Pending => "pending".to_string(),
             HTLCStatus::Succeeded => "succeeded".to_string(),
             HTLCStatus::Failed => "failed".to_string(),
+            HTLCStatus::Cancelled => "cancelled".to_string(),
             HTLCStatus::Unknown => "unknown".to_string(),
         };
         write!(f, "{}", str)
@@ -256,6 +389,8 @@ pub enum PaymentOrigin {
     InvoiceOutgoing,
     SpontaneousIncoming,
     SpontaneousOutgoing,
+    Rebalance,
+    JitChannel,
 }
 
 impl Display for PaymentOrigin {
@@ -265,6 +400,166 @@ impl Display for PaymentOrigin {
             PaymentOrigin::InvoiceOutgoing => "invoice_outgoing".to_string(),
             PaymentOrigin::SpontaneousIncoming => "spontaneous_incoming".to_string(),
             PaymentOrigin::SpontaneousOutgoing => "spontaneous_outgoing".to_string(),
+            PaymentOrigin::Rebalance => "rebalance".to_string(),
+            PaymentOrigin::JitChannel => "jit_channel".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ChannelEventKind {
+    Open,
+    CloseRequested,
+    Close,
+}
+
+impl Display for ChannelEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            ChannelEventKind::Open => "open".to_string(),
+            ChannelEventKind::CloseRequested => "close_requested".to_string(),
+            ChannelEventKind::Close => "close".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ChannelState {
+    PendingOpen,
+    Ready,
+    Closing,
+    Closed,
+}
+
+impl Display for ChannelState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            ChannelState::PendingOpen => "pending_open".to_string(),
+            ChannelState::Ready => "ready".to_string(),
+            ChannelState::Closing => "closing".to_string(),
+            ChannelState::Closed => "closed".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for ChannelState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending_open" => Ok(ChannelState::PendingOpen),
+            "ready" => Ok(ChannelState::Ready),
+            "closing" => Ok(ChannelState::Closing),
+            "closed" => Ok(ChannelState::Closed),
+            _ => Err(Error::Generic(format!("unknown channel state {}", s))),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum InvoiceStatus {
+    Open,
+    Paid,
+    Expired,
+    Cancelled,
+}
+
+impl InvoiceStatus {
+    pub fn from_payment(payment: &entity::payment::Model, now: i64) -> Self {
+        if payment.status == HTLCStatus::Succeeded.to_string() {
+            InvoiceStatus::Paid
+        } else if payment.status == HTLCStatus::Cancelled.to_string() {
+            InvoiceStatus::Cancelled
+        } else if payment
+            .expires_at
+            .map(|expires_at| expires_at < now)
+            .unwrap_or(false)
+        {
+            InvoiceStatus::Expired
+        } else {
+            InvoiceStatus::Open
+        }
+    }
+}
+
+impl Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            InvoiceStatus::Open => "open".to_string(),
+            InvoiceStatus::Paid => "paid".to_string(),
+            InvoiceStatus::Expired => "expired".to_string(),
+            InvoiceStatus::Cancelled => "cancelled".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for InvoiceStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(InvoiceStatus::Open),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "expired" => Ok(InvoiceStatus::Expired),
+            "cancelled" => Ok(InvoiceStatus::Cancelled),
+            _ => Err(Error::Generic(format!("unknown invoice status {}", s))),
+        }
+    }
+}
+
+// LDK only exposes the raw BOLT4 failure code in test builds so we classify
+// the failure from the network update it hands back instead.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum PaymentFailureCode {
+    PayeeRejected,
+    ChannelUpdate,
+    TemporaryChannelFailure,
+    PermanentChannelFailure,
+    TemporaryNodeFailure,
+    PermanentNodeFailure,
+    Unknown,
+}
+
+impl From<(bool, &Option<NetworkUpdate>)> for PaymentFailureCode {
+    fn from((rejected_by_dest, network_update): (bool, &Option<NetworkUpdate>)) -> Self {
+        if rejected_by_dest {
+            return PaymentFailureCode::PayeeRejected;
+        }
+        match network_update {
+            Some(NetworkUpdate::ChannelUpdateMessage { .. }) => PaymentFailureCode::ChannelUpdate,
+            Some(NetworkUpdate::ChannelFailure { is_permanent, .. }) => {
+                if *is_permanent {
+                    PaymentFailureCode::PermanentChannelFailure
+                } else {
+                    PaymentFailureCode::TemporaryChannelFailure
+                }
+            }
+            Some(NetworkUpdate::NodeFailure { is_permanent, .. }) => {
+                if *is_permanent {
+                    PaymentFailureCode::PermanentNodeFailure
+                } else {
+                    PaymentFailureCode::TemporaryNodeFailure
+                }
+            }
+            None => PaymentFailureCode::Unknown,
+        }
+    }
+}
+
+impl Display for PaymentFailureCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            PaymentFailureCode::PayeeRejected => "payee_rejected".to_string(),
+            PaymentFailureCode::ChannelUpdate => "channel_update".to_string(),
+            PaymentFailureCode::TemporaryChannelFailure => "temporary_channel_failure".to_string(),
+            PaymentFailureCode::PermanentChannelFailure => "permanent_channel_failure".to_string(),
+            PaymentFailureCode::TemporaryNodeFailure => "temporary_node_failure".to_string(),
+            PaymentFailureCode::PermanentNodeFailure => "permanent_node_failure".to_string(),
+            PaymentFailureCode::Unknown => "unknown".to_string(),
         };
         write!(f, "{}", str)
     }
@@ -311,23 +606,17 @@ pub type ChainMonitor = chainmonitor::ChainMonitor<
 trait MustSized: Sized {}
 
 pub type SimpleArcChannelManager<M, T, F, L> =
-    LdkChannelManager<InMemorySigner, Arc<M>, Arc<T>, Arc<PhantomKeysManager>, Arc<F>, Arc<L>>;
+    LdkChannelManager<InMemorySigner, Arc<M>, Arc<T>, Arc<SenseiKeysManager>, Arc<F>, Arc<L>>;
 
-pub type SimpleArcPeerManager<SD, M, T, F, L> = LdkPeerManager<
+pub type SimpleArcPeerManager<SD, L> = LdkPeerManager<
     SD,
-    Arc<SimpleArcChannelManager<M, T, F, L>>,
+    Arc<HtlcTracker>,
     Arc<BubbleGossipRouteHandler>,
     Arc<L>,
     Arc<IgnoringMessageHandler>,
 >;
 
-pub type PeerManager = SimpleArcPeerManager<
-    SocketDescriptor,
-    ChainMonitor,
-    SenseiBroadcaster,
-    SenseiFeeEstimator,
-    FilesystemLogger,
->;
+pub type PeerManager = SimpleArcPeerManager<SocketDescriptor, FilesystemLogger>;
 
 pub type SimpleArcRoutingPeerManager<SD, L> = LdkPeerManager<
     SD,
@@ -410,6 +699,189 @@ impl MacaroonSession {
     }
 }
 
+// Spending caveats are first-party caveats of the form `<name> = <value>`. Anyone
+// holding a macaroon can add caveats to it, so each one may only narrow what it
+// can spend: repeated limits keep the lowest value, repeated destination lists
+// are intersected and only the first budget id counts.
+const BUDGET_ID_CAVEAT: &str = "budget_id";
+const MAX_PAYMENT_MSAT_CAVEAT: &str = "max_payment_msat";
+const MAX_DAILY_MSAT_CAVEAT: &str = "max_daily_msat";
+const MAX_MONTHLY_MSAT_CAVEAT: &str = "max_monthly_msat";
+const DESTINATIONS_CAVEAT: &str = "destinations";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const SECONDS_PER_MONTH: i64 = 30 * SECONDS_PER_DAY;
+
+fn parse_spending_caveat(predicate: &[u8]) -> Option<(&str, &str)> {
+    let predicate = std::str::from_utf8(predicate).ok()?;
+    let (name, value) = predicate.split_once('=')?;
+    Some((name.trim(), value.trim()))
+}
+
+fn is_spending_caveat(predicate: &macaroon::ByteString) -> bool {
+    match parse_spending_caveat(&predicate.0) {
+        Some((BUDGET_ID_CAVEAT, value)) => !value.is_empty(),
+        Some((
+            MAX_PAYMENT_MSAT_CAVEAT | MAX_DAILY_MSAT_CAVEAT | MAX_MONTHLY_MSAT_CAVEAT,
+            value,
+        )) => value.parse::<u64>().is_ok(),
+        Some((DESTINATIONS_CAVEAT, value)) => value
+            .split(',')
+            .all(|pubkey| hex_utils::to_compressed_pubkey(pubkey.trim()).is_some()),
+        _ => false,
+    }
+}
+
+fn lowest_limit(current: Option<u64>, value: &str) -> Option<u64> {
+    match (current, value.parse::<u64>().ok()) {
+        (Some(current), Some(value)) => Some(current.min(value)),
+        (current, value) => current.or(value),
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct SpendingLimits {
+    pub budget_id: Option<String>,
+    pub max_payment_msat: Option<u64>,
+    pub max_daily_msat: Option<u64>,
+    pub max_monthly_msat: Option<u64>,
+    pub allowed_destinations: Option<Vec<String>>,
+}
+
+impl SpendingLimits {
+    pub fn from_macaroon(macaroon: &Macaroon) -> Self {
+        let mut limits = SpendingLimits::default();
+        for caveat in macaroon.first_party_caveats() {
+            let predicate = match caveat {
+                macaroon::Caveat::FirstParty(caveat) => caveat.predicate(),
+                macaroon::Caveat::ThirdParty(_) => continue,
+            };
+            match parse_spending_caveat(&predicate.0) {
+                Some((BUDGET_ID_CAVEAT, value)) if limits.budget_id.is_none() => {
+                    limits.budget_id = Some(value.to_string());
+                }
+                Some((MAX_PAYMENT_MSAT_CAVEAT, value)) => {
+                    limits.max_payment_msat = lowest_limit(limits.max_payment_msat, value);
+                }
+                Some((MAX_DAILY_MSAT_CAVEAT, value)) => {
+                    limits.max_daily_msat = lowest_limit(limits.max_daily_msat, value);
+                }
+                Some((MAX_MONTHLY_MSAT_CAVEAT, value)) => {
+                    limits.max_monthly_msat = lowest_limit(limits.max_monthly_msat, value);
+                }
+                Some((DESTINATIONS_CAVEAT, value)) => {
+                    let destinations = value
+                        .split(',')
+                        .map(|destination| destination.trim().to_string())
+                        .collect::<Vec<String>>();
+                    limits.allowed_destinations = Some(match limits.allowed_destinations.take() {
+                        Some(allowed) => allowed
+                            .into_iter()
+                            .filter(|destination| destinations.contains(destination))
+                            .collect(),
+                        None => destinations,
+                    });
+                }
+                _ => {}
+            }
+        }
+        limits
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.max_payment_msat.is_some()
+            || self.max_daily_msat.is_some()
+            || self.max_monthly_msat.is_some()
+            || self.allowed_destinations.is_some()
+    }
+
+    pub fn add_caveats(&self, macaroon: &mut Macaroon) {
+        let mut caveats = vec![];
+        if let Some(budget_id) = &self.budget_id {
+            caveats.push(format!("{} = {}", BUDGET_ID_CAVEAT, budget_id));
+        }
+        if let Some(max_payment_msat) = self.max_payment_msat {
+            caveats.push(format!(
+                "{} = {}",
+                MAX_PAYMENT_MSAT_CAVEAT, max_payment_msat
+            ));
+        }
+        if let Some(max_daily_msat) = self.max_daily_msat {
+            caveats.push(format!("{} = {}", MAX_DAILY_MSAT_CAVEAT, max_daily_msat));
+        }
+        if let Some(max_monthly_msat) = self.max_monthly_msat {
+            caveats.push(format!(
+                "{} = {}",
+                MAX_MONTHLY_MSAT_CAVEAT, max_monthly_msat
+            ));
+        }
+        if let Some(allowed_destinations) = &self.allowed_destinations {
+            caveats.push(format!(
+                "{} = {}",
+                DESTINATIONS_CAVEAT,
+                allowed_destinations.join(",")
+            ));
+        }
+        for caveat in caveats {
+            macaroon.add_first_party_caveat(macaroon::ByteString::from(caveat));
+        }
+    }
+}
+
+async fn check_spending_limits(
+    database: &SenseiDatabase,
+    node_id: &str,
+    limits: &SpendingLimits,
+    budget_id: &str,
+    amt_msat: u64,
+    destinations: &[String],
+) -> Result<(), Error> {
+    if let Some(max_payment_msat) = limits.max_payment_msat {
+        if amt_msat > max_payment_msat {
+            return Err(Error::SpendingLimitExceeded(format!(
+                "{} msats is more than the {} msats allowed per payment",
+                amt_msat, max_payment_msat
+            )));
+        }
+    }
+
+    if let Some(allowed_destinations) = &limits.allowed_destinations {
+        if let Some(destination) = destinations
+            .iter()
+            .find(|destination| !allowed_destinations.contains(destination))
+        {
+            return Err(Error::SpendingLimitExceeded(format!(
+                "{} is not an allowed destination",
+                destination
+            )));
+        }
+    }
+
+    let now = seconds_since_epoch();
+    for (max_msat, period_secs, period) in [
+        (limits.max_daily_msat, SECONDS_PER_DAY, "day"),
+        (limits.max_monthly_msat, SECONDS_PER_MONTH, "month"),
+    ] {
+        if let Some(max_msat) = max_msat {
+            let spent_msat = database
+                .sum_macaroon_spends_since(
+                    node_id.to_string(),
+                    budget_id.to_string(),
+                    now - period_secs,
+                )
+                .await?;
+            if spent_msat + amt_msat > max_msat {
+                return Err(Error::SpendingLimitExceeded(format!(
+                    "{} msats already spent of the {} msats allowed per {}",
+                    spent_msat, max_msat, period
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct LightningNode {
     pub config: Arc<SenseiConfig>,
@@ -423,14 +895,17 @@ pub struct LightningNode {
     pub chain_monitor: Arc<ChainMonitor>,
     pub chain_manager: Arc<SenseiChainManager>,
     pub peer_manager: Arc<PeerManager>,
+    pub htlc_tracker: Arc<HtlcTracker>,
+    pub recovered_outputs: Arc<RecoveredOutputWatcher>,
     pub p2p: Arc<SenseiP2P>,
-    pub keys_manager: Arc<PhantomKeysManager>,
+    pub keys_manager: Arc<SenseiKeysManager>,
     pub logger: Arc<FilesystemLogger>,
     pub invoice_payer: Arc<InvoicePayer>,
     pub stop_listen: Arc<AtomicBool>,
     pub persister: Arc<SenseiPersister>,
     pub event_sender: broadcast::Sender<SenseiEvent>,
     pub broadcaster: Arc<SenseiBroadcaster>,
+    pub spending_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl LightningNode {
@@ -557,22 +1032,271 @@ impl LightningNode {
         }
     }
 
+    // Returns the spending limits carried by the macaroon's caveats. Limits without
+    // a budget id share the budget of the macaroon they were added to.
     pub async fn verify_macaroon(
         &self,
         macaroon: Macaroon,
         session: MacaroonSession,
-    ) -> Result<(), Error> {
-        let existing_macaroon = self.database.find_macaroon_by_id(session.id).await?;
+    ) -> Result<SpendingLimits, Error> {
+        let existing_macaroon = self
+            .database
+            .find_macaroon_by_id(session.id.clone())
+            .await?;
 
         if existing_macaroon.is_none() {
             return Err(Error::InvalidMacaroon);
         }
 
-        let verifier = macaroon::Verifier::default();
+        let mut verifier = macaroon::Verifier::default();
+        verifier.satisfy_general(is_spending_caveat);
         let key = macaroon::MacaroonKey::from(&self.seed[..]);
         verifier
             .verify(&macaroon, &key, vec![])
-            .map_err(|_e| Error::InvalidMacaroon)
+            .map_err(|_e| Error::InvalidMacaroon)?;
+
+        let mut limits = SpendingLimits::from_macaroon(&macaroon);
+        limits.budget_id.get_or_insert(session.id);
+        Ok(limits)
+    }
+
+    pub async fn restrict_macaroon(
+        &self,
+        macaroon_hex: String,
+        mut limits: SpendingLimits,
+    ) -> Result<Macaroon, Error> {
+        let (mut macaroon, session) = macaroon_with_session_from_hex_str(&macaroon_hex)?;
+        if session.pubkey != self.id {
+            return Err(Error::InvalidMacaroon);
+        }
+
+        if let Some(allowed_destinations) = &limits.allowed_destinations {
+            if allowed_destinations
+                .iter()
+                .any(|destination| hex_utils::to_compressed_pubkey(destination).is_none())
+            {
+                return Err(Error::Generic(String::from("invalid destination pubkey")));
+            }
+        }
+
+        if !limits.is_restricted() {
+            return Err(Error::Generic(String::from("no spending limits given")));
+        }
+
+        self.verify_macaroon(macaroon.clone(), session).await?;
+        limits.budget_id = match SpendingLimits::from_macaroon(&macaroon).budget_id {
+            Some(_) => None,
+            None => Some(uuid::Uuid::new_v4().to_string()),
+        };
+
+        limits.add_caveats(&mut macaroon);
+        Ok(macaroon)
+    }
+
+    // Works out how much a request would spend and where the funds would go, or
+    // None when the request does not move funds out of the node. Requests that
+    // move funds in ways a budget can't account for are refused outright.
+    async fn requested_spend(
+        &self,
+        request: &NodeRequest,
+    ) -> Result<Option<(u64, Vec<String>)>, Error> {
+        match request {
+            NodeRequest::SendPayment {
+                invoice,
+                idempotency_key,
+            } => {
+                let invoice = self.get_invoice_from_str(invoice)?;
+                let amt_msat = invoice.amount_milli_satoshis();
+                let payment_hash = hex_utils::hex_str(&(*invoice.payment_hash()).into_inner());
+                if let Some(payment) = self.find_idempotent_payment(idempotency_key).await? {
+                    if payment.payment_hash == payment_hash
+                        && payment.amt_msat == amt_msat.map(|amt| amt as i64)
+                    {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some((
+                    amt_msat.unwrap_or_default(),
+                    vec![invoice.recover_payee_pub_key().to_string()],
+                )))
+            }
+            NodeRequest::Keysend {
+                dest_pubkey,
+                amt_msat,
+                idempotency_key,
+            } => {
+                // keysend payment hashes are random so only the amount and payee can match
+                if let Some(payment) = self.find_idempotent_payment(idempotency_key).await? {
+                    if payment.amt_msat == Some(*amt_msat as i64)
+                        && payment.received_by_node_id.as_ref() == Some(dest_pubkey)
+                    {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some((*amt_msat, vec![dest_pubkey.clone()])))
+            }
+            // the amount comes back to us, only the routing fees leave the node
+            NodeRequest::Rebalance { max_fee_msat, .. } => Ok(Some((*max_fee_msat, vec![]))),
+            NodeRequest::OpenChannels { requests } => Ok(Some((
+                requests
+                    .iter()
+                    .map(|request| request.amount_sats * 1000)
+                    .sum(),
+                requests
+                    .iter()
+                    .map(|request| request.counterparty_pubkey.clone())
+                    .collect(),
+            ))),
+            // scheduled payments are made by the node later on, outside of any budget
+            NodeRequest::CreateScheduledPayment { .. }
+            | NodeRequest::UpdateScheduledPayment { .. } => Err(Error::SpendingLimitExceeded(
+                String::from("scheduled payments are not allowed with spending limits"),
+            )),
+            NodeRequest::CloseChannel {
+                destination_address: Some(_),
+                ..
+            } => Err(Error::SpendingLimitExceeded(String::from(
+                "closing to an external address is not allowed with spending limits",
+            ))),
+            NodeRequest::RunAutopilot { dry_run: false }
+            | NodeRequest::SetAutopilotPolicy { .. } => Err(Error::SpendingLimitExceeded(
+                String::from("autopilot is not allowed with spending limits"),
+            )),
+            NodeRequest::CreateJitInvoice { .. } | NodeRequest::SetJitChannelPolicy { .. } => {
+                Err(Error::SpendingLimitExceeded(String::from(
+                    "jit channels are not allowed with spending limits",
+                )))
+            }
+            NodeRequest::StartNode { .. }
+            | NodeRequest::StopNode {}
+            | NodeRequest::GetUnusedAddress {}
+            | NodeRequest::GetPhantomRouteHints {}
+            | NodeRequest::GetBalance {}
+            | NodeRequest::GetClaimableBalances {}
+            | NodeRequest::GetBalanceHistory { .. }
+            | NodeRequest::DecodeInvoice { .. }
+            | NodeRequest::GetInvoice { .. }
+            | NodeRequest::GetPhantomInvoice { .. }
+            | NodeRequest::ListInvoices { .. }
+            | NodeRequest::CancelInvoice { .. }
+            | NodeRequest::ListScheduledPayments { .. }
+            | NodeRequest::DeleteScheduledPayment { .. }
+            | NodeRequest::ExportLedger { .. }
+            | NodeRequest::RestrictMacaroon { .. }
+            | NodeRequest::LabelPayment { .. }
+            | NodeRequest::DeletePayment { .. }
+            | NodeRequest::ConnectPeer { .. }
+            | NodeRequest::ListChannels { .. }
+            | NodeRequest::ListHtlcs { .. }
+            | NodeRequest::ListPayments { .. }
+            | NodeRequest::ListPhantomPayments { .. }
+            | NodeRequest::ListTransactions { .. }
+            | NodeRequest::ListForwardedPayments { .. }
+            | NodeRequest::GetRoutingRevenue { .. }
+            | NodeRequest::GetPaymentAttempts { .. }
+            | NodeRequest::CloseChannel {
+                destination_address: None,
+                ..
+            }
+            | NodeRequest::GetFeePolicy {}
+            | NodeRequest::SetFeePolicy { .. }
+            | NodeRequest::GetAutopilotPolicy {}
+            | NodeRequest::RunAutopilot { dry_run: true }
+            | NodeRequest::ExportChannelBackup {}
+            | NodeRequest::RecoverFromBackup { .. }
+            | NodeRequest::GetJitChannelPolicy {}
+            | NodeRequest::GetHealthReport {}
+            | NodeRequest::GetHealthPolicy {}
+            | NodeRequest::SetHealthPolicy { .. }
+            | NodeRequest::GetChannelAcceptancePolicy {}
+            | NodeRequest::SetChannelAcceptancePolicy { .. }
+            | NodeRequest::UpdateChannelConfig { .. }
+            | NodeRequest::NodeInfo {}
+            | NodeRequest::ListPeers {}
+            | NodeRequest::SignMessage { .. }
+            | NodeRequest::VerifyMessage { .. }
+            | NodeRequest::ListUnspent {}
+            | NodeRequest::NetworkGraphInfo {}
+            | NodeRequest::ListKnownPeers { .. }
+            | NodeRequest::AddKnownPeer { .. }
+            | NodeRequest::RemoveKnownPeer { .. } => Ok(None),
+        }
+    }
+
+    // Used for requests authenticated with a macaroon, so spends are checked
+    // against and recorded in the macaroon's budget.
+    pub async fn call_with_limits(
+        &self,
+        request: NodeRequest,
+        limits: SpendingLimits,
+    ) -> Result<NodeResponse, NodeRequestError> {
+        if !limits.is_restricted() {
+            return self.call(request).await;
+        }
+
+        // held until the spend is recorded, retries with an idempotency key look for
+        // their original payment under it too
+        let spending_guard = self.spending_lock.lock().await;
+        let (amt_msat, destinations) = match self.requested_spend(&request).await? {
+            Some(spend) => spend,
+            None => {
+                drop(spending_guard);
+                return self.call(request).await;
+            }
+        };
+        let budget_id = limits.budget_id.clone().unwrap_or_default();
+
+        check_spending_limits(
+            &self.database,
+            &self.id,
+            &limits,
+            &budget_id,
+            amt_msat,
+            &destinations,
+        )
+        .await?;
+
+        let response = self.call(request).await?;
+
+        // payments that fail later on are left out when summing up the budget and the
+        // routing fees of the others are added to it, a rebalance only spends fees
+        let (amt_msat, payment_hash) = match &response {
+            NodeResponse::SendPayment {
+                payment_hash,
+                status,
+            }
+            | NodeResponse::Keysend {
+                payment_hash,
+                status,
+            }
+            | NodeResponse::Rebalance {
+                payment_hash,
+                status,
+                ..
+            } => {
+                if *status == HTLCStatus::Failed.to_string() {
+                    return Ok(response);
+                }
+                let amt_msat = match response {
+                    NodeResponse::Rebalance { .. } => 0,
+                    _ => amt_msat,
+                };
+                (amt_msat, Some(payment_hash.clone()))
+            }
+            _ => (amt_msat, None),
+        };
+
+        let macaroon_spend = entity::macaroon_spend::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            budget_id: ActiveValue::Set(budget_id),
+            amt_msat: ActiveValue::Set(amt_msat.try_into().unwrap()),
+            destination: ActiveValue::Set(Some(destinations.join(","))),
+            payment_hash: ActiveValue::Set(payment_hash),
+            ..Default::default()
+        };
+        self.database.insert_macaroon_spend(macaroon_spend).await?;
+
+        Ok(response)
     }
 
     pub fn get_seed_from_entropy(network: Network, entropy: &[u8; 32]) -> [u8; 32] {
@@ -659,27 +1383,45 @@ impl LightningNode {
         let cur = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap();
-        let keys_manager = Arc::new(PhantomKeysManager::new(
+        let keys_manager = Arc::new(SenseiKeysManager::new(PhantomKeysManager::new(
             &seed,
             cur.as_secs(),
             cur.subsec_nanos(),
             &cross_node_seed,
-        ));
+        )));
 
         let broadcaster = Arc::new(SenseiBroadcaster::new(
             id.clone(),
             chain_manager.broadcaster.clone(),
             Arc::new(Mutex::new(wallet_database.clone())),
             event_sender.clone(),
+            database.clone(),
         ));
 
+        let recovered_outputs = Arc::new(
+            RecoveredOutputWatcher::new(
+                id.clone(),
+                database.clone(),
+                bdk_wallet.clone(),
+                keys_manager.clone(),
+                chain_manager.fee_estimator.clone(),
+                broadcaster.clone(),
+            )
+            .await?,
+        );
+
         let persistence_store =
             AnyKVStore::Database(DatabaseStore::new(database.clone(), id.clone()));
 
+        let watchtower_queue = config
+            .watchtower_client_configured()
+            .then(|| Arc::new(MonitorUpdateQueue::new(database.clone(), id.clone())));
+
         let persister = Arc::new(SenseiPersister::new(
             persistence_store,
             config.network,
             logger.clone(),
+            watchtower_queue,
         ));
 
         let chain_monitor: Arc<ChainMonitor> = Arc::new(chainmonitor::ChainMonitor::new(
@@ -700,6 +1442,11 @@ impl LightningNode {
             .force_announced_channel_preference = false;
         user_config.manually_accept_inbound_channels = true;
 
+        let channel_acceptance_policy = database.get_channel_acceptance_policy(id.clone()).await?;
+        if let Some(min_confirmations) = channel_acceptance_policy.min_confirmations {
+            user_config.channel_handshake_config.minimum_depth = min_confirmations;
+        }
+
         let best_block = chain_manager.get_best_block().await?;
 
         let (channel_manager_blockhash, channel_manager) = {
@@ -786,6 +1533,10 @@ impl LightningNode {
             onchain_wallet_sync.hash,
             &wallet_database as &(dyn chain::Listen + Send + Sync),
         ));
+        chain_listeners.push((
+            onchain_wallet_sync.hash,
+            &*recovered_outputs as &(dyn chain::Listen + Send + Sync),
+        ));
 
         let tip = chain_manager
             .synchronize_to_tip(chain_listeners)
@@ -811,12 +1562,16 @@ impl LightningNode {
                 channel_manager_sync,
                 chain_monitor_sync,
                 wallet_database.clone(),
+                recovered_outputs.clone(),
             )
             .await
             .unwrap();
 
+        let htlc_tracker = Arc::new(
+            HtlcTracker::new(id.clone(), database.clone(), channel_manager.clone()).await?,
+        );
         let lightning_msg_handler = MessageHandler {
-            chan_handler: channel_manager.clone(),
+            chan_handler: htlc_tracker.clone(),
             route_handler: Arc::new(BubbleGossipRouteHandler {
                 target: p2p.p2p_gossip.clone(),
             }),
@@ -839,6 +1594,7 @@ impl LightningNode {
             wallet: bdk_wallet.clone(),
             channel_manager: channel_manager.clone(),
             keys_manager: keys_manager.clone(),
+            chain_monitor: chain_monitor.clone(),
             database: database.clone(),
             tokio_handle: Handle::current(),
             chain_manager: chain_manager.clone(),
@@ -961,6 +1717,8 @@ impl LightningNode {
             chain_monitor,
             chain_manager,
             peer_manager,
+            htlc_tracker,
+            recovered_outputs,
             p2p: p2p.clone(),
             keys_manager,
             logger,
@@ -969,6 +1727,7 @@ impl LightningNode {
             persister,
             event_sender,
             broadcaster,
+            spending_lock: Arc::new(tokio::sync::Mutex::new(())),
         };
 
         Ok((lightning_node, handles, background_processor))
@@ -988,7 +1747,21 @@ impl LightningNode {
             self.peer_manager.clone(),
             self.p2p.peer_connector.clone(),
         );
-        opener.open_batch(requests).await
+        let results = opener.open_batch(requests).await?;
+
+        let channels = self.channel_manager.list_channels();
+        for channel_id in results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+        {
+            if let Some(channel) = channels.iter().find(|c| c.channel_id == *channel_id) {
+                if let Err(e) = self.sync_channel(channel).await {
+                    println!("failed to record pending channel: {:?}", e);
+                }
+            }
+        }
+
+        Ok(results)
     }
 
     // `custom_id` will be user_channel_id in FundingGenerated event
@@ -1000,129 +1773,358 @@ impl LightningNode {
         result
     }
 
+    // Clients retrying a request after a timeout send the same idempotency key,
+    // so we hand back the payment it already started instead of paying again.
+    async fn find_idempotent_payment(
+        &self,
+        idempotency_key: &Option<String>,
+    ) -> Result<Option<entity::payment::Model>, Error> {
+        match idempotency_key {
+            Some(idempotency_key) => Ok(self
+                .database
+                .find_payment_by_idempotency_key(self.id.clone(), idempotency_key.clone())
+                .await?),
+            None => Ok(None),
+        }
+    }
+
     async fn keysend<K: KeysInterface>(
         &self,
         invoice_payer: &InvoicePayer,
         payee_pubkey: PublicKey,
         amt_msat: u64,
+        idempotency_key: Option<String>,
         keys: &K,
-    ) -> Result<(), Error> {
+    ) -> Result<entity::payment::Model, Error> {
         let payment_preimage = keys.get_secure_random_bytes();
+        let payment_hash = hex_utils::hex_str(&Sha256::hash(&payment_preimage).into_inner());
+        let preimage = Some(hex_utils::hex_str(&payment_preimage));
+        let dest_pubkey = payee_pubkey.to_string();
 
-        let status = match invoice_payer.pay_pubkey(
-            payee_pubkey,
-            PaymentPreimage(payment_preimage),
-            amt_msat,
-            40,
-        ) {
-            Ok(_payment_id) => {
-                println!(
-                    "EVENT: initiated sending {} msats to {}",
-                    amt_msat, payee_pubkey
-                );
-                print!("> ");
-                HTLCStatus::Pending
-            }
+        let payment = entity::payment::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            preimage: ActiveValue::Set(preimage),
+            payment_hash: ActiveValue::Set(payment_hash),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            amt_msat: ActiveValue::Set(Some(amt_msat.try_into().unwrap())),
+            origin: ActiveValue::Set(PaymentOrigin::SpontaneousOutgoing.to_string()),
+            received_by_node_id: ActiveValue::Set(Some(dest_pubkey.clone())),
+            idempotency_key: ActiveValue::Set(idempotency_key.clone()),
+            ..Default::default()
+        };
+        let payment = match insert_outgoing_payment(
+            &self.database,
+            &self.id,
+            payment,
+            &idempotency_key,
+            |payment| {
+                payment.origin == PaymentOrigin::SpontaneousOutgoing.to_string()
+                    && payment.amt_msat == Some(amt_msat as i64)
+                    && payment
+                        .received_by_node_id
+                        .as_ref()
+                        .map(|received_by_node_id| *received_by_node_id == dest_pubkey)
+                        .unwrap_or(true)
+            },
+        )
+        .await?
+        {
+            OutgoingPayment::New(payment) => payment,
+            OutgoingPayment::Existing(payment) => return Ok(payment),
+        };
+
+        match invoice_payer.pay_pubkey(
+            payee_pubkey,
+            PaymentPreimage(payment_preimage),
+            amt_msat,
+            40,
+        ) {
+            Ok(_payment_id) => {
+                println!(
+                    "EVENT: initiated sending {} msats to {}",
+                    amt_msat, payee_pubkey
+                );
+                print!("> ");
+                Ok(payment)
+            }
             Err(PaymentError::Invoice(e)) => {
                 println!("ERROR: invalid payee: {}", e);
                 print!("> ");
-                return Ok(());
+                payment.delete(self.database.get_connection()).await?;
+                Err(PaymentError::Invoice(e).into())
             }
             Err(PaymentError::Routing(e)) => {
                 println!("ERROR: failed to find route: {}", e.err);
                 print!("> ");
-                return Ok(());
+                payment.delete(self.database.get_connection()).await?;
+                Err(e.into())
             }
             Err(PaymentError::Sending(e)) => {
                 println!("ERROR: failed to send payment: {:?}", e);
                 print!("> ");
-                HTLCStatus::Failed
+                let mut payment: entity::payment::ActiveModel = payment.into();
+                payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+                Ok(payment.update(self.database.get_connection()).await?)
             }
-        };
+        }
+    }
 
-        let payment_hash = hex_utils::hex_str(&Sha256::hash(&payment_preimage).into_inner());
-        let preimage = Some(hex_utils::hex_str(&payment_preimage));
+    pub async fn send_payment(
+        &self,
+        invoice: &Invoice,
+        idempotency_key: Option<String>,
+    ) -> Result<entity::payment::Model, Error> {
+        let payment_hash = hex_utils::hex_str(&(*invoice.payment_hash()).into_inner());
+        let payment_secret = Some(hex_utils::hex_str(&(*invoice.payment_secret()).0));
+        let amt_msat: Option<i64> = invoice
+            .amount_milli_satoshis()
+            .map(|amt| amt.try_into().unwrap());
+        let invoice_str = invoice.to_string();
 
         let payment = entity::payment::ActiveModel {
             node_id: ActiveValue::Set(self.id.clone()),
-            preimage: ActiveValue::Set(preimage),
+            created_by_node_id: ActiveValue::Set(self.id.clone()),
             payment_hash: ActiveValue::Set(payment_hash),
-            status: ActiveValue::Set(status.to_string()),
-            amt_msat: ActiveValue::Set(Some(amt_msat.try_into().unwrap())),
-            origin: ActiveValue::Set(PaymentOrigin::SpontaneousOutgoing.to_string()),
+            secret: ActiveValue::Set(payment_secret),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            amt_msat: ActiveValue::Set(amt_msat),
+            origin: ActiveValue::Set(PaymentOrigin::InvoiceOutgoing.to_string()),
+            invoice: ActiveValue::Set(Some(invoice_str.clone())),
+            idempotency_key: ActiveValue::Set(idempotency_key.clone()),
             ..Default::default()
         };
-        payment.insert(self.database.get_connection()).await?;
-
-        Ok(())
-    }
+        let payment = match insert_outgoing_payment(
+            &self.database,
+            &self.id,
+            payment,
+            &idempotency_key,
+            |payment| payment.invoice.as_ref() == Some(&invoice_str),
+        )
+        .await?
+        {
+            OutgoingPayment::New(payment) => payment,
+            OutgoingPayment::Existing(payment) => return Ok(payment),
+        };
 
-    pub async fn send_payment(&self, invoice: &Invoice) -> Result<(), Error> {
-        let status = match self.invoice_payer.pay_invoice(invoice) {
+        let payee_pubkey = invoice.recover_payee_pub_key();
+        match self.invoice_payer.pay_invoice(invoice) {
             Ok(_payment_id) => {
-                let payee_pubkey = invoice.recover_payee_pub_key();
                 let amt_msat = invoice.amount_milli_satoshis().unwrap();
                 println!(
                     "EVENT: initiated sending {} msats to {}",
                     amt_msat, payee_pubkey
                 );
-                HTLCStatus::Pending
+                Ok(payment)
             }
+            // nothing was sent, so drop the payment and let a retry try again
             Err(PaymentError::Invoice(e)) => {
                 println!("ERROR: invalid invoice: {}", e);
-                return Err(PaymentError::Invoice(e).into());
+                payment.delete(self.database.get_connection()).await?;
+                Err(PaymentError::Invoice(e).into())
             }
             Err(PaymentError::Routing(e)) => {
                 println!("ERROR: failed to find route: {}", e.err);
-                return Err(e.into());
+                payment.delete(self.database.get_connection()).await?;
+                Err(e.into())
             }
             Err(PaymentError::Sending(e)) => {
                 println!("ERROR: failed to send payment: {:?}", e);
-                HTLCStatus::Failed
+                let mut payment: entity::payment::ActiveModel = payment.into();
+                payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+                Ok(payment.update(self.database.get_connection()).await?)
+            }
+        }
+    }
+
+    // Moves liquidity from one of our channels to another by paying ourselves an
+    // invoice over a route that leaves through `from_channel_id` and comes back in
+    // through `to_channel_id`.
+    pub async fn rebalance(
+        &self,
+        from_channel_id: &str,
+        to_channel_id: &str,
+        amt_msat: u64,
+        max_fee_msat: u64,
+    ) -> Result<entity::payment::Model, Error> {
+        if from_channel_id == to_channel_id {
+            return Err(Error::Generic(String::from(
+                "cannot rebalance a channel with itself",
+            )));
+        }
+
+        let channels = self.channel_manager.list_usable_channels();
+        let find_channel = |channel_id: &str| {
+            channels
+                .iter()
+                .find(|channel| hex_utils::hex_str(&channel.channel_id) == channel_id)
+        };
+        let from_channel = find_channel(from_channel_id)
+            .ok_or_else(|| Error::Generic(format!("no usable channel {}", from_channel_id)))?;
+        let to_channel = find_channel(to_channel_id)
+            .ok_or_else(|| Error::Generic(format!("no usable channel {}", to_channel_id)))?;
+
+        if from_channel.outbound_capacity_msat < amt_msat {
+            return Err(Error::Generic(String::from(
+                "not enough outbound capacity on the channel to rebalance from",
+            )));
+        }
+        if to_channel.inbound_capacity_msat < amt_msat {
+            return Err(Error::Generic(String::from(
+                "not enough inbound capacity on the channel to rebalance to",
+            )));
+        }
+
+        let (inbound_scid, forwarding_info) = match (
+            to_channel.get_inbound_payment_scid(),
+            to_channel.counterparty.forwarding_info.as_ref(),
+        ) {
+            (Some(inbound_scid), Some(forwarding_info)) => (inbound_scid, forwarding_info),
+            _ => {
+                return Err(Error::Generic(String::from(
+                    "the channel to rebalance to can't route payments to us yet",
+                )))
             }
         };
+        let last_hop_fee_msat = forwarding_info.fee_base_msat as u64
+            + amt_msat * forwarding_info.fee_proportional_millionths as u64 / 1_000_000;
+
+        let label = format!("rebalance {} -> {}", from_channel_id, to_channel_id);
+        let invoice = self
+            .get_invoice(
+                Some(amt_msat),
+                None,
+                label.clone(),
+                None,
+                None,
+                None,
+                Some(false),
+            )
+            .await?;
+        let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
+        let payment_secret = Some(*invoice.payment_secret());
 
-        let payment_hash = hex_utils::hex_str(&(*invoice.payment_hash()).into_inner());
-        let payment_secret = Some(hex_utils::hex_str(&(*invoice.payment_secret()).0));
-        let amt_msat: Option<i64> = invoice
-            .amount_milli_satoshis()
-            .map(|amt| amt.try_into().unwrap());
+        let payment = self
+            .database
+            .find_payment(self.id.clone(), hex_utils::hex_str(&payment_hash.0))
+            .await?
+            .ok_or_else(|| Error::Generic(String::from("rebalance invoice not found")))?;
+        let mut payment: entity::payment::ActiveModel = payment.into();
+        payment.origin = ActiveValue::Set(PaymentOrigin::Rebalance.to_string());
+        payment.label = ActiveValue::Set(Some(label));
+
+        // find a route to the peer of the inbound channel that starts on the outbound
+        // channel and then add the hop back to us over the inbound channel
+        let our_node_id = self.channel_manager.get_our_node_id();
+        let route = {
+            let router = self.p2p.get_router();
+            let scorer = self.p2p.scorer.lock().unwrap();
+            router.find_route(
+                &our_node_id,
+                &RouteParameters {
+                    // every path gets the full amount appended as the hop back to us, so
+                    // the payment must not be split
+                    payment_params: PaymentParameters::from_node_id(
+                        to_channel.counterparty.node_id,
+                    )
+                    .with_max_path_count(1),
+                    final_value_msat: amt_msat + last_hop_fee_msat,
+                    final_cltv_expiry_delta: MIN_FINAL_CLTV_EXPIRY
+                        + forwarding_info.cltv_expiry_delta as u32,
+                },
+                &payment_hash,
+                Some(&[from_channel]),
+                &*scorer,
+            )
+        };
 
-        let payment = entity::payment::ActiveModel {
-            node_id: ActiveValue::Set(self.id.clone()),
-            created_by_node_id: ActiveValue::Set(self.id.clone()),
-            payment_hash: ActiveValue::Set(payment_hash),
-            secret: ActiveValue::Set(payment_secret),
-            status: ActiveValue::Set(status.to_string()),
-            amt_msat: ActiveValue::Set(amt_msat),
-            origin: ActiveValue::Set(PaymentOrigin::InvoiceOutgoing.to_string()),
-            invoice: ActiveValue::Set(Some(invoice.to_string())),
-            ..Default::default()
+        let route = match route {
+            Ok(route) if route.paths.len() > 1 => {
+                println!(
+                    "ERROR: rebalance route has {} paths, only single path routes are supported",
+                    route.paths.len()
+                );
+                None
+            }
+            Ok(mut route) => {
+                for path in route.paths.iter_mut() {
+                    if let Some(last_hop) = path.last_mut() {
+                        last_hop.fee_msat = last_hop_fee_msat;
+                        last_hop.cltv_expiry_delta = forwarding_info.cltv_expiry_delta as u32;
+                    }
+                    path.push(RouteHop {
+                        pubkey: our_node_id,
+                        node_features: NodeFeatures::known(),
+                        short_channel_id: inbound_scid,
+                        channel_features: ChannelFeatures::known(),
+                        fee_msat: amt_msat,
+                        cltv_expiry_delta: MIN_FINAL_CLTV_EXPIRY,
+                    });
+                }
+                route.payment_params = Some(PaymentParameters::from_node_id(our_node_id));
+                Some(route)
+            }
+            Err(e) => {
+                println!("ERROR: failed to find rebalance route: {}", e.err);
+                None
+            }
         };
 
-        payment.insert(self.database.get_connection()).await?;
+        let route = match route {
+            Some(route) if route.get_total_fees() <= max_fee_msat => route,
+            route => {
+                payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+                payment.update(self.database.get_connection()).await?;
+                return Err(Error::Generic(match route {
+                    Some(route) => format!(
+                        "cheapest rebalance route costs {} msat, more than the maximum fee",
+                        route.get_total_fees()
+                    ),
+                    None => String::from("failed to find a route to rebalance over"),
+                }));
+            }
+        };
 
-        Ok(())
+        payment.fee_paid_msat = ActiveValue::Set(Some(route.get_total_fees().try_into().unwrap()));
+        match self
+            .channel_manager
+            .send_payment(&route, payment_hash, &payment_secret)
+        {
+            Ok(_payment_id) => {
+                println!(
+                    "EVENT: initiated rebalance of {} msats from {} to {}",
+                    amt_msat, from_channel_id, to_channel_id
+                );
+            }
+            Err(e) => {
+                println!("ERROR: failed to send rebalance payment: {:?}", e);
+                payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+                payment.update(self.database.get_connection()).await?;
+                return Err(Error::Generic(format!(
+                    "failed to send rebalance payment: {:?}",
+                    e
+                )));
+            }
+        }
+
+        Ok(payment.update(self.database.get_connection()).await?)
     }
 
     pub async fn get_phantom_invoice(
         &self,
-        amt_msat: u64,
+        amt_msat: Option<u64>,
+        min_amt_msat: Option<u64>,
         description: String,
         phantom_route_hints: Vec<PhantomRouteHints>,
     ) -> Result<Invoice, Error> {
-        let currency = match self.config.network {
-            Network::Bitcoin => Currency::Bitcoin,
-            Network::Testnet => Currency::BitcoinTestnet,
-            Network::Regtest => Currency::Regtest,
-            Network::Signet => Currency::Signet,
-        };
+        check_min_amt_msat(amt_msat, min_amt_msat)?;
 
-        let invoice = utils::create_phantom_invoice::<InMemorySigner, Arc<PhantomKeysManager>>(
-            Some(amt_msat),
+        let currency = self.invoice_currency();
+
+        let invoice = utils::create_phantom_invoice::<InMemorySigner, Arc<SenseiKeysManager>>(
+            amt_msat,
             None,
             description.clone(),
-            3600, // FIXME invoice_expiry_delta_secs
+            DEFAULT_INVOICE_EXPIRY_SECS,
             phantom_route_hints,
             self.keys_manager.clone(),
             currency,
@@ -1137,10 +2139,14 @@ impl LightningNode {
             payment_hash: ActiveValue::Set(payment_hash),
             secret: ActiveValue::Set(payment_secret),
             status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
-            amt_msat: ActiveValue::Set(Some(amt_msat.try_into().unwrap())),
+            amt_msat: ActiveValue::Set(amt_msat.map(|amt| amt.try_into().unwrap())),
+            min_amt_msat: ActiveValue::Set(min_amt_msat.map(|amt| amt.try_into().unwrap())),
             origin: ActiveValue::Set(PaymentOrigin::InvoiceIncoming.to_string()),
             invoice: ActiveValue::Set(Some(invoice.to_string())),
             label: ActiveValue::Set(Some(description)),
+            expires_at: ActiveValue::Set(Some(
+                seconds_since_epoch() + i64::from(DEFAULT_INVOICE_EXPIRY_SECS),
+            )),
             ..Default::default()
         };
 
@@ -1152,22 +2158,134 @@ impl LightningNode {
         Ok(invoice)
     }
 
-    pub async fn get_invoice(&self, amt_msat: u64, description: String) -> Result<Invoice, Error> {
-        let currency = match self.config.network {
+    // Route hints are only included for private channels; when not specified we
+    // follow LDK and only include them if we have no public channels to be found by.
+    fn private_route_hints(&self, include: Option<bool>) -> Vec<RouteHint> {
+        let channels = self.channel_manager.list_usable_channels();
+        let include = include.unwrap_or_else(|| !channels.iter().any(|channel| channel.is_public));
+        if !include {
+            return vec![];
+        }
+
+        let mut best_channels: HashMap<PublicKey, &ChannelDetails> = HashMap::new();
+        for channel in channels.iter().filter(|channel| !channel.is_public) {
+            if channel.get_inbound_payment_scid().is_none()
+                || channel.counterparty.forwarding_info.is_none()
+            {
+                continue;
+            }
+            let best = best_channels
+                .entry(channel.counterparty.node_id)
+                .or_insert(channel);
+            if channel.inbound_capacity_msat > best.inbound_capacity_msat {
+                *best = channel;
+            }
+        }
+
+        best_channels
+            .values()
+            .map(|channel| {
+                let forwarding_info = channel.counterparty.forwarding_info.as_ref().unwrap();
+                RouteHint(vec![RouteHintHop {
+                    src_node_id: channel.counterparty.node_id,
+                    short_channel_id: channel.get_inbound_payment_scid().unwrap(),
+                    fees: RoutingFees {
+                        base_msat: forwarding_info.fee_base_msat,
+                        proportional_millionths: forwarding_info.fee_proportional_millionths,
+                    },
+                    cltv_expiry_delta: forwarding_info.cltv_expiry_delta,
+                    htlc_minimum_msat: channel.inbound_htlc_minimum_msat,
+                    htlc_maximum_msat: channel.inbound_htlc_maximum_msat,
+                }])
+            })
+            .collect()
+    }
+
+    pub fn invoice_currency(&self) -> Currency {
+        match self.config.network {
             Network::Bitcoin => Currency::Bitcoin,
             Network::Testnet => Currency::BitcoinTestnet,
             Network::Regtest => Currency::Regtest,
             Network::Signet => Currency::Signet,
+        }
+    }
+
+    pub fn sign_invoice(&self, raw_invoice: RawInvoice) -> Result<Invoice, Error> {
+        let hrp_str = raw_invoice.hrp.to_string();
+        let data_without_signature = raw_invoice.data.to_base32();
+        let signed_raw_invoice = raw_invoice
+            .sign(|_| {
+                self.keys_manager.sign_invoice(
+                    hrp_str.as_bytes(),
+                    &data_without_signature,
+                    Recipient::Node,
+                )
+            })
+            .map_err(SignOrCreationError::SignError)?;
+        Ok(
+            Invoice::from_signed(signed_raw_invoice)
+                .map_err(ParseOrSemanticError::SemanticError)?,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_invoice(
+        &self,
+        amt_msat: Option<u64>,
+        min_amt_msat: Option<u64>,
+        description: String,
+        description_hash: Option<String>,
+        expiry_secs: Option<u32>,
+        min_final_cltv_expiry: Option<u64>,
+        private_route_hints: Option<bool>,
+    ) -> Result<Invoice, Error> {
+        let currency = self.invoice_currency();
+
+        check_min_amt_msat(amt_msat, min_amt_msat)?;
+
+        let expiry_secs = expiry_secs.unwrap_or(DEFAULT_INVOICE_EXPIRY_SECS);
+        let min_final_cltv_expiry = min_final_cltv_expiry.unwrap_or(MIN_FINAL_CLTV_EXPIRY.into());
+        if min_final_cltv_expiry < MIN_FINAL_CLTV_EXPIRY.into() {
+            return Err(Error::Generic(format!(
+                "min_final_cltv_expiry must be at least {}",
+                MIN_FINAL_CLTV_EXPIRY
+            )));
+        }
+
+        let builder = match description_hash {
+            Some(description_hash) => {
+                let hash = hex_utils::to_vec(&description_hash)
+                    .and_then(|bytes| Sha256::from_slice(&bytes).ok())
+                    .ok_or_else(|| Error::Generic(String::from("invalid description hash")))?;
+                InvoiceBuilder::new(currency).description_hash(hash)
+            }
+            None => InvoiceBuilder::new(currency).description(description.clone()),
         };
 
-        let invoice = utils::create_invoice_from_channelmanager(
-            &self.channel_manager,
-            self.keys_manager.clone(),
-            currency,
-            Some(amt_msat),
-            description.clone(),
-            3600, // FIXME invoice_expiry_delta_secs
-        )?;
+        let (payment_hash, payment_secret) = self
+            .channel_manager
+            .create_inbound_payment(amt_msat, expiry_secs)
+            .map_err(|_| Error::Generic(String::from("invalid invoice amount")))?;
+
+        let mut builder = builder
+            .duration_since_epoch(SystemTime::now().duration_since(UNIX_EPOCH).unwrap())
+            .payee_pub_key(self.channel_manager.get_our_node_id())
+            .payment_hash(Sha256::from_slice(&payment_hash.0).unwrap())
+            .payment_secret(payment_secret)
+            .basic_mpp()
+            .min_final_cltv_expiry(min_final_cltv_expiry)
+            .expiry_time(Duration::from_secs(expiry_secs.into()));
+        if let Some(amt_msat) = amt_msat {
+            builder = builder.amount_milli_satoshis(amt_msat);
+        }
+        for hint in self.private_route_hints(private_route_hints) {
+            builder = builder.private_route(hint);
+        }
+
+        let raw_invoice = builder
+            .build_raw()
+            .map_err(SignOrCreationError::CreationError)?;
+        let invoice = self.sign_invoice(raw_invoice)?;
 
         let payment_hash = hex_utils::hex_str(&(*invoice.payment_hash()).into_inner());
         let payment_secret = Some(hex_utils::hex_str(&(*invoice.payment_secret()).0));
@@ -1178,10 +2296,12 @@ impl LightningNode {
             payment_hash: ActiveValue::Set(payment_hash),
             secret: ActiveValue::Set(payment_secret),
             status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
-            amt_msat: ActiveValue::Set(Some(amt_msat.try_into().unwrap())),
+            amt_msat: ActiveValue::Set(amt_msat.map(|amt| amt.try_into().unwrap())),
+            min_amt_msat: ActiveValue::Set(min_amt_msat.map(|amt| amt.try_into().unwrap())),
             origin: ActiveValue::Set(PaymentOrigin::InvoiceIncoming.to_string()),
             invoice: ActiveValue::Set(Some(invoice.to_string())),
             label: ActiveValue::Set(Some(description)),
+            expires_at: ActiveValue::Set(Some(seconds_since_epoch() + i64::from(expiry_secs))),
             ..Default::default()
         };
 
@@ -1193,42 +2313,404 @@ impl LightningNode {
         Ok(invoice)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_scheduled_payment(
+        &self,
+        kind: String,
+        dest_pubkey: Option<String>,
+        invoice_url: Option<String>,
+        amt_msat: u64,
+        interval_secs: u64,
+        start_at: Option<i64>,
+    ) -> Result<entity::scheduled_payment::Model, Error> {
+        let kind = ScheduledPaymentKind::from_str(&kind)?;
+        match kind {
+            ScheduledPaymentKind::Keysend => {
+                if dest_pubkey
+                    .as_deref()
+                    .and_then(hex_utils::to_compressed_pubkey)
+                    .is_none()
+                {
+                    return Err(Error::Generic(String::from(
+                        "a valid dest_pubkey is required for keysend schedules",
+                    )));
+                }
+            }
+            ScheduledPaymentKind::InvoiceUrl => {
+                if !invoice_url
+                    .as_deref()
+                    .map(|url| url.starts_with("https://") || url.starts_with("http://"))
+                    .unwrap_or(false)
+                {
+                    return Err(Error::Generic(String::from(
+                        "an http(s) invoice_url is required for invoice url schedules",
+                    )));
+                }
+            }
+        }
+
+        let interval_secs: i64 = interval_secs.try_into().unwrap_or(i64::MAX);
+        if interval_secs < MIN_SCHEDULE_INTERVAL_SECS {
+            return Err(Error::Generic(format!(
+                "interval_secs must be at least {}",
+                MIN_SCHEDULE_INTERVAL_SECS
+            )));
+        }
+        if amt_msat == 0 {
+            return Err(Error::Generic(String::from("amt_msat must be positive")));
+        }
+
+        let scheduled_payment = entity::scheduled_payment::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            kind: ActiveValue::Set(kind.to_string()),
+            dest_pubkey: ActiveValue::Set(dest_pubkey),
+            invoice_url: ActiveValue::Set(invoice_url),
+            amt_msat: ActiveValue::Set(amt_msat.try_into().unwrap()),
+            interval_secs: ActiveValue::Set(interval_secs),
+            next_run_at: ActiveValue::Set(start_at.unwrap_or_else(seconds_since_epoch)),
+            enabled: ActiveValue::Set(true),
+            ..Default::default()
+        };
+
+        Ok(scheduled_payment
+            .insert(self.database.get_connection())
+            .await?)
+    }
+
+    pub async fn update_scheduled_payment(
+        &self,
+        id: String,
+        amt_msat: Option<u64>,
+        interval_secs: Option<u64>,
+        enabled: Option<bool>,
+    ) -> Result<entity::scheduled_payment::Model, Error> {
+        let scheduled_payment = self
+            .database
+            .find_scheduled_payment(self.id.clone(), id)
+            .await?
+            .ok_or_else(|| Error::Generic(String::from("scheduled payment not found")))?;
+
+        let mut scheduled_payment: entity::scheduled_payment::ActiveModel =
+            scheduled_payment.into();
+        if let Some(amt_msat) = amt_msat {
+            if amt_msat == 0 {
+                return Err(Error::Generic(String::from("amt_msat must be positive")));
+            }
+            scheduled_payment.amt_msat = ActiveValue::Set(amt_msat.try_into().unwrap());
+        }
+        if let Some(interval_secs) = interval_secs {
+            let interval_secs: i64 = interval_secs.try_into().unwrap_or(i64::MAX);
+            if interval_secs < MIN_SCHEDULE_INTERVAL_SECS {
+                return Err(Error::Generic(format!(
+                    "interval_secs must be at least {}",
+                    MIN_SCHEDULE_INTERVAL_SECS
+                )));
+            }
+            scheduled_payment.interval_secs = ActiveValue::Set(interval_secs);
+        }
+        if let Some(enabled) = enabled {
+            scheduled_payment.enabled = ActiveValue::Set(enabled);
+        }
+
+        Ok(scheduled_payment
+            .update(self.database.get_connection())
+            .await?)
+    }
+
+    pub async fn delete_scheduled_payment(&self, id: String) -> Result<(), Error> {
+        if let Some(scheduled_payment) = self
+            .database
+            .find_scheduled_payment(self.id.clone(), id)
+            .await?
+        {
+            scheduled_payment
+                .delete(self.database.get_connection())
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub fn get_balance(&self) -> Result<Balance, Error> {
+        // TODO: split confirmed vs uncofirmed chain balance
+        //       we currently only have 'unconfirmed' utxos from transactions we broadcast
+        //       we never hear about transactions that enter the mempool
+        let onchain_balance_sats = {
+            let wallet = self.wallet.lock().unwrap();
+            let balance = wallet.get_balance().map_err(Error::Bdk)?;
+            balance.get_total()
+        };
+
+        let channels = self.channel_manager.list_channels();
+
+        let mut channel_balance_msats = 0;
+        let mut channel_outbound_capacity_msats = 0;
+        let mut channel_inbound_capacity_msats = 0;
+        let mut usable_channel_outbound_capacity_msats = 0;
+        let mut usable_channel_inbound_capacity_msats = 0;
+
+        for channel in channels {
+            channel_balance_msats += channel.balance_msat;
+            channel_outbound_capacity_msats += channel.outbound_capacity_msat;
+            channel_inbound_capacity_msats += channel.inbound_capacity_msat;
+
+            if channel.is_usable {
+                usable_channel_outbound_capacity_msats += channel.outbound_capacity_msat;
+                usable_channel_inbound_capacity_msats += channel.inbound_capacity_msat;
+            }
+        }
+
+        Ok(Balance {
+            onchain_balance_sats,
+            channel_balance_msats,
+            channel_outbound_capacity_msats,
+            channel_inbound_capacity_msats,
+            usable_channel_outbound_capacity_msats,
+            usable_channel_inbound_capacity_msats,
+        })
+    }
+
+    // Open channels are left out, their balance is already part of `get_balance`.
+    pub async fn get_claimable_balances(&self) -> Result<ClaimableBalances, Error> {
+        let open_funding_txos = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .filter_map(|channel| channel.funding_txo)
+            .collect::<HashSet<_>>();
+        let best_block_height = self.channel_manager.current_best_block().height();
+
+        let mut claimable_balances = ClaimableBalances {
+            best_block_height,
+            ..Default::default()
+        };
+        for funding_txo in self.chain_monitor.list_monitors() {
+            if open_funding_txos.contains(&funding_txo) {
+                continue;
+            }
+            let balances = match self.chain_monitor.get_monitor(funding_txo) {
+                Ok(monitor) => monitor.get_claimable_balances(),
+                Err(()) => continue,
+            };
+            if balances.is_empty() {
+                continue;
+            }
+
+            let balances = balances
+                .iter()
+                .map(|balance| ClaimableBalance::from_ldk(balance, best_block_height))
+                .collect::<Vec<_>>();
+            let channel_id = hex_utils::hex_str(&funding_txo.to_channel_id());
+            let counterparty_pubkey = self
+                .database
+                .find_channel(self.id.clone(), channel_id.clone())
+                .await?
+                .map(|channel| channel.counterparty_node_id);
+
+            claimable_balances.add_channel(ChannelClaimableBalances {
+                channel_id,
+                funding_txid: funding_txo.txid.to_string(),
+                funding_tx_index: funding_txo.index.into(),
+                counterparty_pubkey,
+                claimable_sats: balances.iter().map(|balance| balance.amount_sats).sum(),
+                balances,
+            });
+        }
+
+        Ok(claimable_balances)
+    }
+
+    pub async fn record_balance_snapshot(&self) -> Result<(), Error> {
+        let balance = self.get_balance()?;
+        let balance_snapshot = entity::balance_snapshot::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            onchain_balance_sats: ActiveValue::Set(balance.onchain_balance_sats as i64),
+            channel_outbound_capacity_msats: ActiveValue::Set(
+                balance.channel_outbound_capacity_msats as i64,
+            ),
+            channel_inbound_capacity_msats: ActiveValue::Set(
+                balance.channel_inbound_capacity_msats as i64,
+            ),
+            ..Default::default()
+        };
+        self.database
+            .insert_balance_snapshot(balance_snapshot)
+            .await?;
+        Ok(())
+    }
+
+    // With an interval the history is downsampled to the last snapshot taken in
+    // each interval, otherwise every snapshot in the range is returned.
+    pub async fn get_balance_history(
+        &self,
+        from: Option<i64>,
+        to: Option<i64>,
+        interval: Option<u64>,
+    ) -> Result<Vec<BalanceSnapshot>, Error> {
+        let snapshots = self
+            .database
+            .list_balance_snapshots(self.id.clone(), from, to)
+            .await?
+            .into_iter()
+            .map(BalanceSnapshot::from);
+
+        let interval = match interval {
+            Some(0) => {
+                return Err(Error::Generic(String::from(
+                    "interval must be greater than zero",
+                )))
+            }
+            Some(interval) => interval as i64,
+            None => return Ok(snapshots.collect()),
+        };
+
+        Ok(downsample_balance_history(snapshots, interval))
+    }
+
+    // A retention of zero keeps every snapshot.
+    pub async fn prune_balance_snapshots(&self, retention_secs: u64) -> Result<(), Error> {
+        if retention_secs == 0 {
+            return Ok(());
+        }
+        let before =
+            seconds_since_epoch().saturating_sub(retention_secs.try_into().unwrap_or(i64::MAX));
+        self.database
+            .delete_balance_snapshots_before(self.id.clone(), before)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn export_ledger(
+        &self,
+        from: Option<i64>,
+        to: Option<i64>,
+        format: &LedgerFormat,
+    ) -> Result<String, Error> {
+        let payments = self
+            .database
+            .list_settled_payments(self.id.clone(), from, to)
+            .await?;
+        let forwarded_payments = self
+            .database
+            .find_forwarded_payments(
+                self.id.clone(),
+                ForwardedPaymentsFilter {
+                    from_hours_since_epoch: from.map(|from| from.max(0) as u64 / 3600),
+                    to_hours_since_epoch: to.map(|to| to.max(0) as u64 / 3600),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let transactions = self.database.list_transactions(self.id.clone()).await?;
+        let channel_events = self.database.list_channel_events(self.id.clone()).await?;
+
+        let entries = LedgerBuilder::new(&channel_events, from, to)
+            .add_payments(&payments)
+            .add_forwarded_payments(&forwarded_payments)
+            .add_transactions(&transactions)
+            .build();
+
+        Ok(ledger::render(&entries, format))
+    }
+
+    pub async fn list_invoices(
+        &self,
+        pagination: PaginationRequest,
+        filter: InvoicesFilter,
+    ) -> Result<(Vec<InvoiceInfo>, PaginationResponse), Error> {
+        let (payments, pagination) = self
+            .database
+            .list_invoices(self.id.clone(), pagination, filter)
+            .await?;
+        let now = seconds_since_epoch();
+        let invoices = payments
+            .into_iter()
+            .map(|payment| InvoiceInfo::from_payment(payment, now))
+            .collect();
+        Ok((invoices, pagination))
+    }
+
+    pub async fn cancel_invoice(&self, payment_hash: String) -> Result<(), Error> {
+        self.database
+            .cancel_invoice(self.id.clone(), payment_hash)
+            .await
+    }
+
     pub fn list_channels(
         &self,
         pagination: PaginationRequest,
     ) -> Result<(Vec<Channel>, PaginationResponse), Error> {
-        let query = pagination.query.unwrap_or_else(|| String::from(""));
-        let per_page: usize = pagination.take.try_into().unwrap();
-        let page: usize = pagination.page.try_into().unwrap();
-        let index = page * per_page;
-
         let channels = self
             .channel_manager
             .list_channels()
             .into_iter()
-            .filter_map(|chan_info| {
+            .map(|chan_info| {
                 let mut channel: Channel = chan_info.clone().into();
-
                 channel.alias = self.get_alias_for_channel_counterparty(&chan_info);
+                channel
+            })
+            .collect::<Vec<Channel>>();
+
+        Ok(Self::paginate_channels(channels, pagination))
+    }
+
+    // Without a state this lists every channel that hasn't closed yet.
+    pub async fn list_channels_by_state(
+        &self,
+        pagination: PaginationRequest,
+        filter: ChannelsFilter,
+    ) -> Result<(Vec<Channel>, PaginationResponse), Error> {
+        self.sync_channels().await?;
+
+        let live_channels = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .map(|chan_info| (hex_utils::hex_str(&chan_info.channel_id), chan_info))
+            .collect::<HashMap<String, ChannelDetails>>();
+
+        let channels = self
+            .database
+            .list_channels(self.id.clone(), filter.state)
+            .await?
+            .iter()
+            .map(|record| match live_channels.get(&record.channel_id) {
+                Some(chan_info) => {
+                    let mut channel: Channel = chan_info.clone().into();
+                    channel.alias = self.get_alias_for_channel_counterparty(chan_info);
+                    channel.with_record(record)
+                }
+                None => Channel::from(record),
+            })
+            .collect::<Vec<Channel>>();
 
-                let match_channel = channel.clone();
-                let matches_channel_id = match_channel.channel_id.contains(&query);
-                let matches_pubkey = match_channel.counterparty_pubkey.contains(&query);
-                let matches_funding_txid = match_channel
+        Ok(Self::paginate_channels(channels, pagination))
+    }
+
+    fn paginate_channels(
+        channels: Vec<Channel>,
+        pagination: PaginationRequest,
+    ) -> (Vec<Channel>, PaginationResponse) {
+        let query = pagination.query.unwrap_or_else(|| String::from(""));
+        let per_page: usize = pagination.take.try_into().unwrap();
+        let page: usize = pagination.page.try_into().unwrap();
+        let index = page * per_page;
+
+        let channels = channels
+            .into_iter()
+            .filter(|channel| {
+                let matches_channel_id = channel.channel_id.contains(&query);
+                let matches_pubkey = channel.counterparty_pubkey.contains(&query);
+                let matches_funding_txid = channel
                     .funding_txid
+                    .as_ref()
                     .map(|txid| txid.contains(&query))
                     .unwrap_or(false);
-                let matches_alias = match_channel
+                let matches_alias = channel
                     .alias
+                    .as_ref()
                     .map(|alias| alias.contains(&query))
                     .unwrap_or(false);
-                let matches =
-                    matches_channel_id || matches_funding_txid || matches_pubkey || matches_alias;
-                if matches {
-                    Some(channel)
-                } else {
-                    None
-                }
+                matches_channel_id || matches_funding_txid || matches_pubkey || matches_alias
             })
             .collect::<Vec<Channel>>();
 
@@ -1243,7 +2725,128 @@ impl LightningNode {
             has_more: next_page.is_some(),
             total: channels.len() as u64,
         };
-        Ok((current_page, pagination_response))
+        (current_page, pagination_response)
+    }
+
+    async fn mark_channel_closing(&self, chan_info: &ChannelDetails) -> Result<(), Error> {
+        let record = self.sync_channel(chan_info).await?;
+        // a force close can be handled before we get here
+        if record.state == ChannelState::Closed.to_string() {
+            return Ok(());
+        }
+        let mut record: entity::channel::ActiveModel = record.into();
+        record.state = ActiveValue::Set(ChannelState::Closing.to_string());
+        record.closing_at = ActiveValue::Set(Some(seconds_since_epoch()));
+        self.database.update_channel(record).await?;
+        Ok(())
+    }
+
+    // Records every channel the channel manager knows about so it is still listed once closed.
+    pub async fn sync_channels(&self) -> Result<(), Error> {
+        for chan_info in self.channel_manager.list_channels() {
+            self.sync_channel(&chan_info).await?;
+        }
+        Ok(())
+    }
+
+    async fn sync_channel(
+        &self,
+        chan_info: &ChannelDetails,
+    ) -> Result<entity::channel::Model, Error> {
+        let channel_id = hex_utils::hex_str(&chan_info.channel_id);
+        let now = seconds_since_epoch();
+
+        // inbound channels have no event of their own, so both directions get their open
+        // event here once the funding outpoint is known
+        if let Some(funding_txo) = chan_info.funding_txo {
+            if self
+                .database
+                .find_channel_open_event(self.id.clone(), channel_id.clone())
+                .await?
+                .is_none()
+            {
+                let channel_event = entity::channel_event::ActiveModel {
+                    node_id: ActiveValue::Set(self.id.clone()),
+                    channel_id: ActiveValue::Set(channel_id.clone()),
+                    counterparty_node_id: ActiveValue::Set(Some(
+                        chan_info.counterparty.node_id.to_string(),
+                    )),
+                    kind: ActiveValue::Set(ChannelEventKind::Open.to_string()),
+                    funding_txid: ActiveValue::Set(Some(funding_txo.txid.to_string())),
+                    channel_value_satoshis: ActiveValue::Set(Some(
+                        chan_info.channel_value_satoshis as i64,
+                    )),
+                    is_outbound: ActiveValue::Set(Some(chan_info.is_outbound)),
+                    ..Default::default()
+                };
+                self.database.insert_channel_event(channel_event).await?;
+            }
+        }
+
+        let ready_at = if chan_info.is_channel_ready {
+            Some(now)
+        } else {
+            None
+        };
+
+        match self
+            .database
+            .find_channel(self.id.clone(), channel_id.clone())
+            .await?
+        {
+            Some(record) => {
+                let state = record.state.clone();
+                let has_ready_at = record.ready_at.is_some();
+                let mut record: entity::channel::ActiveModel = record.into();
+                record.funding_txid =
+                    ActiveValue::Set(chan_info.funding_txo.map(|txo| txo.txid.to_string()));
+                record.funding_tx_index =
+                    ActiveValue::Set(chan_info.funding_txo.map(|txo| txo.index as i32));
+                record.short_channel_id =
+                    ActiveValue::Set(chan_info.short_channel_id.map(|scid| scid as i64));
+                record.balance_msat = ActiveValue::Set(chan_info.balance_msat as i64);
+                if !has_ready_at && chan_info.is_channel_ready {
+                    record.ready_at = ActiveValue::Set(ready_at);
+                }
+                if state == ChannelState::PendingOpen.to_string() && chan_info.is_channel_ready {
+                    record.state = ActiveValue::Set(ChannelState::Ready.to_string());
+                }
+                self.database.update_channel(record).await
+            }
+            None => {
+                let state = if chan_info.is_channel_ready {
+                    ChannelState::Ready
+                } else {
+                    ChannelState::PendingOpen
+                };
+                let record = entity::channel::ActiveModel {
+                    node_id: ActiveValue::Set(self.id.clone()),
+                    channel_id: ActiveValue::Set(channel_id),
+                    counterparty_node_id: ActiveValue::Set(
+                        chan_info.counterparty.node_id.to_string(),
+                    ),
+                    funding_txid: ActiveValue::Set(
+                        chan_info.funding_txo.map(|txo| txo.txid.to_string()),
+                    ),
+                    funding_tx_index: ActiveValue::Set(
+                        chan_info.funding_txo.map(|txo| txo.index as i32),
+                    ),
+                    short_channel_id: ActiveValue::Set(
+                        chan_info.short_channel_id.map(|scid| scid as i64),
+                    ),
+                    channel_value_satoshis: ActiveValue::Set(
+                        chan_info.channel_value_satoshis as i64,
+                    ),
+                    balance_msat: ActiveValue::Set(chan_info.balance_msat as i64),
+                    is_outbound: ActiveValue::Set(chan_info.is_outbound),
+                    is_public: ActiveValue::Set(chan_info.is_public),
+                    state: ActiveValue::Set(state.to_string()),
+                    ready_at: ActiveValue::Set(ready_at),
+                    ..Default::default()
+                };
+                self.database.insert_channel(record).await
+            }
+        }
     }
 
     pub fn list_transactions(
@@ -1273,75 +2876,402 @@ impl LightningNode {
             .unwrap_or_default();
         let next_page = paginated_transactions.page(index + per_page);
 
-        let pagination_response = PaginationResponse {
-            has_more: next_page.is_some(),
-            total: transaction_details.len() as u64,
+        let pagination_response = PaginationResponse {
+            has_more: next_page.is_some(),
+            total: transaction_details.len() as u64,
+        };
+        Ok((current_page, pagination_response))
+    }
+
+    pub fn get_alias_for_channel_counterparty(
+        &self,
+        channel_details: &ChannelDetails,
+    ) -> Option<String> {
+        let db_alias = match self
+            .database
+            .find_peer_sync(&self.id, &channel_details.counterparty.node_id.to_string())
+        {
+            Ok(Some(peer)) => peer.alias,
+            _ => None,
+        };
+        match db_alias {
+            Some(db_alias) => Some(db_alias),
+            None => {
+                let node_id = NodeId::from_pubkey(&channel_details.counterparty.node_id);
+                let alias = self
+                    .p2p
+                    .network_graph
+                    .read_only()
+                    .nodes()
+                    .get(&node_id)
+                    .and_then(|node_info| {
+                        node_info
+                            .announcement_info
+                            .clone()
+                            .map(|ann_info| ann_info.alias)
+                    });
+
+                alias.map(|node_alias| node_alias.to_string())
+            }
+        }
+    }
+
+    pub async fn list_payments(
+        &self,
+        pagination: PaginationRequest,
+        filter: PaymentsFilter,
+    ) -> Result<(Vec<entity::payment::Model>, PaginationResponse), Error> {
+        self.database
+            .list_payments(self.id.clone(), pagination, filter)
+            .await
+    }
+
+    pub async fn list_phantom_payments(
+        &self,
+        pagination: PaginationRequest,
+        filter: PaymentsFilter,
+    ) -> Result<(Vec<entity::payment::Model>, PaginationResponse), Error> {
+        self.database
+            .list_payments(self.get_phantom_node_pubkey(), pagination, filter)
+            .await
+    }
+
+    pub async fn list_forwarded_payments(
+        &self,
+        pagination: PaginationRequest,
+        filter: ForwardedPaymentsFilter,
+    ) -> Result<(Vec<entity::forwarded_payment::Model>, PaginationResponse), Error> {
+        self.database
+            .list_forwarded_payments(self.id.clone(), pagination, filter)
+            .await
+    }
+
+    pub async fn get_routing_revenue(
+        &self,
+        filter: ForwardedPaymentsFilter,
+    ) -> Result<RoutingRevenue, Error> {
+        let forwarded_payments = self
+            .database
+            .find_forwarded_payments(self.id.clone(), filter)
+            .await?;
+
+        let mut revenue = RoutingRevenue::default();
+        let mut channels: BTreeMap<String, ChannelRoutingRevenue> = BTreeMap::new();
+        let mut days: BTreeMap<u64, DailyRoutingRevenue> = BTreeMap::new();
+
+        for forwarded_payment in forwarded_payments {
+            let fees_earned_msat = forwarded_payment.fees_earned_msat.unwrap_or(0) as u64;
+            let days_since_epoch = forwarded_payment.hours_since_epoch as u64 / 24;
+
+            revenue.fees_earned_msat += fees_earned_msat;
+            revenue.num_forwards += 1;
+
+            if let Some(channel_id) = forwarded_payment.from_channel_id {
+                let channel =
+                    channels
+                        .entry(channel_id.clone())
+                        .or_insert_with(|| ChannelRoutingRevenue {
+                            channel_id,
+                            ..Default::default()
+                        });
+                channel.num_forwards_in += 1;
+            }
+
+            // fees are attributed to the channel the payment was forwarded out over
+            if let Some(channel_id) = forwarded_payment.to_channel_id {
+                let channel =
+                    channels
+                        .entry(channel_id.clone())
+                        .or_insert_with(|| ChannelRoutingRevenue {
+                            channel_id,
+                            ..Default::default()
+                        });
+                channel.num_forwards_out += 1;
+                channel.fees_earned_msat += fees_earned_msat;
+            }
+
+            let day = days
+                .entry(days_since_epoch)
+                .or_insert_with(|| DailyRoutingRevenue {
+                    days_since_epoch,
+                    ..Default::default()
+                });
+            day.num_forwards += 1;
+            day.fees_earned_msat += fees_earned_msat;
+        }
+
+        revenue.channels = channels.into_values().collect();
+        revenue.days = days.into_values().collect();
+        Ok(revenue)
+    }
+
+    pub async fn get_payment_attempts(
+        &self,
+        payment_hash: String,
+    ) -> Result<Vec<PaymentAttempt>, Error> {
+        let attempts = self
+            .database
+            .list_payment_attempts(self.id.clone(), payment_hash)
+            .await?;
+        Ok(attempts.into_iter().map(PaymentAttempt::from).collect())
+    }
+
+    // Returns whether a cooperative close pays out to `destination_address`. Channels that
+    // committed to a shutdown script when they were opened always close to that script.
+    pub async fn close_channel(
+        &self,
+        channel_id: [u8; 32],
+        force: bool,
+        destination_address: Option<String>,
+        target_feerate_sats_per_vbyte: Option<u32>,
+    ) -> Result<bool, Error> {
+        if force && (destination_address.is_some() || target_feerate_sats_per_vbyte.is_some()) {
+            return Err(Error::Generic(String::from(
+                "destination address and fee rate only apply to cooperative closes",
+            )));
+        }
+
+        let shutdown_script = match destination_address.as_ref() {
+            Some(destination_address) => {
+                let address = Address::from_str(destination_address)
+                    .map_err(|_| Error::Generic(String::from("invalid destination address")))?;
+                if address.network != self.config.network {
+                    return Err(Error::Generic(format!(
+                        "destination address is not a {} address",
+                        self.config.network
+                    )));
+                }
+                let shutdown_script =
+                    ShutdownScript::try_from(address.script_pubkey()).map_err(|_| {
+                        Error::Generic(String::from("destination address must be a segwit address"))
+                    })?;
+                Some(shutdown_script)
+            }
+            None => None,
+        };
+
+        let cp_id = self.get_channel_counterparty(&channel_id);
+        let chan_info = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|chan_info| chan_info.channel_id == channel_id);
+        let destination_used = if force {
+            self.channel_manager
+                .force_close_broadcasting_latest_txn(&channel_id, &cp_id)?;
+            false
+        } else {
+            let (result, destination_used) =
+                self.keys_manager.with_shutdown_script(shutdown_script, || {
+                    match target_feerate_sats_per_vbyte {
+                        // 1 vbyte is 4 weight units, 253 is the minimum relay fee per 1000 weight
+                        Some(feerate) => self.channel_manager.close_channel_with_target_feerate(
+                            &channel_id,
+                            &cp_id,
+                            feerate.saturating_mul(250).max(253),
+                        ),
+                        None => self.channel_manager.close_channel(&channel_id, &cp_id),
+                    }
+                });
+            result?;
+            destination_used
+        };
+
+        let channel_event = entity::channel_event::ActiveModel {
+            node_id: ActiveValue::Set(self.id.clone()),
+            channel_id: ActiveValue::Set(hex_utils::hex_str(&channel_id)),
+            counterparty_node_id: ActiveValue::Set(Some(cp_id.to_string())),
+            kind: ActiveValue::Set(ChannelEventKind::CloseRequested.to_string()),
+            reason: ActiveValue::Set(Some(String::from(if force {
+                "force"
+            } else {
+                "cooperative"
+            }))),
+            destination_address: ActiveValue::Set(destination_address.filter(|_| destination_used)),
+            target_feerate_sats_per_vbyte: ActiveValue::Set(
+                target_feerate_sats_per_vbyte.map(|feerate| feerate.min(i32::MAX as u32) as i32),
+            ),
+            ..Default::default()
         };
-        Ok((current_page, pagination_response))
+        if let Err(e) = self.database.insert_channel_event(channel_event).await {
+            println!("failed to record channel close request: {:?}", e);
+        }
+
+        if let Some(chan_info) = chan_info {
+            if let Err(e) = self.mark_channel_closing(&chan_info).await {
+                println!("failed to record channel as closing: {:?}", e);
+            }
+        }
+
+        Ok(destination_used)
     }
 
-    pub fn get_alias_for_channel_counterparty(
+    pub async fn set_channel_acceptance_policy(
         &self,
-        channel_details: &ChannelDetails,
-    ) -> Option<String> {
-        let db_alias = match self
-            .database
-            .find_peer_sync(&self.id, &channel_details.counterparty.node_id.to_string())
+        policy: &ChannelAcceptancePolicy,
+    ) -> Result<(), Error> {
+        if let (Some(min_channel_size_sats), Some(max_channel_size_sats)) =
+            (policy.min_channel_size_sats, policy.max_channel_size_sats)
         {
-            Ok(Some(peer)) => peer.alias,
-            _ => None,
-        };
-        match db_alias {
-            Some(db_alias) => Some(db_alias),
-            None => {
-                let node_id = NodeId::from_pubkey(&channel_details.counterparty.node_id);
-                let alias = self
-                    .p2p
-                    .network_graph
-                    .read_only()
-                    .nodes()
-                    .get(&node_id)
-                    .and_then(|node_info| {
-                        node_info
-                            .announcement_info
-                            .clone()
-                            .map(|ann_info| ann_info.alias)
-                    });
-
-                alias.map(|node_alias| node_alias.to_string())
+            if min_channel_size_sats > max_channel_size_sats {
+                return Err(Error::Generic(String::from(
+                    "min_channel_size_sats must not be more than max_channel_size_sats",
+                )));
+            }
+        }
+        if policy.min_confirmations == Some(0) {
+            return Err(Error::Generic(String::from(
+                "min_confirmations must be at least 1, use zero_conf peers for 0-conf channels",
+            )));
+        }
+        // the channel manager only reads the minimum depth when it is created
+        let minimum_depth = self
+            .channel_manager
+            .get_current_default_configuration()
+            .channel_handshake_config
+            .minimum_depth;
+        let min_confirmations = policy
+            .min_confirmations
+            .unwrap_or(UserConfig::default().channel_handshake_config.minimum_depth);
+        if min_confirmations != minimum_depth {
+            return Err(Error::Generic(format!(
+                "min_confirmations can't be changed while the node is running, inbound channels need {} confirmations",
+                minimum_depth
+            )));
+        }
+        for peer in policy
+            .allowed_peers
+            .iter()
+            .chain(policy.denied_peers.iter())
+        {
+            if PublicKey::from_str(peer).is_err() {
+                return Err(Error::Generic(format!("invalid peer pubkey {}", peer)));
+            }
+        }
+        if let Some(webhook_url) = &policy.webhook_url {
+            if reqwest::Url::parse(webhook_url).is_err() {
+                return Err(Error::Generic(format!(
+                    "invalid webhook url {}",
+                    webhook_url
+                )));
             }
         }
+        if policy.webhook_timeout_secs == 0 {
+            return Err(Error::Generic(String::from(
+                "webhook_timeout_secs must be at least 1",
+            )));
+        }
+        self.database
+            .set_channel_acceptance_policy(self.id.clone(), policy)
+            .await
     }
 
-    pub async fn list_payments(
-        &self,
-        pagination: PaginationRequest,
-        filter: PaymentsFilter,
-    ) -> Result<(Vec<entity::payment::Model>, PaginationResponse), Error> {
+    pub async fn set_autopilot_policy(&self, policy: &AutopilotPolicy) -> Result<(), Error> {
+        if policy.allocation_percent == 0 || policy.allocation_percent > 100 {
+            return Err(Error::Generic(String::from(
+                "allocation_percent must be between 1 and 100",
+            )));
+        }
+        if policy.min_channel_size_sats > policy.max_channel_size_sats {
+            return Err(Error::Generic(String::from(
+                "min_channel_size_sats must not be more than max_channel_size_sats",
+            )));
+        }
+        if policy.interval_secs < MIN_AUTOPILOT_INTERVAL_SECS {
+            return Err(Error::Generic(format!(
+                "interval_secs must be at least {}",
+                MIN_AUTOPILOT_INTERVAL_SECS
+            )));
+        }
         self.database
-            .list_payments(self.id.clone(), pagination, filter)
+            .set_autopilot_policy(self.id.clone(), policy)
             .await
     }
 
-    pub async fn list_phantom_payments(
-        &self,
-        pagination: PaginationRequest,
-        filter: PaymentsFilter,
-    ) -> Result<(Vec<entity::payment::Model>, PaginationResponse), Error> {
+    pub async fn set_jit_channel_policy(&self, policy: &JitChannelPolicy) -> Result<(), Error> {
+        if policy.min_channel_size_sats > policy.max_channel_size_sats {
+            return Err(Error::Generic(String::from(
+                "min_channel_size_sats must not be more than max_channel_size_sats",
+            )));
+        }
         self.database
-            .list_payments(self.get_phantom_node_pubkey(), pagination, filter)
+            .set_jit_channel_policy(self.id.clone(), policy)
             .await
     }
 
-    pub fn close_channel(&self, channel_id: [u8; 32], force: bool) -> Result<(), Error> {
-        let cp_id = self.get_channel_counterparty(&channel_id);
-        if force {
-            Ok(self
-                .channel_manager
-                .force_close_broadcasting_latest_txn(&channel_id, &cp_id)?)
-        } else {
-            Ok(self.channel_manager.close_channel(&channel_id, &cp_id)?)
+    pub async fn set_fee_policy(&self, policy: &FeePolicy) -> Result<(), Error> {
+        if policy.min_fee_proportional_millionths > policy.max_fee_proportional_millionths {
+            return Err(Error::Generic(String::from(
+                "min_fee_proportional_millionths must not be more than max_fee_proportional_millionths",
+            )));
+        }
+        if policy.interval_secs < MIN_FEE_POLICY_INTERVAL_SECS {
+            return Err(Error::Generic(format!(
+                "interval_secs must be at least {}",
+                MIN_FEE_POLICY_INTERVAL_SECS
+            )));
+        }
+        self.database.set_fee_policy(self.id.clone(), policy).await
+    }
+
+    // Only the fields that are set are changed, the rest of each channel's config is kept.
+    // The channel manager persists the new config and gossips a channel update for it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_channel_config(
+        &self,
+        channel_ids: Vec<String>,
+        all: bool,
+        forwarding_fee_base_msat: Option<u32>,
+        forwarding_fee_proportional_millionths: Option<u32>,
+        cltv_expiry_delta: Option<u16>,
+        max_dust_htlc_exposure_msat: Option<u64>,
+    ) -> Result<Vec<String>, Error> {
+        if !all && channel_ids.is_empty() {
+            return Err(Error::Generic(String::from(
+                "either provide channel ids or update all channels",
+            )));
+        }
+
+        let mut channels = self.channel_manager.list_channels();
+        if !all {
+            if let Some(channel_id) = channel_ids.iter().find(|channel_id| {
+                !channels
+                    .iter()
+                    .any(|channel| hex_utils::hex_str(&channel.channel_id) == **channel_id)
+            }) {
+                return Err(Error::Generic(format!("channel {} not found", channel_id)));
+            }
+            channels
+                .retain(|channel| channel_ids.contains(&hex_utils::hex_str(&channel.channel_id)));
         }
+
+        let updates = channels
+            .iter()
+            .map(|channel| {
+                let mut config = channel.config.unwrap_or_default();
+                if let Some(forwarding_fee_base_msat) = forwarding_fee_base_msat {
+                    config.forwarding_fee_base_msat = forwarding_fee_base_msat;
+                }
+                if let Some(forwarding_fee_proportional_millionths) =
+                    forwarding_fee_proportional_millionths
+                {
+                    config.forwarding_fee_proportional_millionths =
+                        forwarding_fee_proportional_millionths;
+                }
+                if let Some(cltv_expiry_delta) = cltv_expiry_delta {
+                    config.cltv_expiry_delta = cltv_expiry_delta;
+                }
+                if let Some(max_dust_htlc_exposure_msat) = max_dust_htlc_exposure_msat {
+                    config.max_dust_htlc_exposure_msat = max_dust_htlc_exposure_msat;
+                }
+                (channel.counterparty.node_id, channel.channel_id, config)
+            })
+            .collect::<Vec<_>>();
+
+        apply_channel_configs(updates, |counterparty_node_id, channel_id, config| {
+            self.channel_manager
+                .update_channel_config(counterparty_node_id, &[*channel_id], config)
+        })
     }
 
     fn get_channel_counterparty(&self, channel_id: &[u8; 32]) -> PublicKey {
@@ -1450,43 +3380,26 @@ impl LightningNode {
                 })
             }
             NodeRequest::GetBalance {} => {
-                // TODO: split confirmed vs uncofirmed chain balance
-                //       we currently only have 'unconfirmed' utxos from transactions we broadcast
-                //       we never hear about transactions that enter the mempool
-                let onchain_balance_sats = {
-                    let wallet = self.wallet.lock().unwrap();
-                    let balance = wallet.get_balance().map_err(Error::Bdk)?;
-                    balance.get_total()
-                };
-
-                let channels = self.channel_manager.list_channels();
-
-                let mut channel_balance_msats = 0;
-                let mut channel_outbound_capacity_msats = 0;
-                let mut channel_inbound_capacity_msats = 0;
-                let mut usable_channel_outbound_capacity_msats = 0;
-                let mut usable_channel_inbound_capacity_msats = 0;
-
-                for channel in channels {
-                    channel_balance_msats += channel.balance_msat;
-                    channel_outbound_capacity_msats += channel.outbound_capacity_msat;
-                    channel_inbound_capacity_msats += channel.inbound_capacity_msat;
-
-                    if channel.is_usable {
-                        usable_channel_outbound_capacity_msats += channel.outbound_capacity_msat;
-                        usable_channel_inbound_capacity_msats += channel.inbound_capacity_msat;
-                    }
-                }
-
+                let balance = self.get_balance()?;
                 Ok(NodeResponse::GetBalance {
-                    onchain_balance_sats,
-                    channel_balance_msats,
-                    channel_outbound_capacity_msats,
-                    channel_inbound_capacity_msats,
-                    usable_channel_outbound_capacity_msats,
-                    usable_channel_inbound_capacity_msats,
+                    onchain_balance_sats: balance.onchain_balance_sats,
+                    channel_balance_msats: balance.channel_balance_msats,
+                    channel_outbound_capacity_msats: balance.channel_outbound_capacity_msats,
+                    channel_inbound_capacity_msats: balance.channel_inbound_capacity_msats,
+                    usable_channel_outbound_capacity_msats: balance
+                        .usable_channel_outbound_capacity_msats,
+                    usable_channel_inbound_capacity_msats: balance
+                        .usable_channel_inbound_capacity_msats,
                 })
             }
+            NodeRequest::GetClaimableBalances {} => {
+                let balances = self.get_claimable_balances().await?;
+                Ok(NodeResponse::GetClaimableBalances { balances })
+            }
+            NodeRequest::GetBalanceHistory { from, to, interval } => {
+                let snapshots = self.get_balance_history(from, to, interval).await?;
+                Ok(NodeResponse::GetBalanceHistory { snapshots })
+            }
             NodeRequest::OpenChannels { requests } => self
                 .open_channels(requests.clone())
                 .await
@@ -1509,10 +3422,16 @@ impl LightningNode {
                         .collect::<Vec<_>>(),
                 })
                 .map_err(|e| NodeRequestError::Sensei(e.to_string())),
-            NodeRequest::SendPayment { invoice } => {
+            NodeRequest::SendPayment {
+                invoice,
+                idempotency_key,
+            } => {
                 let invoice = self.get_invoice_from_str(&invoice)?;
-                self.send_payment(&invoice).await?;
-                Ok(NodeResponse::SendPayment {})
+                let payment = self.send_payment(&invoice, idempotency_key).await?;
+                Ok(NodeResponse::SendPayment {
+                    payment_hash: payment.payment_hash,
+                    status: payment.status,
+                })
             }
             NodeRequest::DecodeInvoice { invoice } => {
                 let invoice = self.get_invoice_from_str(&invoice)?;
@@ -1523,19 +3442,60 @@ impl LightningNode {
             NodeRequest::Keysend {
                 dest_pubkey,
                 amt_msat,
+                idempotency_key,
             } => match hex_utils::to_compressed_pubkey(&dest_pubkey) {
                 Some(pubkey) => {
-                    self.keysend(&*self.invoice_payer, pubkey, amt_msat, &*self.keys_manager)
+                    let payment = self
+                        .keysend(
+                            &*self.invoice_payer,
+                            pubkey,
+                            amt_msat,
+                            idempotency_key,
+                            &*self.keys_manager,
+                        )
                         .await?;
-                    Ok(NodeResponse::Keysend {})
+                    Ok(NodeResponse::Keysend {
+                        payment_hash: payment.payment_hash,
+                        status: payment.status,
+                    })
                 }
                 None => Err(NodeRequestError::Sensei("invalid dest_pubkey".into())),
             },
+            NodeRequest::Rebalance {
+                from_channel_id,
+                to_channel_id,
+                amt_msat,
+                max_fee_msat,
+            } => {
+                let payment = self
+                    .rebalance(&from_channel_id, &to_channel_id, amt_msat, max_fee_msat)
+                    .await?;
+                Ok(NodeResponse::Rebalance {
+                    payment_hash: payment.payment_hash,
+                    status: payment.status,
+                    fee_msat: payment.fee_paid_msat.unwrap_or(0).max(0) as u64,
+                })
+            }
             NodeRequest::GetInvoice {
                 amt_msat,
+                min_amt_msat,
                 description,
+                description_hash,
+                expiry_secs,
+                min_final_cltv_expiry,
+                private_route_hints,
             } => {
-                let invoice = self.get_invoice(amt_msat, description).await?;
+                let invoice = self
+                    .get_invoice(
+                        amt_msat,
+                        min_amt_msat,
+                        description,
+                        description_hash,
+                        expiry_secs,
+                        min_final_cltv_expiry,
+                        private_route_hints,
+                    )
+                    .await?;
                 let invoice_str = format!("{}", invoice);
                 Ok(NodeResponse::GetInvoice {
                     invoice: invoice_str,
@@ -1543,6 +3503,7 @@ impl LightningNode {
             }
             NodeRequest::GetPhantomInvoice {
                 amt_msat,
+                min_amt_msat,
                 description,
                 phantom_route_hints_hex,
             } => {
@@ -1555,7 +3516,7 @@ impl LightningNode {
                     .collect::<Vec<PhantomRouteHints>>();
 
                 let invoice = self
-                    .get_phantom_invoice(amt_msat, description, phantom_route_hints)
+                    .get_phantom_invoice(amt_msat, min_amt_msat, description, phantom_route_hints)
                     .await?;
                 let invoice_str = format!("{}", invoice);
                 Ok(NodeResponse::GetPhantomInvoice {
@@ -1586,8 +3547,13 @@ impl LightningNode {
 
                 Ok(NodeResponse::ConnectPeer {})
             }
-            NodeRequest::ListChannels { pagination } => {
-                let (channels, pagination) = self.list_channels(pagination)?;
+            NodeRequest::ListHtlcs { channel_id } => {
+                let htlcs = self.list_htlcs(channel_id).await?;
+                Ok(NodeResponse::ListHtlcs { htlcs })
+            }
+            NodeRequest::ListChannels { pagination, filter } => {
+                let (channels, pagination) =
+                    self.list_channels_by_state(pagination, filter).await?;
                 Ok(NodeResponse::ListChannels {
                     channels,
                     pagination,
@@ -1614,14 +3580,222 @@ impl LightningNode {
                     pagination,
                 })
             }
-            NodeRequest::CloseChannel { channel_id, force } => {
+            NodeRequest::ListForwardedPayments { pagination, filter } => {
+                let (forwarded_payments, pagination) =
+                    self.list_forwarded_payments(pagination, filter).await?;
+                Ok(NodeResponse::ListForwardedPayments {
+                    forwarded_payments,
+                    pagination,
+                })
+            }
+            NodeRequest::GetRoutingRevenue { filter } => {
+                let revenue = self.get_routing_revenue(filter).await?;
+                Ok(NodeResponse::GetRoutingRevenue { revenue })
+            }
+            NodeRequest::ListInvoices { pagination, filter } => {
+                let (invoices, pagination) = self.list_invoices(pagination, filter).await?;
+                Ok(NodeResponse::ListInvoices {
+                    invoices,
+                    pagination,
+                })
+            }
+            NodeRequest::CancelInvoice { payment_hash } => {
+                self.cancel_invoice(payment_hash).await?;
+                Ok(NodeResponse::CancelInvoice {})
+            }
+            NodeRequest::CreateScheduledPayment {
+                kind,
+                dest_pubkey,
+                invoice_url,
+                amt_msat,
+                interval_secs,
+                start_at,
+            } => {
+                let scheduled_payment = self
+                    .create_scheduled_payment(
+                        kind,
+                        dest_pubkey,
+                        invoice_url,
+                        amt_msat,
+                        interval_secs,
+                        start_at,
+                    )
+                    .await?;
+                Ok(NodeResponse::CreateScheduledPayment { scheduled_payment })
+            }
+            NodeRequest::ListScheduledPayments { pagination } => {
+                let (scheduled_payments, pagination) = self
+                    .database
+                    .list_scheduled_payments(self.id.clone(), pagination)
+                    .await?;
+                Ok(NodeResponse::ListScheduledPayments {
+                    scheduled_payments,
+                    pagination,
+                })
+            }
+            NodeRequest::UpdateScheduledPayment {
+                id,
+                amt_msat,
+                interval_secs,
+                enabled,
+            } => {
+                let scheduled_payment = self
+                    .update_scheduled_payment(id, amt_msat, interval_secs, enabled)
+                    .await?;
+                Ok(NodeResponse::UpdateScheduledPayment { scheduled_payment })
+            }
+            NodeRequest::DeleteScheduledPayment { id } => {
+                self.delete_scheduled_payment(id).await?;
+                Ok(NodeResponse::DeleteScheduledPayment {})
+            }
+            NodeRequest::ExportLedger { from, to, format } => {
+                let format = LedgerFormat::from_str(&format)?;
+                let ledger = self.export_ledger(from, to, &format).await?;
+                Ok(NodeResponse::ExportLedger { ledger })
+            }
+            NodeRequest::RestrictMacaroon {
+                macaroon,
+                max_payment_msat,
+                max_daily_msat,
+                max_monthly_msat,
+                allowed_destinations,
+            } => {
+                let limits = SpendingLimits {
+                    budget_id: None,
+                    max_payment_msat,
+                    max_daily_msat,
+                    max_monthly_msat,
+                    allowed_destinations,
+                };
+                let macaroon = self.restrict_macaroon(macaroon, limits).await?;
+                let macaroon = macaroon
+                    .serialize(macaroon::Format::V2)
+                    .map_err(Error::Macaroon)?;
+                Ok(NodeResponse::RestrictMacaroon {
+                    macaroon: hex_utils::hex_str(macaroon.as_slice()),
+                })
+            }
+            NodeRequest::GetPaymentAttempts { payment_hash } => {
+                let attempts = self.get_payment_attempts(payment_hash).await?;
+                Ok(NodeResponse::GetPaymentAttempts { attempts })
+            }
+            NodeRequest::CloseChannel {
+                channel_id,
+                force,
+                destination_address,
+                target_feerate_sats_per_vbyte,
+            } => {
                 let mut channel_id_bytes = [0u8; 32];
                 let bytes = hex_utils::to_vec(&channel_id);
                 if let Some(bytes) = bytes {
                     channel_id_bytes.copy_from_slice(&bytes)
                 }
-                self.close_channel(channel_id_bytes, force)?;
-                Ok(NodeResponse::CloseChannel {})
+                let destination_used = self
+                    .close_channel(
+                        channel_id_bytes,
+                        force,
+                        destination_address,
+                        target_feerate_sats_per_vbyte,
+                    )
+                    .await?;
+                Ok(NodeResponse::CloseChannel { destination_used })
+            }
+            NodeRequest::GetChannelAcceptancePolicy {} => {
+                let policy = self
+                    .database
+                    .get_channel_acceptance_policy(self.id.clone())
+                    .await?;
+                Ok(NodeResponse::GetChannelAcceptancePolicy { policy })
+            }
+            NodeRequest::SetChannelAcceptancePolicy { policy } => {
+                self.set_channel_acceptance_policy(&policy).await?;
+                Ok(NodeResponse::SetChannelAcceptancePolicy { policy })
+            }
+            NodeRequest::GetFeePolicy {} => {
+                let policy = self.database.get_fee_policy(self.id.clone()).await?;
+                Ok(NodeResponse::GetFeePolicy { policy })
+            }
+            NodeRequest::SetFeePolicy { policy } => {
+                self.set_fee_policy(&policy).await?;
+                Ok(NodeResponse::SetFeePolicy { policy })
+            }
+            NodeRequest::GetAutopilotPolicy {} => {
+                let policy = self.database.get_autopilot_policy(self.id.clone()).await?;
+                Ok(NodeResponse::GetAutopilotPolicy { policy })
+            }
+            NodeRequest::SetAutopilotPolicy { policy } => {
+                self.set_autopilot_policy(&policy).await?;
+                Ok(NodeResponse::SetAutopilotPolicy { policy })
+            }
+            NodeRequest::RunAutopilot { dry_run } => {
+                let policy = self.database.get_autopilot_policy(self.id.clone()).await?;
+                let policy = AutopilotPolicy {
+                    dry_run: policy.dry_run || dry_run,
+                    ..policy
+                };
+                let channels = autopilot::run_autopilot(self, &policy).await?;
+                Ok(NodeResponse::RunAutopilot { channels })
+            }
+            NodeRequest::ExportChannelBackup {} => {
+                let backup = self.refresh_channel_backup().await?;
+                Ok(NodeResponse::ExportChannelBackup {
+                    backup: base64::encode(backup),
+                })
+            }
+            NodeRequest::RecoverFromBackup { backup } => {
+                let backup = base64::decode(backup)
+                    .map_err(|_e| Error::Generic(String::from("backup must be base64")))?;
+                let channels = self.recover_from_backup(&backup).await?;
+                Ok(NodeResponse::RecoverFromBackup { channels })
+            }
+            NodeRequest::GetJitChannelPolicy {} => {
+                let policy = self
+                    .database
+                    .get_jit_channel_policy(self.id.clone())
+                    .await?;
+                Ok(NodeResponse::GetJitChannelPolicy { policy })
+            }
+            NodeRequest::SetJitChannelPolicy { policy } => {
+                self.set_jit_channel_policy(&policy).await?;
+                Ok(NodeResponse::SetJitChannelPolicy { policy })
+            }
+            NodeRequest::CreateJitInvoice { invoice } => {
+                let (invoice, jit_channel) = self.create_jit_invoice(&invoice).await?;
+                Ok(NodeResponse::CreateJitInvoice {
+                    invoice: invoice.to_string(),
+                    opening_fee_msat: jit_channel.opening_fee_msat,
+                    channel_size_sats: jit_channel.channel_size_sats,
+                })
+            }
+            NodeRequest::GetHealthReport {} => {
+                let report = self.health_report().await?;
+                Ok(NodeResponse::GetHealthReport { report })
+            }
+            NodeRequest::GetHealthPolicy {} => {
+                let policy = self.database.get_health_policy(self.id.clone()).await?;
+                Ok(NodeResponse::GetHealthPolicy { policy })
+            }
+            NodeRequest::SetHealthPolicy { policy } => {
+                self.set_health_policy(&policy).await?;
+                Ok(NodeResponse::SetHealthPolicy { policy })
+            }
+            NodeRequest::UpdateChannelConfig {
+                channel_ids,
+                all,
+                forwarding_fee_base_msat,
+                forwarding_fee_proportional_millionths,
+                cltv_expiry_delta,
+                max_dust_htlc_exposure_msat,
+            } => {
+                let channel_ids = self.update_channel_config(
+                    channel_ids,
+                    all,
+                    forwarding_fee_base_msat,
+                    forwarding_fee_proportional_millionths,
+                    cltv_expiry_delta,
+                    max_dust_htlc_exposure_msat,
+                )?;
+                Ok(NodeResponse::UpdateChannelConfig { channel_ids })
             }
             NodeRequest::NodeInfo {} => {
                 let node_info = self.node_info()?;
@@ -1719,3 +3893,353 @@ impl LightningNode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use entity::sea_orm::EntityTrait;
+
+    fn outgoing_payment(
+        payment_hash: &str,
+        invoice: &str,
+        idempotency_key: Option<&str>,
+    ) -> entity::payment::ActiveModel {
+        entity::payment::ActiveModel {
+            node_id: ActiveValue::Set(String::from("node")),
+            created_by_node_id: ActiveValue::Set(String::from("node")),
+            payment_hash: ActiveValue::Set(payment_hash.to_string()),
+            status: ActiveValue::Set(HTLCStatus::Pending.to_string()),
+            origin: ActiveValue::Set(PaymentOrigin::InvoiceOutgoing.to_string()),
+            invoice: ActiveValue::Set(Some(invoice.to_string())),
+            idempotency_key: ActiveValue::Set(idempotency_key.map(String::from)),
+            ..Default::default()
+        }
+    }
+
+    async fn insert(
+        database: &SenseiDatabase,
+        payment_hash: &str,
+        invoice: &str,
+        idempotency_key: Option<&str>,
+    ) -> Result<OutgoingPayment, Error> {
+        let key = idempotency_key.map(String::from);
+        insert_outgoing_payment(
+            database,
+            "node",
+            outgoing_payment(payment_hash, invoice, idempotency_key),
+            &key,
+            |payment| payment.invoice.as_deref() == Some(invoice),
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn insert_outgoing_payment_test() {
+        let database = crate::database::test::test_database().await;
+
+        // without a key every request is a new payment
+        for _ in 0..2 {
+            assert!(matches!(
+                insert(&database, "hash", "invoice", None).await.unwrap(),
+                OutgoingPayment::New(_)
+            ));
+        }
+
+        let first = match insert(&database, "first", "invoice", Some("key"))
+            .await
+            .unwrap()
+        {
+            OutgoingPayment::New(payment) => payment,
+            OutgoingPayment::Existing(_) => panic!("expected a new payment"),
+        };
+
+        // a retry gets the original payment back, still pending while it is in flight
+        match insert(&database, "retry", "invoice", Some("key"))
+            .await
+            .unwrap()
+        {
+            OutgoingPayment::Existing(payment) => {
+                assert_eq!(payment.id, first.id);
+                assert_eq!(payment.status, HTLCStatus::Pending.to_string());
+            }
+            OutgoingPayment::New(_) => panic!("paid twice for the same key"),
+        }
+
+        // the same key with a different invoice is refused
+        assert!(insert(&database, "other", "other invoice", Some("key"))
+            .await
+            .is_err());
+
+        // concurrent requests with the same key insert a single payment
+        let (a, b) = tokio::join!(
+            insert(&database, "a", "invoice", Some("concurrent")),
+            insert(&database, "b", "invoice", Some("concurrent"))
+        );
+        let new_payments = [a.unwrap(), b.unwrap()]
+            .iter()
+            .filter(|payment| matches!(payment, OutgoingPayment::New(_)))
+            .count();
+        assert_eq!(new_payments, 1);
+    }
+
+    fn balance_snapshot(timestamp: i64, onchain_balance_sats: u64) -> BalanceSnapshot {
+        BalanceSnapshot {
+            timestamp,
+            onchain_balance_sats,
+            channel_outbound_capacity_msats: 0,
+            channel_inbound_capacity_msats: 0,
+        }
+    }
+
+    #[test]
+    fn downsample_balance_history_test() {
+        let snapshots = vec![
+            balance_snapshot(0, 1),
+            balance_snapshot(30, 2),
+            balance_snapshot(59, 3),
+            balance_snapshot(60, 4),
+            balance_snapshot(200, 5),
+            balance_snapshot(230, 6),
+        ];
+
+        // the last snapshot of each minute wins, empty minutes are skipped
+        let history = downsample_balance_history(snapshots.clone().into_iter(), 60)
+            .iter()
+            .map(|snapshot| (snapshot.timestamp, snapshot.onchain_balance_sats))
+            .collect::<Vec<_>>();
+        assert_eq!(history, vec![(59, 3), (60, 4), (230, 6)]);
+
+        let history = downsample_balance_history(snapshots.into_iter(), 1000);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].onchain_balance_sats, 6);
+
+        assert!(downsample_balance_history(vec![].into_iter(), 60).is_empty());
+    }
+
+    #[test]
+    fn apply_channel_configs_test() {
+        let counterparty_node_id = PublicKey::from_str(
+            "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619",
+        )
+        .unwrap();
+        let config = ChannelConfig::default();
+        let updates = vec![
+            (counterparty_node_id, [1; 32], config),
+            (counterparty_node_id, [2; 32], config),
+            (counterparty_node_id, [3; 32], config),
+        ];
+
+        let mut applied = vec![];
+        let channel_ids = apply_channel_configs(updates.clone(), |_, channel_id, _| {
+            applied.push(*channel_id);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(applied, vec![[1; 32], [2; 32], [3; 32]]);
+        assert_eq!(
+            channel_ids,
+            vec![
+                hex_utils::hex_str(&[1; 32]),
+                hex_utils::hex_str(&[2; 32]),
+                hex_utils::hex_str(&[3; 32])
+            ]
+        );
+
+        // the second channel fails, the first one already took the new config
+        let mut applied = vec![];
+        let err = apply_channel_configs(updates, |_, channel_id, _| {
+            if *channel_id == [2; 32] {
+                return Err(APIError::ChannelUnavailable {
+                    err: String::from("peer disconnected"),
+                });
+            }
+            applied.push(*channel_id);
+            Ok(())
+        })
+        .unwrap_err()
+        .to_string();
+        assert_eq!(applied, vec![[1; 32]]);
+        assert!(err.starts_with(&format!(
+            "failed to update channel {}",
+            hex_utils::hex_str(&[2; 32])
+        )));
+        assert!(err.ends_with(&format!(
+            "already updated: [{}]",
+            hex_utils::hex_str(&[1; 32])
+        )));
+    }
+
+    fn test_pubkey(byte: u8) -> String {
+        let secret_key = bitcoin::secp256k1::SecretKey::from_slice(&[byte; 32]).unwrap();
+        PublicKey::from_secret_key(&Secp256k1::new(), &secret_key).to_string()
+    }
+
+    #[test]
+    fn spending_limits_from_macaroon_test() {
+        let key = macaroon::MacaroonKey::from(&[0; 32][..]);
+        let mut macaroon = macaroon::Macaroon::create(
+            Some("senseid".to_string()),
+            &key,
+            macaroon::ByteString::from("session"),
+        )
+        .unwrap();
+        assert!(!SpendingLimits::from_macaroon(&macaroon).is_restricted());
+
+        let (alice, bob, carol) = (test_pubkey(1), test_pubkey(2), test_pubkey(3));
+        for caveat in [
+            String::from("budget_id = first"),
+            String::from("max_payment_msat = 1000"),
+            String::from("max_payment_msat = 500"),
+            String::from("max_payment_msat = 2000"),
+            String::from("budget_id = second"),
+            String::from("max_daily_msat = 10000"),
+            format!("destinations = {},{}", alice, bob),
+            format!("destinations = {}, {}", bob, carol),
+            String::from("scope = *"),
+        ] {
+            macaroon.add_first_party_caveat(macaroon::ByteString::from(caveat));
+        }
+
+        // caveats can only narrow the limits of the macaroon they are added to
+        let limits = SpendingLimits::from_macaroon(&macaroon);
+        assert!(limits.is_restricted());
+        assert_eq!(limits.budget_id, Some(String::from("first")));
+        assert_eq!(limits.max_payment_msat, Some(500));
+        assert_eq!(limits.max_daily_msat, Some(10000));
+        assert_eq!(limits.max_monthly_msat, None);
+        assert_eq!(limits.allowed_destinations, Some(vec![bob.clone()]));
+
+        // the limits survive being written to a fresh macaroon
+        let mut restricted = macaroon::Macaroon::create(
+            Some("senseid".to_string()),
+            &key,
+            macaroon::ByteString::from("session"),
+        )
+        .unwrap();
+        limits.add_caveats(&mut restricted);
+        let parsed = SpendingLimits::from_macaroon(&restricted);
+        assert_eq!(parsed.budget_id, limits.budget_id);
+        assert_eq!(parsed.max_payment_msat, limits.max_payment_msat);
+        assert_eq!(parsed.max_daily_msat, limits.max_daily_msat);
+        assert_eq!(parsed.allowed_destinations, limits.allowed_destinations);
+
+        let budget_only = SpendingLimits {
+            budget_id: Some(String::from("first")),
+            ..Default::default()
+        };
+        assert!(!budget_only.is_restricted());
+
+        for (predicate, expected) in [
+            (String::from("budget_id = first"), true),
+            (String::from("budget_id = "), false),
+            (String::from("max_monthly_msat=42"), true),
+            (String::from("max_daily_msat = lots"), false),
+            (format!("destinations = {},{}", alice, bob), true),
+            (format!("destinations = {},not-a-pubkey", alice), false),
+            (String::from("scope = *"), false),
+        ] {
+            assert_eq!(
+                is_spending_caveat(&macaroon::ByteString::from(predicate.clone())),
+                expected,
+                "{}",
+                predicate
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn check_spending_limits_test() {
+        let database = crate::database::test::test_database().await;
+        let now = seconds_since_epoch();
+
+        let mut failed_payment = outgoing_payment("failed", "lnbc1failed", None);
+        failed_payment.status = ActiveValue::Set(HTLCStatus::Failed.to_string());
+        failed_payment
+            .insert(database.get_connection())
+            .await
+            .unwrap();
+
+        let mut sent_payment = outgoing_payment("sent", "lnbc1sent", None);
+        sent_payment.status = ActiveValue::Set(HTLCStatus::Succeeded.to_string());
+        sent_payment.fee_paid_msat = ActiveValue::Set(Some(20));
+        sent_payment
+            .insert(database.get_connection())
+            .await
+            .unwrap();
+
+        for (budget_id, amt_msat, payment_hash, created_at) in [
+            ("budget", 600, None, now),
+            ("budget", 280, Some("sent"), now - 2 * SECONDS_PER_DAY),
+            ("budget", 200, None, now - 40 * SECONDS_PER_DAY),
+            ("budget", 400, Some("failed"), now),
+            ("other", 100, None, now),
+        ] {
+            let spend = entity::macaroon_spend::ActiveModel {
+                node_id: ActiveValue::Set(String::from("node")),
+                budget_id: ActiveValue::Set(budget_id.to_string()),
+                amt_msat: ActiveValue::Set(amt_msat),
+                payment_hash: ActiveValue::Set(payment_hash.map(String::from)),
+                ..Default::default()
+            };
+            let spend = database.insert_macaroon_spend(spend).await.unwrap();
+            // created_at is stamped on insert, move it back in time
+            let mut spend: entity::macaroon_spend::ActiveModel = spend.into();
+            spend.created_at = ActiveValue::Set(created_at);
+            entity::macaroon_spend::Entity::update(spend)
+                .exec(database.get_connection())
+                .await
+                .unwrap();
+        }
+
+        let destination = test_pubkey(1);
+        let limits = SpendingLimits {
+            budget_id: Some(String::from("budget")),
+            max_payment_msat: Some(500),
+            max_daily_msat: Some(1000),
+            max_monthly_msat: Some(1200),
+            allowed_destinations: Some(vec![destination.clone()]),
+        };
+        let check = |amt_msat: u64, destinations: Vec<String>| {
+            let database = &database;
+            let limits = &limits;
+            async move {
+                check_spending_limits(database, "node", limits, "budget", amt_msat, &destinations)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        };
+
+        // 600 spent today and 900 this month including the fee paid, the failed
+        // payment doesn't count
+        assert!(check(300, vec![destination.clone()]).await.is_ok());
+        assert!(check(501, vec![destination.clone()])
+            .await
+            .unwrap_err()
+            .contains("allowed per payment"));
+        assert!(check(100, vec![test_pubkey(2)])
+            .await
+            .unwrap_err()
+            .contains("is not an allowed destination"));
+        assert!(check(301, vec![destination.clone()])
+            .await
+            .unwrap_err()
+            .contains("900 msats already spent of the 1200 msats allowed per month"));
+
+        let daily_only = SpendingLimits {
+            max_monthly_msat: None,
+            ..limits.clone()
+        };
+        assert!(
+            check_spending_limits(&database, "node", &daily_only, "budget", 400, &[])
+                .await
+                .is_ok()
+        );
+        assert!(
+            check_spending_limits(&database, "node", &daily_only, "budget", 401, &[])
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("600 msats already spent of the 1000 msats allowed per day")
+        );
+    }
+}
@@ -80,6 +80,7 @@ impl SenseiP2P {
             persistence_store,
             config.network,
             logger.clone(),
+            None,
         ));
 
         let network_graph = Arc::new(persister.read_network_graph());
@@ -13,10 +13,11 @@ use lightning::{
 use crate::{
     database::SenseiDatabase,
     error::Error,
+    health::HealthTracker,
     node::{ChannelManager, PeerManager, RoutingPeerManager},
 };
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     io::Cursor,
     sync::{Arc, Mutex},
     time::Duration,
@@ -36,6 +37,7 @@ pub struct PeerConnector {
     pub node_info_lookup: Arc<NodeInfoLookup>,
     pub interval: Duration,
     pub target_routing_peers: u16,
+    pub health_tracker: Arc<HealthTracker>,
 }
 
 impl PeerConnector {
@@ -51,6 +53,7 @@ impl PeerConnector {
             node_info_lookup,
             interval: Duration::from_secs(5),
             target_routing_peers: 10,
+            health_tracker: Arc::new(HealthTracker::default()),
         }
     }
 
@@ -138,91 +141,93 @@ impl PeerConnector {
         pubkey: PublicKey,
         peer_addr: NetAddress,
         peer_manager: Arc<PeerManager>,
-    ) -> Result<(), ()> {
-        let socket_addr = net_address_to_socket_addr(peer_addr.clone());
-        if socket_addr.is_none() {
-            return Err(());
+    ) -> Result<(), String> {
+        let socket_addr = net_address_to_socket_addr(peer_addr.clone())
+            .ok_or_else(|| String::from("peer address is not reachable over tcp"))?;
+        // lightning_net_tokio::connect_outbound would only tell us that it failed
+        let stream = match tokio::time::timeout(
+            Duration::from_secs(10),
+            tokio::net::TcpStream::connect(&socket_addr),
+        )
+        .await
+        {
+            Ok(Ok(stream)) => stream
+                .into_std()
+                .map_err(|e| format!("failed to connect to {}: {}", socket_addr, e))?,
+            Ok(Err(e)) => return Err(format!("failed to connect to {}: {}", socket_addr, e)),
+            Err(_) => return Err(format!("timed out connecting to {}", socket_addr)),
+        };
+        let connection_closed_future =
+            lightning_net_tokio::setup_outbound(Arc::clone(&peer_manager), pubkey, stream);
+        let mut connection_closed_future = Box::pin(connection_closed_future);
+        loop {
+            match futures::poll!(&mut connection_closed_future) {
+                std::task::Poll::Ready(_) => {
+                    println!("ERROR: Peer disconnected before we finished the handshake");
+                    return Err(format!(
+                        "{} disconnected before the handshake finished",
+                        socket_addr
+                    ));
+                }
+                std::task::Poll::Pending => {}
+            }
+            // Avoid blocking the tokio context by sleeping a bit
+            match peer_manager
+                .get_peer_node_ids()
+                .iter()
+                .find(|id| **id == pubkey)
+            {
+                Some(_) => break,
+                None => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
         }
-        let socket_addr = socket_addr.unwrap();
-        match lightning_net_tokio::connect_outbound(Arc::clone(&peer_manager), pubkey, socket_addr)
+
+        match self
+            .database
+            .list_peer_addresses(local_node_id, &pubkey.to_string())
             .await
         {
-            Some(connection_closed_future) => {
-                let mut connection_closed_future = Box::pin(connection_closed_future);
-                loop {
-                    match futures::poll!(&mut connection_closed_future) {
-                        std::task::Poll::Ready(_) => {
-                            println!("ERROR: Peer disconnected before we finished the handshake");
-                            return Err(());
-                        }
-                        std::task::Poll::Pending => {}
-                    }
-                    // Avoid blocking the tokio context by sleeping a bit
-                    match peer_manager
-                        .get_peer_node_ids()
-                        .iter()
-                        .find(|id| **id == pubkey)
-                    {
-                        Some(_) => break,
-                        None => tokio::time::sleep(Duration::from_millis(10)).await,
-                    }
-                }
+            Ok(known_addresses) => {
+                let known_address = known_addresses.iter().find(|address| {
+                    let mut existing_address_readable = Cursor::new(address.address.clone());
+                    let address = NetAddress::read(&mut existing_address_readable).unwrap();
+                    address == peer_addr
+                });
 
-                match self
-                    .database
-                    .list_peer_addresses(local_node_id, &pubkey.to_string())
-                    .await
-                {
-                    Ok(known_addresses) => {
-                        let known_address = known_addresses.iter().find(|address| {
-                            let mut existing_address_readable =
-                                Cursor::new(address.address.clone());
-                            let address = NetAddress::read(&mut existing_address_readable).unwrap();
-                            address == peer_addr
-                        });
-
-                        let now: i64 = seconds_since_epoch();
-
-                        let result = match known_address {
-                            Some(address) => {
-                                let mut peer_address: entity::peer_address::ActiveModel =
-                                    address.clone().into();
-                                peer_address.last_connected_at = ActiveValue::Set(now);
-                                peer_address.update(self.database.get_connection()).await
-                            }
-                            None => {
-                                let peer_address = entity::peer_address::ActiveModel {
-                                    node_id: ActiveValue::Set(local_node_id.to_string()),
-                                    pubkey: ActiveValue::Set(pubkey.to_string()),
-                                    last_connected_at: ActiveValue::Set(now),
-                                    address: ActiveValue::Set(peer_addr.encode()),
-                                    source: ActiveValue::Set(
-                                        PeerAddressSource::OutboundConnect.into(),
-                                    ),
-                                    ..Default::default()
-                                };
-                                peer_address.insert(self.database.get_connection()).await
-                            }
-                        };
+                let now: i64 = seconds_since_epoch();
 
-                        if let Err(e) = result {
-                            println!("failed to update list peers database: {:?}", e);
-                        }
+                let result = match known_address {
+                    Some(address) => {
+                        let mut peer_address: entity::peer_address::ActiveModel =
+                            address.clone().into();
+                        peer_address.last_connected_at = ActiveValue::Set(now);
+                        peer_address.update(self.database.get_connection()).await
                     }
-                    Err(e) => {
-                        println!("{:?}", e);
+                    None => {
+                        let peer_address = entity::peer_address::ActiveModel {
+                            node_id: ActiveValue::Set(local_node_id.to_string()),
+                            pubkey: ActiveValue::Set(pubkey.to_string()),
+                            last_connected_at: ActiveValue::Set(now),
+                            address: ActiveValue::Set(peer_addr.encode()),
+                            source: ActiveValue::Set(PeerAddressSource::OutboundConnect.into()),
+                            ..Default::default()
+                        };
+                        peer_address.insert(self.database.get_connection()).await
                     }
-                }
+                };
 
-                if self.router_should_connect_to_peer(&pubkey) {
-                    let _res = self.connect_routing_peer(pubkey, peer_addr).await;
+                if let Err(e) = result {
+                    println!("failed to update list peers database: {:?}", e);
                 }
             }
-            None => {
-                //println!("ERROR: failed to connect to peer");
-                return Err(());
+            Err(e) => {
+                println!("{:?}", e);
             }
         }
+
+        if self.router_should_connect_to_peer(&pubkey) {
+            let _res = self.connect_routing_peer(pubkey, peer_addr).await;
+        }
         Ok(())
     }
 
@@ -260,7 +265,7 @@ impl PeerConnector {
         pubkey: PublicKey,
         peer_addr: NetAddress,
         peer_manager: Arc<PeerManager>,
-    ) -> Result<(), ()> {
+    ) -> Result<(), String> {
         if !peer_manager.get_peer_node_ids().contains(&pubkey) {
             self.connect_peer(local_node_id, pubkey, peer_addr, peer_manager)
                 .await?
@@ -287,34 +292,73 @@ impl PeerConnector {
         requests.push_back(PeerConnectorRequest::UnregisterNode(id));
     }
 
+    // Tries every known address of a channel peer, the error is kept for the health report.
+    async fn reconnect_peer(
+        &self,
+        node_id: &str,
+        pubkey: PublicKey,
+        peer_manager: &Arc<PeerManager>,
+    ) -> Result<(), String> {
+        let addresses = self
+            .get_addresses_for_pubkey(node_id, &pubkey)
+            .await
+            .map_err(|e| format!("failed to look up peer addresses: {}", e))?;
+        if addresses.is_empty() {
+            return Err(String::from("no known addresses for peer"));
+        }
+
+        let mut last_error = String::new();
+        for address in addresses {
+            match self
+                .connect_peer_if_necessary(node_id, pubkey, address, peer_manager.clone())
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
     pub async fn run(&self) {
         let mut nodes: HashMap<String, (Arc<PeerManager>, Arc<ChannelManager>)> = HashMap::new();
         let mut interval = tokio::time::interval(self.interval);
         loop {
             interval.tick().await;
+            let now = seconds_since_epoch();
             for (node_id, (peer_manager, channel_manager)) in nodes.iter() {
+                let mut attempted_peers = HashSet::new();
+                let mut peers = HashSet::new();
+                let mut channel_ids = HashSet::new();
                 for chan_info in channel_manager.list_channels() {
                     let pubkey = chan_info.counterparty.node_id;
-                    if !chan_info.is_usable && !peer_manager.get_peer_node_ids().contains(&pubkey) {
-                        if let Ok(addresses) =
-                            self.get_addresses_for_pubkey(&node_id[..], &pubkey).await
-                        {
-                            for address in addresses {
-                                if let Ok(()) = self
-                                    .connect_peer_if_necessary(
-                                        node_id,
-                                        pubkey,
-                                        address,
-                                        peer_manager.clone(),
-                                    )
-                                    .await
-                                {
-                                    break;
-                                }
+                    peers.insert(pubkey);
+                    channel_ids.insert(chan_info.channel_id);
+                    let connected = peer_manager.get_peer_node_ids().contains(&pubkey);
+                    if chan_info.is_channel_ready {
+                        self.health_tracker.channel_checked(
+                            node_id,
+                            chan_info.channel_id,
+                            chan_info.is_usable,
+                            now,
+                        );
+                    }
+                    self.health_tracker
+                        .peer_checked(node_id, pubkey, connected, now);
+
+                    if !chan_info.is_usable && !connected && attempted_peers.insert(pubkey) {
+                        match self.reconnect_peer(node_id, pubkey, peer_manager).await {
+                            Ok(()) => {
+                                self.health_tracker.peer_checked(node_id, pubkey, true, now);
+                            }
+                            Err(e) => {
+                                self.health_tracker.reconnect_failed(node_id, pubkey, e);
                             }
                         }
                     }
                 }
+                self.health_tracker
+                    .retain_node(node_id, &peers, &channel_ids);
             }
 
             // if routing peers disconnected then try to add some more
@@ -356,6 +400,7 @@ impl PeerConnector {
                         nodes.insert(id, (peer_manager, channel_manager));
                     }
                     PeerConnectorRequest::UnregisterNode(id) => {
+                        self.health_tracker.remove_node(&id);
                         nodes.remove(&id);
                     }
                 }
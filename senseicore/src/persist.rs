@@ -7,7 +7,7 @@ use std::{
 };
 
 use super::database::SenseiDatabase;
-use crate::{disk::FilesystemLogger, node::NetworkGraph};
+use crate::{disk::FilesystemLogger, node::NetworkGraph, watchtower::MonitorUpdateQueue};
 use bitcoin::{
     blockdata::constants::genesis_block, hashes::hex::FromHex, BlockHash, Network, Txid,
 };
@@ -149,17 +149,28 @@ pub struct SenseiPersister {
     store: AnyKVStore,
     network: Network,
     logger: Arc<FilesystemLogger>,
+    watchtower_queue: Option<Arc<MonitorUpdateQueue>>,
 }
 
 impl SenseiPersister {
-    pub fn new(store: AnyKVStore, network: Network, logger: Arc<FilesystemLogger>) -> Self {
+    pub fn new(
+        store: AnyKVStore,
+        network: Network,
+        logger: Arc<FilesystemLogger>,
+        watchtower_queue: Option<Arc<MonitorUpdateQueue>>,
+    ) -> Self {
         Self {
             store,
             network,
             logger,
+            watchtower_queue,
         }
     }
 
+    pub fn watchtower_queue(&self) -> Option<Arc<MonitorUpdateQueue>> {
+        self.watchtower_queue.clone()
+    }
+
     pub fn read_channel_manager(&self) -> std::io::Result<Option<Vec<u8>>> {
         self.store.read("manager")
     }
@@ -274,10 +285,31 @@ impl SenseiPersister {
         }
         Ok(res)
     }
+
+    /// Read the serialized `ChannelMonitor`s without decoding them.
+    pub fn read_channelmonitor_bytes(&self) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+        let mut res = Vec::new();
+        for filename in self.store.list("monitors")? {
+            if filename.ends_with(".tmp") {
+                continue;
+            }
+            let monitor_path = format!("monitors/{}", filename);
+            if let Some(contents) = self.store.read(&monitor_path)? {
+                res.push((monitor_path, contents));
+            }
+        }
+        Ok(res)
+    }
 }
 
 impl KVStorePersister for SenseiPersister {
     fn persist<W: Writeable>(&self, key: &str, object: &W) -> std::io::Result<()> {
-        self.store.persist(key, object)
+        self.store.persist(key, object)?;
+        if let Some(watchtower_queue) = self.watchtower_queue.as_ref() {
+            if key.starts_with("monitors/") {
+                watchtower_queue.push(key, object.encode());
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,290 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::node::{HTLCStatus, LightningNode};
+use crate::services::node::{NodeRequest, NodeRequestError, NodeResponse};
+use entity::scheduled_payment;
+use entity::sea_orm::{ActiveModelTrait, ActiveValue};
+use entity::seconds_since_epoch;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often each node looks for scheduled payments that are due.
+const SCHEDULER_TICK_SECS: u64 = 60;
+
+pub const MIN_SCHEDULE_INTERVAL_SECS: i64 = 60;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ScheduledPaymentKind {
+    Keysend,
+    InvoiceUrl,
+}
+
+impl Display for ScheduledPaymentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            ScheduledPaymentKind::Keysend => "keysend",
+            ScheduledPaymentKind::InvoiceUrl => "invoice_url",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for ScheduledPaymentKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keysend" => Ok(ScheduledPaymentKind::Keysend),
+            "invoice_url" => Ok(ScheduledPaymentKind::InvoiceUrl),
+            _ => Err(Error::Generic(format!(
+                "unknown scheduled payment kind: {}",
+                s
+            ))),
+        }
+    }
+}
+
+// The url can either respond with a bare bolt11 invoice or a json object with
+// the invoice in an `invoice` or `pr` field.
+async fn fetch_invoice(url: &str) -> Result<String, Error> {
+    let body = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Generic(format!("failed to fetch invoice: {}", e)))?
+        .text()
+        .await
+        .map_err(|e| Error::Generic(format!("failed to read invoice: {}", e)))?;
+
+    let invoice = match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(json) => json
+            .get("invoice")
+            .or_else(|| json.get("pr"))
+            .and_then(|invoice| invoice.as_str())
+            .map(String::from)
+            .ok_or_else(|| Error::Generic(String::from("no invoice in response")))?,
+        Err(_) => body.trim().to_string(),
+    };
+    Ok(invoice)
+}
+
+pub struct PaymentScheduler {
+    node: Arc<LightningNode>,
+}
+
+impl PaymentScheduler {
+    pub fn new(node: Arc<LightningNode>) -> Self {
+        Self { node }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_TICK_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.run_due_payments().await {
+                println!("ERROR: failed to run scheduled payments: {}", e);
+            }
+        }
+    }
+
+    async fn run_due_payments(&self) -> Result<(), Error> {
+        let now = seconds_since_epoch();
+        let due = self
+            .node
+            .database
+            .find_due_scheduled_payments(self.node.id.clone(), now)
+            .await?;
+
+        // one broken schedule must not hold up the others
+        for scheduled_payment in due {
+            let scheduled_payment_id = scheduled_payment.id.clone();
+            if let Err(e) = self.run_scheduled_payment(scheduled_payment, now).await {
+                println!(
+                    "ERROR: failed to run scheduled payment {}: {}",
+                    scheduled_payment_id, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_scheduled_payment(
+        &self,
+        scheduled_payment: scheduled_payment::Model,
+        now: i64,
+    ) -> Result<(), Error> {
+        // Tying the payment to this particular run means a restart between paying
+        // and updating the schedule cannot pay the same run twice.
+        let idempotency_key = format!(
+            "schedule:{}:{}",
+            scheduled_payment.id, scheduled_payment.next_run_at
+        );
+        let (payment_hash, error) = match self.pay(&scheduled_payment, idempotency_key).await {
+            Ok(payment_hash) => (Some(payment_hash), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        // Runs missed while the node was offline are skipped rather than paid in a burst.
+        let interval_secs = scheduled_payment.interval_secs;
+        let mut next_run_at = scheduled_payment.next_run_at + interval_secs;
+        if next_run_at <= now {
+            next_run_at += ((now - next_run_at) / interval_secs + 1) * interval_secs;
+        }
+
+        let scheduled_payment_id = scheduled_payment.id.clone();
+        let mut scheduled_payment: scheduled_payment::ActiveModel = scheduled_payment.into();
+        scheduled_payment.next_run_at = ActiveValue::Set(next_run_at);
+        scheduled_payment.last_run_at = ActiveValue::Set(Some(now));
+        scheduled_payment.last_payment_hash = ActiveValue::Set(payment_hash.clone());
+        scheduled_payment.last_error = ActiveValue::Set(error.clone());
+        scheduled_payment
+            .update(self.node.database.get_connection())
+            .await?;
+
+        let _res = self
+            .node
+            .event_sender
+            .send(SenseiEvent::ScheduledPaymentExecuted {
+                node_id: self.node.id.clone(),
+                scheduled_payment_id,
+                payment_hash,
+                error,
+            });
+
+        Ok(())
+    }
+
+    async fn pay(
+        &self,
+        scheduled_payment: &scheduled_payment::Model,
+        idempotency_key: String,
+    ) -> Result<String, Error> {
+        let amt_msat: u64 = scheduled_payment.amt_msat.try_into().unwrap();
+
+        let request = match ScheduledPaymentKind::from_str(&scheduled_payment.kind)? {
+            ScheduledPaymentKind::Keysend => NodeRequest::Keysend {
+                dest_pubkey: scheduled_payment.dest_pubkey.clone().unwrap_or_default(),
+                amt_msat,
+                idempotency_key: Some(idempotency_key),
+            },
+            ScheduledPaymentKind::InvoiceUrl => {
+                let url = scheduled_payment.invoice_url.clone().unwrap_or_default();
+                let invoice = fetch_invoice(&url).await?;
+
+                // For invoice urls the scheduled amount is the most we are willing to pay.
+                match self
+                    .node
+                    .get_invoice_from_str(&invoice)?
+                    .amount_milli_satoshis()
+                {
+                    Some(invoice_amt_msat) if invoice_amt_msat <= amt_msat => {}
+                    Some(invoice_amt_msat) => {
+                        return Err(Error::Generic(format!(
+                            "invoice amount {} msats is more than the scheduled {} msats",
+                            invoice_amt_msat, amt_msat
+                        )))
+                    }
+                    None => {
+                        return Err(Error::Generic(String::from(
+                            "invoice from url has no amount",
+                        )))
+                    }
+                }
+
+                NodeRequest::SendPayment {
+                    invoice,
+                    idempotency_key: Some(idempotency_key),
+                }
+            }
+        };
+
+        match self.node.call(request).await {
+            Ok(NodeResponse::SendPayment {
+                payment_hash,
+                status,
+            })
+            | Ok(NodeResponse::Keysend {
+                payment_hash,
+                status,
+            }) => {
+                if status == HTLCStatus::Failed.to_string() {
+                    Err(Error::Generic(format!("payment {} failed", payment_hash)))
+                } else {
+                    Ok(payment_hash)
+                }
+            }
+            Ok(_) => Err(Error::Generic(String::from("unexpected response"))),
+            Err(NodeRequestError::Sensei(e))
+            | Err(NodeRequestError::Bdk(e))
+            | Err(NodeRequestError::Io(e)) => Err(Error::Generic(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const INVOICE: &str = "lnbcrt10u1p0000000000";
+
+    // Stands in for an invoice url, answering each path with a canned response.
+    async fn invoice_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 1024];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let (status, body) = match path.as_str() {
+                    "/bare" => ("200 OK", format!("{}\n", INVOICE)),
+                    "/invoice" => ("200 OK", format!("{{\"invoice\":\"{}\"}}", INVOICE)),
+                    "/pr" => ("200 OK", format!("{{\"pr\":\"{}\"}}", INVOICE)),
+                    "/empty" => ("200 OK", String::from("{\"status\":\"ERROR\"}")),
+                    _ => ("500 Internal Server Error", String::from("down")),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _res = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn fetch_invoice_test() {
+        let url = invoice_server().await;
+
+        for path in ["/bare", "/invoice", "/pr"] {
+            assert_eq!(
+                fetch_invoice(&format!("{}{}", url, path)).await.unwrap(),
+                INVOICE
+            );
+        }
+
+        let err = fetch_invoice(&format!("{}/empty", url)).await.unwrap_err();
+        assert_eq!(err.to_string(), "no invoice in response");
+
+        let err = fetch_invoice(&format!("{}/down", url)).await.unwrap_err();
+        assert!(err.to_string().starts_with("failed to fetch invoice"));
+    }
+}
@@ -8,13 +8,22 @@
 // licenses.
 
 use super::{PaginationRequest, PaginationResponse};
+use crate::autopilot::Autopilot;
+use crate::backup::ChannelBackupManager;
 use crate::chain::manager::SenseiChainManager;
 use crate::database::SenseiDatabase;
 use crate::disk::FilesystemLogger;
 use crate::error::Error as SenseiError;
 use crate::events::SenseiEvent;
+use crate::fee_manager::FeeManager;
+use crate::health::HealthMonitor;
+use crate::htlc_tracker::HtlcExpiryMonitor;
+use crate::jit_channels::JitChannelManager;
+use crate::p2p::router::RemoteSenseiInfo;
 use crate::p2p::utils::parse_peer_info;
 use crate::p2p::SenseiP2P;
+use crate::scheduler::PaymentScheduler;
+use crate::watchtower::{WatchtowerBlob, WatchtowerClient, WatchtowerServer, WatchtowerTarget};
 use crate::{config::SenseiConfig, hex_utils, node::LightningNode, version};
 
 use entity::node::{self, NodeRole};
@@ -35,10 +44,16 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Cursor;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use std::{collections::hash_map::Entry, fs, sync::Arc};
 use tokio::sync::{broadcast, Mutex};
 use tokio::task::JoinHandle;
 
+// How often a running node records the state of its channels.
+const CHANNEL_SYNC_INTERVAL_SECS: u64 = 30;
+// Stopping a node waits at most this long for the watchtower to take its updates.
+const WATCHTOWER_FLUSH_TIMEOUT_SECS: u64 = 30;
+
 pub struct NodeHandle {
     pub node: Arc<LightningNode>,
     pub background_processor: BackgroundProcessor,
@@ -141,6 +156,10 @@ pub enum AdminRequest {
     },
     GetNetworkGraph {},
     ChainUpdated {},
+    StoreWatchtowerBlobs {
+        client_pubkey: String,
+        blobs: Vec<WatchtowerBlob>,
+    },
 }
 
 #[derive(Serialize, Debug)]
@@ -205,6 +224,7 @@ pub enum AdminResponse {
         channels: Vec<String>,
     },
     ChainUpdated {},
+    StoreWatchtowerBlobs {},
     Error(Error),
 }
 
@@ -222,6 +242,7 @@ pub struct AdminService {
     pub p2p: Arc<SenseiP2P>,
     pub logger: Arc<FilesystemLogger>,
     pub stop_signal: Arc<AtomicBool>,
+    pub watchtower: Option<Arc<WatchtowerServer>>,
 }
 
 impl AdminService {
@@ -268,6 +289,18 @@ impl AdminService {
             .await,
         );
 
+        let watchtower = if config.watchtower_server {
+            let watchtower = Arc::new(
+                WatchtowerServer::new(database.clone(), &chain_manager, event_sender.clone())
+                    .await
+                    .expect("failed to start watchtower"),
+            );
+            chain_manager.listener.set_watchtower(watchtower.clone());
+            Some(watchtower)
+        } else {
+            None
+        };
+
         Self {
             data_dir: String::from(data_dir),
             config,
@@ -279,6 +312,7 @@ impl AdminService {
             logger,
             p2p,
             stop_signal,
+            watchtower,
         }
     }
 }
@@ -638,6 +672,18 @@ impl AdminService {
                 self.chain_manager.chain_updated();
                 Ok(AdminResponse::ChainUpdated {})
             }
+            AdminRequest::StoreWatchtowerBlobs {
+                client_pubkey,
+                blobs,
+            } => match self.watchtower.as_ref() {
+                Some(watchtower) => {
+                    watchtower.store_blobs(client_pubkey, blobs).await?;
+                    Ok(AdminResponse::StoreWatchtowerBlobs {})
+                }
+                None => Err(Error::Generic(String::from(
+                    "watchtower server is not enabled",
+                ))),
+            },
             AdminRequest::GetNetworkGraph {} => {
                 let graph = self.p2p.network_graph.read_only();
                 let channels = graph.channels();
@@ -963,7 +1009,7 @@ impl AdminService {
         };
         match status {
             None => {
-                let (lightning_node, handles, background_processor) = LightningNode::new(
+                let (lightning_node, mut handles, background_processor) = LightningNode::new(
                     self.config.clone(),
                     node.id.clone(),
                     vec![node.listen_addr.clone()],
@@ -990,11 +1036,67 @@ impl AdminService {
                     node.listen_port
                 );
 
+                let lightning_node = Arc::new(lightning_node);
+                handles.push(tokio::spawn(
+                    PaymentScheduler::new(lightning_node.clone()).run(),
+                ));
+                handles.push(tokio::spawn(FeeManager::new(lightning_node.clone()).run()));
+                handles.push(tokio::spawn(Autopilot::new(lightning_node.clone()).run()));
+                handles.push(tokio::spawn(
+                    ChannelBackupManager::new(lightning_node.clone()).run(),
+                ));
+                handles.push(tokio::spawn(
+                    HtlcExpiryMonitor::new(lightning_node.clone()).run(),
+                ));
+                handles.push(tokio::spawn(
+                    JitChannelManager::new(lightning_node.clone()).run(),
+                ));
+                handles.push(tokio::spawn(
+                    HealthMonitor::new(lightning_node.clone()).run(),
+                ));
+                if let Some(target) = self.watchtower_target() {
+                    handles.push(tokio::spawn(
+                        WatchtowerClient::new(lightning_node.clone(), target).run(),
+                    ));
+                }
+
+                let snapshot_node = lightning_node.clone();
+                let snapshot_interval_secs = self.config.balance_snapshot_interval_secs.max(1);
+                let snapshot_retention_secs = self.config.balance_snapshot_retention_secs;
+                handles.push(tokio::spawn(async move {
+                    let mut interval =
+                        tokio::time::interval(Duration::from_secs(snapshot_interval_secs));
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = snapshot_node.record_balance_snapshot().await {
+                            println!("ERROR: failed to record balance snapshot: {}", e);
+                        }
+                        if let Err(e) = snapshot_node
+                            .prune_balance_snapshots(snapshot_retention_secs)
+                            .await
+                        {
+                            println!("ERROR: failed to prune balance snapshots: {}", e);
+                        }
+                    }
+                }));
+
+                let channel_sync_node = lightning_node.clone();
+                handles.push(tokio::spawn(async move {
+                    let mut interval =
+                        tokio::time::interval(Duration::from_secs(CHANNEL_SYNC_INTERVAL_SECS));
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = channel_sync_node.sync_channels().await {
+                            println!("ERROR: failed to sync channels: {}", e);
+                        }
+                    }
+                }));
+
                 {
                     let mut node_directory = self.node_directory.lock().await;
                     if let Entry::Occupied(mut entry) = node_directory.entry(node.id.clone()) {
                         entry.insert(Some(NodeHandle {
-                            node: Arc::new(lightning_node.clone()),
+                            node: lightning_node,
                             background_processor,
                             handles,
                         }));
@@ -1013,34 +1115,65 @@ impl AdminService {
         }
     }
 
+    fn watchtower_target(&self) -> Option<WatchtowerTarget> {
+        if self.config.remote_watchtower_configured() {
+            Some(WatchtowerTarget::Remote(RemoteSenseiInfo {
+                host: self.config.remote_watchtower_host.clone().unwrap(),
+                token: self.config.remote_watchtower_token.clone().unwrap(),
+            }))
+        } else {
+            self.watchtower.clone().map(WatchtowerTarget::Local)
+        }
+    }
+
     async fn stop_node(&self, pubkey: String) -> Result<(), crate::error::Error> {
-        let mut node_directory = self.node_directory.lock().await;
-        let entry = node_directory.entry(pubkey.clone());
-
-        if let Entry::Occupied(entry) = entry {
-            if let Some(node_handle) = entry.remove() {
-                // Disconnect our peers and stop accepting new connections. This ensures we don't continue
-                // updating our channel data after we've stopped the background processor.
-                node_handle.node.peer_manager.disconnect_all_peers();
-                node_handle.node.stop_listen.store(true, Ordering::Release);
-                self.p2p
-                    .peer_connector
-                    .unregister_node(node_handle.node.id.clone());
-                let _res = node_handle.background_processor.stop();
-                for handle in node_handle.handles {
-                    handle.abort();
+        // Leave an empty entry behind while stopping so the node can't be started again
+        // until it's done, without holding the directory lock the whole time.
+        let node_handle = {
+            let mut node_directory = self.node_directory.lock().await;
+            match node_directory.get_mut(&pubkey) {
+                Some(entry) => entry.take(),
+                None => None,
+            }
+        };
+
+        if let Some(node_handle) = node_handle {
+            // Disconnect our peers and stop accepting new connections. This ensures we don't continue
+            // updating our channel data after we've stopped the background processor.
+            node_handle.node.peer_manager.disconnect_all_peers();
+            node_handle.node.stop_listen.store(true, Ordering::Release);
+            self.p2p
+                .peer_connector
+                .unregister_node(node_handle.node.id.clone());
+            let _res = node_handle.background_processor.stop();
+            // hand the tower the final state of our channels before we stop watching them
+            if let Some(target) = self.watchtower_target() {
+                let client = WatchtowerClient::new(node_handle.node.clone(), target);
+                match tokio::time::timeout(
+                    Duration::from_secs(WATCHTOWER_FLUSH_TIMEOUT_SECS),
+                    client.flush(),
+                )
+                .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => println!("ERROR: failed to push watchtower updates: {}", e),
+                    Err(_) => println!("ERROR: timed out pushing watchtower updates"),
                 }
+            }
+            for handle in node_handle.handles {
+                handle.abort();
+            }
+            self.node_directory.lock().await.remove(&pubkey);
 
-                match self.database.get_node_by_pubkey(&pubkey).await? {
-                    Some(node) => {
-                        let mut node: node::ActiveModel = node.into();
-                        node.status = ActiveValue::Set(node::NodeStatus::Stopped.into());
-                        node.save(self.database.get_connection()).await?;
-                    }
-                    None => {
-                        // TODO: this shouldn't be possible.  surface this some other way?
-                        println!("couldn't find node pubkey in database while stopping");
-                    }
+            match self.database.get_node_by_pubkey(&pubkey).await? {
+                Some(node) => {
+                    let mut node: node::ActiveModel = node.into();
+                    node.status = ActiveValue::Set(node::NodeStatus::Stopped.into());
+                    node.save(self.database.get_connection()).await?;
+                }
+                None => {
+                    // TODO: this shouldn't be possible.  surface this some other way?
+                    println!("couldn't find node pubkey in database while stopping");
                 }
             }
         }
@@ -52,6 +52,18 @@ pub struct PaymentsFilter {
     pub status: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ChannelsFilter {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub state: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InvoicesFilter {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub status: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ForwardedPaymentsFilter {
     #[serde(default, deserialize_with = "empty_string_as_none")]
@@ -102,12 +114,99 @@ impl From<ListPaymentsParams> for PaginationRequest {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListInvoicesParams {
+    pub page: u32,
+    pub take: u32,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub status: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub query: Option<String>,
+}
+
+impl Default for ListInvoicesParams {
+    fn default() -> Self {
+        Self {
+            page: 0,
+            take: 10,
+            status: None,
+            query: None,
+        }
+    }
+}
+
+impl From<ListInvoicesParams> for InvoicesFilter {
+    fn from(params: ListInvoicesParams) -> Self {
+        Self {
+            status: params.status,
+        }
+    }
+}
+
+impl From<ListInvoicesParams> for PaginationRequest {
+    fn from(params: ListInvoicesParams) -> Self {
+        Self {
+            page: params.page,
+            take: params.take,
+            query: params.query,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListForwardedPaymentsParams {
+    pub page: u32,
+    pub take: u32,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub from_channel_id: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub to_channel_id: Option<String>,
+    pub from_hours_since_epoch: Option<u64>,
+    pub to_hours_since_epoch: Option<u64>,
+}
+
+impl Default for ListForwardedPaymentsParams {
+    fn default() -> Self {
+        Self {
+            page: 0,
+            take: 10,
+            from_channel_id: None,
+            to_channel_id: None,
+            from_hours_since_epoch: None,
+            to_hours_since_epoch: None,
+        }
+    }
+}
+
+impl From<ListForwardedPaymentsParams> for ForwardedPaymentsFilter {
+    fn from(params: ListForwardedPaymentsParams) -> Self {
+        Self {
+            from_channel_id: params.from_channel_id,
+            to_channel_id: params.to_channel_id,
+            from_hours_since_epoch: params.from_hours_since_epoch,
+            to_hours_since_epoch: params.to_hours_since_epoch,
+        }
+    }
+}
+
+impl From<ListForwardedPaymentsParams> for PaginationRequest {
+    fn from(params: ListForwardedPaymentsParams) -> Self {
+        Self {
+            page: params.page,
+            take: params.take,
+            query: None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ListChannelsParams {
     pub page: u32,
     pub take: u32,
     #[serde(default, deserialize_with = "empty_string_as_none")]
     pub query: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub state: Option<String>,
 }
 
 impl Default for ListChannelsParams {
@@ -116,6 +215,15 @@ impl Default for ListChannelsParams {
             page: 1,
             take: 10,
             query: None,
+            state: None,
+        }
+    }
+}
+
+impl From<ListChannelsParams> for ChannelsFilter {
+    fn from(params: ListChannelsParams) -> Self {
+        Self {
+            state: params.state,
         }
     }
 }
@@ -130,6 +238,34 @@ impl From<ListChannelsParams> for PaginationRequest {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListScheduledPaymentsParams {
+    pub page: u32,
+    pub take: u32,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub query: Option<String>,
+}
+
+impl Default for ListScheduledPaymentsParams {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            take: 10,
+            query: None,
+        }
+    }
+}
+
+impl From<ListScheduledPaymentsParams> for PaginationRequest {
+    fn from(params: ListScheduledPaymentsParams) -> Self {
+        Self {
+            page: params.page,
+            take: params.take,
+            query: params.query,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ListTransactionsParams {
     pub page: u32,
@@ -7,12 +7,21 @@
 // You may not use this file except in accordance with one or both of these
 // licenses.
 
-use crate::node::{LightningNode, LocalInvoice};
+use crate::autopilot::{AutopilotChannel, AutopilotPolicy};
+use crate::backup::RecoveredChannel;
+use crate::channel_acceptance::ChannelAcceptancePolicy;
+use crate::fee_manager::FeePolicy;
+use crate::health::{HealthPolicy, HealthReport};
+use crate::htlc_tracker::PendingHtlc;
+use crate::jit_channels::JitChannelPolicy;
+use crate::node::{ChannelState, InvoiceStatus, LightningNode, LocalInvoice};
 use bdk::TransactionDetails;
 use futures::Future;
+use lightning::chain::channelmonitor::{Balance as LdkBalance, ANTI_REORG_DELAY};
 use lightning::util::config::{
     ChannelConfig, ChannelHandshakeConfig, ChannelHandshakeLimits, UserConfig,
 };
+use std::fmt::{self, Display};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tower::Service;
@@ -20,9 +29,13 @@ use tower::Service;
 use crate::hex_utils;
 
 use lightning::ln::channelmanager::ChannelDetails;
+use lightning::routing::router::RouteHop;
 use serde::{Deserialize, Serialize};
 
-use super::{PaginationRequest, PaginationResponse, PaymentsFilter};
+use super::{
+    ChannelsFilter, ForwardedPaymentsFilter, InvoicesFilter, PaginationRequest, PaginationResponse,
+    PaymentsFilter,
+};
 
 #[derive(Serialize)]
 pub struct Peer {
@@ -39,6 +52,165 @@ pub struct NodeInfo {
     pub local_balance_msat: u64,
 }
 
+pub struct Balance {
+    pub onchain_balance_sats: u64,
+    pub channel_balance_msats: u64,
+    pub channel_outbound_capacity_msats: u64,
+    pub channel_inbound_capacity_msats: u64,
+    pub usable_channel_outbound_capacity_msats: u64,
+    pub usable_channel_inbound_capacity_msats: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BalanceSnapshot {
+    pub timestamp: i64,
+    pub onchain_balance_sats: u64,
+    pub channel_outbound_capacity_msats: u64,
+    pub channel_inbound_capacity_msats: u64,
+}
+
+impl From<entity::balance_snapshot::Model> for BalanceSnapshot {
+    fn from(snapshot: entity::balance_snapshot::Model) -> Self {
+        Self {
+            timestamp: snapshot.created_at,
+            onchain_balance_sats: snapshot.onchain_balance_sats as u64,
+            channel_outbound_capacity_msats: snapshot.channel_outbound_capacity_msats as u64,
+            channel_inbound_capacity_msats: snapshot.channel_inbound_capacity_msats as u64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimableBalanceKind {
+    // the closing transaction hasn't confirmed yet
+    AwaitingConfirmation,
+    // our output is locked until the maturity height by the channel's to_self_delay
+    PendingTimelock,
+    // an HTLC our counterparty may still claim before the maturity height
+    ContestedHtlc,
+    // the claim has confirmed and is swept to the wallet once it is safe from reorgs
+    SweepInProgress,
+}
+
+impl Display for ClaimableBalanceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            ClaimableBalanceKind::AwaitingConfirmation => "awaiting_confirmation",
+            ClaimableBalanceKind::PendingTimelock => "pending_timelock",
+            ClaimableBalanceKind::ContestedHtlc => "contested_htlc",
+            ClaimableBalanceKind::SweepInProgress => "sweep_in_progress",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl ClaimableBalanceKind {
+    // Outputs without a timelock mature within ANTI_REORG_DELAY of confirming,
+    // anything further out is waiting on our to_self_delay.
+    pub fn awaiting_confirmations(confirmation_height: u32, best_block_height: u32) -> Self {
+        if confirmation_height > best_block_height + ANTI_REORG_DELAY {
+            ClaimableBalanceKind::PendingTimelock
+        } else {
+            ClaimableBalanceKind::SweepInProgress
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClaimableBalance {
+    pub kind: ClaimableBalanceKind,
+    pub amount_sats: u64,
+    pub maturity_height: Option<u32>,
+}
+
+impl ClaimableBalance {
+    pub fn from_ldk(balance: &LdkBalance, best_block_height: u32) -> Self {
+        match *balance {
+            LdkBalance::ClaimableOnChannelClose {
+                claimable_amount_satoshis,
+            } => Self {
+                kind: ClaimableBalanceKind::AwaitingConfirmation,
+                amount_sats: claimable_amount_satoshis,
+                maturity_height: None,
+            },
+            LdkBalance::ClaimableAwaitingConfirmations {
+                claimable_amount_satoshis,
+                confirmation_height,
+            } => Self {
+                kind: ClaimableBalanceKind::awaiting_confirmations(
+                    confirmation_height,
+                    best_block_height,
+                ),
+                amount_sats: claimable_amount_satoshis,
+                maturity_height: Some(confirmation_height),
+            },
+            LdkBalance::ContentiousClaimable {
+                claimable_amount_satoshis,
+                timeout_height,
+            } => Self {
+                kind: ClaimableBalanceKind::ContestedHtlc,
+                amount_sats: claimable_amount_satoshis,
+                maturity_height: Some(timeout_height),
+            },
+            LdkBalance::MaybeClaimableHTLCAwaitingTimeout {
+                claimable_amount_satoshis,
+                claimable_height,
+            } => Self {
+                kind: ClaimableBalanceKind::ContestedHtlc,
+                amount_sats: claimable_amount_satoshis,
+                maturity_height: Some(claimable_height),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChannelClaimableBalances {
+    pub channel_id: String,
+    pub funding_txid: String,
+    pub funding_tx_index: u32,
+    pub counterparty_pubkey: Option<String>,
+    pub claimable_sats: u64,
+    pub balances: Vec<ClaimableBalance>,
+}
+
+// Funds in closing or closed channels that haven't made it back to the on-chain
+// wallet yet, which `Balance` doesn't include.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ClaimableBalances {
+    pub best_block_height: u32,
+    pub total_sats: u64,
+    pub awaiting_confirmation_sats: u64,
+    pub pending_timelock_sats: u64,
+    pub contested_htlc_sats: u64,
+    pub sweep_in_progress_sats: u64,
+    pub channels: Vec<ChannelClaimableBalances>,
+}
+
+impl ClaimableBalances {
+    pub fn add_channel(&mut self, channel: ChannelClaimableBalances) {
+        for balance in channel.balances.iter() {
+            self.total_sats += balance.amount_sats;
+            match balance.kind {
+                ClaimableBalanceKind::AwaitingConfirmation => {
+                    self.awaiting_confirmation_sats += balance.amount_sats
+                }
+                ClaimableBalanceKind::PendingTimelock => {
+                    self.pending_timelock_sats += balance.amount_sats
+                }
+                ClaimableBalanceKind::ContestedHtlc => {
+                    self.contested_htlc_sats += balance.amount_sats
+                }
+                ClaimableBalanceKind::SweepInProgress => {
+                    self.sweep_in_progress_sats += balance.amount_sats
+                }
+            }
+        }
+        self.channels.push(channel);
+    }
+}
+
 // #[derive(Serialize)]
 // pub struct Payment {
 //     pub hash: String,
@@ -89,6 +261,13 @@ pub struct Channel {
     pub is_public: bool,
     pub counterparty_pubkey: String,
     pub alias: Option<String>,
+    pub state: String,
+    pub closing_reason: Option<String>,
+    pub closing_txid: Option<String>,
+    pub opened_at: Option<i64>,
+    pub ready_at: Option<i64>,
+    pub closing_at: Option<i64>,
+    pub closed_at: Option<i64>,
 }
 
 impl From<ChannelDetails> for Channel {
@@ -114,7 +293,66 @@ impl From<ChannelDetails> for Channel {
             is_public: channel_detail.is_public,
             counterparty_pubkey: channel_detail.counterparty.node_id.to_string(),
             alias: None,
+            state: if channel_detail.is_channel_ready {
+                ChannelState::Ready.to_string()
+            } else {
+                ChannelState::PendingOpen.to_string()
+            },
+            closing_reason: None,
+            closing_txid: None,
+            opened_at: None,
+            ready_at: None,
+            closing_at: None,
+            closed_at: None,
+        }
+    }
+}
+
+// Channels that are no longer in the channel manager only have what we recorded,
+// their balance is the last one we saw before the channel closed.
+impl From<&entity::channel::Model> for Channel {
+    fn from(record: &entity::channel::Model) -> Self {
+        Self {
+            channel_id: record.channel_id.clone(),
+            funding_txid: record.funding_txid.clone(),
+            funding_tx_index: record.funding_tx_index.map(|index| index as u32),
+            short_channel_id: record.short_channel_id.map(|scid| scid as u64),
+            channel_value_satoshis: record.channel_value_satoshis as u64,
+            balance_msat: record.balance_msat as u64,
+            unspendable_punishment_reserve: None,
+            user_channel_id: 0,
+            outbound_capacity_msat: 0,
+            inbound_capacity_msat: 0,
+            confirmations_required: None,
+            force_close_spend_delay: None,
+            is_outbound: record.is_outbound,
+            is_channel_ready: false,
+            is_usable: false,
+            is_public: record.is_public,
+            counterparty_pubkey: record.counterparty_node_id.clone(),
+            alias: None,
+            state: String::new(),
+            closing_reason: None,
+            closing_txid: None,
+            opened_at: None,
+            ready_at: None,
+            closing_at: None,
+            closed_at: None,
         }
+        .with_record(record)
+    }
+}
+
+impl Channel {
+    pub fn with_record(mut self, record: &entity::channel::Model) -> Self {
+        self.state = record.state.clone();
+        self.closing_reason = record.closing_reason.clone();
+        self.closing_txid = record.closing_txid.clone();
+        self.opened_at = Some(record.created_at);
+        self.ready_at = record.ready_at;
+        self.closing_at = record.closing_at;
+        self.closed_at = record.closed_at;
+        self
     }
 }
 
@@ -124,6 +362,8 @@ pub struct OpenChannelRequest {
     pub amount_sats: u64,
     pub public: bool,
     pub scid_alias: Option<bool>,
+    // opting out leaves the destination of a cooperative close to be chosen when closing
+    pub upfront_shutdown_script: Option<bool>,
     pub custom_id: Option<u64>,
     pub push_amount_msats: Option<u64>,
     pub counterparty_host_port: Option<String>,
@@ -164,6 +404,7 @@ impl From<&OpenChannelRequest> for UserConfig {
             channel_handshake_config: ChannelHandshakeConfig {
                 announced_channel: request.public,
                 negotiate_scid_privacy: request.scid_alias.unwrap_or(false),
+                commit_upfront_shutdown_pubkey: request.upfront_shutdown_script.unwrap_or(true),
                 ..Default::default()
             },
             ..Default::default()
@@ -178,6 +419,112 @@ pub struct OpenChannelResult {
     pub channel_id: Option<String>,
 }
 
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ChannelRoutingRevenue {
+    pub channel_id: String,
+    pub fees_earned_msat: u64,
+    pub num_forwards_in: u64,
+    pub num_forwards_out: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DailyRoutingRevenue {
+    pub days_since_epoch: u64,
+    pub fees_earned_msat: u64,
+    pub num_forwards: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RoutingRevenue {
+    pub fees_earned_msat: u64,
+    pub num_forwards: u64,
+    pub channels: Vec<ChannelRoutingRevenue>,
+    pub days: Vec<DailyRoutingRevenue>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct InvoiceInfo {
+    pub payment_hash: String,
+    pub invoice: Option<String>,
+    pub amt_msat: Option<i64>,
+    pub label: Option<String>,
+    pub status: String,
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl InvoiceInfo {
+    pub fn from_payment(payment: entity::payment::Model, now: i64) -> Self {
+        Self {
+            status: InvoiceStatus::from_payment(&payment, now).to_string(),
+            payment_hash: payment.payment_hash,
+            invoice: payment.invoice,
+            amt_msat: payment.amt_msat,
+            label: payment.label,
+            expires_at: payment.expires_at,
+            created_at: payment.created_at,
+            updated_at: payment.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PaymentAttemptHop {
+    pub pubkey: String,
+    pub short_channel_id: u64,
+    pub fee_msat: u64,
+    pub cltv_expiry_delta: u32,
+}
+
+impl From<&RouteHop> for PaymentAttemptHop {
+    fn from(hop: &RouteHop) -> Self {
+        Self {
+            pubkey: hop.pubkey.to_string(),
+            short_channel_id: hop.short_channel_id,
+            fee_msat: hop.fee_msat,
+            cltv_expiry_delta: hop.cltv_expiry_delta,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PaymentAttempt {
+    pub id: String,
+    pub payment_hash: String,
+    pub payment_id: Option<String>,
+    pub status: String,
+    pub failure_code: Option<String>,
+    pub failed_short_channel_id: Option<u64>,
+    pub network_update: Option<String>,
+    pub rejected_by_dest: bool,
+    pub all_paths_failed: bool,
+    pub amt_msat: u64,
+    pub fee_msat: u64,
+    pub hops: Vec<PaymentAttemptHop>,
+    pub created_at: i64,
+}
+
+impl From<entity::payment_attempt::Model> for PaymentAttempt {
+    fn from(attempt: entity::payment_attempt::Model) -> Self {
+        Self {
+            id: attempt.id,
+            payment_hash: attempt.payment_hash,
+            payment_id: attempt.payment_id,
+            status: attempt.status,
+            failure_code: attempt.failure_code,
+            failed_short_channel_id: attempt.failed_short_channel_id.map(|scid| scid as u64),
+            network_update: attempt.network_update,
+            rejected_by_dest: attempt.rejected_by_dest,
+            all_paths_failed: attempt.all_paths_failed,
+            amt_msat: attempt.amt_msat as u64,
+            fee_msat: attempt.fee_msat as u64,
+            hops: serde_json::from_str(&attempt.hops).unwrap_or_default(),
+            created_at: attempt.created_at,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Utxo {
     pub amount_sat: u64,
@@ -194,28 +541,87 @@ pub enum NodeRequest {
     GetUnusedAddress {},
     GetPhantomRouteHints {},
     GetBalance {},
+    GetClaimableBalances {},
+    GetBalanceHistory {
+        from: Option<i64>,
+        to: Option<i64>,
+        interval: Option<u64>,
+    },
     OpenChannels {
         requests: Vec<OpenChannelRequest>,
     },
     SendPayment {
         invoice: String,
+        idempotency_key: Option<String>,
     },
     Keysend {
         dest_pubkey: String,
         amt_msat: u64,
+        idempotency_key: Option<String>,
+    },
+    Rebalance {
+        from_channel_id: String,
+        to_channel_id: String,
+        amt_msat: u64,
+        max_fee_msat: u64,
     },
     DecodeInvoice {
         invoice: String,
     },
     GetInvoice {
-        amt_msat: u64,
+        amt_msat: Option<u64>,
+        min_amt_msat: Option<u64>,
         description: String,
+        description_hash: Option<String>,
+        expiry_secs: Option<u32>,
+        min_final_cltv_expiry: Option<u64>,
+        private_route_hints: Option<bool>,
     },
     GetPhantomInvoice {
-        amt_msat: u64,
+        amt_msat: Option<u64>,
+        min_amt_msat: Option<u64>,
         description: String,
         phantom_route_hints_hex: Vec<String>,
     },
+    ListInvoices {
+        pagination: PaginationRequest,
+        filter: InvoicesFilter,
+    },
+    CancelInvoice {
+        payment_hash: String,
+    },
+    CreateScheduledPayment {
+        kind: String,
+        dest_pubkey: Option<String>,
+        invoice_url: Option<String>,
+        amt_msat: u64,
+        interval_secs: u64,
+        start_at: Option<i64>,
+    },
+    ListScheduledPayments {
+        pagination: PaginationRequest,
+    },
+    UpdateScheduledPayment {
+        id: String,
+        amt_msat: Option<u64>,
+        interval_secs: Option<u64>,
+        enabled: Option<bool>,
+    },
+    DeleteScheduledPayment {
+        id: String,
+    },
+    ExportLedger {
+        from: Option<i64>,
+        to: Option<i64>,
+        format: String,
+    },
+    RestrictMacaroon {
+        macaroon: String,
+        max_payment_msat: Option<u64>,
+        max_daily_msat: Option<u64>,
+        max_monthly_msat: Option<u64>,
+        allowed_destinations: Option<Vec<String>>,
+    },
     LabelPayment {
         label: String,
         payment_hash: String,
@@ -228,6 +634,10 @@ pub enum NodeRequest {
     },
     ListChannels {
         pagination: PaginationRequest,
+        filter: ChannelsFilter,
+    },
+    ListHtlcs {
+        channel_id: Option<String>,
     },
     ListPayments {
         pagination: PaginationRequest,
@@ -240,9 +650,60 @@ pub enum NodeRequest {
     ListTransactions {
         pagination: PaginationRequest,
     },
+    ListForwardedPayments {
+        pagination: PaginationRequest,
+        filter: ForwardedPaymentsFilter,
+    },
+    GetRoutingRevenue {
+        filter: ForwardedPaymentsFilter,
+    },
+    GetPaymentAttempts {
+        payment_hash: String,
+    },
     CloseChannel {
         channel_id: String,
         force: bool,
+        destination_address: Option<String>,
+        target_feerate_sats_per_vbyte: Option<u32>,
+    },
+    GetFeePolicy {},
+    SetFeePolicy {
+        policy: FeePolicy,
+    },
+    GetAutopilotPolicy {},
+    SetAutopilotPolicy {
+        policy: AutopilotPolicy,
+    },
+    RunAutopilot {
+        dry_run: bool,
+    },
+    ExportChannelBackup {},
+    RecoverFromBackup {
+        backup: String,
+    },
+    GetJitChannelPolicy {},
+    SetJitChannelPolicy {
+        policy: JitChannelPolicy,
+    },
+    CreateJitInvoice {
+        invoice: String,
+    },
+    GetHealthReport {},
+    GetHealthPolicy {},
+    SetHealthPolicy {
+        policy: HealthPolicy,
+    },
+    GetChannelAcceptancePolicy {},
+    SetChannelAcceptancePolicy {
+        policy: ChannelAcceptancePolicy,
+    },
+    UpdateChannelConfig {
+        channel_ids: Vec<String>,
+        all: bool,
+        forwarding_fee_base_msat: Option<u32>,
+        forwarding_fee_proportional_millionths: Option<u32>,
+        cltv_expiry_delta: Option<u16>,
+        max_dust_htlc_exposure_msat: Option<u64>,
     },
     NodeInfo {},
     ListPeers {},
@@ -287,21 +748,60 @@ pub enum NodeResponse {
         usable_channel_outbound_capacity_msats: u64,
         usable_channel_inbound_capacity_msats: u64,
     },
+    GetClaimableBalances {
+        balances: ClaimableBalances,
+    },
+    GetBalanceHistory {
+        snapshots: Vec<BalanceSnapshot>,
+    },
     OpenChannels {
         requests: Vec<OpenChannelRequest>,
         results: Vec<OpenChannelResult>,
     },
-    SendPayment {},
+    SendPayment {
+        payment_hash: String,
+        status: String,
+    },
     DecodeInvoice {
         invoice: LocalInvoice,
     },
-    Keysend {},
+    Keysend {
+        payment_hash: String,
+        status: String,
+    },
+    Rebalance {
+        payment_hash: String,
+        status: String,
+        fee_msat: u64,
+    },
     GetInvoice {
         invoice: String,
     },
     GetPhantomInvoice {
         invoice: String,
     },
+    ListInvoices {
+        invoices: Vec<InvoiceInfo>,
+        pagination: PaginationResponse,
+    },
+    CancelInvoice {},
+    CreateScheduledPayment {
+        scheduled_payment: entity::scheduled_payment::Model,
+    },
+    ListScheduledPayments {
+        scheduled_payments: Vec<entity::scheduled_payment::Model>,
+        pagination: PaginationResponse,
+    },
+    UpdateScheduledPayment {
+        scheduled_payment: entity::scheduled_payment::Model,
+    },
+    DeleteScheduledPayment {},
+    ExportLedger {
+        ledger: String,
+    },
+    RestrictMacaroon {
+        macaroon: String,
+    },
     LabelPayment {},
     DeletePayment {},
     ConnectPeer {},
@@ -309,6 +809,9 @@ pub enum NodeResponse {
         channels: Vec<Channel>,
         pagination: PaginationResponse,
     },
+    ListHtlcs {
+        htlcs: Vec<PendingHtlc>,
+    },
     ListPayments {
         payments: Vec<entity::payment::Model>,
         pagination: PaginationResponse,
@@ -321,7 +824,69 @@ pub enum NodeResponse {
         transactions: Vec<TransactionDetails>,
         pagination: PaginationResponse,
     },
-    CloseChannel {},
+    ListForwardedPayments {
+        forwarded_payments: Vec<entity::forwarded_payment::Model>,
+        pagination: PaginationResponse,
+    },
+    GetRoutingRevenue {
+        revenue: RoutingRevenue,
+    },
+    GetPaymentAttempts {
+        attempts: Vec<PaymentAttempt>,
+    },
+    CloseChannel {
+        destination_used: bool,
+    },
+    UpdateChannelConfig {
+        channel_ids: Vec<String>,
+    },
+    GetFeePolicy {
+        policy: FeePolicy,
+    },
+    SetFeePolicy {
+        policy: FeePolicy,
+    },
+    GetChannelAcceptancePolicy {
+        policy: ChannelAcceptancePolicy,
+    },
+    SetChannelAcceptancePolicy {
+        policy: ChannelAcceptancePolicy,
+    },
+    GetAutopilotPolicy {
+        policy: AutopilotPolicy,
+    },
+    SetAutopilotPolicy {
+        policy: AutopilotPolicy,
+    },
+    RunAutopilot {
+        channels: Vec<AutopilotChannel>,
+    },
+    ExportChannelBackup {
+        backup: String,
+    },
+    RecoverFromBackup {
+        channels: Vec<RecoveredChannel>,
+    },
+    GetJitChannelPolicy {
+        policy: JitChannelPolicy,
+    },
+    SetJitChannelPolicy {
+        policy: JitChannelPolicy,
+    },
+    CreateJitInvoice {
+        invoice: String,
+        opening_fee_msat: u64,
+        channel_size_sats: u64,
+    },
+    GetHealthReport {
+        report: HealthReport,
+    },
+    GetHealthPolicy {
+        policy: HealthPolicy,
+    },
+    SetHealthPolicy {
+        policy: HealthPolicy,
+    },
     NodeInfo {
         node_info: NodeInfo,
     },
@@ -396,3 +961,90 @@ impl Service<NodeRequest> for LightningNode {
         Box::pin(fut)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn claimable_balance_from_ldk_test() {
+        let best_block_height = 1000;
+        let balances = [
+            LdkBalance::ClaimableOnChannelClose {
+                claimable_amount_satoshis: 1,
+            },
+            LdkBalance::ClaimableAwaitingConfirmations {
+                claimable_amount_satoshis: 10,
+                confirmation_height: 1144,
+            },
+            LdkBalance::ClaimableAwaitingConfirmations {
+                claimable_amount_satoshis: 100,
+                confirmation_height: 1005,
+            },
+            LdkBalance::ContentiousClaimable {
+                claimable_amount_satoshis: 1_000,
+                timeout_height: 1040,
+            },
+            LdkBalance::MaybeClaimableHTLCAwaitingTimeout {
+                claimable_amount_satoshis: 10_000,
+                claimable_height: 1080,
+            },
+        ]
+        .iter()
+        .map(|balance| ClaimableBalance::from_ldk(balance, best_block_height))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            balances
+                .iter()
+                .map(|balance| (balance.kind, balance.amount_sats, balance.maturity_height))
+                .collect::<Vec<_>>(),
+            vec![
+                (ClaimableBalanceKind::AwaitingConfirmation, 1, None),
+                (ClaimableBalanceKind::PendingTimelock, 10, Some(1144)),
+                (ClaimableBalanceKind::SweepInProgress, 100, Some(1005)),
+                (ClaimableBalanceKind::ContestedHtlc, 1_000, Some(1040)),
+                (ClaimableBalanceKind::ContestedHtlc, 10_000, Some(1080)),
+            ]
+        );
+    }
+
+    #[test]
+    fn claimable_balances_test() {
+        assert_eq!(
+            ClaimableBalanceKind::awaiting_confirmations(1144, 1000),
+            ClaimableBalanceKind::PendingTimelock
+        );
+        assert_eq!(
+            ClaimableBalanceKind::awaiting_confirmations(1005, 1000),
+            ClaimableBalanceKind::SweepInProgress
+        );
+
+        let balance = |kind, amount_sats| ClaimableBalance {
+            kind,
+            amount_sats,
+            maturity_height: None,
+        };
+        let mut claimable_balances = ClaimableBalances::default();
+        claimable_balances.add_channel(ChannelClaimableBalances {
+            channel_id: String::from("00"),
+            funding_txid: String::from("txid"),
+            funding_tx_index: 0,
+            counterparty_pubkey: None,
+            claimable_sats: 11_111,
+            balances: vec![
+                balance(ClaimableBalanceKind::AwaitingConfirmation, 1),
+                balance(ClaimableBalanceKind::PendingTimelock, 10),
+                balance(ClaimableBalanceKind::SweepInProgress, 100),
+                balance(ClaimableBalanceKind::ContestedHtlc, 1_000),
+                balance(ClaimableBalanceKind::ContestedHtlc, 10_000),
+            ],
+        });
+        assert_eq!(claimable_balances.total_sats, 11_111);
+        assert_eq!(claimable_balances.awaiting_confirmation_sats, 1);
+        assert_eq!(claimable_balances.pending_timelock_sats, 10);
+        assert_eq!(claimable_balances.sweep_in_progress_sats, 100);
+        assert_eq!(claimable_balances.contested_htlc_sats, 11_000);
+        assert_eq!(claimable_balances.channels.len(), 1);
+    }
+}
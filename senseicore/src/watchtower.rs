@@ -0,0 +1,791 @@
+// This file is Copyright its original authors, visible in version control
+// history.
+//
+// This file is licensed under the Apache License, Version 2.0 <LICENSE-APACHE
+// or http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option.
+// You may not use this file except in accordance with one or both of these
+// licenses.
+
+use crate::chain::fee_estimator::SenseiFeeEstimator;
+use crate::chain::manager::SenseiChainManager;
+use crate::database::SenseiDatabase;
+use crate::error::Error;
+use crate::events::SenseiEvent;
+use crate::hex_utils;
+use crate::monitor_info::MonitorInfo;
+use crate::node::LightningNode;
+use crate::p2p::router::RemoteSenseiInfo;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use bitcoin::util::sighash::SighashCache;
+use bitcoin::{
+    BlockHeader, EcdsaSighashType, OutPoint, Script, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use lightning::chain::chaininterface::{BroadcasterInterface, ConfirmationTarget, FeeEstimator};
+use lightning::chain::keysinterface::BaseSign;
+use lightning::chain::transaction::TransactionData;
+use lightning::chain::Listen;
+use lightning::ln::chan_utils;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tindercrypt::cryptors::RingCryptor;
+use tokio::sync::{broadcast, Notify};
+
+// Failed pushes to the watchtower are retried on this interval.
+const WATCHTOWER_RETRY_SECS: u64 = 60;
+
+// A justice transaction is rebroadcast every block until the breached output is spent,
+// kept around until that spend is safe from a reorg and given up on after two weeks.
+const MIN_WATCH_BLOCKS: u32 = 6;
+const MAX_WATCH_BLOCKS: u32 = 2016;
+
+// Blobs from every client are kept under this node id in the kv store.
+const WATCHTOWER_NODE_ID: &str = "watchtower";
+
+// Witness of a revoked to_local spend: item count, signature, the OP_TRUE selecting the
+// revocation branch and the witness script, each with their length prefix.
+const REVOKED_OUTPUT_WITNESS_WEIGHT: usize = 1 + 1 + 73 + 1 + 1 + 1 + 77;
+
+const DUST_LIMIT_SATS: u64 = 546;
+
+// What the tower needs to sweep the to_local output of one revoked counterparty
+// commitment back to the client. The revocation key is derived for that commitment
+// alone, so it can't unlock anything else in the channel. Htlc outputs on the revoked
+// commitment are left for the client's own chain monitor once it is back online.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct JusticeData {
+    pub revocation_key: String,
+    pub delayed_payment_key: String,
+    pub to_self_delay: u16,
+    pub sweep_script: String,
+}
+
+impl JusticeData {
+    // Returns None until the counterparty revoked the given commitment.
+    pub fn new(
+        monitor_info: &MonitorInfo,
+        revocation_base_key: &SecretKey,
+        commitment_number: u64,
+    ) -> Option<Self> {
+        let secp_ctx = Secp256k1::new();
+        let per_commitment_secret = monitor_info
+            .commitment_secrets
+            .get_secret(commitment_number)?;
+        let per_commitment_key = SecretKey::from_slice(&per_commitment_secret).ok()?;
+        let per_commitment_point = PublicKey::from_secret_key(&secp_ctx, &per_commitment_key);
+        let revocation_key = chan_utils::derive_private_revocation_key(
+            &secp_ctx,
+            &per_commitment_key,
+            revocation_base_key,
+        )
+        .ok()?;
+        let delayed_payment_key = chan_utils::derive_public_key(
+            &secp_ctx,
+            &per_commitment_point,
+            &monitor_info.counterparty_delayed_payment_base_key,
+        )
+        .ok()?;
+        Some(Self {
+            revocation_key: hex_utils::hex_str(&revocation_key[..]),
+            delayed_payment_key: delayed_payment_key.to_string(),
+            to_self_delay: monitor_info.on_counterparty_tx_csv,
+            sweep_script: hex_utils::hex_str(monitor_info.destination_script.as_bytes()),
+        })
+    }
+
+    // Spends the revoked to_local output of `breach_tx` to the sweep script, or returns
+    // None when the breach doesn't pay anything to the counterparty worth sweeping.
+    pub fn build_justice_tx(
+        &self,
+        breach_tx: &Transaction,
+        feerate_sat_per_1000_weight: u32,
+    ) -> Option<Transaction> {
+        let secp_ctx = Secp256k1::new();
+        let revocation_key =
+            SecretKey::from_slice(&hex_utils::to_vec(&self.revocation_key)?).ok()?;
+        let delayed_payment_key = hex_utils::to_compressed_pubkey(&self.delayed_payment_key)?;
+        let sweep_script = Script::from(hex_utils::to_vec(&self.sweep_script)?);
+
+        let redeemscript = chan_utils::get_revokeable_redeemscript(
+            &PublicKey::from_secret_key(&secp_ctx, &revocation_key),
+            self.to_self_delay,
+            &delayed_payment_key,
+        );
+        let script_pubkey = redeemscript.to_v0_p2wsh();
+        let (vout, output) = breach_tx
+            .output
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.script_pubkey == script_pubkey)?;
+
+        let mut justice_tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: breach_tx.txid(),
+                    vout: vout as u32,
+                },
+                script_sig: Script::new(),
+                sequence: 0xfffffffd,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                script_pubkey: sweep_script,
+                value: 0,
+            }],
+        };
+        let weight = justice_tx.weight() + REVOKED_OUTPUT_WITNESS_WEIGHT;
+        let fee = weight as u64 * u64::from(feerate_sat_per_1000_weight) / 1000;
+        if output.value < fee + DUST_LIMIT_SATS {
+            return None;
+        }
+        justice_tx.output[0].value = output.value - fee;
+
+        let sighash = SighashCache::new(&justice_tx)
+            .segwit_signature_hash(0, &redeemscript, output.value, EcdsaSighashType::All)
+            .ok()?;
+        let signature =
+            secp_ctx.sign_ecdsa(&Message::from_slice(&sighash[..]).ok()?, &revocation_key);
+        let mut signature = signature.serialize_der().to_vec();
+        signature.push(EcdsaSighashType::All as u8);
+        justice_tx.input[0].witness.push(signature);
+        justice_tx.input[0].witness.push(vec![1]);
+        justice_tx.input[0].witness.push(redeemscript.into_bytes());
+        Some(justice_tx)
+    }
+}
+
+// Justice data encrypted with a key derived from the txid of the revoked commitment it
+// punishes. The tower only stores a hash of that txid, so it can't read a blob or even
+// tell which channel it belongs to unless the revoked commitment shows up on-chain.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WatchtowerBlob {
+    pub hint: String,
+    pub blob: String,
+}
+
+impl WatchtowerBlob {
+    fn derive(tag: &[u8], commitment_txid: &Txid) -> [u8; 32] {
+        let mut engine = sha256::Hash::engine();
+        engine.input(tag);
+        engine.input(commitment_txid.as_inner());
+        sha256::Hash::from_engine(engine).into_inner()
+    }
+
+    pub fn hint(commitment_txid: &Txid) -> String {
+        hex_utils::hex_str(&Self::derive(b"sensei watchtower hint", commitment_txid)[..16])
+    }
+
+    pub fn seal(commitment_txid: &Txid, justice_data: &JusticeData) -> Result<Self, Error> {
+        let cryptor = RingCryptor::new();
+        let blob = cryptor.seal_with_key(
+            &Self::derive(b"sensei watchtower key", commitment_txid),
+            &serde_json::to_vec(justice_data).unwrap(),
+        )?;
+        Ok(Self {
+            hint: Self::hint(commitment_txid),
+            blob: base64::encode(&blob),
+        })
+    }
+
+    // Returns None when the blob wasn't sealed for this commitment.
+    pub fn open(&self, commitment_txid: &Txid) -> Option<JusticeData> {
+        let blob = base64::decode(&self.blob).ok()?;
+        let cryptor = RingCryptor::new();
+        let justice_data = cryptor
+            .open(
+                &Self::derive(b"sensei watchtower key", commitment_txid),
+                &blob,
+            )
+            .ok()?;
+        serde_json::from_slice(&justice_data).ok()
+    }
+}
+
+// Counterparty commitments waiting for their revocation secret are kept under
+// `watchtower/commitments/<funding txid>_<funding output index>/<commitment number>`.
+fn commitments_prefix(funding_outpoint: &OutPoint) -> String {
+    format!(
+        "watchtower/commitments/{}_{}/",
+        funding_outpoint.txid, funding_outpoint.vout
+    )
+}
+
+// Monitor updates waiting to be turned into justice data for the watchtower. Only the
+// latest copy of each monitor matters since it holds every revocation secret the
+// channel has seen.
+pub struct MonitorUpdateQueue {
+    database: Arc<SenseiDatabase>,
+    node_id: String,
+    pending: Mutex<HashMap<String, Vec<u8>>>,
+    notify: Notify,
+}
+
+impl MonitorUpdateQueue {
+    pub fn new(database: Arc<SenseiDatabase>, node_id: String) -> Self {
+        Self {
+            database,
+            node_id,
+            pending: Mutex::new(HashMap::new()),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn push(&self, key: &str, monitor: Vec<u8>) {
+        // A counterparty commitment is only the current one until the next update,
+        // so it has to be remembered now to be punished once it gets revoked.
+        match MonitorInfo::read(&monitor) {
+            Ok(monitor_info) => {
+                if let Some(txid) = monitor_info.current_counterparty_commitment_txid {
+                    if let Err(e) = self.database.set_value_sync(
+                        self.node_id.clone(),
+                        format!(
+                            "{}{}",
+                            commitments_prefix(&monitor_info.funding_outpoint),
+                            monitor_info.current_counterparty_commitment_number
+                        ),
+                        txid.to_vec(),
+                    ) {
+                        println!("ERROR: failed to save counterparty commitment: {}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                // there is nothing the watchtower could be sent for a monitor we can't read
+                println!("ERROR: failed to read channel monitor: {:?}", e);
+                return;
+            }
+        }
+
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), monitor);
+        self.notify.notify_one();
+    }
+
+    fn take(&self) -> HashMap<String, Vec<u8>> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+
+    // Puts back updates that failed to push unless a newer copy arrived meanwhile.
+    fn requeue(&self, updates: HashMap<String, Vec<u8>>) {
+        let mut pending = self.pending.lock().unwrap();
+        for (key, monitor) in updates {
+            pending.entry(key).or_insert(monitor);
+        }
+    }
+}
+
+pub enum WatchtowerTarget {
+    Local(Arc<WatchtowerServer>),
+    Remote(RemoteSenseiInfo),
+}
+
+// Pushes justice data for every revoked counterparty commitment of a node to a
+// watchtower so its channels stay protected while the node is stopped.
+pub struct WatchtowerClient {
+    node: Arc<LightningNode>,
+    target: WatchtowerTarget,
+}
+
+impl WatchtowerClient {
+    pub fn new(node: Arc<LightningNode>, target: WatchtowerTarget) -> Self {
+        Self { node, target }
+    }
+
+    async fn push(&self, blobs: Vec<WatchtowerBlob>) -> Result<(), Error> {
+        let client_pubkey = self.node.channel_manager.get_our_node_id().to_string();
+        match &self.target {
+            WatchtowerTarget::Local(watchtower) => {
+                watchtower.store_blobs(client_pubkey, blobs).await
+            }
+            WatchtowerTarget::Remote(remote_sensei) => reqwest::Client::new()
+                .post(format!("{}/v1/watchtower/blobs", remote_sensei.host))
+                .header("token", remote_sensei.token.clone())
+                .json(&serde_json::json!({
+                    "client_pubkey": client_pubkey,
+                    "blobs": blobs,
+                }))
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map(|_| ())
+                .map_err(|e| Error::Generic(e.to_string())),
+        }
+    }
+
+    // Seals justice data for the commitments of a monitor that were revoked since the
+    // last push, along with the keys of the commitments they came from.
+    async fn revoked_commitments(
+        &self,
+        monitor: &[u8],
+    ) -> Result<(Vec<WatchtowerBlob>, Vec<String>), Error> {
+        let monitor_info = MonitorInfo::read(monitor)
+            .map_err(|e| Error::Generic(format!("failed to read channel monitor: {:?}", e)))?;
+        let signer = self.node.keys_manager.derive_channel_keys(
+            monitor_info.channel_value_satoshis,
+            &monitor_info.channel_keys_id,
+        );
+        if signer.pubkeys().revocation_basepoint != monitor_info.holder_revocation_basepoint {
+            return Err(Error::Generic(String::from(
+                "channel monitor was not created with this node's keys",
+            )));
+        }
+
+        let mut blobs = vec![];
+        let mut keys = vec![];
+        for entry in self
+            .node
+            .database
+            .list_values(
+                self.node.id.clone(),
+                commitments_prefix(&monitor_info.funding_outpoint),
+            )
+            .await?
+        {
+            let commitment_number = entry.k.rsplit('/').next().unwrap_or_default();
+            let justice_data = commitment_number.parse::<u64>().ok().and_then(|number| {
+                JusticeData::new(&monitor_info, &signer.revocation_base_key, number)
+            });
+            if let (Some(justice_data), Ok(txid)) = (justice_data, Txid::from_slice(&entry.v)) {
+                blobs.push(WatchtowerBlob::seal(&txid, &justice_data)?);
+                keys.push(entry.k);
+            }
+        }
+        Ok((blobs, keys))
+    }
+
+    pub async fn flush(&self) -> Result<(), Error> {
+        let queue = match self.node.persister.watchtower_queue() {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+        let updates = queue.take();
+
+        let mut blobs = vec![];
+        let mut revoked_keys = vec![];
+        for (key, monitor) in updates.iter() {
+            match self.revoked_commitments(monitor).await {
+                Ok((monitor_blobs, monitor_keys)) => {
+                    blobs.extend(monitor_blobs);
+                    revoked_keys.extend(monitor_keys);
+                }
+                Err(e) => println!(
+                    "ERROR: failed to read revoked commitments of {}: {}",
+                    key, e
+                ),
+            }
+        }
+        if blobs.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.push(blobs).await {
+            queue.requeue(updates);
+            return Err(e);
+        }
+        for key in revoked_keys {
+            self.node
+                .database
+                .delete_value(self.node.id.clone(), key)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn run(self) {
+        let queue = match self.node.persister.watchtower_queue() {
+            Some(queue) => queue,
+            None => return,
+        };
+
+        // the tower may have been added after these channels were opened
+        match self.node.persister.read_channelmonitor_bytes() {
+            Ok(monitors) => {
+                for (key, monitor) in monitors {
+                    queue.push(&key, monitor);
+                }
+            }
+            Err(e) => println!("ERROR: failed to read channel monitors: {}", e),
+        }
+
+        let mut interval = tokio::time::interval(Duration::from_secs(WATCHTOWER_RETRY_SECS));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = queue.notify.notified() => {}
+            }
+            if let Err(e) = self.flush().await {
+                println!("ERROR: failed to push watchtower updates: {}", e);
+            }
+        }
+    }
+}
+
+struct PendingJustice {
+    client_pubkey: String,
+    justice_tx: Transaction,
+    broadcast_height: u32,
+    spent_height: Option<u32>,
+}
+
+// Stores blobs for any number of clients and checks the txid of every transaction in
+// every block against their hints. When a revoked commitment shows up the matching
+// blob is opened and the justice transaction it allows for is broadcast, then
+// rebroadcast each block until the breached output is spent.
+pub struct WatchtowerServer {
+    database: Arc<SenseiDatabase>,
+    broadcaster: Arc<dyn BroadcasterInterface + Send + Sync>,
+    fee_estimator: Arc<SenseiFeeEstimator>,
+    event_sender: broadcast::Sender<SenseiEvent>,
+    hints: Mutex<HashSet<String>>,
+    pending_justice: Mutex<HashMap<OutPoint, PendingJustice>>,
+}
+
+impl WatchtowerServer {
+    pub async fn new(
+        database: Arc<SenseiDatabase>,
+        chain_manager: &SenseiChainManager,
+        event_sender: broadcast::Sender<SenseiEvent>,
+    ) -> Result<Self, Error> {
+        let hints = database
+            .list_keys(String::from(WATCHTOWER_NODE_ID), "blobs/")
+            .await?
+            .iter()
+            .filter_map(|key| key.split('/').nth(1).map(String::from))
+            .collect::<HashSet<_>>();
+
+        Ok(Self {
+            database,
+            broadcaster: chain_manager.broadcaster.clone(),
+            fee_estimator: chain_manager.fee_estimator.clone(),
+            event_sender,
+            hints: Mutex::new(hints),
+            pending_justice: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn store_blobs(
+        &self,
+        client_pubkey: String,
+        blobs: Vec<WatchtowerBlob>,
+    ) -> Result<(), Error> {
+        for blob in blobs {
+            if hex_utils::to_vec(&blob.hint).map(|hint| hint.len()) != Some(16) {
+                return Err(Error::Generic(String::from("invalid watchtower hint")));
+            }
+            let encrypted_justice_data = base64::decode(&blob.blob)
+                .map_err(|_| Error::Generic(String::from("invalid watchtower blob")))?;
+            self.database
+                .set_value(
+                    String::from(WATCHTOWER_NODE_ID),
+                    format!("blobs/{}/{}", blob.hint, client_pubkey),
+                    encrypted_justice_data,
+                )
+                .await?;
+            self.hints.lock().unwrap().insert(blob.hint);
+        }
+        Ok(())
+    }
+
+    // Opens the blobs stored for a revoked commitment that was just confirmed.
+    fn punish(&self, breach_tx: &Transaction, height: u32) {
+        let breach_txid = breach_tx.txid();
+        let hint = WatchtowerBlob::hint(&breach_txid);
+        let entries = match self
+            .database
+            .list_values_sync(String::from(WATCHTOWER_NODE_ID), format!("blobs/{}/", hint))
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("ERROR: failed to read watchtower blobs: {}", e);
+                return;
+            }
+        };
+
+        let feerate = self
+            .fee_estimator
+            .get_est_sat_per_1000_weight(ConfirmationTarget::HighPriority);
+        let mut pending_justice = self.pending_justice.lock().unwrap();
+        for entry in entries {
+            let client_pubkey = entry.k.rsplit('/').next().unwrap_or_default().to_string();
+            let blob = WatchtowerBlob {
+                hint: hint.clone(),
+                blob: base64::encode(&entry.v),
+            };
+            let justice_tx = match blob
+                .open(&breach_txid)
+                .and_then(|justice_data| justice_data.build_justice_tx(breach_tx, feerate))
+            {
+                Some(justice_tx) => justice_tx,
+                None => {
+                    println!(
+                        "ERROR: failed to build justice transaction for {}",
+                        breach_txid
+                    );
+                    continue;
+                }
+            };
+
+            self.broadcaster.broadcast_transaction(&justice_tx);
+            let _res = self
+                .event_sender
+                .send(SenseiEvent::WatchtowerJusticeBroadcast {
+                    client_pubkey: client_pubkey.clone(),
+                    breach_txid,
+                    justice_txid: justice_tx.txid(),
+                });
+            pending_justice.insert(
+                justice_tx.input[0].previous_output,
+                PendingJustice {
+                    client_pubkey,
+                    justice_tx,
+                    broadcast_height: height,
+                    spent_height: None,
+                },
+            );
+        }
+    }
+
+    pub fn pending_justice_count(&self) -> usize {
+        self.pending_justice.lock().unwrap().len()
+    }
+}
+
+impl Listen for WatchtowerServer {
+    fn filtered_block_connected(
+        &self,
+        _header: &BlockHeader,
+        txdata: &TransactionData,
+        height: u32,
+    ) {
+        let breach_txs = {
+            let hints = self.hints.lock().unwrap();
+            txdata
+                .iter()
+                .filter(|(_, tx)| hints.contains(&WatchtowerBlob::hint(&tx.txid())))
+                .map(|(_, tx)| (*tx).clone())
+                .collect::<Vec<_>>()
+        };
+        for breach_tx in breach_txs.iter() {
+            self.punish(breach_tx, height);
+        }
+
+        let mut pending_justice = self.pending_justice.lock().unwrap();
+        for (_, tx) in txdata.iter() {
+            for input in tx.input.iter() {
+                if let Some(pending) = pending_justice.get_mut(&input.previous_output) {
+                    pending.spent_height.get_or_insert(height);
+                }
+            }
+        }
+        pending_justice.retain(|_, pending| match pending.spent_height {
+            Some(spent_height) => height.saturating_sub(spent_height) < MIN_WATCH_BLOCKS,
+            None => {
+                let watched_blocks = height.saturating_sub(pending.broadcast_height);
+                if watched_blocks >= MAX_WATCH_BLOCKS {
+                    println!(
+                        "ERROR: gave up on justice transaction {} for {}",
+                        pending.justice_tx.txid(),
+                        pending.client_pubkey
+                    );
+                    return false;
+                }
+                self.broadcaster.broadcast_transaction(&pending.justice_tx);
+                true
+            }
+        });
+    }
+
+    fn block_disconnected(&self, _header: &BlockHeader, height: u32) {
+        let mut pending_justice = self.pending_justice.lock().unwrap();
+        for pending in pending_justice.values_mut() {
+            if pending.spent_height >= Some(height) {
+                pending.spent_height = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lightning::ln::chan_utils::CounterpartyCommitmentSecrets;
+    use std::str::FromStr;
+
+    const INITIAL_COMMITMENT_NUMBER: u64 = (1 << 48) - 1;
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn monitor_info(revoked_commitments: u64) -> MonitorInfo {
+        let secp_ctx = Secp256k1::new();
+        let mut commitment_secrets = CounterpartyCommitmentSecrets::new();
+        for i in 0..revoked_commitments {
+            let idx = INITIAL_COMMITMENT_NUMBER - i;
+            commitment_secrets
+                .provide_secret(idx, chan_utils::build_commitment_secret(&[7; 32], idx))
+                .unwrap();
+        }
+        MonitorInfo {
+            destination_script: Script::from(vec![0; 22]),
+            counterparty_payment_script: Script::new(),
+            channel_keys_id: [0; 32],
+            holder_revocation_basepoint: PublicKey::from_secret_key(&secp_ctx, &secret_key(1)),
+            funding_outpoint: OutPoint::default(),
+            current_counterparty_commitment_txid: None,
+            counterparty_delayed_payment_base_key: PublicKey::from_secret_key(
+                &secp_ctx,
+                &secret_key(2),
+            ),
+            on_counterparty_tx_csv: 144,
+            channel_value_satoshis: 100_000,
+            commitment_secrets,
+            current_counterparty_commitment_number: INITIAL_COMMITMENT_NUMBER - revoked_commitments,
+        }
+    }
+
+    // a counterparty commitment paying its to_local output the way LDK builds it
+    fn breach_tx(monitor_info: &MonitorInfo, commitment_number: u64, value: u64) -> Transaction {
+        let secp_ctx = Secp256k1::new();
+        let per_commitment_secret = SecretKey::from_slice(&chan_utils::build_commitment_secret(
+            &[7; 32],
+            commitment_number,
+        ))
+        .unwrap();
+        let per_commitment_point = PublicKey::from_secret_key(&secp_ctx, &per_commitment_secret);
+        let revocation_key = chan_utils::derive_public_revocation_key(
+            &secp_ctx,
+            &per_commitment_point,
+            &monitor_info.holder_revocation_basepoint,
+        )
+        .unwrap();
+        let delayed_payment_key = chan_utils::derive_public_key(
+            &secp_ctx,
+            &per_commitment_point,
+            &monitor_info.counterparty_delayed_payment_base_key,
+        )
+        .unwrap();
+        let to_local_script = chan_utils::get_revokeable_redeemscript(
+            &revocation_key,
+            monitor_info.on_counterparty_tx_csv,
+            &delayed_payment_key,
+        )
+        .to_v0_p2wsh();
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn::default()],
+            output: vec![
+                TxOut {
+                    script_pubkey: Script::from(vec![0; 22]),
+                    value: 5_000,
+                },
+                TxOut {
+                    script_pubkey: to_local_script,
+                    value,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn watchtower_blob_encryption_test() {
+        let txid =
+            Txid::from_str("8d1a6b1bc6e2c3d7a39a0b0d6e2b5f6a4c3e2d1f0a9b8c7d6e5f4a3b2c1d0e0f")
+                .unwrap();
+        let other_txid =
+            Txid::from_str("0e0d1c2b3a4f5e6d7c8b9a0f1d2e3c4a6f5b2e6d0b0a9a3d7c3e2c6b1b6a1d8d")
+                .unwrap();
+        let justice_data = JusticeData {
+            revocation_key: hex_utils::hex_str(&[1; 32]),
+            delayed_payment_key: String::from("key"),
+            to_self_delay: 144,
+            sweep_script: String::new(),
+        };
+
+        let blob = WatchtowerBlob::seal(&txid, &justice_data).unwrap();
+        assert_eq!(blob.hint, WatchtowerBlob::hint(&txid));
+        assert_ne!(blob.hint, WatchtowerBlob::hint(&other_txid));
+        // the hint doesn't give the commitment txid away
+        assert!(!txid.to_string().contains(&blob.hint));
+        assert_eq!(blob.open(&txid), Some(justice_data));
+        assert_eq!(blob.open(&other_txid), None);
+    }
+
+    #[test]
+    fn justice_tx_test() {
+        let revoked_commitment = INITIAL_COMMITMENT_NUMBER;
+        let info = monitor_info(1);
+        let revocation_base_key = secret_key(1);
+
+        // nothing to hand the tower until the counterparty revoked the commitment
+        assert!(JusticeData::new(&info, &revocation_base_key, revoked_commitment - 1).is_none());
+        let justice_data =
+            JusticeData::new(&info, &revocation_base_key, revoked_commitment).unwrap();
+
+        let revoked_tx = breach_tx(&info, revoked_commitment, 50_000);
+        let justice_tx = justice_data.build_justice_tx(&revoked_tx, 1000).unwrap();
+        assert_eq!(
+            justice_tx.input[0].previous_output,
+            OutPoint {
+                txid: revoked_tx.txid(),
+                vout: 1
+            }
+        );
+        assert_eq!(justice_tx.output.len(), 1);
+        assert_eq!(justice_tx.output[0].script_pubkey, info.destination_script);
+        let mut unsigned_tx = justice_tx.clone();
+        unsigned_tx.input[0].witness = Witness::new();
+        let estimated_weight = unsigned_tx.weight() + REVOKED_OUTPUT_WITNESS_WEIGHT;
+        assert!(estimated_weight + 2 >= justice_tx.weight());
+        assert_eq!(justice_tx.output[0].value, 50_000 - estimated_weight as u64);
+
+        // the signature commits to the breached output and the revocation key
+        let secp_ctx = Secp256k1::new();
+        let witness = justice_tx.input[0].witness.to_vec();
+        let redeemscript = Script::from(witness[2].clone());
+        let sighash = SighashCache::new(&justice_tx)
+            .segwit_signature_hash(0, &redeemscript, 50_000, EcdsaSighashType::All)
+            .unwrap();
+        let signature =
+            bitcoin::secp256k1::ecdsa::Signature::from_der(&witness[0][..witness[0].len() - 1])
+                .unwrap();
+        let revocation_key =
+            SecretKey::from_slice(&hex_utils::to_vec(&justice_data.revocation_key).unwrap())
+                .unwrap();
+        assert!(secp_ctx
+            .verify_ecdsa(
+                &Message::from_slice(&sighash[..]).unwrap(),
+                &signature,
+                &PublicKey::from_secret_key(&secp_ctx, &revocation_key),
+            )
+            .is_ok());
+
+        // a different commitment or an output too small to pay for its sweep
+        let other_breach_tx = breach_tx(&info, revoked_commitment - 1, 50_000);
+        assert!(justice_data
+            .build_justice_tx(&other_breach_tx, 1000)
+            .is_none());
+        let dust_breach_tx = breach_tx(&info, revoked_commitment, 600);
+        assert!(justice_data
+            .build_justice_tx(&dust_breach_tx, 1000)
+            .is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn monitor_update_queue_test() {
+        let database = Arc::new(crate::database::test::test_database().await);
+        let queue = MonitorUpdateQueue::new(database, String::from("node"));
+
+        // a monitor written in a layout we don't understand is never queued
+        queue.push("monitor", vec![2, 1, 0, 0]);
+        assert!(queue.take().is_empty());
+    }
+}
@@ -1,18 +1,26 @@
 #[cfg(test)]
 mod test {
-    use bitcoin::{Address, Amount, Network};
+    use bitcoin::hashes::Hash;
+    use bitcoin::{Address, Amount, BlockHash, Network, Txid};
     use bitcoincore_rpc::RpcApi;
     use bitcoind::BitcoinD;
-    use entity::sea_orm::{ConnectOptions, Database};
+    use entity::sea_orm::{ActiveModelTrait, ActiveValue, ConnectOptions, Database};
+    use entity::seconds_since_epoch;
     use futures::{future, Future};
+    use lightning::chain::channelmonitor::ChannelMonitor;
+    use lightning::chain::keysinterface::InMemorySigner;
+    use lightning::util::ser::ReadableArgs;
     use migration::{Migrator, MigratorTrait};
     use senseicore::events::SenseiEvent;
+    use senseicore::hex_utils;
+    use senseicore::jit_channels::JitChannelPolicy;
     use senseicore::node::{HTLCStatus, LightningNode};
     use senseicore::services::node::{Channel, OpenChannelRequest};
-    use senseicore::services::{PaginationRequest, PaymentsFilter};
+    use senseicore::services::{ForwardedPaymentsFilter, PaginationRequest, PaymentsFilter};
+    use senseicore::watchtower::WatchtowerBlob;
     use serial_test::serial;
     use std::sync::atomic::{AtomicBool, Ordering};
-    use std::{str::FromStr, sync::Arc, time::Duration};
+    use std::{io::Cursor, str::FromStr, sync::Arc, time::Duration};
     use tokio::runtime::{Builder, Handle};
     use tokio::sync::broadcast;
 
@@ -166,6 +174,8 @@ mod test {
         from.call(NodeRequest::CloseChannel {
             channel_id: channel.channel_id.clone(),
             force,
+            destination_address: None,
+            target_feerate_sats_per_vbyte: None,
         })
         .await
         .unwrap();
@@ -296,6 +306,7 @@ mod test {
                 amount_sats: amt_sat,
                 public: true,
                 scid_alias: None,
+                upfront_shutdown_script: None,
                 custom_id: None,
                 push_amount_msats: None,
                 forwarding_fee_proportional_millionths: None,
@@ -402,6 +413,16 @@ mod test {
         from: Arc<LightningNode>,
         to: Arc<LightningNode>,
         amt_sat: u64,
+    ) -> Channel {
+        open_channel_with_shutdown_policy(bitcoind, from, to, amt_sat, None).await
+    }
+
+    async fn open_channel_with_shutdown_policy(
+        bitcoind: &BitcoinD,
+        from: Arc<LightningNode>,
+        to: Arc<LightningNode>,
+        amt_sat: u64,
+        upfront_shutdown_script: Option<bool>,
     ) -> Channel {
         let miner_address = bitcoind.client.get_new_address(None, None).unwrap();
         let mut event_receiver = from.event_sender.subscribe();
@@ -417,6 +438,7 @@ mod test {
                 amount_sats: amt_sat,
                 public: true,
                 scid_alias: None,
+                upfront_shutdown_script,
                 custom_id: None,
                 push_amount_msats: None,
                 forwarding_fee_proportional_millionths: None,
@@ -500,7 +522,8 @@ mod test {
 
         match node
             .call(NodeRequest::GetPhantomInvoice {
-                amt_msat: amt_sat * 1000,
+                amt_msat: Some(amt_sat * 1000),
+                min_amt_msat: None,
                 description: String::from("test"),
                 phantom_route_hints_hex,
             })
@@ -516,8 +539,13 @@ mod test {
     async fn create_invoice(node: Arc<LightningNode>, amt_sat: u64) -> String {
         match node
             .call(NodeRequest::GetInvoice {
-                amt_msat: amt_sat * 1000,
+                amt_msat: Some(amt_sat * 1000),
+                min_amt_msat: None,
                 description: String::from("test"),
+                description_hash: None,
+                expiry_secs: None,
+                min_final_cltv_expiry: None,
+                private_route_hints: None,
             })
             .await
             .unwrap()
@@ -544,9 +572,29 @@ mod test {
     }
 
     async fn pay_invoice(node: Arc<LightningNode>, invoice: String) {
-        node.call(NodeRequest::SendPayment { invoice })
-            .await
-            .unwrap();
+        node.call(NodeRequest::SendPayment {
+            invoice,
+            idempotency_key: None,
+        })
+        .await
+        .unwrap();
+    }
+
+    fn invoice_payment_hash(node: Arc<LightningNode>, invoice: &str) -> String {
+        let invoice = node.get_invoice_from_str(invoice).unwrap();
+        hex_utils::hex_str(&(*invoice.payment_hash()).into_inner())
+    }
+
+    fn has_payment_status(
+        node: Arc<LightningNode>,
+        payment_hash: &str,
+        status: HTLCStatus,
+    ) -> bool {
+        node.database
+            .find_payment_sync(node.id.clone(), payment_hash.to_string())
+            .unwrap()
+            .map(|payment| payment.status == status.to_string())
+            .unwrap_or(false)
     }
 
     fn within_range(actual: f64, expected: f64, pct_err: f64) -> bool {
@@ -589,12 +637,14 @@ mod test {
         sensei_dir: &str,
         bitcoind: &BitcoinD,
         persistence_handle: Handle,
+        configure: fn(&mut SenseiConfig),
     ) -> AdminService {
         let (event_sender, _event_receiver): (
             broadcast::Sender<SenseiEvent>,
             broadcast::Receiver<SenseiEvent>,
         ) = broadcast::channel(256);
-        let config = setup_test_environment(&bitcoind, sensei_dir);
+        let mut config = setup_test_environment(&bitcoind, sensei_dir);
+        configure(&mut config);
 
         let mut db_connection_options = ConnectOptions::new(config.database_url.clone());
         db_connection_options
@@ -647,6 +697,17 @@ mod test {
     }
 
     fn run_test<F>(name: &str, test: fn(BitcoinD, AdminService) -> F) -> F::Output
+    where
+        F: Future,
+    {
+        run_test_with_config(name, |_config| {}, test)
+    }
+
+    fn run_test_with_config<F>(
+        name: &str,
+        configure: fn(&mut SenseiConfig),
+        test: fn(BitcoinD, AdminService) -> F,
+    ) -> F::Output
     where
         F: Future,
     {
@@ -668,8 +729,13 @@ mod test {
             .block_on(async move {
                 let sensei_dir = format!("./.sensei-tests/{}", name);
                 let bitcoind = setup_bitcoind();
-                let admin_service =
-                    setup_sensei(&sensei_dir, &bitcoind, persistence_runtime_handle).await;
+                let admin_service = setup_sensei(
+                    &sensei_dir,
+                    &bitcoind,
+                    persistence_runtime_handle,
+                    configure,
+                )
+                .await;
                 let output = test(bitcoind, admin_service.clone()).await;
                 admin_service.stop_signal.store(true, Ordering::Relaxed);
                 admin_service.stop().await.unwrap();
@@ -732,6 +798,275 @@ mod test {
         assert!(bob_balance > bob_start_balance);
     }
 
+    async fn jit_channel_test(bitcoind: BitcoinD, admin_service: AdminService) {
+        let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
+        let (hub, ..) = create_node(&admin_service, "hub", "hub", true, None, None).await;
+        let (client, ..) = create_node(&admin_service, "client", "client", true, None, None).await;
+        let (payer, ..) = create_node(&admin_service, "payer", "payer", true, None, None).await;
+
+        fund_node(&bitcoind, hub.clone()).await;
+        fund_node(&bitcoind, payer.clone()).await;
+        let _payer_hub_channel =
+            open_channel(&bitcoind, payer.clone(), hub.clone(), 1_000_000).await;
+
+        hub.call(NodeRequest::SetJitChannelPolicy {
+            policy: JitChannelPolicy {
+                enabled: true,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap();
+
+        // the client trusts the hub with 0-conf channels and has no channels of its own
+        client
+            .call(NodeRequest::AddKnownPeer {
+                pubkey: hub.get_pubkey(),
+                label: String::from("hub"),
+                zero_conf: true,
+            })
+            .await
+            .unwrap();
+        client
+            .call(NodeRequest::ConnectPeer {
+                node_connection_string: format!(
+                    "{}@{}:{}",
+                    hub.get_pubkey(),
+                    hub.listen_addresses.first().unwrap(),
+                    hub.listen_port
+                ),
+            })
+            .await
+            .unwrap();
+
+        let client_invoice = create_invoice(client.clone(), 50_000).await;
+        let jit_invoice = match hub
+            .call(NodeRequest::CreateJitInvoice {
+                invoice: client_invoice,
+            })
+            .await
+            .unwrap()
+        {
+            NodeResponse::CreateJitInvoice { invoice, .. } => Some(invoice),
+            _ => None,
+        }
+        .unwrap();
+
+        pay_invoice(payer.clone(), jit_invoice).await;
+
+        let payer_test = payer.clone();
+        let has_payments = move || {
+            let pagination = PaginationRequest {
+                page: 0,
+                take: 1,
+                query: None,
+            };
+            let filter = PaymentsFilter {
+                status: Some(HTLCStatus::Succeeded.to_string()),
+                origin: None,
+            };
+            let (_payments, pagination) = payer_test
+                .database
+                .list_payments_sync(payer_test.id.clone(), pagination, filter)
+                .unwrap();
+            pagination.total == 1 as u64
+        };
+
+        assert!(wait_until(has_payments, 90000, 500).await);
+        assert!(get_channel_balance_sats(client.clone()).await >= 50_000);
+    }
+
+    async fn watchtower_test(bitcoind: BitcoinD, admin_service: AdminService) {
+        let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
+        let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
+        let (bob, ..) = create_node(&admin_service, "bob", "bob", true, None, None).await;
+        let mut event_receiver = admin_service.event_sender.subscribe();
+
+        fund_node(&bitcoind, alice.clone()).await;
+        let channel = open_channel(&bitcoind, alice.clone(), bob.clone(), 1_000_000).await;
+        let funding_txid = Txid::from_str(&channel.funding_txid.unwrap()).unwrap();
+
+        // alice keeps a copy of her channel state from before she paid bob
+        let (_, stale_monitor_bytes) = alice
+            .persister
+            .read_channelmonitor_bytes()
+            .unwrap()
+            .into_iter()
+            .find(|(key, _)| key.contains(&funding_txid.to_string()))
+            .unwrap();
+
+        let invoice = create_invoice(bob.clone(), 200_000).await;
+        pay_invoice(alice.clone(), invoice).await;
+        let bob_test = bob.clone();
+        let bob_has_balance = move || {
+            bob_test
+                .list_channels(PaginationRequest {
+                    page: 0,
+                    take: 1,
+                    query: None,
+                })
+                .unwrap()
+                .0[0]
+                .balance_msat
+                >= 200_000_000
+        };
+        assert!(wait_until(bob_has_balance, 60000, 500).await);
+
+        let (_, stale_monitor) = <(BlockHash, ChannelMonitor<InMemorySigner>)>::read(
+            &mut Cursor::new(&stale_monitor_bytes),
+            &*alice.keys_manager,
+        )
+        .unwrap();
+        let breach_tx = stale_monitor
+            .get_latest_holder_commitment_txn(&alice.logger)
+            .remove(0);
+
+        // bob hands the tower what it needs to punish that state, then goes offline
+        let database = admin_service.database.clone();
+        let blob_key = format!(
+            "blobs/{}/{}",
+            WatchtowerBlob::hint(&breach_tx.txid()),
+            bob.get_pubkey()
+        );
+        let has_blob = move || {
+            database
+                .get_value_sync(String::from("watchtower"), blob_key.clone())
+                .unwrap()
+                .is_some()
+        };
+        assert!(wait_until(has_blob, 30000, 500).await);
+        admin_service
+            .call(AdminRequest::StopNode {
+                pubkey: bob.get_pubkey(),
+            })
+            .await
+            .unwrap();
+
+        let miner_address = bitcoind.client.get_new_address(None, None).unwrap();
+        bitcoind.client.send_raw_transaction(&breach_tx).unwrap();
+        bitcoind
+            .client
+            .generate_to_address(1, &miner_address)
+            .unwrap();
+
+        let breach_txid = breach_tx.txid();
+        let event = wait_for_event(
+            &mut event_receiver,
+            move |event| match event {
+                SenseiEvent::WatchtowerJusticeBroadcast {
+                    breach_txid: txid, ..
+                } => txid == breach_txid,
+                _ => false,
+            },
+            30000,
+            250,
+        )
+        .await;
+        let justice_txid = match event {
+            Some(SenseiEvent::WatchtowerJusticeBroadcast { justice_txid, .. }) => justice_txid,
+            _ => panic!("the watchtower never punished the breach"),
+        };
+        let justice_in_mempool = move || bitcoind.client.get_mempool_entry(&justice_txid).is_ok();
+        assert!(wait_until(justice_in_mempool, 15000, 250).await);
+    }
+
+    async fn channel_backup_recovery_test(bitcoind: BitcoinD, admin_service: AdminService) {
+        let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
+        let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
+        let (bob, ..) = create_node(&admin_service, "bob", "bob", true, None, None).await;
+        fund_node(&bitcoind, alice.clone()).await;
+        let _channel = open_channel(&bitcoind, alice.clone(), bob.clone(), 1_000_000).await;
+
+        let invoice = create_invoice(bob.clone(), 200_000).await;
+        pay_invoice(alice.clone(), invoice).await;
+        let bob_test = bob.clone();
+        let bob_has_balance = move || {
+            bob_test
+                .list_channels(PaginationRequest {
+                    page: 0,
+                    take: 1,
+                    query: None,
+                })
+                .unwrap()
+                .0[0]
+                .balance_msat
+                >= 200_000_000
+        };
+        assert!(wait_until(bob_has_balance, 60000, 500).await);
+
+        let backup = match bob.call(NodeRequest::ExportChannelBackup {}).await.unwrap() {
+            NodeResponse::ExportChannelBackup { backup } => Some(backup),
+            _ => None,
+        }
+        .unwrap();
+
+        // bob loses his channel state but keeps his seed
+        admin_service
+            .call(AdminRequest::StopNode {
+                pubkey: bob.get_pubkey(),
+            })
+            .await
+            .unwrap();
+        for key in admin_service
+            .database
+            .list_keys(bob.id.clone(), "monitors/")
+            .await
+            .unwrap()
+            .into_iter()
+            .chain(std::iter::once(String::from("manager")))
+        {
+            admin_service
+                .database
+                .delete_value(bob.id.clone(), key)
+                .await
+                .unwrap();
+        }
+        admin_service
+            .call(AdminRequest::StartNode {
+                pubkey: bob.get_pubkey(),
+                passphrase: String::from("bob"),
+            })
+            .await
+            .unwrap();
+        let bob = {
+            let directory = admin_service.node_directory.lock().await;
+            let handle = directory.get(&bob.get_pubkey()).unwrap();
+            handle.as_ref().unwrap().node.clone()
+        };
+        assert_eq!(get_onchain_balance_sats(bob.clone()).await, 0);
+
+        let channels = match bob
+            .call(NodeRequest::RecoverFromBackup { backup })
+            .await
+            .unwrap()
+        {
+            NodeResponse::RecoverFromBackup { channels } => Some(channels),
+            _ => None,
+        }
+        .unwrap();
+        assert_eq!(channels.len(), 1);
+        assert!(channels[0].connected);
+        assert_eq!(bob.recovered_outputs.watched_count(), 1);
+
+        // alice force-closes and her commitment pays bob's balance straight to him
+        let miner_address = bitcoind.client.get_new_address(None, None).unwrap();
+        let mut bob_balance = 0;
+        for _ in 0..60 {
+            bitcoind
+                .client
+                .generate_to_address(1, &miner_address)
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            bob_balance = get_onchain_balance_sats(bob.clone()).await;
+            if bob_balance > 0 {
+                break;
+            }
+        }
+
+        assert_eq!(bob.recovered_outputs.watched_count(), 0);
+        assert!(within_range(bob_balance as f64, 200_000.0, 0.01));
+    }
+
     async fn smoke_test(bitcoind: BitcoinD, admin_service: AdminService) {
         let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
         let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
@@ -778,6 +1113,37 @@ mod test {
 
         assert!(wait_until(has_payments, 60000, 500).await);
 
+        let bob_test = bob.clone();
+        let has_forwarded_payments = move || {
+            let forwarded_payments = bob_test
+                .database
+                .find_forwarded_payments_sync(
+                    bob_test.id.clone(),
+                    ForwardedPaymentsFilter::default(),
+                )
+                .unwrap();
+            forwarded_payments.len() == num_invoices
+        };
+
+        assert!(wait_until(has_forwarded_payments, 60000, 500).await);
+
+        let revenue = match bob
+            .call(NodeRequest::GetRoutingRevenue {
+                filter: ForwardedPaymentsFilter {
+                    to_channel_id: Some(bob_charlie_channel.channel_id.clone()),
+                    ..Default::default()
+                },
+            })
+            .await
+            .unwrap()
+        {
+            NodeResponse::GetRoutingRevenue { revenue } => Some(revenue),
+            _ => None,
+        }
+        .unwrap();
+        assert_eq!(revenue.num_forwards, num_invoices as u64);
+        assert!(revenue.fees_earned_msat > 0);
+
         close_channel(
             &bitcoind,
             alice.clone(),
@@ -841,6 +1207,69 @@ mod test {
         ));
     }
 
+    async fn invoice_test(bitcoind: BitcoinD, admin_service: AdminService) {
+        let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
+        let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
+        let (bob, ..) = create_node(&admin_service, "bob", "bob", true, None, None).await;
+        fund_node(&bitcoind, alice.clone()).await;
+        let _alice_bob_channel =
+            open_channel(&bitcoind, alice.clone(), bob.clone(), 1_000_000).await;
+
+        // paying a cancelled invoice is failed back by bob
+        let cancelled_invoice = create_invoice(bob.clone(), 5000).await;
+        let cancelled_payment_hash = invoice_payment_hash(bob.clone(), &cancelled_invoice);
+        bob.call(NodeRequest::CancelInvoice {
+            payment_hash: cancelled_payment_hash.clone(),
+        })
+        .await
+        .unwrap();
+        pay_invoice(alice.clone(), cancelled_invoice).await;
+        let alice_test = alice.clone();
+        let payment_hash = cancelled_payment_hash.clone();
+        let payment_failed =
+            move || has_payment_status(alice_test.clone(), &payment_hash, HTLCStatus::Failed);
+        assert!(wait_until(payment_failed, 60000, 500).await);
+        assert!(has_payment_status(
+            bob.clone(),
+            &cancelled_payment_hash,
+            HTLCStatus::Cancelled
+        ));
+
+        // so is paying an invoice that bob considers expired
+        let expired_invoice = create_invoice(bob.clone(), 5000).await;
+        let expired_payment_hash = invoice_payment_hash(bob.clone(), &expired_invoice);
+        let payment = bob
+            .database
+            .find_invoice(bob.id.clone(), expired_payment_hash.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut payment: entity::payment::ActiveModel = payment.into();
+        payment.expires_at = ActiveValue::Set(Some(seconds_since_epoch() - 1));
+        payment.update(bob.database.get_connection()).await.unwrap();
+        pay_invoice(alice.clone(), expired_invoice).await;
+        let alice_test = alice.clone();
+        let payment_hash = expired_payment_hash.clone();
+        let payment_failed =
+            move || has_payment_status(alice_test.clone(), &payment_hash, HTLCStatus::Failed);
+        assert!(wait_until(payment_failed, 60000, 500).await);
+        assert!(has_payment_status(
+            bob.clone(),
+            &expired_payment_hash,
+            HTLCStatus::Pending
+        ));
+
+        // while an open invoice still gets paid over the same channel
+        let open_invoice = create_invoice(bob.clone(), 5000).await;
+        let open_payment_hash = invoice_payment_hash(bob.clone(), &open_invoice);
+        pay_invoice(alice.clone(), open_invoice).await;
+        let alice_test = alice.clone();
+        let payment_hash = open_payment_hash.clone();
+        let payment_succeeded =
+            move || has_payment_status(alice_test.clone(), &payment_hash, HTLCStatus::Succeeded);
+        assert!(wait_until(payment_succeeded, 60000, 500).await);
+    }
+
     async fn batch_open_channels_test(bitcoind: BitcoinD, admin_service: AdminService) {
         let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
         let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
@@ -903,6 +1332,86 @@ mod test {
         }
     }
 
+    async fn close_to_address_test(bitcoind: BitcoinD, admin_service: AdminService) {
+        let _admin_token = create_admin_account(&admin_service, "admin", "admin").await;
+        let (alice, ..) = create_node(&admin_service, "alice", "alice", true, None, None).await;
+        let (bob, ..) = create_node(&admin_service, "bob", "bob", true, None, None).await;
+        let (charlie, ..) =
+            create_node(&admin_service, "charlie", "charlie", true, None, None).await;
+        fund_node(&bitcoind, alice.clone()).await;
+
+        let committed_channel =
+            open_channel(&bitcoind, alice.clone(), charlie.clone(), 500_000).await;
+        let uncommitted_channel = open_channel_with_shutdown_policy(
+            &bitcoind,
+            alice.clone(),
+            bob.clone(),
+            1_000_000,
+            Some(false),
+        )
+        .await;
+
+        let miner_address = bitcoind.client.get_new_address(None, None).unwrap();
+        let destination_address = bitcoind.client.get_new_address(None, None).unwrap();
+
+        // a channel that committed to alice's shutdown script can only close to it
+        let destination_used = match alice
+            .call(NodeRequest::CloseChannel {
+                channel_id: committed_channel.channel_id.clone(),
+                force: false,
+                destination_address: Some(destination_address.to_string()),
+                target_feerate_sats_per_vbyte: None,
+            })
+            .await
+            .unwrap()
+        {
+            NodeResponse::CloseChannel { destination_used } => Some(destination_used),
+            _ => None,
+        }
+        .unwrap();
+        assert!(!destination_used);
+
+        let destination_used = match alice
+            .call(NodeRequest::CloseChannel {
+                channel_id: uncommitted_channel.channel_id.clone(),
+                force: false,
+                destination_address: Some(destination_address.to_string()),
+                target_feerate_sats_per_vbyte: None,
+            })
+            .await
+            .unwrap()
+        {
+            NodeResponse::CloseChannel { destination_used } => Some(destination_used),
+            _ => None,
+        }
+        .unwrap();
+        assert!(destination_used);
+
+        let mut received_sats = 0;
+        for _ in 0..60 {
+            bitcoind
+                .client
+                .generate_to_address(1, &miner_address)
+                .unwrap();
+            received_sats = bitcoind
+                .client
+                .get_received_by_address(&destination_address, Some(1))
+                .unwrap()
+                .as_sat();
+            if received_sats > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        // everything but the closing fee goes to the external address
+        assert!(within_range(
+            received_sats as f64,
+            uncommitted_channel.balance_msat as f64 / 1000.0,
+            0.01
+        ));
+    }
+
     #[test]
     #[serial]
     fn run_batch_open_channel_test() {
@@ -915,9 +1424,43 @@ mod test {
         run_test("smoke_test", smoke_test)
     }
 
+    #[test]
+    #[serial]
+    fn run_invoice_test() {
+        run_test("invoice", invoice_test)
+    }
+
     #[test]
     #[serial]
     fn run_phantom_payment_test() {
         run_test("phantom_payment", phantom_payment_test)
     }
+
+    #[test]
+    #[serial]
+    fn run_jit_channel_test() {
+        run_test("jit_channel", jit_channel_test)
+    }
+
+    #[test]
+    #[serial]
+    fn run_close_to_address_test() {
+        run_test("close_to_address", close_to_address_test)
+    }
+
+    #[test]
+    #[serial]
+    fn run_channel_backup_recovery_test() {
+        run_test("channel_backup_recovery", channel_backup_recovery_test)
+    }
+
+    #[test]
+    #[serial]
+    fn run_watchtower_test() {
+        run_test_with_config(
+            "watchtower",
+            |config| config.watchtower_server = true,
+            watchtower_test,
+        )
+    }
 }
@@ -19,11 +19,21 @@ use sensei::{admin_client::AdminClient, node_client::NodeClient};
 use tonic::{metadata::MetadataValue, transport::Channel, Request};
 
 use crate::sensei::{
+    AutopilotPolicy, CancelInvoiceRequest, ChannelAcceptancePolicy, ChannelsFilter,
     CloseChannelRequest, ConnectPeerRequest, CreateAdminRequest, CreateInvoiceRequest,
-    CreateNodeRequest, GetUnusedAddressRequest, InfoRequest, KeysendRequest, ListChannelsRequest,
-    ListNodesRequest, ListPaymentsRequest, ListPeersRequest, ListUnspentRequest,
-    NetworkGraphInfoRequest, OpenChannelRequest, OpenChannelsRequest, PayInvoiceRequest,
-    SignMessageRequest, StartNodeRequest,
+    CreateJitInvoiceRequest, CreateNodeRequest, DeleteScheduledPaymentRequest,
+    ExportChannelBackupRequest, ExportLedgerRequest, FeePolicy, GetAutopilotPolicyRequest,
+    GetBalanceHistoryRequest, GetChannelAcceptancePolicyRequest, GetClaimableBalancesRequest,
+    GetFeePolicyRequest, GetHealthPolicyRequest, GetHealthReportRequest,
+    GetJitChannelPolicyRequest, GetPaymentAttemptsRequest, GetRoutingRevenueRequest,
+    GetUnusedAddressRequest, HealthPolicy, InfoRequest, JitChannelPolicy, KeysendRequest,
+    ListChannelsRequest, ListForwardedPaymentsRequest, ListHtlcsRequest, ListInvoicesRequest,
+    ListNodesRequest, ListPaymentsRequest, ListPeersRequest, ListScheduledPaymentsRequest,
+    ListUnspentRequest, NetworkGraphInfoRequest, OpenChannelRequest, OpenChannelsRequest,
+    PayInvoiceRequest, RebalanceRequest, RecoverFromBackupRequest, RestrictMacaroonRequest,
+    RunAutopilotRequest, SetAutopilotPolicyRequest, SetChannelAcceptancePolicyRequest,
+    SetFeePolicyRequest, SetHealthPolicyRequest, SetJitChannelPolicyRequest, SignMessageRequest,
+    StartNodeRequest, UpdateChannelConfigRequest,
 };
 
 pub mod sensei {
@@ -88,15 +98,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .subcommand(Command::new("startnode").about("start a child lightning node"))
         .subcommand(Command::new("getbalance").about("gets wallet's balance"))
+        .subcommand(
+            Command::new("getclaimablebalances")
+                .about("see funds in closing channels that aren't back in the wallet yet"),
+        )
+        .subcommand(
+            Command::new("balancehistory")
+                .about("see how your balances have changed over time")
+                .arg(
+                    Arg::new("from")
+                        .takes_value(true)
+                        .long("from")
+                        .help("only include snapshots at or after this unix timestamp"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .takes_value(true)
+                        .long("to")
+                        .help("only include snapshots at or before this unix timestamp"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .takes_value(true)
+                        .long("interval")
+                        .help("seconds between points in the returned history"),
+                ),
+        )
         .subcommand(Command::new("getaddress").about("get wallet's next unused address"))
         .subcommand(
             Command::new("createinvoice")
                 .about("create an invoice for an amount in msats")
                 .arg(
                     Arg::new("amt_msat")
-                        .required(true)
                         .index(1)
-                        .help("amount in msats"),
+                        .help("amount in msats, leave out to let the payer choose"),
                 ),
         )
         .subcommand(
@@ -141,6 +176,348 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .possible_values(&["true", "false"])
                         .required(true)
                         .help("force close this channel?"),
+                )
+                .arg(
+                    Arg::new("destination_address")
+                        .takes_value(true)
+                        .long("destination-address")
+                        .help("address to send your funds to on a cooperative close"),
+                )
+                .arg(
+                    Arg::new("fee_rate")
+                        .takes_value(true)
+                        .long("fee-rate")
+                        .help("target fee rate in sats/vbyte for a cooperative close"),
+                ),
+        )
+        .subcommand(
+            Command::new("updatechannelconfig")
+                .about("update the forwarding policy of your channels")
+                .arg(
+                    Arg::new("channel_id")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .long("channel-id")
+                        .help("the id of a channel to update"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("update all of your channels"),
+                )
+                .arg(
+                    Arg::new("fee_base_msat")
+                        .takes_value(true)
+                        .long("fee-base-msat")
+                        .help("base fee charged for forwarding a payment"),
+                )
+                .arg(
+                    Arg::new("fee_proportional_millionths")
+                        .takes_value(true)
+                        .long("fee-proportional-millionths")
+                        .help("fee charged per million msats forwarded"),
+                )
+                .arg(
+                    Arg::new("cltv_expiry_delta")
+                        .takes_value(true)
+                        .long("cltv-expiry-delta")
+                        .help("blocks required between incoming and outgoing htlc expiry"),
+                )
+                .arg(
+                    Arg::new("max_dust_htlc_exposure_msat")
+                        .takes_value(true)
+                        .long("max-dust-htlc-exposure-msat")
+                        .help("most that can be held in dust htlcs"),
+                ),
+        )
+        .subcommand(
+            Command::new("getchannelacceptancepolicy")
+                .about("see which inbound channels are accepted"),
+        )
+        .subcommand(
+            Command::new("setchannelacceptancepolicy")
+                .about("choose which inbound channels are accepted")
+                .arg(
+                    Arg::new("min_channel_size_sats")
+                        .takes_value(true)
+                        .long("min-channel-size-sats")
+                        .help("smallest inbound channel to accept"),
+                )
+                .arg(
+                    Arg::new("max_channel_size_sats")
+                        .takes_value(true)
+                        .long("max-channel-size-sats")
+                        .help("largest inbound channel to accept"),
+                )
+                .arg(
+                    Arg::new("min_confirmations")
+                        .takes_value(true)
+                        .long("min-confirmations")
+                        .help(
+                            "confirmations required before a channel is usable, fixed while the node runs",
+                        ),
+                )
+                .arg(
+                    Arg::new("allow_peer")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .long("allow-peer")
+                        .help("only accept channels from this peer"),
+                )
+                .arg(
+                    Arg::new("deny_peer")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .long("deny-peer")
+                        .help("never accept channels from this peer"),
+                )
+                .arg(
+                    Arg::new("max_pending_channels")
+                        .takes_value(true)
+                        .long("max-pending-channels")
+                        .help("most inbound channels waiting to confirm at once"),
+                )
+                .arg(
+                    Arg::new("allow_private_channels")
+                        .takes_value(true)
+                        .long("allow-private-channels")
+                        .possible_values(["true", "false"])
+                        .default_value("true")
+                        .help("accept channels that won't be announced?"),
+                )
+                .arg(
+                    Arg::new("webhook_url")
+                        .takes_value(true)
+                        .long("webhook-url")
+                        .help("url asked to accept or reject channels that pass the policy"),
+                )
+                .arg(
+                    Arg::new("webhook_timeout_secs")
+                        .takes_value(true)
+                        .long("webhook-timeout-secs")
+                        .help("how long to wait for the webhook to answer"),
+                )
+                .arg(
+                    Arg::new("webhook_fallback")
+                        .takes_value(true)
+                        .long("webhook-fallback")
+                        .possible_values(["accept", "accept_zero_conf", "reject"])
+                        .default_value("reject")
+                        .help("decision used when the webhook fails or times out"),
+                ),
+        )
+        .subcommand(Command::new("getfeepolicy").about("see how fees are managed automatically"))
+        .subcommand(
+            Command::new("setfeepolicy")
+                .about("adjust channel fees automatically based on liquidity")
+                .arg(
+                    Arg::new("enabled")
+                        .takes_value(true)
+                        .long("enabled")
+                        .possible_values(&["true", "false"])
+                        .required(true)
+                        .help("whether fees should be managed automatically"),
+                )
+                .arg(
+                    Arg::new("min_fee_proportional_millionths")
+                        .takes_value(true)
+                        .long("min-fee-proportional-millionths")
+                        .required(true)
+                        .help("lowest fee to charge per million msats forwarded"),
+                )
+                .arg(
+                    Arg::new("max_fee_proportional_millionths")
+                        .takes_value(true)
+                        .long("max-fee-proportional-millionths")
+                        .required(true)
+                        .help("highest fee to charge per million msats forwarded"),
+                )
+                .arg(
+                    Arg::new("fee_base_msat")
+                        .takes_value(true)
+                        .long("fee-base-msat")
+                        .help("base fee to set on every channel"),
+                )
+                .arg(
+                    Arg::new("interval_secs")
+                        .takes_value(true)
+                        .long("interval-secs")
+                        .default_value("3600")
+                        .help("seconds between fee adjustments"),
+                ),
+        )
+        .subcommand(Command::new("getautopilotpolicy").about("show the autopilot policy"))
+        .subcommand(
+            Command::new("setautopilotpolicy")
+                .about("open channels to well connected peers automatically")
+                .arg(
+                    Arg::new("enabled")
+                        .takes_value(true)
+                        .long("enabled")
+                        .possible_values(["true", "false"])
+                        .required(true)
+                        .help("whether the autopilot should run on its own"),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .takes_value(true)
+                        .long("dry-run")
+                        .possible_values(["true", "false"])
+                        .default_value("false")
+                        .help("only report the channels it would open"),
+                )
+                .arg(
+                    Arg::new("target_channels")
+                        .takes_value(true)
+                        .long("target-channels")
+                        .default_value("5")
+                        .help("number of channels to keep open"),
+                )
+                .arg(
+                    Arg::new("allocation_percent")
+                        .takes_value(true)
+                        .long("allocation-percent")
+                        .default_value("60")
+                        .help("share of on-chain funds to put into channels each run"),
+                )
+                .arg(
+                    Arg::new("min_channel_size_sats")
+                        .takes_value(true)
+                        .long("min-channel-size-sats")
+                        .default_value("100000")
+                        .help("smallest channel to open"),
+                )
+                .arg(
+                    Arg::new("max_channel_size_sats")
+                        .takes_value(true)
+                        .long("max-channel-size-sats")
+                        .default_value("5000000")
+                        .help("largest channel to open"),
+                )
+                .arg(
+                    Arg::new("max_channels_per_run")
+                        .takes_value(true)
+                        .long("max-channels-per-run")
+                        .default_value("3")
+                        .help("most channels to open at once"),
+                )
+                .arg(
+                    Arg::new("min_peer_channels")
+                        .takes_value(true)
+                        .long("min-peer-channels")
+                        .default_value("5")
+                        .help("channels a peer needs before it is considered"),
+                )
+                .arg(
+                    Arg::new("public_channels")
+                        .takes_value(true)
+                        .long("public-channels")
+                        .possible_values(["true", "false"])
+                        .default_value("true")
+                        .help("announce the channels it opens?"),
+                )
+                .arg(
+                    Arg::new("interval_secs")
+                        .takes_value(true)
+                        .long("interval-secs")
+                        .default_value("3600")
+                        .help("seconds between autopilot runs"),
+                ),
+        )
+        .subcommand(
+            Command::new("runautopilot")
+                .about("run the autopilot once now")
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .help("only report the channels it would open"),
+                ),
+        )
+        .subcommand(
+            Command::new("exportchannelbackup").about("export an encrypted static channel backup"),
+        )
+        .subcommand(
+            Command::new("recoverfrombackup")
+                .about("ask the peers in a channel backup to force-close our channels")
+                .arg(
+                    Arg::new("backup")
+                        .required(true)
+                        .index(1)
+                        .help("base64 encoded backup from exportchannelbackup"),
+                ),
+        )
+        .subcommand(Command::new("getjitchannelpolicy").about("show the jit channel policy"))
+        .subcommand(
+            Command::new("setjitchannelpolicy")
+                .about("open channels to clients when a payment for them arrives")
+                .arg(
+                    Arg::new("enabled")
+                        .takes_value(true)
+                        .long("enabled")
+                        .possible_values(["true", "false"])
+                        .required(true)
+                        .help("whether to create jit invoices for clients"),
+                )
+                .arg(
+                    Arg::new("opening_fee_base_msat")
+                        .takes_value(true)
+                        .long("opening-fee-base-msat")
+                        .default_value("2000000")
+                        .help("flat fee taken from each payment that opens a channel"),
+                )
+                .arg(
+                    Arg::new("opening_fee_proportional_millionths")
+                        .takes_value(true)
+                        .long("opening-fee-proportional-millionths")
+                        .default_value("10000")
+                        .help("fee taken from each payment that opens a channel, in millionths"),
+                )
+                .arg(
+                    Arg::new("min_channel_size_sats")
+                        .takes_value(true)
+                        .long("min-channel-size-sats")
+                        .default_value("100000")
+                        .help("smallest channel to open"),
+                )
+                .arg(
+                    Arg::new("max_channel_size_sats")
+                        .takes_value(true)
+                        .long("max-channel-size-sats")
+                        .default_value("5000000")
+                        .help("largest channel to open"),
+                ),
+        )
+        .subcommand(
+            Command::new("createjitinvoice")
+                .about("wrap a client's invoice so paying it opens a channel to them")
+                .arg(
+                    Arg::new("invoice")
+                        .required(true)
+                        .index(1)
+                        .help("the client's invoice"),
+                ),
+        )
+        .subcommand(
+            Command::new("gethealthreport")
+                .about("show how long channels have been unusable and peers offline"),
+        )
+        .subcommand(Command::new("gethealthpolicy").about("show the health alert thresholds"))
+        .subcommand(
+            Command::new("sethealthpolicy")
+                .about("set when unusable channels and offline peers raise alerts")
+                .arg(
+                    Arg::new("channel_unhealthy_after_secs")
+                        .takes_value(true)
+                        .long("channel-unhealthy-after-secs")
+                        .default_value("3600")
+                        .help("seconds a channel can be unusable before it is unhealthy"),
+                )
+                .arg(
+                    Arg::new("peer_offline_after_secs")
+                        .takes_value(true)
+                        .long("peer-offline-after-secs")
+                        .default_value("3600")
+                        .help("seconds a channel peer can be disconnected before it is offline"),
                 ),
         )
         .subcommand(
@@ -167,6 +544,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .help("amount of millisatoshis to pay"),
                 ),
         )
+        .subcommand(
+            Command::new("rebalance")
+                .about("move liquidity between two of your channels by paying yourself")
+                .arg(
+                    Arg::new("from_channel_id")
+                        .required(true)
+                        .index(1)
+                        .help("channel to move outbound liquidity out of"),
+                )
+                .arg(
+                    Arg::new("to_channel_id")
+                        .required(true)
+                        .index(2)
+                        .help("channel to move outbound liquidity into"),
+                )
+                .arg(
+                    Arg::new("amt_msat")
+                        .required(true)
+                        .index(3)
+                        .help("amount of millisatoshis to move"),
+                )
+                .arg(
+                    Arg::new("max_fee_msat")
+                        .required(true)
+                        .index(4)
+                        .help("most routing fees to pay in millisatoshis"),
+                ),
+        )
         .subcommand(
             Command::new("connectpeer")
                 .about("connect to a peer on the lightning network")
@@ -187,8 +592,116 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .help("the message to be signed"),
                 ),
         )
-        .subcommand(Command::new("listchannels").about("list channels"))
+        .subcommand(
+            Command::new("listchannels").about("list channels").arg(
+                Arg::new("state")
+                    .takes_value(true)
+                    .long("state")
+                    .possible_values(["pending_open", "ready", "closing", "closed"])
+                    .help("only list channels in this state"),
+            ),
+        )
+        .subcommand(
+            Command::new("listhtlcs")
+                .about("list the htlcs in flight in your channels")
+                .arg(
+                    Arg::new("channel_id")
+                        .takes_value(true)
+                        .long("channel-id")
+                        .help("only list htlcs in this channel"),
+                ),
+        )
         .subcommand(Command::new("listpayments").about("list payments"))
+        .subcommand(Command::new("listinvoices").about("list invoices you have created"))
+        .subcommand(
+            Command::new("cancelinvoice")
+                .about("cancel an unpaid invoice so payments to it are rejected")
+                .arg(
+                    Arg::new("payment_hash")
+                        .required(true)
+                        .index(1)
+                        .help("the payment hash of the invoice"),
+                ),
+        )
+        .subcommand(Command::new("listscheduledpayments").about("list scheduled payments"))
+        .subcommand(
+            Command::new("deletescheduledpayment")
+                .about("stop and remove a scheduled payment")
+                .arg(
+                    Arg::new("id")
+                        .required(true)
+                        .index(1)
+                        .help("the id of the scheduled payment"),
+                ),
+        )
+        .subcommand(
+            Command::new("paymentattempts")
+                .about("see the routes attempted for a payment and why they failed")
+                .arg(
+                    Arg::new("payment_hash")
+                        .required(true)
+                        .index(1)
+                        .help("the payment hash of the payment"),
+                ),
+        )
+        .subcommand(Command::new("listforwardedpayments").about("list forwarded payments"))
+        .subcommand(
+            Command::new("routingrevenue")
+                .about("see fees earned routing payments by channel and day"),
+        )
+        .subcommand(
+            Command::new("exportledger")
+                .about("export payments, forwards and on-chain activity as a ledger")
+                .arg(
+                    Arg::new("from")
+                        .takes_value(true)
+                        .long("from")
+                        .help("only include entries at or after this unix timestamp"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .takes_value(true)
+                        .long("to")
+                        .help("only include entries at or before this unix timestamp"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .takes_value(true)
+                        .long("format")
+                        .possible_values(["csv", "json"])
+                        .default_value("csv")
+                        .help("the format of the exported ledger"),
+                ),
+        )
+        .subcommand(
+            Command::new("restrictmacaroon")
+                .about("create a copy of your macaroon that can only spend within limits")
+                .arg(
+                    Arg::new("max_payment_msat")
+                        .takes_value(true)
+                        .long("max-payment-msat")
+                        .help("most that can be spent in a single payment"),
+                )
+                .arg(
+                    Arg::new("max_daily_msat")
+                        .takes_value(true)
+                        .long("max-daily-msat")
+                        .help("most that can be spent in a day"),
+                )
+                .arg(
+                    Arg::new("max_monthly_msat")
+                        .takes_value(true)
+                        .long("max-monthly-msat")
+                        .help("most that can be spent in 30 days"),
+                )
+                .arg(
+                    Arg::new("destination")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .long("destination")
+                        .help("pubkey that payments are allowed to go to"),
+                ),
+        )
         .subcommand(Command::new("listpeers").about("list peers"))
         .subcommand(Command::new("nodeinfo").about("see information about your node"))
         .get_matches();
@@ -286,31 +799,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let response = client.get_balance(request).await?;
                 println!("{:?}", response.into_inner());
             }
+            "getclaimablebalances" => {
+                let request = tonic::Request::new(GetClaimableBalancesRequest {});
+                let response = client.get_claimable_balances(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "balancehistory" => {
+                let args = command_args;
+
+                let request = tonic::Request::new(GetBalanceHistoryRequest {
+                    from: args
+                        .value_of("from")
+                        .map(|ts| ts.parse().expect("timestamp must be in unix seconds")),
+                    to: args
+                        .value_of("to")
+                        .map(|ts| ts.parse().expect("timestamp must be in unix seconds")),
+                    interval: args
+                        .value_of("interval")
+                        .map(|secs| secs.parse().expect("interval must be in seconds")),
+                });
+                let response = client.get_balance_history(request).await?;
+                println!("{:?}", response.into_inner());
+            }
             "getaddress" => {
                 let request = tonic::Request::new(GetUnusedAddressRequest {});
                 let response = client.get_unused_address(request).await?;
                 println!("{:?}", response.into_inner());
             }
             "createinvoice" => {
-                let amt_msat: Option<Result<u64, _>> = command_args
+                let amt_msat: Result<Option<u64>, _> = command_args
                     .value_of("amt_msat")
-                    .map(|str_amt| str_amt.parse());
-                match amt_msat {
-                    Some(amt_msat) => {
-                        if let Ok(amt_msat) = amt_msat {
-                            let request = tonic::Request::new(CreateInvoiceRequest {
-                                amt_msat,
-                                description: String::from(""),
-                            });
-                            let response = client.create_invoice(request).await?;
-                            println!("{:?}", response.into_inner());
-                        } else {
-                            println!("invalid amount, please specify in msats");
-                        }
-                    }
-                    None => {
-                        println!("amt_msat is required to create an invoice");
-                    }
+                    .map(|str_amt| str_amt.parse())
+                    .transpose();
+                if let Ok(amt_msat) = amt_msat {
+                    let request = tonic::Request::new(CreateInvoiceRequest {
+                        amt_msat,
+                        description: String::from(""),
+                        description_hash: None,
+                        expiry_secs: None,
+                        min_final_cltv_expiry: None,
+                        private_route_hints: None,
+                        min_amt_msat: None,
+                    });
+                    let response = client.create_invoice(request).await?;
+                    println!("{:?}", response.into_inner());
+                } else {
+                    println!("invalid amount, please specify in msats");
                 }
             }
             "openchannel" => {
@@ -340,6 +874,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         amount_sats: amt_satoshis,
                         public,
                         scid_alias: None,
+                        upfront_shutdown_script: None,
                         push_amount_msats: None,
                         custom_id: None,
                         counterparty_host_port: Some(host_and_port.to_string()),
@@ -354,6 +889,335 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let response = client.open_channels(request).await?;
                 println!("{:?}", response.into_inner());
             }
+            "exportledger" => {
+                let args = command_args;
+
+                let parse_timestamp = |name: &str| {
+                    args.value_of(name)
+                        .map(|ts| ts.parse().expect("timestamp must be in unix seconds"))
+                };
+
+                let request = tonic::Request::new(ExportLedgerRequest {
+                    from: parse_timestamp("from"),
+                    to: parse_timestamp("to"),
+                    format: args.value_of("format").unwrap().to_string(),
+                });
+
+                let response = client.export_ledger(request).await?;
+                print!("{}", response.into_inner().ledger);
+            }
+            "restrictmacaroon" => {
+                let args = command_args;
+
+                let parse_msat = |name: &str| {
+                    args.value_of(name)
+                        .map(|amt| amt.parse().expect("amount must be in millisatoshis"))
+                };
+
+                let request = tonic::Request::new(RestrictMacaroonRequest {
+                    macaroon: macaroon_hex_str.clone(),
+                    max_payment_msat: parse_msat("max_payment_msat"),
+                    max_daily_msat: parse_msat("max_daily_msat"),
+                    max_monthly_msat: parse_msat("max_monthly_msat"),
+                    allowed_destinations: args
+                        .values_of("destination")
+                        .map(|destinations| destinations.map(String::from).collect())
+                        .unwrap_or_default(),
+                });
+
+                let response = client.restrict_macaroon(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "updatechannelconfig" => {
+                let args = command_args;
+
+                let request = tonic::Request::new(UpdateChannelConfigRequest {
+                    channel_ids: args
+                        .values_of("channel_id")
+                        .map(|channel_ids| channel_ids.map(String::from).collect())
+                        .unwrap_or_default(),
+                    all: args.is_present("all"),
+                    forwarding_fee_base_msat: args
+                        .value_of("fee_base_msat")
+                        .map(|fee| fee.parse().expect("fee must be in millisatoshis")),
+                    forwarding_fee_proportional_millionths: args
+                        .value_of("fee_proportional_millionths")
+                        .map(|fee| fee.parse().expect("fee must be in millionths")),
+                    cltv_expiry_delta: args
+                        .value_of("cltv_expiry_delta")
+                        .map(|delta| delta.parse().expect("cltv expiry delta must be in blocks")),
+                    max_dust_htlc_exposure_msat: args
+                        .value_of("max_dust_htlc_exposure_msat")
+                        .map(|amt| amt.parse().expect("amount must be in millisatoshis")),
+                });
+
+                let response = client.update_channel_config(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "getchannelacceptancepolicy" => {
+                let request = tonic::Request::new(GetChannelAcceptancePolicyRequest {});
+                let response = client.get_channel_acceptance_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "setchannelacceptancepolicy" => {
+                let args = command_args;
+
+                let policy = ChannelAcceptancePolicy {
+                    min_channel_size_sats: args
+                        .value_of("min_channel_size_sats")
+                        .map(|size| size.parse().expect("size must be in sats")),
+                    max_channel_size_sats: args
+                        .value_of("max_channel_size_sats")
+                        .map(|size| size.parse().expect("size must be in sats")),
+                    min_confirmations: args
+                        .value_of("min_confirmations")
+                        .map(|confs| confs.parse().expect("confirmations must be a number")),
+                    allowed_peers: args
+                        .values_of("allow_peer")
+                        .map(|peers| peers.map(String::from).collect())
+                        .unwrap_or_default(),
+                    denied_peers: args
+                        .values_of("deny_peer")
+                        .map(|peers| peers.map(String::from).collect())
+                        .unwrap_or_default(),
+                    max_pending_channels: args
+                        .value_of("max_pending_channels")
+                        .map(|max| max.parse().expect("max pending channels must be a number")),
+                    allow_private_channels: args
+                        .value_of("allow_private_channels")
+                        .unwrap()
+                        .parse()
+                        .expect("allow private channels must be true or false"),
+                    webhook_url: args.value_of("webhook_url").map(String::from),
+                    webhook_timeout_secs: args
+                        .value_of("webhook_timeout_secs")
+                        .map(|secs| secs.parse().expect("timeout must be in seconds")),
+                    webhook_fallback: args.value_of("webhook_fallback").map(String::from),
+                };
+
+                let request = tonic::Request::new(SetChannelAcceptancePolicyRequest {
+                    policy: Some(policy),
+                });
+                let response = client.set_channel_acceptance_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "getfeepolicy" => {
+                let request = tonic::Request::new(GetFeePolicyRequest {});
+                let response = client.get_fee_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "setfeepolicy" => {
+                let args = command_args;
+
+                let policy = FeePolicy {
+                    enabled: args
+                        .value_of("enabled")
+                        .unwrap()
+                        .parse()
+                        .expect("enabled must be true or false"),
+                    min_fee_proportional_millionths: args
+                        .value_of("min_fee_proportional_millionths")
+                        .unwrap()
+                        .parse()
+                        .expect("fee must be in millionths"),
+                    max_fee_proportional_millionths: args
+                        .value_of("max_fee_proportional_millionths")
+                        .unwrap()
+                        .parse()
+                        .expect("fee must be in millionths"),
+                    forwarding_fee_base_msat: args
+                        .value_of("fee_base_msat")
+                        .map(|fee| fee.parse().expect("fee must be in millisatoshis")),
+                    interval_secs: args
+                        .value_of("interval_secs")
+                        .unwrap()
+                        .parse()
+                        .expect("interval must be in seconds"),
+                };
+
+                let request = tonic::Request::new(SetFeePolicyRequest {
+                    policy: Some(policy),
+                });
+                let response = client.set_fee_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "getautopilotpolicy" => {
+                let request = tonic::Request::new(GetAutopilotPolicyRequest {});
+                let response = client.get_autopilot_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "setautopilotpolicy" => {
+                let args = command_args;
+
+                let policy = AutopilotPolicy {
+                    enabled: args
+                        .value_of("enabled")
+                        .unwrap()
+                        .parse()
+                        .expect("enabled must be true or false"),
+                    dry_run: args
+                        .value_of("dry_run")
+                        .unwrap()
+                        .parse()
+                        .expect("dry run must be true or false"),
+                    target_channels: args
+                        .value_of("target_channels")
+                        .unwrap()
+                        .parse()
+                        .expect("target channels must be a number"),
+                    allocation_percent: args
+                        .value_of("allocation_percent")
+                        .unwrap()
+                        .parse()
+                        .expect("allocation must be a percentage"),
+                    min_channel_size_sats: args
+                        .value_of("min_channel_size_sats")
+                        .unwrap()
+                        .parse()
+                        .expect("size must be in sats"),
+                    max_channel_size_sats: args
+                        .value_of("max_channel_size_sats")
+                        .unwrap()
+                        .parse()
+                        .expect("size must be in sats"),
+                    max_channels_per_run: args
+                        .value_of("max_channels_per_run")
+                        .unwrap()
+                        .parse()
+                        .expect("max channels per run must be a number"),
+                    min_peer_channels: args
+                        .value_of("min_peer_channels")
+                        .unwrap()
+                        .parse()
+                        .expect("min peer channels must be a number"),
+                    public_channels: args
+                        .value_of("public_channels")
+                        .unwrap()
+                        .parse()
+                        .expect("public channels must be true or false"),
+                    interval_secs: args
+                        .value_of("interval_secs")
+                        .unwrap()
+                        .parse()
+                        .expect("interval must be in seconds"),
+                };
+
+                let request = tonic::Request::new(SetAutopilotPolicyRequest {
+                    policy: Some(policy),
+                });
+                let response = client.set_autopilot_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "runautopilot" => {
+                let args = command_args;
+
+                let request = tonic::Request::new(RunAutopilotRequest {
+                    dry_run: args.is_present("dry_run"),
+                });
+                let response = client.run_autopilot(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "exportchannelbackup" => {
+                let request = tonic::Request::new(ExportChannelBackupRequest {});
+                let response = client.export_channel_backup(request).await?;
+                println!("{}", response.into_inner().backup);
+            }
+            "recoverfrombackup" => {
+                let args = command_args;
+
+                let backup = args.value_of("backup").expect("backup required");
+
+                let request = tonic::Request::new(RecoverFromBackupRequest {
+                    backup: backup.to_string(),
+                });
+                let response = client.recover_from_backup(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "getjitchannelpolicy" => {
+                let request = tonic::Request::new(GetJitChannelPolicyRequest {});
+                let response = client.get_jit_channel_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "setjitchannelpolicy" => {
+                let args = command_args;
+
+                let policy = JitChannelPolicy {
+                    enabled: args
+                        .value_of("enabled")
+                        .unwrap()
+                        .parse()
+                        .expect("enabled must be true or false"),
+                    opening_fee_base_msat: args
+                        .value_of("opening_fee_base_msat")
+                        .unwrap()
+                        .parse()
+                        .expect("fee must be in msat"),
+                    opening_fee_proportional_millionths: args
+                        .value_of("opening_fee_proportional_millionths")
+                        .unwrap()
+                        .parse()
+                        .expect("fee must be in millionths"),
+                    min_channel_size_sats: args
+                        .value_of("min_channel_size_sats")
+                        .unwrap()
+                        .parse()
+                        .expect("size must be in sats"),
+                    max_channel_size_sats: args
+                        .value_of("max_channel_size_sats")
+                        .unwrap()
+                        .parse()
+                        .expect("size must be in sats"),
+                };
+
+                let request = tonic::Request::new(SetJitChannelPolicyRequest {
+                    policy: Some(policy),
+                });
+                let response = client.set_jit_channel_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "createjitinvoice" => {
+                let args = command_args;
+
+                let invoice = args.value_of("invoice").expect("invoice required");
+
+                let request = tonic::Request::new(CreateJitInvoiceRequest {
+                    invoice: invoice.to_string(),
+                });
+                let response = client.create_jit_invoice(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "gethealthreport" => {
+                let request = tonic::Request::new(GetHealthReportRequest {});
+                let response = client.get_health_report(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "gethealthpolicy" => {
+                let request = tonic::Request::new(GetHealthPolicyRequest {});
+                let response = client.get_health_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "sethealthpolicy" => {
+                let args = command_args;
+
+                let policy = HealthPolicy {
+                    channel_unhealthy_after_secs: args
+                        .value_of("channel_unhealthy_after_secs")
+                        .unwrap()
+                        .parse()
+                        .expect("threshold must be in seconds"),
+                    peer_offline_after_secs: args
+                        .value_of("peer_offline_after_secs")
+                        .unwrap()
+                        .parse()
+                        .expect("threshold must be in seconds"),
+                };
+
+                let request = tonic::Request::new(SetHealthPolicyRequest {
+                    policy: Some(policy),
+                });
+                let response = client.set_health_policy(request).await?;
+                println!("{:?}", response.into_inner());
+            }
             "closechannel" => {
                 let args = command_args;
 
@@ -368,6 +1232,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let request = tonic::Request::new(CloseChannelRequest {
                     channel_id: channel_id.to_string(),
                     force,
+                    destination_address: args.value_of("destination_address").map(String::from),
+                    target_feerate_sats_per_vbyte: args
+                        .value_of("fee_rate")
+                        .map(|fee_rate| fee_rate.parse().expect("fee rate must be in sats/vbyte")),
                 });
 
                 let response = client.close_channel(request).await?;
@@ -380,6 +1248,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                 let request = tonic::Request::new(PayInvoiceRequest {
                     invoice: invoice.to_string(),
+                    idempotency_key: None,
                 });
 
                 let response = client.pay_invoice(request).await?;
@@ -399,11 +1268,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let request = tonic::Request::new(KeysendRequest {
                     dest_pubkey: dest_pubkey.to_string(),
                     amt_msat,
+                    idempotency_key: None,
                 });
 
                 let response = client.keysend(request).await?;
                 println!("{:?}", response.into_inner());
             }
+            "rebalance" => {
+                let args = command_args;
+
+                let from_channel_id = args
+                    .value_of("from_channel_id")
+                    .expect("from_channel_id required");
+                let to_channel_id = args
+                    .value_of("to_channel_id")
+                    .expect("to_channel_id required");
+
+                let amt_msat: u64 = args
+                    .value_of("amt_msat")
+                    .expect("amt_msat is required field")
+                    .parse()
+                    .expect("amount must be in millisatoshis");
+
+                let max_fee_msat: u64 = args
+                    .value_of("max_fee_msat")
+                    .expect("max_fee_msat is required field")
+                    .parse()
+                    .expect("max fee must be in millisatoshis");
+
+                let request = tonic::Request::new(RebalanceRequest {
+                    from_channel_id: from_channel_id.to_string(),
+                    to_channel_id: to_channel_id.to_string(),
+                    amt_msat,
+                    max_fee_msat,
+                });
+
+                let response = client.rebalance(request).await?;
+                println!("{:?}", response.into_inner());
+            }
             "connectpeer" => {
                 let args = command_args;
 
@@ -431,10 +1333,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("{:?}", response.into_inner());
             }
             "listchannels" => {
-                let request = tonic::Request::new(ListChannelsRequest { pagination: None });
+                let request = tonic::Request::new(ListChannelsRequest {
+                    pagination: None,
+                    filter: Some(ChannelsFilter {
+                        state: command_args.value_of("state").map(String::from),
+                    }),
+                });
                 let response = client.list_channels(request).await?;
                 println!("{:?}", response.into_inner());
             }
+            "listhtlcs" => {
+                let request = tonic::Request::new(ListHtlcsRequest {
+                    channel_id: command_args.value_of("channel_id").map(String::from),
+                });
+                let response = client.list_htlcs(request).await?;
+                println!("{:?}", response.into_inner());
+            }
             "listpayments" => {
                 let request = tonic::Request::new(ListPaymentsRequest {
                     pagination: None,
@@ -443,6 +1357,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let response = client.list_payments(request).await?;
                 println!("{:?}", response.into_inner());
             }
+            "listinvoices" => {
+                let request = tonic::Request::new(ListInvoicesRequest {
+                    pagination: None,
+                    filter: None,
+                });
+                let response = client.list_invoices(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "cancelinvoice" => {
+                let payment_hash = command_args.value_of("payment_hash").unwrap();
+                let request = tonic::Request::new(CancelInvoiceRequest {
+                    payment_hash: payment_hash.to_string(),
+                });
+                let response = client.cancel_invoice(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "listscheduledpayments" => {
+                let request =
+                    tonic::Request::new(ListScheduledPaymentsRequest { pagination: None });
+                let response = client.list_scheduled_payments(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "deletescheduledpayment" => {
+                let id = command_args.value_of("id").unwrap();
+                let request =
+                    tonic::Request::new(DeleteScheduledPaymentRequest { id: id.to_string() });
+                let response = client.delete_scheduled_payment(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "paymentattempts" => {
+                let payment_hash = command_args.value_of("payment_hash").unwrap();
+                let request = tonic::Request::new(GetPaymentAttemptsRequest {
+                    payment_hash: payment_hash.to_string(),
+                });
+                let response = client.get_payment_attempts(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "listforwardedpayments" => {
+                let request = tonic::Request::new(ListForwardedPaymentsRequest {
+                    pagination: None,
+                    filter: None,
+                });
+                let response = client.list_forwarded_payments(request).await?;
+                println!("{:?}", response.into_inner());
+            }
+            "routingrevenue" => {
+                let request = tonic::Request::new(GetRoutingRevenueRequest { filter: None });
+                let response = client.get_routing_revenue(request).await?;
+                println!("{:?}", response.into_inner());
+            }
             "listpeers" => {
                 let request = tonic::Request::new(ListPeersRequest {});
                 let response = client.list_peers(request).await?;
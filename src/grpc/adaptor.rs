@@ -8,16 +8,48 @@
 // licenses.
 
 use super::sensei::{
-    self, AddKnownPeerRequest, AddKnownPeerResponse, Channel as ChannelMessage,
-    CreatePhantomInvoiceRequest, CreatePhantomInvoiceResponse, DeletePaymentRequest,
-    DeletePaymentResponse, GetPhantomRouteHintsRequest, GetPhantomRouteHintsResponse,
-    Info as InfoMessage, KnownPeer, LabelPaymentRequest, LabelPaymentResponse,
-    ListKnownPeersRequest, ListKnownPeersResponse, ListPhantomPaymentsRequest,
-    ListPhantomPaymentsResponse, NetworkGraphInfoRequest, NetworkGraphInfoResponse,
+    self, AddKnownPeerRequest, AddKnownPeerResponse, AutopilotChannel as AutopilotChannelMessage,
+    AutopilotPolicy as AutopilotPolicyMessage, BalanceSnapshot as BalanceSnapshotMessage,
+    CancelInvoiceRequest, CancelInvoiceResponse, Channel as ChannelMessage,
+    ChannelAcceptancePolicy as ChannelAcceptancePolicyMessage,
+    ChannelClaimableBalances as ChannelClaimableBalancesMessage,
+    ChannelHealth as ChannelHealthMessage, ChannelRoutingRevenue as ChannelRoutingRevenueMessage,
+    ChannelsFilter, ClaimableBalance as ClaimableBalanceMessage, CreateJitInvoiceRequest,
+    CreateJitInvoiceResponse, CreatePhantomInvoiceRequest, CreatePhantomInvoiceResponse,
+    CreateScheduledPaymentRequest, CreateScheduledPaymentResponse,
+    CreatedInvoice as CreatedInvoiceMessage, DailyRoutingRevenue as DailyRoutingRevenueMessage,
+    DeletePaymentRequest, DeletePaymentResponse, DeleteScheduledPaymentRequest,
+    DeleteScheduledPaymentResponse, ExportChannelBackupRequest, ExportChannelBackupResponse,
+    ExportLedgerRequest, ExportLedgerResponse, FeePolicy as FeePolicyMessage,
+    ForwardedPayment as ForwardedPaymentMessage, ForwardedPaymentsFilter,
+    GetAutopilotPolicyRequest, GetAutopilotPolicyResponse, GetBalanceHistoryRequest,
+    GetBalanceHistoryResponse, GetChannelAcceptancePolicyRequest,
+    GetChannelAcceptancePolicyResponse, GetClaimableBalancesRequest, GetClaimableBalancesResponse,
+    GetFeePolicyRequest, GetFeePolicyResponse, GetHealthPolicyRequest, GetHealthPolicyResponse,
+    GetHealthReportRequest, GetHealthReportResponse, GetJitChannelPolicyRequest,
+    GetJitChannelPolicyResponse, GetPaymentAttemptsRequest, GetPaymentAttemptsResponse,
+    GetPhantomRouteHintsRequest, GetPhantomRouteHintsResponse, GetRoutingRevenueRequest,
+    GetRoutingRevenueResponse, HealthPolicy as HealthPolicyMessage, Htlc as HtlcMessage,
+    Info as InfoMessage, InvoicesFilter, JitChannelPolicy as JitChannelPolicyMessage, KnownPeer,
+    LabelPaymentRequest, LabelPaymentResponse, ListForwardedPaymentsRequest,
+    ListForwardedPaymentsResponse, ListHtlcsRequest, ListHtlcsResponse, ListInvoicesRequest,
+    ListInvoicesResponse, ListKnownPeersRequest, ListKnownPeersResponse,
+    ListPhantomPaymentsRequest, ListPhantomPaymentsResponse, ListScheduledPaymentsRequest,
+    ListScheduledPaymentsResponse, NetworkGraphInfoRequest, NetworkGraphInfoResponse,
     OpenChannelRequest as GrpcOpenChannelRequest, OpenChannelsRequest, OpenChannelsResponse,
-    PaginationRequest, PaginationResponse, Payment as PaymentMessage, PaymentsFilter,
-    Peer as PeerMessage, RemoveKnownPeerRequest, RemoveKnownPeerResponse, StartNodeRequest,
-    StartNodeResponse, StopNodeRequest, StopNodeResponse, Utxo as UtxoMessage,
+    PaginationRequest, PaginationResponse, Payment as PaymentMessage,
+    PaymentAttempt as PaymentAttemptMessage, PaymentAttemptHop as PaymentAttemptHopMessage,
+    PaymentsFilter, Peer as PeerMessage, PeerHealth as PeerHealthMessage, RebalanceRequest,
+    RebalanceResponse, RecoverFromBackupRequest, RecoverFromBackupResponse,
+    RecoveredChannel as RecoveredChannelMessage, RemoveKnownPeerRequest, RemoveKnownPeerResponse,
+    RestrictMacaroonRequest, RestrictMacaroonResponse, RunAutopilotRequest, RunAutopilotResponse,
+    ScheduledPayment as ScheduledPaymentMessage, SetAutopilotPolicyRequest,
+    SetAutopilotPolicyResponse, SetChannelAcceptancePolicyRequest,
+    SetChannelAcceptancePolicyResponse, SetFeePolicyRequest, SetFeePolicyResponse,
+    SetHealthPolicyRequest, SetHealthPolicyResponse, SetJitChannelPolicyRequest,
+    SetJitChannelPolicyResponse, StartNodeRequest, StartNodeResponse, StopNodeRequest,
+    StopNodeResponse, UpdateChannelConfigRequest, UpdateChannelConfigResponse,
+    UpdateScheduledPaymentRequest, UpdateScheduledPaymentResponse, Utxo as UtxoMessage,
 };
 
 use super::sensei::{
@@ -31,10 +63,23 @@ use super::sensei::{
     VerifyMessageResponse,
 };
 
+use senseicore::autopilot::{AutopilotChannel, AutopilotPolicy};
+use senseicore::backup::RecoveredChannel;
+use senseicore::channel_acceptance::{
+    ChannelAcceptanceDecision, ChannelAcceptancePolicy, DEFAULT_WEBHOOK_TIMEOUT_SECS,
+};
+use senseicore::fee_manager::FeePolicy;
+use senseicore::health::{ChannelHealth, HealthPolicy, PeerHealth};
+use senseicore::htlc_tracker::PendingHtlc;
+use senseicore::jit_channels::JitChannelPolicy;
 use senseicore::services::node::OpenChannelRequest;
 use senseicore::services::{
     self,
-    node::{Channel, NodeInfo, NodeRequest, NodeResponse, Peer, Utxo},
+    node::{
+        BalanceSnapshot, Channel, ChannelClaimableBalances, ChannelRoutingRevenue,
+        ClaimableBalance, DailyRoutingRevenue, InvoiceInfo, NodeInfo, NodeRequest, NodeResponse,
+        PaymentAttempt, PaymentAttemptHop, Peer, Utxo,
+    },
 };
 
 impl From<PaymentsFilter> for services::PaymentsFilter {
@@ -46,6 +91,33 @@ impl From<PaymentsFilter> for services::PaymentsFilter {
     }
 }
 
+impl From<ChannelsFilter> for services::ChannelsFilter {
+    fn from(filter: ChannelsFilter) -> Self {
+        Self {
+            state: filter.state,
+        }
+    }
+}
+
+impl From<InvoicesFilter> for services::InvoicesFilter {
+    fn from(filter: InvoicesFilter) -> Self {
+        Self {
+            status: filter.status,
+        }
+    }
+}
+
+impl From<ForwardedPaymentsFilter> for services::ForwardedPaymentsFilter {
+    fn from(filter: ForwardedPaymentsFilter) -> Self {
+        Self {
+            from_channel_id: filter.from_channel_id,
+            to_channel_id: filter.to_channel_id,
+            from_hours_since_epoch: filter.from_hours_since_epoch,
+            to_hours_since_epoch: filter.to_hours_since_epoch,
+        }
+    }
+}
+
 impl From<PaginationRequest> for services::PaginationRequest {
     fn from(pagination: PaginationRequest) -> Self {
         Self {
@@ -86,6 +158,13 @@ impl From<Channel> for ChannelMessage {
             is_public: channel.is_public,
             counterparty_pubkey: channel.counterparty_pubkey,
             alias: channel.alias,
+            state: channel.state,
+            closing_reason: channel.closing_reason,
+            closing_txid: channel.closing_txid,
+            opened_at: channel.opened_at,
+            ready_at: channel.ready_at,
+            closing_at: channel.closing_at,
+            closed_at: channel.closed_at,
         }
     }
 }
@@ -109,6 +188,91 @@ impl From<entity::payment::Model> for PaymentMessage {
     }
 }
 
+impl From<entity::forwarded_payment::Model> for ForwardedPaymentMessage {
+    fn from(forwarded_payment: entity::forwarded_payment::Model) -> Self {
+        Self {
+            id: forwarded_payment.id,
+            from_channel_id: forwarded_payment.from_channel_id,
+            to_channel_id: forwarded_payment.to_channel_id,
+            fees_earned_msat: forwarded_payment.fees_earned_msat,
+            claim_from_onchain_tx: forwarded_payment.claim_from_onchain_tx,
+            hours_since_epoch: forwarded_payment.hours_since_epoch,
+            created_at: forwarded_payment.created_at,
+        }
+    }
+}
+
+impl From<ChannelRoutingRevenue> for ChannelRoutingRevenueMessage {
+    fn from(revenue: ChannelRoutingRevenue) -> Self {
+        Self {
+            channel_id: revenue.channel_id,
+            fees_earned_msat: revenue.fees_earned_msat,
+            num_forwards_in: revenue.num_forwards_in,
+            num_forwards_out: revenue.num_forwards_out,
+        }
+    }
+}
+
+impl From<DailyRoutingRevenue> for DailyRoutingRevenueMessage {
+    fn from(revenue: DailyRoutingRevenue) -> Self {
+        Self {
+            days_since_epoch: revenue.days_since_epoch,
+            fees_earned_msat: revenue.fees_earned_msat,
+            num_forwards: revenue.num_forwards,
+        }
+    }
+}
+
+impl From<PaymentAttemptHop> for PaymentAttemptHopMessage {
+    fn from(hop: PaymentAttemptHop) -> Self {
+        Self {
+            pubkey: hop.pubkey,
+            short_channel_id: hop.short_channel_id,
+            fee_msat: hop.fee_msat,
+            cltv_expiry_delta: hop.cltv_expiry_delta,
+        }
+    }
+}
+
+impl From<InvoiceInfo> for CreatedInvoiceMessage {
+    fn from(invoice: InvoiceInfo) -> Self {
+        Self {
+            payment_hash: invoice.payment_hash,
+            invoice: invoice.invoice,
+            amt_msat: invoice.amt_msat,
+            label: invoice.label,
+            status: invoice.status,
+            expires_at: invoice.expires_at,
+            created_at: invoice.created_at,
+            updated_at: invoice.updated_at,
+        }
+    }
+}
+
+impl From<PaymentAttempt> for PaymentAttemptMessage {
+    fn from(attempt: PaymentAttempt) -> Self {
+        Self {
+            id: attempt.id,
+            payment_hash: attempt.payment_hash,
+            payment_id: attempt.payment_id,
+            status: attempt.status,
+            failure_code: attempt.failure_code,
+            failed_short_channel_id: attempt.failed_short_channel_id,
+            network_update: attempt.network_update,
+            rejected_by_dest: attempt.rejected_by_dest,
+            all_paths_failed: attempt.all_paths_failed,
+            amt_msat: attempt.amt_msat,
+            fee_msat: attempt.fee_msat,
+            hops: attempt
+                .hops
+                .into_iter()
+                .map(|hop| hop.into())
+                .collect::<Vec<PaymentAttemptHopMessage>>(),
+            created_at: attempt.created_at,
+        }
+    }
+}
+
 impl From<NodeInfo> for InfoMessage {
     fn from(info: NodeInfo) -> Self {
         Self {
@@ -224,6 +388,88 @@ impl TryFrom<NodeResponse> for GetBalanceResponse {
     }
 }
 
+impl From<ClaimableBalance> for ClaimableBalanceMessage {
+    fn from(balance: ClaimableBalance) -> Self {
+        Self {
+            kind: balance.kind.to_string(),
+            amount_sats: balance.amount_sats,
+            maturity_height: balance.maturity_height,
+        }
+    }
+}
+
+impl From<ChannelClaimableBalances> for ChannelClaimableBalancesMessage {
+    fn from(channel: ChannelClaimableBalances) -> Self {
+        Self {
+            channel_id: channel.channel_id,
+            funding_txid: channel.funding_txid,
+            funding_tx_index: channel.funding_tx_index,
+            counterparty_pubkey: channel.counterparty_pubkey,
+            claimable_sats: channel.claimable_sats,
+            balances: channel.balances.into_iter().map(|b| b.into()).collect(),
+        }
+    }
+}
+
+impl From<GetClaimableBalancesRequest> for NodeRequest {
+    fn from(_req: GetClaimableBalancesRequest) -> Self {
+        NodeRequest::GetClaimableBalances {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetClaimableBalancesResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetClaimableBalances { balances } => Ok(Self {
+                best_block_height: balances.best_block_height,
+                total_sats: balances.total_sats,
+                awaiting_confirmation_sats: balances.awaiting_confirmation_sats,
+                pending_timelock_sats: balances.pending_timelock_sats,
+                contested_htlc_sats: balances.contested_htlc_sats,
+                sweep_in_progress_sats: balances.sweep_in_progress_sats,
+                channels: balances.channels.into_iter().map(|c| c.into()).collect(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<BalanceSnapshot> for BalanceSnapshotMessage {
+    fn from(snapshot: BalanceSnapshot) -> Self {
+        Self {
+            timestamp: snapshot.timestamp,
+            onchain_balance_sats: snapshot.onchain_balance_sats,
+            channel_outbound_capacity_msats: snapshot.channel_outbound_capacity_msats,
+            channel_inbound_capacity_msats: snapshot.channel_inbound_capacity_msats,
+        }
+    }
+}
+
+impl From<GetBalanceHistoryRequest> for NodeRequest {
+    fn from(req: GetBalanceHistoryRequest) -> Self {
+        NodeRequest::GetBalanceHistory {
+            from: req.from,
+            to: req.to,
+            interval: req.interval,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for GetBalanceHistoryResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetBalanceHistory { snapshots } => Ok(Self {
+                snapshots: snapshots.into_iter().map(|s| s.into()).collect(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
 impl From<OpenChannelsRequest> for NodeRequest {
     fn from(req: OpenChannelsRequest) -> Self {
         NodeRequest::OpenChannels {
@@ -235,6 +481,7 @@ impl From<OpenChannelsRequest> for NodeRequest {
                     amount_sats: request.amount_sats,
                     public: request.public,
                     scid_alias: request.scid_alias,
+                    upfront_shutdown_script: request.upfront_shutdown_script,
                     custom_id: request.custom_id,
                     push_amount_msats: request.push_amount_msats,
                     counterparty_host_port: request.counterparty_host_port,
@@ -266,6 +513,7 @@ impl TryFrom<NodeResponse> for OpenChannelsResponse {
                         amount_sats: request.amount_sats,
                         public: request.public,
                         scid_alias: request.scid_alias,
+                        upfront_shutdown_script: request.upfront_shutdown_script,
                         custom_id: request.custom_id,
                         push_amount_msats: request.push_amount_msats,
                         counterparty_host_port: request.counterparty_host_port,
@@ -298,6 +546,7 @@ impl From<PayInvoiceRequest> for NodeRequest {
     fn from(req: PayInvoiceRequest) -> Self {
         NodeRequest::SendPayment {
             invoice: req.invoice,
+            idempotency_key: req.idempotency_key,
         }
     }
 }
@@ -307,7 +556,13 @@ impl TryFrom<NodeResponse> for PayInvoiceResponse {
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::SendPayment {} => Ok(Self {}),
+            NodeResponse::SendPayment {
+                payment_hash,
+                status,
+            } => Ok(Self {
+                payment_hash,
+                status,
+            }),
             _ => Err("impossible".to_string()),
         }
     }
@@ -339,6 +594,7 @@ impl From<KeysendRequest> for NodeRequest {
         NodeRequest::Keysend {
             dest_pubkey: req.dest_pubkey,
             amt_msat: req.amt_msat,
+            idempotency_key: req.idempotency_key,
         }
     }
 }
@@ -348,7 +604,43 @@ impl TryFrom<NodeResponse> for KeysendResponse {
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::Keysend {} => Ok(Self {}),
+            NodeResponse::Keysend {
+                payment_hash,
+                status,
+            } => Ok(Self {
+                payment_hash,
+                status,
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<RebalanceRequest> for NodeRequest {
+    fn from(req: RebalanceRequest) -> Self {
+        NodeRequest::Rebalance {
+            from_channel_id: req.from_channel_id,
+            to_channel_id: req.to_channel_id,
+            amt_msat: req.amt_msat,
+            max_fee_msat: req.max_fee_msat,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for RebalanceResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::Rebalance {
+                payment_hash,
+                status,
+                fee_msat,
+            } => Ok(Self {
+                payment_hash,
+                status,
+                fee_msat,
+            }),
             _ => Err("impossible".to_string()),
         }
     }
@@ -358,7 +650,12 @@ impl From<CreateInvoiceRequest> for NodeRequest {
     fn from(req: CreateInvoiceRequest) -> Self {
         NodeRequest::GetInvoice {
             amt_msat: req.amt_msat,
+            min_amt_msat: req.min_amt_msat,
             description: req.description,
+            description_hash: req.description_hash,
+            expiry_secs: req.expiry_secs,
+            min_final_cltv_expiry: req.min_final_cltv_expiry,
+            private_route_hints: req.private_route_hints,
         }
     }
 }
@@ -378,6 +675,7 @@ impl From<CreatePhantomInvoiceRequest> for NodeRequest {
     fn from(req: CreatePhantomInvoiceRequest) -> Self {
         NodeRequest::GetPhantomInvoice {
             amt_msat: req.amt_msat,
+            min_amt_msat: req.min_amt_msat,
             description: req.description,
             phantom_route_hints_hex: req.phantom_route_hints_hex,
         }
@@ -455,112 +753,126 @@ impl TryFrom<NodeResponse> for DeletePaymentResponse {
     }
 }
 
-impl From<ConnectPeerRequest> for NodeRequest {
-    fn from(req: ConnectPeerRequest) -> Self {
-        NodeRequest::ConnectPeer {
-            node_connection_string: req.node_connection_string,
+impl From<ListInvoicesRequest> for NodeRequest {
+    fn from(req: ListInvoicesRequest) -> Self {
+        NodeRequest::ListInvoices {
+            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
         }
     }
 }
 
-impl TryFrom<NodeResponse> for ConnectPeerResponse {
+impl TryFrom<NodeResponse> for ListInvoicesResponse {
     type Error = String;
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::ConnectPeer {} => Ok(Self {}),
+            NodeResponse::ListInvoices {
+                invoices,
+                pagination,
+            } => {
+                let pagination: PaginationResponse = pagination.into();
+                Ok(Self {
+                    invoices: invoices
+                        .into_iter()
+                        .map(|invoice| invoice.into())
+                        .collect::<Vec<CreatedInvoiceMessage>>(),
+                    pagination: Some(pagination),
+                })
+            }
             _ => Err("impossible".to_string()),
         }
     }
 }
 
-impl From<ListChannelsRequest> for NodeRequest {
-    fn from(req: ListChannelsRequest) -> Self {
-        NodeRequest::ListChannels {
-            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+impl From<CancelInvoiceRequest> for NodeRequest {
+    fn from(req: CancelInvoiceRequest) -> Self {
+        NodeRequest::CancelInvoice {
+            payment_hash: req.payment_hash,
         }
     }
 }
 
-impl TryFrom<NodeResponse> for ListChannelsResponse {
+impl TryFrom<NodeResponse> for CancelInvoiceResponse {
     type Error = String;
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::ListChannels {
-                channels,
-                pagination,
-            } => {
-                let pagination: PaginationResponse = pagination.into();
-                Ok(Self {
-                    channels: channels
-                        .into_iter()
-                        .map(|chan| chan.into())
-                        .collect::<Vec<ChannelMessage>>(),
-                    pagination: Some(pagination),
-                })
-            }
+            NodeResponse::CancelInvoice {} => Ok(Self {}),
             _ => Err("impossible".to_string()),
         }
     }
 }
 
-impl From<ListPaymentsRequest> for NodeRequest {
-    fn from(req: ListPaymentsRequest) -> Self {
-        NodeRequest::ListPayments {
-            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
-            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+impl From<entity::scheduled_payment::Model> for ScheduledPaymentMessage {
+    fn from(scheduled_payment: entity::scheduled_payment::Model) -> Self {
+        Self {
+            id: scheduled_payment.id,
+            kind: scheduled_payment.kind,
+            dest_pubkey: scheduled_payment.dest_pubkey,
+            invoice_url: scheduled_payment.invoice_url,
+            amt_msat: scheduled_payment.amt_msat,
+            interval_secs: scheduled_payment.interval_secs,
+            next_run_at: scheduled_payment.next_run_at,
+            last_run_at: scheduled_payment.last_run_at,
+            last_payment_hash: scheduled_payment.last_payment_hash,
+            last_error: scheduled_payment.last_error,
+            enabled: scheduled_payment.enabled,
+            created_at: scheduled_payment.created_at,
+            updated_at: scheduled_payment.updated_at,
         }
     }
 }
 
-impl TryFrom<NodeResponse> for ListPaymentsResponse {
+impl From<CreateScheduledPaymentRequest> for NodeRequest {
+    fn from(req: CreateScheduledPaymentRequest) -> Self {
+        NodeRequest::CreateScheduledPayment {
+            kind: req.kind,
+            dest_pubkey: req.dest_pubkey,
+            invoice_url: req.invoice_url,
+            amt_msat: req.amt_msat,
+            interval_secs: req.interval_secs,
+            start_at: req.start_at,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for CreateScheduledPaymentResponse {
     type Error = String;
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::ListPayments {
-                payments,
-                pagination,
-            } => {
-                let pagination: PaginationResponse = pagination.into();
-                Ok(Self {
-                    payments: payments
-                        .into_iter()
-                        .map(|payment| payment.into())
-                        .collect::<Vec<PaymentMessage>>(),
-                    pagination: Some(pagination),
-                })
-            }
+            NodeResponse::CreateScheduledPayment { scheduled_payment } => Ok(Self {
+                scheduled_payment: Some(scheduled_payment.into()),
+            }),
             _ => Err("impossible".to_string()),
         }
     }
 }
 
-impl From<ListPhantomPaymentsRequest> for NodeRequest {
-    fn from(req: ListPhantomPaymentsRequest) -> Self {
-        NodeRequest::ListPhantomPayments {
+impl From<ListScheduledPaymentsRequest> for NodeRequest {
+    fn from(req: ListScheduledPaymentsRequest) -> Self {
+        NodeRequest::ListScheduledPayments {
             pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
-            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
         }
     }
 }
 
-impl TryFrom<NodeResponse> for ListPhantomPaymentsResponse {
+impl TryFrom<NodeResponse> for ListScheduledPaymentsResponse {
     type Error = String;
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::ListPhantomPayments {
-                payments,
+            NodeResponse::ListScheduledPayments {
+                scheduled_payments,
                 pagination,
             } => {
                 let pagination: PaginationResponse = pagination.into();
                 Ok(Self {
-                    payments: payments
+                    scheduled_payments: scheduled_payments
                         .into_iter()
-                        .map(|payment| payment.into())
-                        .collect::<Vec<PaymentMessage>>(),
+                        .map(|scheduled_payment| scheduled_payment.into())
+                        .collect::<Vec<ScheduledPaymentMessage>>(),
                     pagination: Some(pagination),
                 })
             }
@@ -569,21 +881,888 @@ impl TryFrom<NodeResponse> for ListPhantomPaymentsResponse {
     }
 }
 
-impl From<CloseChannelRequest> for NodeRequest {
-    fn from(req: CloseChannelRequest) -> Self {
-        NodeRequest::CloseChannel {
-            channel_id: req.channel_id,
-            force: req.force,
+impl From<UpdateScheduledPaymentRequest> for NodeRequest {
+    fn from(req: UpdateScheduledPaymentRequest) -> Self {
+        NodeRequest::UpdateScheduledPayment {
+            id: req.id,
+            amt_msat: req.amt_msat,
+            interval_secs: req.interval_secs,
+            enabled: req.enabled,
         }
     }
 }
 
-impl TryFrom<NodeResponse> for CloseChannelResponse {
+impl TryFrom<NodeResponse> for UpdateScheduledPaymentResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::UpdateScheduledPayment { scheduled_payment } => Ok(Self {
+                scheduled_payment: Some(scheduled_payment.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<DeleteScheduledPaymentRequest> for NodeRequest {
+    fn from(req: DeleteScheduledPaymentRequest) -> Self {
+        NodeRequest::DeleteScheduledPayment { id: req.id }
+    }
+}
+
+impl TryFrom<NodeResponse> for DeleteScheduledPaymentResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::DeleteScheduledPayment {} => Ok(Self {}),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ExportLedgerRequest> for NodeRequest {
+    fn from(req: ExportLedgerRequest) -> Self {
+        NodeRequest::ExportLedger {
+            from: req.from,
+            to: req.to,
+            format: req.format,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ExportLedgerResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ExportLedger { ledger } => Ok(Self { ledger }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<RestrictMacaroonRequest> for NodeRequest {
+    fn from(req: RestrictMacaroonRequest) -> Self {
+        let allowed_destinations = if req.allowed_destinations.is_empty() {
+            None
+        } else {
+            Some(req.allowed_destinations)
+        };
+        NodeRequest::RestrictMacaroon {
+            macaroon: req.macaroon,
+            max_payment_msat: req.max_payment_msat,
+            max_daily_msat: req.max_daily_msat,
+            max_monthly_msat: req.max_monthly_msat,
+            allowed_destinations,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for RestrictMacaroonResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::RestrictMacaroon { macaroon } => Ok(Self { macaroon }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ConnectPeerRequest> for NodeRequest {
+    fn from(req: ConnectPeerRequest) -> Self {
+        NodeRequest::ConnectPeer {
+            node_connection_string: req.node_connection_string,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ConnectPeerResponse {
     type Error = String;
 
     fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
         match res {
-            NodeResponse::CloseChannel {} => Ok(Self {}),
+            NodeResponse::ConnectPeer {} => Ok(Self {}),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<PendingHtlc> for HtlcMessage {
+    fn from(htlc: PendingHtlc) -> Self {
+        Self {
+            channel_id: htlc.channel_id,
+            htlc_id: htlc.htlc_id,
+            direction: htlc.direction.to_string(),
+            amount_msat: htlc.amount_msat,
+            payment_hash: htlc.payment_hash,
+            cltv_expiry: htlc.cltv_expiry,
+            blocks_until_expiry: htlc.blocks_until_expiry,
+            forward: htlc.forward,
+        }
+    }
+}
+
+impl From<ListHtlcsRequest> for NodeRequest {
+    fn from(req: ListHtlcsRequest) -> Self {
+        NodeRequest::ListHtlcs {
+            channel_id: req.channel_id,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ListHtlcsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ListHtlcs { htlcs } => Ok(Self {
+                htlcs: htlcs.into_iter().map(|htlc| htlc.into()).collect(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ListChannelsRequest> for NodeRequest {
+    fn from(req: ListChannelsRequest) -> Self {
+        NodeRequest::ListChannels {
+            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ListChannelsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ListChannels {
+                channels,
+                pagination,
+            } => {
+                let pagination: PaginationResponse = pagination.into();
+                Ok(Self {
+                    channels: channels
+                        .into_iter()
+                        .map(|chan| chan.into())
+                        .collect::<Vec<ChannelMessage>>(),
+                    pagination: Some(pagination),
+                })
+            }
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ListPaymentsRequest> for NodeRequest {
+    fn from(req: ListPaymentsRequest) -> Self {
+        NodeRequest::ListPayments {
+            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ListPaymentsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ListPayments {
+                payments,
+                pagination,
+            } => {
+                let pagination: PaginationResponse = pagination.into();
+                Ok(Self {
+                    payments: payments
+                        .into_iter()
+                        .map(|payment| payment.into())
+                        .collect::<Vec<PaymentMessage>>(),
+                    pagination: Some(pagination),
+                })
+            }
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ListPhantomPaymentsRequest> for NodeRequest {
+    fn from(req: ListPhantomPaymentsRequest) -> Self {
+        NodeRequest::ListPhantomPayments {
+            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ListPhantomPaymentsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ListPhantomPayments {
+                payments,
+                pagination,
+            } => {
+                let pagination: PaginationResponse = pagination.into();
+                Ok(Self {
+                    payments: payments
+                        .into_iter()
+                        .map(|payment| payment.into())
+                        .collect::<Vec<PaymentMessage>>(),
+                    pagination: Some(pagination),
+                })
+            }
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ListForwardedPaymentsRequest> for NodeRequest {
+    fn from(req: ListForwardedPaymentsRequest) -> Self {
+        NodeRequest::ListForwardedPayments {
+            pagination: req.pagination.map(|p| p.into()).unwrap_or_default(),
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for ListForwardedPaymentsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ListForwardedPayments {
+                forwarded_payments,
+                pagination,
+            } => {
+                let pagination: PaginationResponse = pagination.into();
+                Ok(Self {
+                    forwarded_payments: forwarded_payments
+                        .into_iter()
+                        .map(|forwarded_payment| forwarded_payment.into())
+                        .collect::<Vec<ForwardedPaymentMessage>>(),
+                    pagination: Some(pagination),
+                })
+            }
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<GetRoutingRevenueRequest> for NodeRequest {
+    fn from(req: GetRoutingRevenueRequest) -> Self {
+        NodeRequest::GetRoutingRevenue {
+            filter: req.filter.map(|f| f.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for GetRoutingRevenueResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetRoutingRevenue { revenue } => Ok(Self {
+                fees_earned_msat: revenue.fees_earned_msat,
+                num_forwards: revenue.num_forwards,
+                channels: revenue
+                    .channels
+                    .into_iter()
+                    .map(|channel| channel.into())
+                    .collect::<Vec<ChannelRoutingRevenueMessage>>(),
+                days: revenue
+                    .days
+                    .into_iter()
+                    .map(|day| day.into())
+                    .collect::<Vec<DailyRoutingRevenueMessage>>(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<GetPaymentAttemptsRequest> for NodeRequest {
+    fn from(req: GetPaymentAttemptsRequest) -> Self {
+        NodeRequest::GetPaymentAttempts {
+            payment_hash: req.payment_hash,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for GetPaymentAttemptsResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetPaymentAttempts { attempts } => Ok(Self {
+                attempts: attempts
+                    .into_iter()
+                    .map(|attempt| attempt.into())
+                    .collect::<Vec<PaymentAttemptMessage>>(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<CloseChannelRequest> for NodeRequest {
+    fn from(req: CloseChannelRequest) -> Self {
+        NodeRequest::CloseChannel {
+            channel_id: req.channel_id,
+            force: req.force,
+            destination_address: req.destination_address,
+            target_feerate_sats_per_vbyte: req.target_feerate_sats_per_vbyte,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for CloseChannelResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::CloseChannel { destination_used } => Ok(Self { destination_used }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<UpdateChannelConfigRequest> for NodeRequest {
+    fn from(req: UpdateChannelConfigRequest) -> Self {
+        NodeRequest::UpdateChannelConfig {
+            channel_ids: req.channel_ids,
+            all: req.all,
+            forwarding_fee_base_msat: req.forwarding_fee_base_msat,
+            forwarding_fee_proportional_millionths: req.forwarding_fee_proportional_millionths,
+            cltv_expiry_delta: req.cltv_expiry_delta.map(|cltv_delta| cltv_delta as u16),
+            max_dust_htlc_exposure_msat: req.max_dust_htlc_exposure_msat,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for UpdateChannelConfigResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::UpdateChannelConfig { channel_ids } => Ok(Self { channel_ids }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<FeePolicy> for FeePolicyMessage {
+    fn from(policy: FeePolicy) -> Self {
+        Self {
+            enabled: policy.enabled,
+            min_fee_proportional_millionths: policy.min_fee_proportional_millionths,
+            max_fee_proportional_millionths: policy.max_fee_proportional_millionths,
+            forwarding_fee_base_msat: policy.forwarding_fee_base_msat,
+            interval_secs: policy.interval_secs,
+        }
+    }
+}
+
+impl From<FeePolicyMessage> for FeePolicy {
+    fn from(policy: FeePolicyMessage) -> Self {
+        Self {
+            enabled: policy.enabled,
+            min_fee_proportional_millionths: policy.min_fee_proportional_millionths,
+            max_fee_proportional_millionths: policy.max_fee_proportional_millionths,
+            forwarding_fee_base_msat: policy.forwarding_fee_base_msat,
+            interval_secs: policy.interval_secs,
+        }
+    }
+}
+
+impl From<GetFeePolicyRequest> for NodeRequest {
+    fn from(_req: GetFeePolicyRequest) -> Self {
+        NodeRequest::GetFeePolicy {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetFeePolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetFeePolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<SetFeePolicyRequest> for NodeRequest {
+    fn from(req: SetFeePolicyRequest) -> Self {
+        NodeRequest::SetFeePolicy {
+            policy: req.policy.map(|policy| policy.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for SetFeePolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::SetFeePolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<AutopilotPolicy> for AutopilotPolicyMessage {
+    fn from(policy: AutopilotPolicy) -> Self {
+        Self {
+            enabled: policy.enabled,
+            dry_run: policy.dry_run,
+            target_channels: policy.target_channels,
+            allocation_percent: policy.allocation_percent.into(),
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+            max_channels_per_run: policy.max_channels_per_run,
+            min_peer_channels: policy.min_peer_channels,
+            public_channels: policy.public_channels,
+            interval_secs: policy.interval_secs,
+        }
+    }
+}
+
+impl From<AutopilotPolicyMessage> for AutopilotPolicy {
+    fn from(policy: AutopilotPolicyMessage) -> Self {
+        Self {
+            enabled: policy.enabled,
+            dry_run: policy.dry_run,
+            target_channels: policy.target_channels,
+            // anything over 100 fails validation the same way
+            allocation_percent: policy.allocation_percent.try_into().unwrap_or(u8::MAX),
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+            max_channels_per_run: policy.max_channels_per_run,
+            min_peer_channels: policy.min_peer_channels,
+            public_channels: policy.public_channels,
+            interval_secs: policy.interval_secs,
+        }
+    }
+}
+
+impl From<GetAutopilotPolicyRequest> for NodeRequest {
+    fn from(_req: GetAutopilotPolicyRequest) -> Self {
+        NodeRequest::GetAutopilotPolicy {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetAutopilotPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetAutopilotPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<SetAutopilotPolicyRequest> for NodeRequest {
+    fn from(req: SetAutopilotPolicyRequest) -> Self {
+        NodeRequest::SetAutopilotPolicy {
+            policy: req.policy.map(|policy| policy.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for SetAutopilotPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::SetAutopilotPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<AutopilotChannel> for AutopilotChannelMessage {
+    fn from(channel: AutopilotChannel) -> Self {
+        Self {
+            counterparty_pubkey: channel.counterparty_pubkey,
+            counterparty_host_port: channel.counterparty_host_port,
+            amount_sats: channel.amount_sats,
+            peer_num_channels: channel.peer_num_channels,
+            peer_capacity_sats: channel.peer_capacity_sats,
+            channel_id: channel.channel_id,
+            error: channel.error,
+        }
+    }
+}
+
+impl From<RunAutopilotRequest> for NodeRequest {
+    fn from(req: RunAutopilotRequest) -> Self {
+        NodeRequest::RunAutopilot {
+            dry_run: req.dry_run,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for RunAutopilotResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::RunAutopilot { channels } => Ok(Self {
+                channels: channels
+                    .into_iter()
+                    .map(|channel| channel.into())
+                    .collect::<Vec<AutopilotChannelMessage>>(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ExportChannelBackupRequest> for NodeRequest {
+    fn from(_req: ExportChannelBackupRequest) -> Self {
+        NodeRequest::ExportChannelBackup {}
+    }
+}
+
+impl TryFrom<NodeResponse> for ExportChannelBackupResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::ExportChannelBackup { backup } => Ok(Self { backup }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<RecoveredChannel> for RecoveredChannelMessage {
+    fn from(channel: RecoveredChannel) -> Self {
+        Self {
+            channel_id: channel.channel_id,
+            counterparty_pubkey: channel.counterparty_pubkey,
+            connected: channel.connected,
+        }
+    }
+}
+
+impl From<RecoverFromBackupRequest> for NodeRequest {
+    fn from(req: RecoverFromBackupRequest) -> Self {
+        NodeRequest::RecoverFromBackup { backup: req.backup }
+    }
+}
+
+impl TryFrom<NodeResponse> for RecoverFromBackupResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::RecoverFromBackup { channels } => Ok(Self {
+                channels: channels
+                    .into_iter()
+                    .map(|channel| channel.into())
+                    .collect::<Vec<RecoveredChannelMessage>>(),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<JitChannelPolicy> for JitChannelPolicyMessage {
+    fn from(policy: JitChannelPolicy) -> Self {
+        Self {
+            enabled: policy.enabled,
+            opening_fee_base_msat: policy.opening_fee_base_msat,
+            opening_fee_proportional_millionths: policy.opening_fee_proportional_millionths,
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+        }
+    }
+}
+
+impl From<JitChannelPolicyMessage> for JitChannelPolicy {
+    fn from(policy: JitChannelPolicyMessage) -> Self {
+        Self {
+            enabled: policy.enabled,
+            opening_fee_base_msat: policy.opening_fee_base_msat,
+            opening_fee_proportional_millionths: policy.opening_fee_proportional_millionths,
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+        }
+    }
+}
+
+impl From<GetJitChannelPolicyRequest> for NodeRequest {
+    fn from(_req: GetJitChannelPolicyRequest) -> Self {
+        NodeRequest::GetJitChannelPolicy {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetJitChannelPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetJitChannelPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<SetJitChannelPolicyRequest> for NodeRequest {
+    fn from(req: SetJitChannelPolicyRequest) -> Self {
+        NodeRequest::SetJitChannelPolicy {
+            policy: req.policy.map(|policy| policy.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for SetJitChannelPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::SetJitChannelPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<CreateJitInvoiceRequest> for NodeRequest {
+    fn from(req: CreateJitInvoiceRequest) -> Self {
+        NodeRequest::CreateJitInvoice {
+            invoice: req.invoice,
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for CreateJitInvoiceResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::CreateJitInvoice {
+                invoice,
+                opening_fee_msat,
+                channel_size_sats,
+            } => Ok(Self {
+                invoice,
+                opening_fee_msat,
+                channel_size_sats,
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ChannelHealth> for ChannelHealthMessage {
+    fn from(channel: ChannelHealth) -> Self {
+        Self {
+            channel_id: channel.channel_id,
+            counterparty_pubkey: channel.counterparty_pubkey,
+            short_channel_id: channel.short_channel_id,
+            is_usable: channel.is_usable,
+            peer_connected: channel.peer_connected,
+            last_usable_at: channel.last_usable_at,
+            unusable_secs: channel.unusable_secs,
+            reconnect_attempts: channel.reconnect_attempts,
+            last_error: channel.last_error,
+            healthy: channel.healthy,
+        }
+    }
+}
+
+impl From<PeerHealth> for PeerHealthMessage {
+    fn from(peer: PeerHealth) -> Self {
+        Self {
+            pubkey: peer.pubkey,
+            connected: peer.connected,
+            offline_secs: peer.offline_secs,
+            reconnect_attempts: peer.reconnect_attempts,
+            last_error: peer.last_error,
+            num_channels: peer.num_channels,
+            offline: peer.offline,
+        }
+    }
+}
+
+impl From<GetHealthReportRequest> for NodeRequest {
+    fn from(_req: GetHealthReportRequest) -> Self {
+        NodeRequest::GetHealthReport {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetHealthReportResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetHealthReport { report } => Ok(Self {
+                channels: report
+                    .channels
+                    .into_iter()
+                    .map(|channel| channel.into())
+                    .collect(),
+                peers: report.peers.into_iter().map(|peer| peer.into()).collect(),
+                unhealthy_channels: report.unhealthy_channels,
+                offline_peers: report.offline_peers,
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<HealthPolicy> for HealthPolicyMessage {
+    fn from(policy: HealthPolicy) -> Self {
+        Self {
+            channel_unhealthy_after_secs: policy.channel_unhealthy_after_secs,
+            peer_offline_after_secs: policy.peer_offline_after_secs,
+        }
+    }
+}
+
+impl From<HealthPolicyMessage> for HealthPolicy {
+    fn from(policy: HealthPolicyMessage) -> Self {
+        Self {
+            channel_unhealthy_after_secs: policy.channel_unhealthy_after_secs,
+            peer_offline_after_secs: policy.peer_offline_after_secs,
+        }
+    }
+}
+
+impl From<GetHealthPolicyRequest> for NodeRequest {
+    fn from(_req: GetHealthPolicyRequest) -> Self {
+        NodeRequest::GetHealthPolicy {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetHealthPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetHealthPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<SetHealthPolicyRequest> for NodeRequest {
+    fn from(req: SetHealthPolicyRequest) -> Self {
+        NodeRequest::SetHealthPolicy {
+            policy: req.policy.map(|policy| policy.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for SetHealthPolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::SetHealthPolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<ChannelAcceptancePolicy> for ChannelAcceptancePolicyMessage {
+    fn from(policy: ChannelAcceptancePolicy) -> Self {
+        Self {
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+            min_confirmations: policy.min_confirmations,
+            allowed_peers: policy.allowed_peers,
+            denied_peers: policy.denied_peers,
+            max_pending_channels: policy.max_pending_channels,
+            allow_private_channels: policy.allow_private_channels,
+            webhook_url: policy.webhook_url,
+            webhook_timeout_secs: Some(policy.webhook_timeout_secs),
+            webhook_fallback: Some(policy.webhook_fallback.to_string()),
+        }
+    }
+}
+
+impl From<ChannelAcceptancePolicyMessage> for ChannelAcceptancePolicy {
+    fn from(policy: ChannelAcceptancePolicyMessage) -> Self {
+        Self {
+            min_channel_size_sats: policy.min_channel_size_sats,
+            max_channel_size_sats: policy.max_channel_size_sats,
+            min_confirmations: policy.min_confirmations,
+            allowed_peers: policy.allowed_peers,
+            denied_peers: policy.denied_peers,
+            max_pending_channels: policy.max_pending_channels,
+            allow_private_channels: policy.allow_private_channels,
+            webhook_url: policy.webhook_url,
+            webhook_timeout_secs: policy
+                .webhook_timeout_secs
+                .unwrap_or(DEFAULT_WEBHOOK_TIMEOUT_SECS),
+            // an unknown fallback rejects rather than accepting channels nobody vetted
+            webhook_fallback: policy
+                .webhook_fallback
+                .and_then(|fallback| fallback.parse().ok())
+                .unwrap_or(ChannelAcceptanceDecision::Reject),
+        }
+    }
+}
+
+impl From<GetChannelAcceptancePolicyRequest> for NodeRequest {
+    fn from(_req: GetChannelAcceptancePolicyRequest) -> Self {
+        NodeRequest::GetChannelAcceptancePolicy {}
+    }
+}
+
+impl TryFrom<NodeResponse> for GetChannelAcceptancePolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::GetChannelAcceptancePolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
+            _ => Err("impossible".to_string()),
+        }
+    }
+}
+
+impl From<SetChannelAcceptancePolicyRequest> for NodeRequest {
+    fn from(req: SetChannelAcceptancePolicyRequest) -> Self {
+        NodeRequest::SetChannelAcceptancePolicy {
+            policy: req.policy.map(|policy| policy.into()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<NodeResponse> for SetChannelAcceptancePolicyResponse {
+    type Error = String;
+
+    fn try_from(res: NodeResponse) -> Result<Self, Self::Error> {
+        match res {
+            NodeResponse::SetChannelAcceptancePolicy { policy } => Ok(Self {
+                policy: Some(policy.into()),
+            }),
             _ => Err("impossible".to_string()),
         }
     }
@@ -13,20 +13,40 @@ pub use super::sensei::node_server::{Node, NodeServer};
 
 use super::{
     sensei::{
-        AddKnownPeerRequest, AddKnownPeerResponse, CloseChannelRequest, CloseChannelResponse,
-        ConnectPeerRequest, ConnectPeerResponse, CreateInvoiceRequest, CreateInvoiceResponse,
-        CreatePhantomInvoiceRequest, CreatePhantomInvoiceResponse, DecodeInvoiceRequest,
-        DecodeInvoiceResponse, DeletePaymentRequest, DeletePaymentResponse, GetBalanceRequest,
-        GetBalanceResponse, GetPhantomRouteHintsRequest, GetPhantomRouteHintsResponse,
-        GetUnusedAddressRequest, GetUnusedAddressResponse, InfoRequest, InfoResponse,
-        KeysendRequest, KeysendResponse, LabelPaymentRequest, LabelPaymentResponse,
-        ListChannelsRequest, ListChannelsResponse, ListKnownPeersRequest, ListKnownPeersResponse,
-        ListPaymentsRequest, ListPaymentsResponse, ListPeersRequest, ListPeersResponse,
-        ListPhantomPaymentsRequest, ListPhantomPaymentsResponse, ListUnspentRequest,
-        ListUnspentResponse, NetworkGraphInfoRequest, NetworkGraphInfoResponse,
+        AddKnownPeerRequest, AddKnownPeerResponse, CancelInvoiceRequest, CancelInvoiceResponse,
+        CloseChannelRequest, CloseChannelResponse, ConnectPeerRequest, ConnectPeerResponse,
+        CreateInvoiceRequest, CreateInvoiceResponse, CreateJitInvoiceRequest,
+        CreateJitInvoiceResponse, CreatePhantomInvoiceRequest, CreatePhantomInvoiceResponse,
+        CreateScheduledPaymentRequest, CreateScheduledPaymentResponse, DecodeInvoiceRequest,
+        DecodeInvoiceResponse, DeletePaymentRequest, DeletePaymentResponse,
+        DeleteScheduledPaymentRequest, DeleteScheduledPaymentResponse, ExportChannelBackupRequest,
+        ExportChannelBackupResponse, ExportLedgerRequest, ExportLedgerResponse,
+        GetAutopilotPolicyRequest, GetAutopilotPolicyResponse, GetBalanceHistoryRequest,
+        GetBalanceHistoryResponse, GetBalanceRequest, GetBalanceResponse,
+        GetChannelAcceptancePolicyRequest, GetChannelAcceptancePolicyResponse,
+        GetClaimableBalancesRequest, GetClaimableBalancesResponse, GetFeePolicyRequest,
+        GetFeePolicyResponse, GetHealthPolicyRequest, GetHealthPolicyResponse,
+        GetHealthReportRequest, GetHealthReportResponse, GetJitChannelPolicyRequest,
+        GetJitChannelPolicyResponse, GetPaymentAttemptsRequest, GetPaymentAttemptsResponse,
+        GetPhantomRouteHintsRequest, GetPhantomRouteHintsResponse, GetRoutingRevenueRequest,
+        GetRoutingRevenueResponse, GetUnusedAddressRequest, GetUnusedAddressResponse, InfoRequest,
+        InfoResponse, KeysendRequest, KeysendResponse, LabelPaymentRequest, LabelPaymentResponse,
+        ListChannelsRequest, ListChannelsResponse, ListForwardedPaymentsRequest,
+        ListForwardedPaymentsResponse, ListHtlcsRequest, ListHtlcsResponse, ListInvoicesRequest,
+        ListInvoicesResponse, ListKnownPeersRequest, ListKnownPeersResponse, ListPaymentsRequest,
+        ListPaymentsResponse, ListPeersRequest, ListPeersResponse, ListPhantomPaymentsRequest,
+        ListPhantomPaymentsResponse, ListScheduledPaymentsRequest, ListScheduledPaymentsResponse,
+        ListUnspentRequest, ListUnspentResponse, NetworkGraphInfoRequest, NetworkGraphInfoResponse,
         OpenChannelsRequest, OpenChannelsResponse, PayInvoiceRequest, PayInvoiceResponse,
-        RemoveKnownPeerRequest, RemoveKnownPeerResponse, SignMessageRequest, SignMessageResponse,
-        StartNodeRequest, StartNodeResponse, StopNodeRequest, StopNodeResponse,
+        RebalanceRequest, RebalanceResponse, RecoverFromBackupRequest, RecoverFromBackupResponse,
+        RemoveKnownPeerRequest, RemoveKnownPeerResponse, RestrictMacaroonRequest,
+        RestrictMacaroonResponse, RunAutopilotRequest, RunAutopilotResponse,
+        SetAutopilotPolicyRequest, SetAutopilotPolicyResponse, SetChannelAcceptancePolicyRequest,
+        SetChannelAcceptancePolicyResponse, SetFeePolicyRequest, SetFeePolicyResponse,
+        SetHealthPolicyRequest, SetHealthPolicyResponse, SetJitChannelPolicyRequest,
+        SetJitChannelPolicyResponse, SignMessageRequest, SignMessageResponse, StartNodeRequest,
+        StartNodeResponse, StopNodeRequest, StopNodeResponse, UpdateChannelConfigRequest,
+        UpdateChannelConfigResponse, UpdateScheduledPaymentRequest, UpdateScheduledPaymentResponse,
         VerifyMessageRequest, VerifyMessageResponse,
     },
     utils::raw_macaroon_from_metadata,
@@ -62,7 +82,7 @@ impl NodeService {
 
                 match node_directory.get(&session.pubkey) {
                     Some(Some(handle)) => {
-                        handle
+                        let limits = handle
                             .node
                             .verify_macaroon(macaroon, session)
                             .await
@@ -83,7 +103,7 @@ impl NodeService {
                             }
                             _ => handle
                                 .node
-                                .call(request)
+                                .call_with_limits(request, limits)
                                 .await
                                 .map_err(|_e| Status::unknown("error")),
                         }
@@ -386,4 +406,314 @@ impl Node for NodeService {
             .map(Response::new)
             .map_err(|_e| Status::unknown("unknown error"))
     }
+    async fn list_forwarded_payments(
+        &self,
+        request: tonic::Request<ListForwardedPaymentsRequest>,
+    ) -> Result<Response<ListForwardedPaymentsResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_routing_revenue(
+        &self,
+        request: tonic::Request<GetRoutingRevenueRequest>,
+    ) -> Result<Response<GetRoutingRevenueResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_payment_attempts(
+        &self,
+        request: tonic::Request<GetPaymentAttemptsRequest>,
+    ) -> Result<Response<GetPaymentAttemptsResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn list_invoices(
+        &self,
+        request: tonic::Request<ListInvoicesRequest>,
+    ) -> Result<Response<ListInvoicesResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn cancel_invoice(
+        &self,
+        request: tonic::Request<CancelInvoiceRequest>,
+    ) -> Result<Response<CancelInvoiceResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn restrict_macaroon(
+        &self,
+        request: tonic::Request<RestrictMacaroonRequest>,
+    ) -> Result<Response<RestrictMacaroonResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn create_scheduled_payment(
+        &self,
+        request: tonic::Request<CreateScheduledPaymentRequest>,
+    ) -> Result<Response<CreateScheduledPaymentResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn list_scheduled_payments(
+        &self,
+        request: tonic::Request<ListScheduledPaymentsRequest>,
+    ) -> Result<Response<ListScheduledPaymentsResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn update_scheduled_payment(
+        &self,
+        request: tonic::Request<UpdateScheduledPaymentRequest>,
+    ) -> Result<Response<UpdateScheduledPaymentResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn delete_scheduled_payment(
+        &self,
+        request: tonic::Request<DeleteScheduledPaymentRequest>,
+    ) -> Result<Response<DeleteScheduledPaymentResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn export_ledger(
+        &self,
+        request: tonic::Request<ExportLedgerRequest>,
+    ) -> Result<Response<ExportLedgerResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_balance_history(
+        &self,
+        request: tonic::Request<GetBalanceHistoryRequest>,
+    ) -> Result<Response<GetBalanceHistoryResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn update_channel_config(
+        &self,
+        request: tonic::Request<UpdateChannelConfigRequest>,
+    ) -> Result<Response<UpdateChannelConfigResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_fee_policy(
+        &self,
+        request: tonic::Request<GetFeePolicyRequest>,
+    ) -> Result<Response<GetFeePolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn set_fee_policy(
+        &self,
+        request: tonic::Request<SetFeePolicyRequest>,
+    ) -> Result<Response<SetFeePolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_channel_acceptance_policy(
+        &self,
+        request: tonic::Request<GetChannelAcceptancePolicyRequest>,
+    ) -> Result<Response<GetChannelAcceptancePolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn set_channel_acceptance_policy(
+        &self,
+        request: tonic::Request<SetChannelAcceptancePolicyRequest>,
+    ) -> Result<Response<SetChannelAcceptancePolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_autopilot_policy(
+        &self,
+        request: tonic::Request<GetAutopilotPolicyRequest>,
+    ) -> Result<Response<GetAutopilotPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn set_autopilot_policy(
+        &self,
+        request: tonic::Request<SetAutopilotPolicyRequest>,
+    ) -> Result<Response<SetAutopilotPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn run_autopilot(
+        &self,
+        request: tonic::Request<RunAutopilotRequest>,
+    ) -> Result<Response<RunAutopilotResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn export_channel_backup(
+        &self,
+        request: tonic::Request<ExportChannelBackupRequest>,
+    ) -> Result<Response<ExportChannelBackupResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn recover_from_backup(
+        &self,
+        request: tonic::Request<RecoverFromBackupRequest>,
+    ) -> Result<Response<RecoverFromBackupResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn rebalance(
+        &self,
+        request: tonic::Request<RebalanceRequest>,
+    ) -> Result<Response<RebalanceResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_claimable_balances(
+        &self,
+        request: tonic::Request<GetClaimableBalancesRequest>,
+    ) -> Result<Response<GetClaimableBalancesResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn list_htlcs(
+        &self,
+        request: tonic::Request<ListHtlcsRequest>,
+    ) -> Result<Response<ListHtlcsResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_jit_channel_policy(
+        &self,
+        request: tonic::Request<GetJitChannelPolicyRequest>,
+    ) -> Result<Response<GetJitChannelPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn set_jit_channel_policy(
+        &self,
+        request: tonic::Request<SetJitChannelPolicyRequest>,
+    ) -> Result<Response<SetJitChannelPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_health_report(
+        &self,
+        request: tonic::Request<GetHealthReportRequest>,
+    ) -> Result<Response<GetHealthReportResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn get_health_policy(
+        &self,
+        request: tonic::Request<GetHealthPolicyRequest>,
+    ) -> Result<Response<GetHealthPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn set_health_policy(
+        &self,
+        request: tonic::Request<SetHealthPolicyRequest>,
+    ) -> Result<Response<SetHealthPolicyResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
+    async fn create_jit_invoice(
+        &self,
+        request: tonic::Request<CreateJitInvoiceRequest>,
+    ) -> Result<Response<CreateJitInvoiceResponse>, Status> {
+        self.authenticated_request(request.metadata().clone(), request.into_inner().into())
+            .await?
+            .try_into()
+            .map(Response::new)
+            .map_err(|_e| Status::unknown("unknown error"))
+    }
 }
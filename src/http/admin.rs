@@ -27,6 +27,7 @@ use senseicore::{
         PaginationRequest,
     },
     utils,
+    watchtower::WatchtowerBlob,
 };
 
 use super::{auth_header::AuthHeader, utils::get_macaroon_hex_str_from_cookies_or_header};
@@ -280,6 +281,21 @@ impl From<CreateAdminParams> for AdminRequest {
     }
 }
 
+#[derive(Deserialize)]
+pub struct StoreWatchtowerBlobsParams {
+    pub client_pubkey: String,
+    pub blobs: Vec<WatchtowerBlob>,
+}
+
+impl From<StoreWatchtowerBlobsParams> for AdminRequest {
+    fn from(params: StoreWatchtowerBlobsParams) -> Self {
+        Self::StoreWatchtowerBlobs {
+            client_pubkey: params.client_pubkey,
+            blobs: params.blobs,
+        }
+    }
+}
+
 pub fn get_token_from_cookies_or_header(
     cookies: &Cookies,
     token: Option<HeaderValue>,
@@ -370,6 +386,32 @@ pub fn add_routes(router: Router) -> Router {
             post(gossip_channel_update),
         )
         .route("/v1/ldk/network/graph", get(get_network_graph))
+        .route("/v1/watchtower/blobs", post(store_watchtower_blobs))
+}
+
+pub async fn store_watchtower_blobs(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    cookies: Cookies,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon: _, token }: AuthHeader,
+) -> Result<Json<AdminResponse>, StatusCode> {
+    let authenticated = authenticate_request(&admin_service, "watchtower", &cookies, token).await?;
+    let request = {
+        let params: Result<StoreWatchtowerBlobsParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err(StatusCode::UNPROCESSABLE_ENTITY),
+        }
+    }?;
+
+    if authenticated {
+        match admin_service.call(request).await {
+            Ok(response) => Ok(Json(response)),
+            Err(_err) => Err(StatusCode::UNPROCESSABLE_ENTITY),
+        }
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
 }
 
 pub async fn get_network_graph(
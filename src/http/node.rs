@@ -12,14 +12,21 @@ use std::sync::Arc;
 use crate::http::auth_header::AuthHeader;
 use crate::AdminService;
 use axum::extract::{Extension, Json, Query};
-use axum::response::{IntoResponse, Response};
+use axum::response::{Headers, IntoResponse, Response};
 use axum::routing::{delete, get, post};
 use axum::Router;
-use http::{HeaderValue, StatusCode};
+use http::{header, HeaderValue, StatusCode};
+use senseicore::autopilot::AutopilotPolicy;
+use senseicore::channel_acceptance::ChannelAcceptancePolicy;
+use senseicore::fee_manager::FeePolicy;
+use senseicore::health::HealthPolicy;
+use senseicore::jit_channels::JitChannelPolicy;
+use senseicore::ledger::LedgerFormat;
 use senseicore::services::admin::AdminRequest;
 use senseicore::services::node::{NodeRequest, NodeRequestError, NodeResponse, OpenChannelRequest};
 use senseicore::services::{
-    ListChannelsParams, ListKnownPeersParams, ListPaymentsParams, ListTransactionsParams,
+    ListChannelsParams, ListForwardedPaymentsParams, ListInvoicesParams, ListKnownPeersParams,
+    ListPaymentsParams, ListScheduledPaymentsParams, ListTransactionsParams,
 };
 use senseicore::utils;
 use serde::Deserialize;
@@ -30,22 +37,33 @@ use super::utils::get_macaroon_hex_str_from_cookies_or_header;
 
 #[derive(Deserialize)]
 pub struct GetInvoiceParams {
-    pub amt_msat: u64,
+    pub amt_msat: Option<u64>,
+    pub min_amt_msat: Option<u64>,
     pub description: String,
+    pub description_hash: Option<String>,
+    pub expiry_secs: Option<u32>,
+    pub min_final_cltv_expiry: Option<u64>,
+    pub private_route_hints: Option<bool>,
 }
 
 impl From<GetInvoiceParams> for NodeRequest {
     fn from(params: GetInvoiceParams) -> Self {
         Self::GetInvoice {
             amt_msat: params.amt_msat,
+            min_amt_msat: params.min_amt_msat,
             description: params.description,
+            description_hash: params.description_hash,
+            expiry_secs: params.expiry_secs,
+            min_final_cltv_expiry: params.min_final_cltv_expiry,
+            private_route_hints: params.private_route_hints,
         }
     }
 }
 
 #[derive(Deserialize)]
 pub struct GetPhantomInvoiceParams {
-    pub amt_msat: u64,
+    pub amt_msat: Option<u64>,
+    pub min_amt_msat: Option<u64>,
     pub description: String,
     pub phantom_route_hints_hex: Vec<String>,
 }
@@ -54,6 +72,7 @@ impl From<GetPhantomInvoiceParams> for NodeRequest {
     fn from(params: GetPhantomInvoiceParams) -> Self {
         Self::GetPhantomInvoice {
             amt_msat: params.amt_msat,
+            min_amt_msat: params.min_amt_msat,
             description: params.description,
             phantom_route_hints_hex: params.phantom_route_hints_hex,
         }
@@ -88,6 +107,141 @@ impl From<DeletePaymentParams> for NodeRequest {
     }
 }
 
+#[derive(Deserialize)]
+pub struct CancelInvoiceParams {
+    pub payment_hash: String,
+}
+
+impl From<CancelInvoiceParams> for NodeRequest {
+    fn from(params: CancelInvoiceParams) -> Self {
+        Self::CancelInvoice {
+            payment_hash: params.payment_hash,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RestrictMacaroonParams {
+    pub macaroon: String,
+    pub max_payment_msat: Option<u64>,
+    pub max_daily_msat: Option<u64>,
+    pub max_monthly_msat: Option<u64>,
+    pub allowed_destinations: Option<Vec<String>>,
+}
+
+impl From<RestrictMacaroonParams> for NodeRequest {
+    fn from(params: RestrictMacaroonParams) -> Self {
+        Self::RestrictMacaroon {
+            macaroon: params.macaroon,
+            max_payment_msat: params.max_payment_msat,
+            max_daily_msat: params.max_daily_msat,
+            max_monthly_msat: params.max_monthly_msat,
+            allowed_destinations: params.allowed_destinations,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateScheduledPaymentParams {
+    pub kind: String,
+    pub dest_pubkey: Option<String>,
+    pub invoice_url: Option<String>,
+    pub amt_msat: u64,
+    pub interval_secs: u64,
+    pub start_at: Option<i64>,
+}
+
+impl From<CreateScheduledPaymentParams> for NodeRequest {
+    fn from(params: CreateScheduledPaymentParams) -> Self {
+        Self::CreateScheduledPayment {
+            kind: params.kind,
+            dest_pubkey: params.dest_pubkey,
+            invoice_url: params.invoice_url,
+            amt_msat: params.amt_msat,
+            interval_secs: params.interval_secs,
+            start_at: params.start_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateScheduledPaymentParams {
+    pub id: String,
+    pub amt_msat: Option<u64>,
+    pub interval_secs: Option<u64>,
+    pub enabled: Option<bool>,
+}
+
+impl From<UpdateScheduledPaymentParams> for NodeRequest {
+    fn from(params: UpdateScheduledPaymentParams) -> Self {
+        Self::UpdateScheduledPayment {
+            id: params.id,
+            amt_msat: params.amt_msat,
+            interval_secs: params.interval_secs,
+            enabled: params.enabled,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeleteScheduledPaymentParams {
+    pub id: String,
+}
+
+impl From<DeleteScheduledPaymentParams> for NodeRequest {
+    fn from(params: DeleteScheduledPaymentParams) -> Self {
+        Self::DeleteScheduledPayment { id: params.id }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListHtlcsParams {
+    pub channel_id: Option<String>,
+}
+
+impl From<ListHtlcsParams> for NodeRequest {
+    fn from(params: ListHtlcsParams) -> Self {
+        Self::ListHtlcs {
+            channel_id: params.channel_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GetBalanceHistoryParams {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub interval: Option<u64>,
+}
+
+impl From<GetBalanceHistoryParams> for NodeRequest {
+    fn from(params: GetBalanceHistoryParams) -> Self {
+        Self::GetBalanceHistory {
+            from: params.from,
+            to: params.to,
+            interval: params.interval,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportLedgerParams {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub format: LedgerFormat,
+}
+
+impl From<ExportLedgerParams> for NodeRequest {
+    fn from(params: ExportLedgerParams) -> Self {
+        Self::ExportLedger {
+            from: params.from,
+            to: params.to,
+            format: params.format.to_string(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct BatchOpenChannelParams {
     requests: Vec<OpenChannelRequest>,
@@ -104,12 +258,14 @@ impl From<BatchOpenChannelParams> for NodeRequest {
 #[derive(Deserialize)]
 pub struct SendPaymentParams {
     pub invoice: String,
+    pub idempotency_key: Option<String>,
 }
 
 impl From<SendPaymentParams> for NodeRequest {
     fn from(params: SendPaymentParams) -> Self {
         Self::SendPayment {
             invoice: params.invoice,
+            idempotency_key: params.idempotency_key,
         }
     }
 }
@@ -131,6 +287,7 @@ impl From<DecodeInvoiceParams> for NodeRequest {
 pub struct KeysendParams {
     pub dest_pubkey: String,
     pub amt_msat: u64,
+    pub idempotency_key: Option<String>,
 }
 
 impl From<KeysendParams> for NodeRequest {
@@ -138,6 +295,26 @@ impl From<KeysendParams> for NodeRequest {
         Self::Keysend {
             dest_pubkey: params.dest_pubkey,
             amt_msat: params.amt_msat,
+            idempotency_key: params.idempotency_key,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RebalanceParams {
+    pub from_channel_id: String,
+    pub to_channel_id: String,
+    pub amt_msat: u64,
+    pub max_fee_msat: u64,
+}
+
+impl From<RebalanceParams> for NodeRequest {
+    fn from(params: RebalanceParams) -> Self {
+        Self::Rebalance {
+            from_channel_id: params.from_channel_id,
+            to_channel_id: params.to_channel_id,
+            amt_msat: params.amt_msat,
+            max_fee_msat: params.max_fee_msat,
         }
     }
 }
@@ -159,6 +336,8 @@ impl From<ConnectPeerParams> for NodeRequest {
 pub struct CloseChannelParams {
     pub channel_id: String,
     pub force: bool,
+    pub destination_address: Option<String>,
+    pub target_feerate_sats_per_vbyte: Option<u32>,
 }
 
 impl From<CloseChannelParams> for NodeRequest {
@@ -166,6 +345,73 @@ impl From<CloseChannelParams> for NodeRequest {
         Self::CloseChannel {
             channel_id: params.channel_id,
             force: params.force,
+            destination_address: params.destination_address,
+            target_feerate_sats_per_vbyte: params.target_feerate_sats_per_vbyte,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecoverFromBackupParams {
+    pub backup: String,
+}
+
+impl From<RecoverFromBackupParams> for NodeRequest {
+    fn from(params: RecoverFromBackupParams) -> Self {
+        Self::RecoverFromBackup {
+            backup: params.backup,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateJitInvoiceParams {
+    pub invoice: String,
+}
+
+impl From<CreateJitInvoiceParams> for NodeRequest {
+    fn from(params: CreateJitInvoiceParams) -> Self {
+        Self::CreateJitInvoice {
+            invoice: params.invoice,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RunAutopilotParams {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl From<RunAutopilotParams> for NodeRequest {
+    fn from(params: RunAutopilotParams) -> Self {
+        Self::RunAutopilot {
+            dry_run: params.dry_run,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateChannelConfigParams {
+    #[serde(default)]
+    pub channel_ids: Vec<String>,
+    #[serde(default)]
+    pub all: bool,
+    pub forwarding_fee_base_msat: Option<u32>,
+    pub forwarding_fee_proportional_millionths: Option<u32>,
+    pub cltv_expiry_delta: Option<u16>,
+    pub max_dust_htlc_exposure_msat: Option<u64>,
+}
+
+impl From<UpdateChannelConfigParams> for NodeRequest {
+    fn from(params: UpdateChannelConfigParams) -> Self {
+        Self::UpdateChannelConfig {
+            channel_ids: params.channel_ids,
+            all: params.all,
+            forwarding_fee_base_msat: params.forwarding_fee_base_msat,
+            forwarding_fee_proportional_millionths: params.forwarding_fee_proportional_millionths,
+            cltv_expiry_delta: params.cltv_expiry_delta,
+            max_dust_htlc_exposure_msat: params.max_dust_htlc_exposure_msat,
         }
     }
 }
@@ -241,6 +487,19 @@ impl From<RemoveKnownPeerParams> for NodeRequest {
     }
 }
 
+#[derive(Deserialize)]
+pub struct GetPaymentAttemptsParams {
+    pub payment_hash: String,
+}
+
+impl From<GetPaymentAttemptsParams> for NodeRequest {
+    fn from(params: GetPaymentAttemptsParams) -> Self {
+        Self::GetPaymentAttempts {
+            payment_hash: params.payment_hash,
+        }
+    }
+}
+
 pub fn add_routes(router: Router) -> Router {
     router
         .route("/v1/node/payments", get(handle_get_payments))
@@ -248,10 +507,27 @@ pub fn add_routes(router: Router) -> Router {
             "/v1/node/phantom-payments",
             get(handle_get_phantom_payments),
         )
+        .route(
+            "/v1/node/forwarded-payments",
+            get(handle_get_forwarded_payments),
+        )
+        .route(
+            "/v1/node/forwarded-payments/revenue",
+            get(handle_get_routing_revenue),
+        )
         .route("/v1/node/wallet/address", get(get_unused_address))
         .route("/v1/node/wallet/balance", get(get_wallet_balance))
+        .route(
+            "/v1/node/wallet/balance/claimable",
+            get(get_claimable_balances),
+        )
+        .route(
+            "/v1/node/wallet/balance/history",
+            get(get_wallet_balance_history),
+        )
         .route("/v1/node/wallet/utxos", get(list_unspent))
         .route("/v1/node/channels", get(get_channels))
+        .route("/v1/node/channels/htlcs", get(get_htlcs))
         .route("/v1/node/transactions", get(get_transactions))
         .route("/v1/node/info", get(get_info))
         .route("/v1/node/peers", get(get_peers))
@@ -263,8 +539,52 @@ pub fn add_routes(router: Router) -> Router {
         .route("/v1/node/invoices/decode", post(decode_invoice))
         .route("/v1/node/payments/label", post(label_payment))
         .route("/v1/node/payments/delete", post(delete_payment))
+        .route("/v1/node/payments/attempts", get(get_payment_attempts))
+        .route("/v1/node/invoices", get(handle_get_invoices))
+        .route("/v1/node/invoices/cancel", post(cancel_invoice))
+        .route("/v1/node/macaroon/restrict", post(restrict_macaroon))
+        .route(
+            "/v1/node/scheduled-payments",
+            get(handle_get_scheduled_payments),
+        )
+        .route(
+            "/v1/node/scheduled-payments",
+            post(create_scheduled_payment),
+        )
+        .route(
+            "/v1/node/scheduled-payments/update",
+            post(update_scheduled_payment),
+        )
+        .route(
+            "/v1/node/scheduled-payments/delete",
+            post(delete_scheduled_payment),
+        )
+        .route("/v1/node/ledger", get(export_ledger))
         .route("/v1/node/channels/open", post(open_channels))
         .route("/v1/node/channels/close", post(close_channel))
+        .route("/v1/node/channels/config", post(update_channel_config))
+        .route("/v1/node/channels/rebalance", post(rebalance))
+        .route("/v1/node/fee-policy", get(get_fee_policy))
+        .route("/v1/node/fee-policy", post(set_fee_policy))
+        .route("/v1/node/autopilot-policy", get(get_autopilot_policy))
+        .route("/v1/node/autopilot-policy", post(set_autopilot_policy))
+        .route("/v1/node/autopilot/run", post(run_autopilot))
+        .route("/v1/node/channels/backup", get(export_channel_backup))
+        .route("/v1/node/channels/recover", post(recover_from_backup))
+        .route("/v1/node/jit-channel-policy", get(get_jit_channel_policy))
+        .route("/v1/node/jit-channel-policy", post(set_jit_channel_policy))
+        .route("/v1/node/invoices/jit", post(create_jit_invoice))
+        .route("/v1/node/health", get(get_health_report))
+        .route("/v1/node/health-policy", get(get_health_policy))
+        .route("/v1/node/health-policy", post(set_health_policy))
+        .route(
+            "/v1/node/channel-acceptance-policy",
+            get(get_channel_acceptance_policy),
+        )
+        .route(
+            "/v1/node/channel-acceptance-policy",
+            post(set_channel_acceptance_policy),
+        )
         .route("/v1/node/keysend", post(keysend))
         .route("/v1/node/peers/connect", post(connect_peer))
         .route("/v1/node/sign/message", post(sign_message))
@@ -312,6 +632,29 @@ pub async fn get_wallet_balance(
     handle_authenticated_request(admin_service, NodeRequest::GetBalance {}, macaroon, cookies).await
 }
 
+pub async fn get_claimable_balances(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetClaimableBalances {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn get_wallet_balance_history(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<GetBalanceHistoryParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(admin_service, params.into(), macaroon, cookies).await
+}
+
 pub async fn handle_get_payments(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Query(params): Query<ListPaymentsParams>,
@@ -326,6 +669,20 @@ pub async fn handle_get_payments(
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn handle_get_invoices(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ListInvoicesParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = NodeRequest::ListInvoices {
+        pagination: params.clone().into(),
+        filter: params.into(),
+    };
+
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
 pub async fn handle_get_phantom_payments(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Query(params): Query<ListPaymentsParams>,
@@ -340,6 +697,42 @@ pub async fn handle_get_phantom_payments(
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn handle_get_forwarded_payments(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ListForwardedPaymentsParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = NodeRequest::ListForwardedPayments {
+        pagination: params.clone().into(),
+        filter: params.into(),
+    };
+
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn handle_get_routing_revenue(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ListForwardedPaymentsParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = NodeRequest::GetRoutingRevenue {
+        filter: params.into(),
+    };
+
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_payment_attempts(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<GetPaymentAttemptsParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(admin_service, params.into(), macaroon, cookies).await
+}
+
 pub async fn get_channels(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Query(params): Query<ListChannelsParams>,
@@ -348,11 +741,21 @@ pub async fn get_channels(
 ) -> Result<Json<NodeResponse>, Response> {
     let request = NodeRequest::ListChannels {
         pagination: params.clone().into(),
+        filter: params.into(),
     };
 
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn get_htlcs(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ListHtlcsParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(admin_service, params.into(), macaroon, cookies).await
+}
+
 pub async fn get_transactions(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Query(params): Query<ListTransactionsParams>,
@@ -407,7 +810,7 @@ pub async fn handle_authenticated_request(
 
     match node {
         Some(Some(handle)) => {
-            handle
+            let limits = handle
                 .node
                 .verify_macaroon(macaroon, session)
                 .await
@@ -421,7 +824,7 @@ pub async fn handle_authenticated_request(
                     })?;
                     Ok(Json(NodeResponse::StopNode {}))
                 }
-                _ => match handle.node.call(request).await {
+                _ => match handle.node.call_with_limits(request, limits).await {
                     Ok(response) => Ok(Json(response)),
                     Err(err) => {
                         let error_message = match err {
@@ -547,6 +950,126 @@ pub async fn delete_payment(
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn cancel_invoice(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<CancelInvoiceParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn restrict_macaroon(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<RestrictMacaroonParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn handle_get_scheduled_payments(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ListScheduledPaymentsParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = NodeRequest::ListScheduledPayments {
+        pagination: params.into(),
+    };
+
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn export_ledger(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Query(params): Query<ExportLedgerParams>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Response, Response> {
+    let format = params.format.clone();
+    let Json(response) =
+        handle_authenticated_request(admin_service, params.into(), macaroon, cookies).await?;
+
+    match response {
+        NodeResponse::ExportLedger { ledger } => {
+            let disposition = format!("attachment; filename=\"ledger.{}\"", format);
+            Ok((
+                StatusCode::OK,
+                Headers([
+                    (header::CONTENT_TYPE, format.content_type().to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ]),
+                ledger,
+            )
+                .into_response())
+        }
+        _ => Err((StatusCode::INTERNAL_SERVER_ERROR, "unexpected response").into_response()),
+    }
+}
+
+pub async fn create_scheduled_payment(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<CreateScheduledPaymentParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn update_scheduled_payment(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<UpdateScheduledPaymentParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn delete_scheduled_payment(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<DeleteScheduledPaymentParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
 pub async fn pay_invoice(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Json(payload): Json<Value>,
@@ -611,6 +1134,248 @@ pub async fn close_channel(
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn update_channel_config(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<UpdateChannelConfigParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_fee_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetFeePolicy {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn set_fee_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let policy: Result<FeePolicy, _> = serde_json::from_value(payload);
+        match policy {
+            Ok(policy) => Ok(NodeRequest::SetFeePolicy { policy }),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_autopilot_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetAutopilotPolicy {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn set_autopilot_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let policy: Result<AutopilotPolicy, _> = serde_json::from_value(payload);
+        match policy {
+            Ok(policy) => Ok(NodeRequest::SetAutopilotPolicy { policy }),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn run_autopilot(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<RunAutopilotParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn export_channel_backup(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::ExportChannelBackup {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn recover_from_backup(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<RecoverFromBackupParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_jit_channel_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetJitChannelPolicy {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn set_jit_channel_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let policy: Result<JitChannelPolicy, _> = serde_json::from_value(payload);
+        match policy {
+            Ok(policy) => Ok(NodeRequest::SetJitChannelPolicy { policy }),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_health_report(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetHealthReport {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn get_health_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetHealthPolicy {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn set_health_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let policy: Result<HealthPolicy, _> = serde_json::from_value(payload);
+        match policy {
+            Ok(policy) => Ok(NodeRequest::SetHealthPolicy { policy }),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn create_jit_invoice(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<CreateJitInvoiceParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
+pub async fn get_channel_acceptance_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    handle_authenticated_request(
+        admin_service,
+        NodeRequest::GetChannelAcceptancePolicy {},
+        macaroon,
+        cookies,
+    )
+    .await
+}
+
+pub async fn set_channel_acceptance_policy(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let policy: Result<ChannelAcceptancePolicy, _> = serde_json::from_value(payload);
+        match policy {
+            Ok(policy) => Ok(NodeRequest::SetChannelAcceptancePolicy { policy }),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
 pub async fn keysend(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Json(payload): Json<Value>,
@@ -627,6 +1392,22 @@ pub async fn keysend(
     handle_authenticated_request(admin_service, request, macaroon, cookies).await
 }
 
+pub async fn rebalance(
+    Extension(admin_service): Extension<Arc<AdminService>>,
+    Json(payload): Json<Value>,
+    AuthHeader { macaroon, token: _ }: AuthHeader,
+    cookies: Cookies,
+) -> Result<Json<NodeResponse>, Response> {
+    let request = {
+        let params: Result<RebalanceParams, _> = serde_json::from_value(payload);
+        match params {
+            Ok(params) => Ok(params.into()),
+            Err(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "invalid params").into_response()),
+        }
+    }?;
+    handle_authenticated_request(admin_service, request, macaroon, cookies).await
+}
+
 pub async fn connect_peer(
     Extension(admin_service): Extension<Arc<AdminService>>,
     Json(payload): Json<Value>,
@@ -110,6 +110,20 @@ struct SenseiArgs {
     allow_origins: Option<Vec<String>>,
     #[clap(long, env = "RAPID_GOSSIP_SYNC_SERVER_HOST")]
     rapid_gossip_sync_server_host: Option<String>,
+    #[clap(long, env = "BALANCE_SNAPSHOT_INTERVAL_SECS")]
+    balance_snapshot_interval_secs: Option<u64>,
+    #[clap(long, env = "BALANCE_SNAPSHOT_RETENTION_SECS")]
+    balance_snapshot_retention_secs: Option<u64>,
+    #[clap(long, env = "CHANNEL_BACKUP_URL")]
+    channel_backup_url: Option<String>,
+    #[clap(long, env = "CHANNEL_BACKUP_TOKEN")]
+    channel_backup_token: Option<String>,
+    #[clap(long, env = "WATCHTOWER_SERVER")]
+    watchtower_server: Option<bool>,
+    #[clap(long, env = "REMOTE_WATCHTOWER_HOST")]
+    remote_watchtower_host: Option<String>,
+    #[clap(long, env = "REMOTE_WATCHTOWER_TOKEN")]
+    remote_watchtower_token: Option<String>,
 }
 
 pub type AdminRequestResponse = (AdminRequest, Sender<AdminResponse>);
@@ -205,6 +219,27 @@ fn main() {
     if let Some(rapid_gossip_sync_server_host) = args.rapid_gossip_sync_server_host {
         config.rapid_gossip_sync_server_host = Some(rapid_gossip_sync_server_host)
     }
+    if let Some(balance_snapshot_interval_secs) = args.balance_snapshot_interval_secs {
+        config.balance_snapshot_interval_secs = balance_snapshot_interval_secs
+    }
+    if let Some(balance_snapshot_retention_secs) = args.balance_snapshot_retention_secs {
+        config.balance_snapshot_retention_secs = balance_snapshot_retention_secs
+    }
+    if let Some(channel_backup_url) = args.channel_backup_url {
+        config.channel_backup_url = Some(channel_backup_url);
+    }
+    if let Some(channel_backup_token) = args.channel_backup_token {
+        config.channel_backup_token = Some(channel_backup_token);
+    }
+    if let Some(watchtower_server) = args.watchtower_server {
+        config.watchtower_server = watchtower_server
+    }
+    if let Some(remote_watchtower_host) = args.remote_watchtower_host {
+        config.remote_watchtower_host = Some(remote_watchtower_host);
+    }
+    if let Some(remote_watchtower_token) = args.remote_watchtower_token {
+        config.remote_watchtower_token = Some(remote_watchtower_token);
+    }
 
     if !config.database_url.starts_with("postgres:") && !config.database_url.starts_with("mysql:") {
         let sqlite_path = format!("{}/{}/{}", sensei_dir, config.network, config.database_url);
@@ -27,6 +27,7 @@ const NewTokenForm = () => {
       text: "Node Management",
     },
     { value: "routing", text: "Routing" },
+    { value: "watchtower", text: "Watchtower" },
   ];
 
   let expirationOptions = [